@@ -0,0 +1,449 @@
+/**
+ * Anonymization of diagrams for sharing in bug reports: identifiers and
+ * text are replaced with placeholders while the structure (statement
+ * kinds, arrows, shapes, nesting) is kept.
+ */
+
+import type {
+  BlockStartStatement,
+  Diagram,
+  DiagramType,
+  Statement,
+} from './types.js';
+import { parse } from './parser.js';
+import {
+  CLASS_DECL_PATTERN,
+  ER_RELATION_PATTERN,
+  matchParticipantDecl,
+  STATE_TRANSITION_PATTERN,
+} from './rules.js';
+import { formatFlowchartChain, parseFlowchartLine } from './flowchart.js';
+
+/**
+ * How placeholders are chosen: numbered in order of appearance, or
+ * derived from a hash of the original so they match across diagrams.
+ */
+export type AnonymizeStrategy = 'sequential' | 'hash';
+
+export interface AnonymizeOptions {
+  /** Placeholder scheme (default: 'sequential') */
+  strategy?: AnonymizeStrategy;
+}
+
+/** What lines that cannot be anonymized safely are replaced with */
+const REDACTED = '%% redacted';
+/** The same for YAML frontmatter lines, as a YAML comment */
+const REDACTED_YAML = '# redacted';
+
+/** ER attribute: type, name, keys, and comment */
+const ER_ATTRIBUTE_PATTERN =
+  /^(\S+)\s+(\S+)((?:\s+(?:PK|FK|UK),?)*)\s*("[^"]*")?/;
+
+/** Node attributes holding text rather than layout */
+const TEXT_ATTRIBUTES = /^(?:label|icon|img|tooltip|href)$/;
+
+/** Diagram types whose statements are understood well enough */
+const SUPPORTED: DiagramType[] = [
+  'flowchart',
+  'graph',
+  'sequenceDiagram',
+  'classDiagram',
+  'stateDiagram',
+  'stateDiagram-v2',
+  'erDiagram',
+];
+
+/** Placeholder prefix for the identifiers of each supported type */
+const ID_PREFIXES: Partial<Record<DiagramType, string>> = {
+  flowchart: 'n',
+  graph: 'n',
+  sequenceDiagram: 'p',
+  classDiagram: 'C',
+  stateDiagram: 's',
+  'stateDiagram-v2': 's',
+  erDiagram: 'E',
+};
+
+/**
+ * Replace identifiers and text in a diagram with stable placeholders:
+ * the same name always gets the same placeholder, so the diagram keeps
+ * its shape and connections. Node and participant IDs, labels, messages,
+ * notes, block labels, class members, and ER attributes are replaced;
+ * comments and lines that cannot be anonymized safely (e.g. click
+ * handlers with URLs) become `%% redacted`, and trailing comments are
+ * dropped.
+ *
+ * @param diagram - Diagram to anonymize; it is not modified
+ * @param options - Placeholder strategy
+ * @returns The anonymized diagram
+ * @throws Error for diagram types other than flowchart, sequence, class,
+ *   state, and ER diagrams
+ *
+ * @example
+ * ```ts
+ * format(anonymizeDiagram(parse('flowchart LR\nDb[(Orders)] --> Api')));
+ * // "flowchart LR\n    n1[(text1)] --> n2\n"
+ * ```
+ */
+export function anonymizeDiagram(
+  diagram: Diagram,
+  options: AnonymizeOptions = {}
+): Diagram {
+  if (!SUPPORTED.includes(diagram.type)) {
+    throw new Error(`Cannot anonymize ${diagram.type} diagrams`);
+  }
+  const anonymizer = new Anonymizer(
+    diagram.type,
+    options.strategy ?? 'sequential'
+  );
+  const lines = diagram.statements.map((stmt) => anonymizer.statement(stmt));
+  return parse(lines.join('\n'));
+}
+
+/**
+ * Placeholder assignment and per-statement rewriting. Each kind of name
+ * (IDs, text, class names, members) has its own placeholders.
+ */
+class Anonymizer {
+  private readonly type: DiagramType;
+  private readonly strategy: AnonymizeStrategy;
+  private readonly placeholders = new Map<string, Map<string, string>>();
+  private readonly taken = new Set<string>();
+  /** Kinds of the enclosing brace blocks, innermost last */
+  private readonly braces: string[] = [];
+
+  constructor(type: DiagramType, strategy: AnonymizeStrategy) {
+    this.type = type;
+    this.strategy = strategy;
+  }
+
+  /** The anonymized source line of a statement */
+  statement(stmt: Statement): string {
+    switch (stmt.type) {
+      case 'diagram-decl':
+      case 'directive':
+      case 'autonumber':
+      case 'blank-line':
+      case 'block-end':
+        return stmt.content;
+      case 'brace-block-end':
+        this.braces.pop();
+        return stmt.content;
+      case 'comment':
+      case 'link':
+      case 'timeline-event':
+      case 'mindmap-node':
+      case 'mindmap-decoration':
+      case 'unknown':
+        return REDACTED;
+      case 'frontmatter':
+        return stmt.content.trim() === '---' ? stmt.content : REDACTED_YAML;
+      case 'participant': {
+        const decl = matchParticipantDecl(stmt.content);
+        if (!decl) return REDACTED;
+        const { kind, id, alias, aliasQuoted } = decl;
+        if (alias === undefined) return `${kind} ${this.id(id)}`;
+        // Keep the quotes so the alias may still contain spaces
+        const text = aliasQuoted
+          ? `"${this.text(alias.slice(1, -1))}"`
+          : this.text(alias);
+        return `${kind} ${this.id(id)} as ${text}`;
+      }
+      case 'arrow-message': {
+        const { arrow, message } = stmt;
+        const [from, to] = [this.id(stmt.from), this.id(stmt.to)];
+        const text = message ? this.text(message) : '';
+        // Sequence messages need the colon; state transitions do not
+        if (this.type === 'sequenceDiagram') {
+          return `${from}${arrow}${to}:${text && ` ${text}`}`;
+        }
+        return `${from} ${arrow} ${to}${text && ` : ${text}`}`;
+      }
+      case 'note': {
+        if (!stmt.position || !stmt.participants) {
+          return this.classNote(stmt.content) ?? REDACTED;
+        }
+        const keyword = stmt.content.split(/\s/, 1)[0];
+        const targets = stmt.participants.map((id) => this.id(id)).join(',');
+        const text = this.text(stmt.text ?? '');
+        return `${keyword} ${stmt.position} ${targets} : ${text}`;
+      }
+      case 'activate':
+      case 'deactivate':
+        return `${stmt.type} ${this.id(stmt.participant)}`;
+      case 'block-start':
+        return this.blockStart(stmt);
+      case 'block-else':
+      case 'block-and':
+      case 'block-option': {
+        const keyword = stmt.content.split(/\s/, 1)[0];
+        return stmt.label ? `${keyword} ${this.text(stmt.label)}` : keyword;
+      }
+      case 'brace-block-start': {
+        this.braces.push(stmt.blockKind);
+        const name = this.id(stmt.name.replace(/~.*~$|\[.*\]$/, ''));
+        if (stmt.blockKind === 'entity') return `${name} {`;
+        return `${stmt.blockKind} ${name} {`;
+      }
+      case 'annotation': {
+        const target = stmt.target ? ` ${this.id(stmt.target)}` : '';
+        return `<<${stmt.name}>>${target}`;
+      }
+      case 'class-relation': {
+        const { from, to, arrow, label } = stmt;
+        const { fromCardinality: head, toCardinality: tail } = stmt;
+        const parts = [
+          this.id(from),
+          head === undefined ? '' : `"${head}"`,
+          arrow,
+          tail === undefined ? '' : `"${tail}"`,
+          this.id(to),
+        ];
+        const text = label ? ` : ${this.text(label)}` : '';
+        return parts.filter(Boolean).join(' ') + text;
+      }
+      case 'state-stereotype':
+        return `state ${this.id(stmt.name)} <<${stmt.stereotype}>>`;
+      case 'state-separator':
+        return stmt.content;
+      // Dates and formats carry no names
+      case 'gantt-setting':
+        return stmt.content;
+      case 'attribute-map': {
+        // Keep layout attributes; labels and links are text
+        const attributes = stmt.attributes
+          .filter(({ key }) => !TEXT_ATTRIBUTES.test(key))
+          .map(({ key, value }) => `${key}: ${value}`);
+        const map = attributes.length > 0 ? ` ${attributes.join(', ')} ` : '';
+        return `${this.id(stmt.id)}@{${map}}`;
+      }
+      case 'generic-line':
+        return this.genericLine(stmt.content) ?? REDACTED;
+    }
+  }
+
+  private blockStart(stmt: BlockStartStatement): string {
+    const keyword = stmt.content.split(/\s/, 1)[0];
+    const { blockKind, label } = stmt;
+    if (blockKind === 'rect') return stmt.content;
+    if (blockKind === 'block') {
+      // "block:ID:2": keep the width
+      const [id, width] = label?.split(':') ?? [];
+      if (!id) return 'block';
+      return `block:${this.id(id)}${width ? `:${width}` : ''}`;
+    }
+    if (blockKind === 'box') {
+      const parts = [keyword, stmt.color, label && this.text(label)];
+      return parts.filter(Boolean).join(' ');
+    }
+    if (blockKind === 'subgraph') {
+      // "subgraph ID [Title]", "subgraph ID", or "subgraph Some title"
+      const titled = label?.match(/^(\S+)\s*\[(.*)\]$/);
+      if (titled) {
+        return `${keyword} ${this.id(titled[1])} [${this.text(titled[2])}]`;
+      }
+      return label ? `${keyword} ${this.id(label)}` : keyword;
+    }
+    return label ? `${keyword} ${this.text(label)}` : keyword;
+  }
+
+  /** Diagram-specific lines without a statement type of their own */
+  private genericLine(content: string): string | null {
+    const body = this.braces[this.braces.length - 1];
+    if (body === 'class') return this.member(content);
+    if (body === 'entity') return this.erAttribute(content);
+    if (/^direction\s+\w+$/.test(content) || /^linkStyle\s/.test(content)) {
+      return content;
+    }
+    const styled = this.styleLine(content);
+    if (styled !== undefined) return styled;
+
+    switch (this.type) {
+      case 'flowchart':
+      case 'graph': {
+        const chain = parseFlowchartLine(content);
+        if (!chain) return null;
+        for (const group of chain.groups) {
+          group.forEach((node, i) => {
+            const { shape, attributes, className } = node;
+            group[i] = {
+              id: this.id(node.id),
+              shape: shape && { ...shape, text: this.text(shape.text) },
+              attributes: attributes && layoutAttributes(attributes),
+              className: className && this.name('class', className),
+            };
+          });
+        }
+        chain.links.forEach((link, i) => {
+          chain.links[i] = {
+            ...link,
+            edgeId: link.edgeId && this.id(link.edgeId),
+            label: link.label && this.text(link.label),
+          };
+        });
+        return formatFlowchartChain(chain);
+      }
+      case 'classDiagram': {
+        const decl = content.match(CLASS_DECL_PATTERN);
+        if (decl) return `class ${this.id(decl[1])}`;
+        const member = content.match(/^([\w-]+)\s*:\s*(.+)$/);
+        if (!member) return null;
+        return `${this.id(member[1])} : ${this.member(member[2])}`;
+      }
+      case 'stateDiagram':
+      case 'stateDiagram-v2': {
+        if (content === '--' || content === '[*]') return content;
+        const transition = content.match(STATE_TRANSITION_PATTERN);
+        if (transition) {
+          return `${this.id(transition[1])} --> ${this.id(transition[2])}`;
+        }
+        const named = content.match(/^state\s+"([^"]*)"\s+as\s+([\w-]+)$/);
+        if (named) {
+          return `state "${this.text(named[1])}" as ${this.id(named[2])}`;
+        }
+        const decl = content.match(/^state\s+([\w-]+)(\s+<<\w+>>)?$/);
+        if (decl) return `state ${this.id(decl[1])}${decl[2] ?? ''}`;
+        const description = content.match(/^([\w-]+)\s*:\s*(.+)$/);
+        if (description) {
+          return `${this.id(description[1])} : ${this.text(description[2])}`;
+        }
+        return /^[\w-]+$/.test(content) ? this.id(content) : null;
+      }
+      case 'erDiagram': {
+        const relation = content.match(ER_RELATION_PATTERN);
+        if (!relation) {
+          return /^[\w-]+$/.test(content) ? this.id(content) : null;
+        }
+        const [, from, arrow, to, label] = relation;
+        const text = label === undefined ? '' : ` : ${this.text(label)}`;
+        return `${this.id(from)} ${arrow} ${this.id(to)}${text}`;
+      }
+      default:
+        return null;
+    }
+  }
+
+  /** Class diagram note: 'note for A "text"' or 'note "text"' */
+  private classNote(content: string): string | null {
+    const note = content.match(/^note\s+(?:for\s+([\w-]+)\s+)?"(.*)"$/);
+    if (!note) return null;
+    const target = note[1] ? `for ${this.id(note[1])} ` : '';
+    return `note ${target}"${this.text(note[2])}"`;
+  }
+
+  /**
+   * "style A ...", "classDef name ...", "class A,B name", and
+   * "cssClass "A" name"; undefined for other lines.
+   */
+  private styleLine(content: string): string | null | undefined {
+    const [keyword, target, ...rest] = content.split(/\s+/);
+    const ids = (list: string) =>
+      list
+        .split(',')
+        .map((id) => this.id(id))
+        .join(',');
+    switch (keyword) {
+      case 'style':
+        return target ? [keyword, ids(target), ...rest].join(' ') : null;
+      case 'classDef': {
+        if (!target) return null;
+        const names = target
+          .split(',')
+          .map((name) =>
+            name === 'default' ? name : this.name('class', name)
+          )
+          .join(',');
+        return [keyword, names, ...rest].join(' ');
+      }
+      case 'class':
+      case 'cssClass': {
+        // "class A" in class diagrams is a declaration
+        if (rest.length !== 1) return keyword === 'class' ? undefined : null;
+        const targets = ids(target.replace(/"/g, ''));
+        const quoted = keyword === 'cssClass' ? `"${targets}"` : targets;
+        return `${keyword} ${quoted} ${this.name('class', rest[0])}`;
+      }
+      case 'click':
+      case 'link':
+      case 'links':
+      case 'callback':
+        return null;
+      default:
+        return undefined;
+    }
+  }
+
+  /** A class member, keeping its visibility and whether it is a method */
+  private member(content: string): string {
+    const [, visibility] = content.match(/^([+\-#~]?)/) ?? ['', ''];
+    const method = content.includes('(') ? '()' : '';
+    const classifier = content.match(/[$*]$/)?.[0] ?? '';
+    return `${visibility}${this.name('member', content)}${method}${classifier}`;
+  }
+
+  /** An ER attribute "type name PK "comment"", keeping type and keys */
+  private erAttribute(content: string): string {
+    const match = content.match(ER_ATTRIBUTE_PATTERN);
+    if (!match) return REDACTED;
+    const [, type, name, keys = '', comment] = match;
+    const text = comment ? ` "${this.text(comment)}"` : '';
+    return `${type} ${this.name('attribute', name)}${keys}${text}`;
+  }
+
+  /** Placeholder for a node, participant, class, state, or entity ID */
+  private id(original: string): string {
+    // Flowchart and state start/end markers are syntax
+    if (original === '[*]') return original;
+    return this.name('id', original);
+  }
+
+  private text(original: string): string {
+    return this.name('text', original);
+  }
+
+  /**
+   * Stable placeholder for a name of some kind, e.g. "n1" or "n_1c9e2b4f"
+   * for IDs and "text1" for text.
+   */
+  private name(kind: string, original: string): string {
+    let names = this.placeholders.get(kind);
+    if (!names) {
+      names = new Map();
+      this.placeholders.set(kind, names);
+    }
+    const existing = names.get(original);
+    if (existing) return existing;
+
+    const prefix = kind === 'id' ? (ID_PREFIXES[this.type] ?? 'n') : kind;
+    let placeholder =
+      this.strategy === 'hash'
+        ? `${prefix}_${fnv1a(`${kind}:${original}`)}`
+        : `${prefix}${names.size + 1}`;
+    // Hashes can collide
+    for (let n = 2; this.taken.has(placeholder); n++) {
+      placeholder = `${prefix}_${fnv1a(`${kind}:${original}:${n}`)}`;
+    }
+    this.taken.add(placeholder);
+    names.set(original, placeholder);
+    return placeholder;
+  }
+}
+
+/** Inline node attributes without the ones holding text */
+function layoutAttributes(attributes: string): string {
+  return attributes
+    .split(',')
+    .filter((pair) => !TEXT_ATTRIBUTES.test(pair.split(':')[0].trim()))
+    .join(',')
+    .trim();
+}
+
+/** 32-bit FNV-1a hash as 8 hex digits; stable across runs and platforms */
+function fnv1a(text: string): string {
+  let hash = 0x811c9dc5;
+  for (const char of text) {
+    hash ^= char.codePointAt(0) ?? 0;
+    hash = Math.imul(hash, 0x01000193) >>> 0;
+  }
+  return hash.toString(16).padStart(8, '0');
+}
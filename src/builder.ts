@@ -0,0 +1,293 @@
+/**
+ * Builders for constructing diagrams in code instead of concatenating
+ * strings. They produce the same statements the parser does, so results
+ * can be passed to `format` or inspected like parsed diagrams.
+ */
+
+import type {
+  BlockKind,
+  Diagram,
+  FormatOptions,
+  NotePosition,
+  Statement,
+} from './types.js';
+import { format } from './formatter.js';
+import { matchParticipantDecl } from './rules.js';
+
+/** Sequence diagram blocks closed with "end" */
+export type SequenceBlockKind = Exclude<BlockKind, 'subgraph' | 'box'>;
+
+/** Flowchart node shapes and their delimiters */
+const NODE_SHAPES = {
+  rect: ['[', ']'],
+  round: ['(', ')'],
+  stadium: ['([', '])'],
+  subroutine: ['[[', ']]'],
+  cylinder: ['[(', ')]'],
+  circle: ['((', '))'],
+  diamond: ['{', '}'],
+  hexagon: ['{{', '}}'],
+} as const;
+
+/** Flowchart node shape name */
+export type NodeShape = keyof typeof NODE_SHAPES;
+
+/** Options for a flowchart edge */
+export interface EdgeOptions {
+  label?: string;
+  /** Link operator (default: "-->") */
+  arrow?: string;
+}
+
+/**
+ * Shared statement list with block nesting.
+ */
+abstract class DiagramBuilder {
+  protected statements: Statement[] = [];
+  private readonly type: Diagram['type'];
+
+  constructor(type: Diagram['type'], header: string) {
+    this.type = type;
+    this.statements.push({
+      type: 'diagram-decl',
+      diagramType: type,
+      content: header,
+    });
+  }
+
+  /** Add a "%% text" comment */
+  comment(text: string): this {
+    this.statements.push({ type: 'comment', content: `%% ${text}` });
+    return this;
+  }
+
+  /** Build the diagram AST */
+  build(): Diagram {
+    return { type: this.type, statements: [...this.statements] };
+  }
+
+  /** Render the diagram with the formatter */
+  toString(options?: FormatOptions): string {
+    return format(this.build(), options);
+  }
+
+  protected line(content: string): this {
+    this.statements.push({ type: 'generic-line', content });
+    return this;
+  }
+
+  protected block(
+    blockKind: BlockKind,
+    label: string | undefined,
+    body: (builder: this) => void
+  ): this {
+    const content = label ? `${blockKind} ${label}` : blockKind;
+    this.statements.push({ type: 'block-start', blockKind, label, content });
+    body(this);
+    this.statements.push({ type: 'block-end', content: 'end' });
+    return this;
+  }
+}
+
+/**
+ * Build a sequence diagram.
+ *
+ * @example
+ * ```ts
+ * new SequenceDiagramBuilder()
+ *   .participant('A', 'Alice')
+ *   .message('A', 'B', 'Hello')
+ *   .loop('Every minute', (b) => b.message('B', 'A', 'ping'))
+ *   .toString();
+ * ```
+ */
+export class SequenceDiagramBuilder extends DiagramBuilder {
+  constructor() {
+    super('sequenceDiagram', 'sequenceDiagram');
+  }
+
+  autonumber(): this {
+    this.statements.push({ type: 'autonumber', content: 'autonumber' });
+    return this;
+  }
+
+  participant(id: string, alias?: string): this {
+    return this.declare('participant', id, alias);
+  }
+
+  actor(id: string, alias?: string): this {
+    return this.declare('actor', id, alias);
+  }
+
+  /** Add a message; the arrow defaults to "->>" */
+  message(from: string, to: string, text: string, arrow = '->>'): this {
+    this.statements.push({
+      type: 'arrow-message',
+      from,
+      arrow,
+      to,
+      message: text,
+      content: `${from}${arrow}${to}: ${text}`,
+    });
+    return this;
+  }
+
+  note(position: NotePosition, participants: string[], text: string): this {
+    const content = `Note ${position} ${participants.join(',')}: ${text}`;
+    this.statements.push({
+      type: 'note',
+      position,
+      participants,
+      text,
+      content,
+    });
+    return this;
+  }
+
+  activate(participant: string): this {
+    this.statements.push({
+      type: 'activate',
+      participant,
+      content: `activate ${participant}`,
+    });
+    return this;
+  }
+
+  deactivate(participant: string): this {
+    this.statements.push({
+      type: 'deactivate',
+      participant,
+      content: `deactivate ${participant}`,
+    });
+    return this;
+  }
+
+  loop(label: string, body: (builder: this) => void): this {
+    return this.block('loop', label, body);
+  }
+
+  opt(label: string, body: (builder: this) => void): this {
+    return this.block('opt', label, body);
+  }
+
+  /**
+   * Add an alt block. Call `else` inside the body to start a branch.
+   */
+  alt(label: string, body: (builder: this) => void): this {
+    return this.block('alt', label, body);
+  }
+
+  /** Start an else branch inside an alt block */
+  else(label?: string): this {
+    const content = label ? `else ${label}` : 'else';
+    this.statements.push({ type: 'block-else', label, content });
+    return this;
+  }
+
+  /**
+   * Add a par block. Call `and` inside the body to start a branch.
+   */
+  par(label: string, body: (builder: this) => void): this {
+    return this.block('par', label, body);
+  }
+
+  /** Start a parallel branch inside a par block */
+  and(label?: string): this {
+    const content = label ? `and ${label}` : 'and';
+    this.statements.push({ type: 'block-and', label, content });
+    return this;
+  }
+
+  /**
+   * Add a critical block. Call `option` inside the body for alternatives.
+   */
+  critical(label: string, body: (builder: this) => void): this {
+    return this.block('critical', label, body);
+  }
+
+  /** Start an option inside a critical block */
+  option(label?: string): this {
+    const content = label ? `option ${label}` : 'option';
+    this.statements.push({ type: 'block-option', label, content });
+    return this;
+  }
+
+  /** Add any block kind, e.g. "break" or "rect rgb(0,0,255)" */
+  group(
+    kind: SequenceBlockKind,
+    label: string | undefined,
+    body: (builder: this) => void
+  ): this {
+    return this.block(kind, label, body);
+  }
+
+  private declare(keyword: string, id: string, alias?: string): this {
+    const content = alias
+      ? `${keyword} ${id} as ${alias}`
+      : `${keyword} ${id}`;
+    const decl = matchParticipantDecl(content);
+    this.statements.push({ type: 'participant', ...decl, content });
+    return this;
+  }
+}
+
+/**
+ * Build a flowchart.
+ *
+ * @example
+ * ```ts
+ * new FlowchartBuilder('LR')
+ *   .node('A', 'Start', 'stadium')
+ *   .edge('A', 'B', { label: 'next' })
+ *   .toString();
+ * ```
+ */
+export class FlowchartBuilder extends DiagramBuilder {
+  constructor(direction = 'TD') {
+    super('flowchart', `flowchart ${direction}`);
+  }
+
+  /** Declare a node; the shape defaults to a rectangle */
+  node(id: string, label?: string, shape: NodeShape = 'rect'): this {
+    if (label === undefined) return this.line(id);
+    const [open, close] = NODE_SHAPES[shape];
+    return this.line(`${id}${open}${quoteLabel(label)}${close}`);
+  }
+
+  edge(from: string, to: string, options: EdgeOptions = {}): this {
+    const { label, arrow = '-->' } = options;
+    const link = label === undefined ? arrow : `${arrow}|${quoteLabel(label)}|`;
+    return this.line(`${from} ${link} ${to}`);
+  }
+
+  subgraph(
+    id: string,
+    label: string | undefined,
+    body: (builder: this) => void
+  ): this {
+    return this.block('subgraph', label ? `${id} [${label}]` : id, body);
+  }
+
+  classDef(name: string, style: string): this {
+    return this.line(`classDef ${name} ${style}`);
+  }
+
+  /** Apply a class to nodes */
+  class(ids: string[], name: string): this {
+    return this.line(`class ${ids.join(',')} ${name}`);
+  }
+
+  style(id: string, style: string): this {
+    return this.line(`style ${id} ${style}`);
+  }
+}
+
+/**
+ * Quote a label when it contains characters that end a shape or label,
+ * escaping embedded double quotes.
+ */
+function quoteLabel(label: string): string {
+  if (!/["[\](){}|<>]/.test(label)) return label;
+  const escaped = label.replace(/"/g, '#quot;');
+  return `"${escaped}"`;
+}
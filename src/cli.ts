@@ -1,14 +1,89 @@
 #!/usr/bin/env node
 
-import { readFileSync, writeFileSync } from 'node:fs';
-import { formatMermaid, formatMarkdownMermaidBlocks } from './index.js';
-import type { FormatOptions } from './types.js';
+import { existsSync, mkdirSync, readFileSync, writeFileSync } from 'node:fs';
+import { dirname, join } from 'node:path';
+import { once } from 'node:events';
+import {
+  formatMermaid,
+  formatMarkdownMermaidBlocks,
+  formatStream,
+  checkFormatted,
+  fixMermaid,
+  formatWithCursor,
+  formatWithSourceMap,
+  convertDiagram,
+  convertDiagramWithWarnings,
+  detectDiagramType,
+  isConversionTarget,
+  getDiagramStats,
+  lint,
+  LINT_RULES,
+  parse,
+  format,
+  diagramToJSON,
+  diagramFromJSON,
+  diagramStructure,
+  exportDiagram,
+  isExportFormat,
+  importDiagram,
+  isImportFormat,
+  anonymizeDiagram,
+} from './index.js';
+import type {
+  AnonymizeStrategy,
+  ConversionTarget,
+  RuleLevel,
+} from './index.js';
+import {
+  fenceFileNames,
+  findMermaidFences,
+  replaceMermaidFences,
+} from './markdown.js';
+import { startLanguageServer } from './lsp.js';
+import { startDaemon } from './daemon.js';
+import type {
+  EdgeLabelWrap,
+  FormatOptions,
+  ParticipantOrder,
+  StyleLevel,
+  TraceEvent,
+} from './types.js';
 
 interface CliArgs {
   file?: string;
   write: boolean;
+  check: boolean;
   indent: number;
   tabs: boolean;
+  flushLeft: boolean;
+  styleLevel?: StyleLevel;
+  lossless: boolean;
+  alignMessages: boolean;
+  alignArrows: boolean;
+  alignEdgeLabels: boolean;
+  alignErAttributes: boolean;
+  alignGanttTasks: boolean;
+  alignQuadrantPoints: boolean;
+  maxWidth?: number;
+  groupParticipants: boolean;
+  participantOrder?: ParticipantOrder;
+  declareParticipants: boolean;
+  sortClassMembers: boolean;
+  alphabetizeClassMembers: boolean;
+  groupStyleStatements: boolean;
+  groupGanttSettings: boolean;
+  reindentMindmaps: boolean;
+  spaceArrows: boolean;
+  dedupeNodeLabels: boolean;
+  wrapEdgeLabels?: EdgeLabelWrap;
+  blankLineBetweenSections: boolean;
+  expandChains: boolean;
+  hoistDirectives: boolean;
+  stdin: boolean;
+  stream: boolean;
+  cursorOffset?: number;
+  sourceMap?: string;
+  trace: boolean;
   help: boolean;
   version: boolean;
 }
@@ -16,8 +91,32 @@ interface CliArgs {
 function parseArgs(args: string[]): CliArgs {
   const result: CliArgs = {
     write: false,
+    check: false,
     indent: 4,
     tabs: false,
+    flushLeft: false,
+    lossless: false,
+    alignMessages: false,
+    alignArrows: false,
+    alignEdgeLabels: false,
+    alignErAttributes: false,
+    alignGanttTasks: false,
+    alignQuadrantPoints: false,
+    groupParticipants: false,
+    declareParticipants: false,
+    sortClassMembers: false,
+    alphabetizeClassMembers: false,
+    groupStyleStatements: false,
+    groupGanttSettings: false,
+    reindentMindmaps: false,
+    spaceArrows: false,
+    dedupeNodeLabels: false,
+    blankLineBetweenSections: false,
+    expandChains: false,
+    hoistDirectives: false,
+    stdin: false,
+    stream: false,
+    trace: false,
     help: false,
     version: false,
   };
@@ -31,8 +130,51 @@ function parseArgs(args: string[]): CliArgs {
       result.version = true;
     } else if (arg === '-w' || arg === '--write') {
       result.write = true;
+    } else if (arg === '--check') {
+      result.check = true;
     } else if (arg === '--tabs') {
       result.tabs = true;
+    } else if (arg === '--flush-left') {
+      result.flushLeft = true;
+    } else if (arg === '--style') {
+      result.styleLevel = toStyleLevel(args[++i]);
+    } else if (arg.startsWith('--style=')) {
+      result.styleLevel = toStyleLevel(arg.slice(8));
+    } else if (arg === '--lossless') {
+      result.lossless = true;
+    } else if (arg === '--section-blank-lines') {
+      result.blankLineBetweenSections = true;
+    } else if (arg === '--align-messages') {
+      result.alignMessages = true;
+    } else if (arg === '--space-arrows') {
+      result.spaceArrows = true;
+    } else if (arg === '--expand-chains') {
+      result.expandChains = true;
+    } else if (arg === '--hoist-directives') {
+      result.hoistDirectives = true;
+    } else if (arg === '--dedupe-labels') {
+      result.dedupeNodeLabels = true;
+    } else if (arg === '--wrap-edge-labels') {
+      result.wrapEdgeLabels = toEdgeLabelWrap(args[++i]);
+    } else if (arg.startsWith('--wrap-edge-labels=')) {
+      result.wrapEdgeLabels = toEdgeLabelWrap(arg.slice(19));
+    } else if (arg === '--align-arrows') {
+      result.alignArrows = true;
+    } else if (arg === '--align-edge-labels') {
+      result.alignArrows = true;
+      result.alignEdgeLabels = true;
+    } else if (arg === '--align-er-attributes') {
+      result.alignErAttributes = true;
+    } else if (arg === '--align-gantt-tasks') {
+      result.alignGanttTasks = true;
+    } else if (arg === '--align-quadrant-points') {
+      result.alignQuadrantPoints = true;
+    } else if (arg === '--stdin') {
+      result.stdin = true;
+    } else if (arg === '--stream') {
+      result.stream = true;
+    } else if (arg === '--trace') {
+      result.trace = true;
     } else if (arg === '--indent') {
       const next = args[++i];
       const parsed = parseInt(next, 10);
@@ -40,6 +182,43 @@ function parseArgs(args: string[]): CliArgs {
     } else if (arg.startsWith('--indent=')) {
       const parsed = parseInt(arg.slice(9), 10);
       result.indent = Number.isNaN(parsed) ? 4 : parsed;
+    } else if (arg === '--cursor-offset') {
+      const parsed = parseInt(args[++i], 10);
+      result.cursorOffset = Number.isNaN(parsed) ? undefined : parsed;
+    } else if (arg.startsWith('--cursor-offset=')) {
+      const parsed = parseInt(arg.slice(16), 10);
+      result.cursorOffset = Number.isNaN(parsed) ? undefined : parsed;
+    } else if (arg === '--group-participants') {
+      result.groupParticipants = true;
+    } else if (arg === '--declare-participants') {
+      result.declareParticipants = true;
+    } else if (arg === '--sort-class-members') {
+      result.sortClassMembers = true;
+    } else if (arg === '--alphabetize-class-members') {
+      result.sortClassMembers = true;
+      result.alphabetizeClassMembers = true;
+    } else if (arg === '--group-styles') {
+      result.groupStyleStatements = true;
+    } else if (arg === '--group-gantt-settings') {
+      result.groupGanttSettings = true;
+    } else if (arg === '--reindent-mindmaps') {
+      result.reindentMindmaps = true;
+    } else if (arg === '--participant-order') {
+      result.groupParticipants = true;
+      result.participantOrder = toParticipantOrder(args[++i]);
+    } else if (arg.startsWith('--participant-order=')) {
+      result.groupParticipants = true;
+      result.participantOrder = toParticipantOrder(arg.slice(20));
+    } else if (arg === '--max-width') {
+      const parsed = parseInt(args[++i], 10);
+      result.maxWidth = Number.isNaN(parsed) ? undefined : parsed;
+    } else if (arg.startsWith('--max-width=')) {
+      const parsed = parseInt(arg.slice(12), 10);
+      result.maxWidth = Number.isNaN(parsed) ? undefined : parsed;
+    } else if (arg === '--source-map') {
+      result.sourceMap = args[++i];
+    } else if (arg.startsWith('--source-map=')) {
+      result.sourceMap = arg.slice(13);
     } else if (!arg.startsWith('-')) {
       result.file = arg;
     }
@@ -48,20 +227,190 @@ function parseArgs(args: string[]): CliArgs {
   return result;
 }
 
+function toFormatOptions(args: CliArgs): FormatOptions {
+  return {
+    indentSize: args.indent,
+    useTabs: args.tabs,
+    indentTopLevel: !args.flushLeft,
+    ...(args.styleLevel && { styleLevel: args.styleLevel }),
+    // Options a style level turns on are left out unless their flag is
+    // given, so the level decides
+    ...(args.lossless && { lossless: true }),
+    ...(args.alignMessages && { alignMessages: true }),
+    ...(args.alignArrows && { alignArrows: true }),
+    ...(args.alignEdgeLabels && { alignEdgeLabels: true }),
+    ...(args.alignErAttributes && { alignErAttributes: true }),
+    ...(args.alignGanttTasks && { alignGanttTasks: true }),
+    ...(args.alignQuadrantPoints && { alignQuadrantPoints: true }),
+    ...(args.maxWidth !== undefined && { maxWidth: args.maxWidth }),
+    groupParticipants: args.groupParticipants,
+    ...(args.participantOrder && { participantOrder: args.participantOrder }),
+    declareParticipants: args.declareParticipants,
+    ...(args.sortClassMembers && { sortClassMembers: true }),
+    alphabetizeClassMembers: args.alphabetizeClassMembers,
+    groupStyleStatements: args.groupStyleStatements,
+    groupGanttSettings: args.groupGanttSettings,
+    reindentMindmaps: args.reindentMindmaps,
+    ...(args.spaceArrows && { spaceArrows: true }),
+    dedupeNodeLabels: args.dedupeNodeLabels,
+    ...(args.wrapEdgeLabels && { wrapEdgeLabels: args.wrapEdgeLabels }),
+    blankLineBetweenSections: args.blankLineBetweenSections,
+    expandChains: args.expandChains,
+    hoistDirectives: args.hoistDirectives,
+    ...(args.trace && { trace: printTraceEvent }),
+  };
+}
+
+// Prints e.g. line 3 (generic-line) collapse-spaces: "A  --> B" -> "A --> B"
+function printTraceEvent(event: TraceEvent): void {
+  const where = event.line === null ? 'inserted' : `line ${event.line}`;
+  const text = (value: string | null) =>
+    value === null ? '(none)' : JSON.stringify(value);
+  console.error(
+    `${where} (${event.statementType}) ${event.rule}: ` +
+      `${text(event.before)} -> ${text(event.after)}`
+  );
+}
+
+function toEdgeLabelWrap(value: string | undefined): EdgeLabelWrap {
+  if (value === 'off' || value === 'br' || value === 'quoted') return value;
+  console.error('Error: --wrap-edge-labels must be off, br, or quoted');
+  process.exit(1);
+}
+
+function toStyleLevel(value: string | undefined): StyleLevel {
+  if (
+    value === 'conservative' ||
+    value === 'standard' ||
+    value === 'aggressive'
+  ) {
+    return value;
+  }
+  console.error('Error: --style must be conservative, standard, or aggressive');
+  process.exit(1);
+}
+
+function toParticipantOrder(value: string | undefined): ParticipantOrder {
+  if (value === 'source' || value === 'first-use' || value === 'alphabetical') {
+    return value;
+  }
+  console.error(
+    'Error: --participant-order must be source, first-use, or alphabetical'
+  );
+  process.exit(1);
+}
+
+function toAnonymizeStrategy(value: string | undefined): AnonymizeStrategy {
+  if (value === 'sequential' || value === 'hash') return value;
+  console.error('Error: --strategy must be sequential or hash');
+  process.exit(1);
+}
+
 function printHelp(): void {
   console.log(`
 mermaidfmt - Mermaid diagram formatter
 
 USAGE:
     mermaidfmt [OPTIONS] [FILE]
+    mermaidfmt <COMMAND> [OPTIONS] [FILE]
+
+COMMANDS:
+    stats               Print diagram metrics (use --json for JSON output)
+    lint                Report semantic issues with rule IDs
+                        (--deny/--warn/--allow <RULE>, --json)
+    fix                 Apply safe lint fixes, then format
+                        (--allow <RULE> to skip a fix, -w to write)
+    extract             Write each mermaid fence of a Markdown file to
+                        its own .mmd file (--out-dir <DIR>)
+    embed               Replace Markdown fences with formatted .mmd files
+                        from include markers or --dir <DIR> name matches
+    convert             Migrate diagram type, leaving other lines untouched
+                        (--to flowchart|stateDiagram-v2)
+    ast                 Print the parsed AST as JSON (--structure for
+                        nodes/edges/participants/messages, --from-json to
+                        format an AST JSON file instead)
+    export              Print the diagram in another language
+                        (--to dot for flowcharts, --to plantuml for
+                        sequence and class diagrams, --to d2 for both
+                        flowcharts and sequence diagrams)
+    import              Convert another language to formatted Mermaid
+                        (--from dot for flowcharts, --from plantuml for
+                        sequence diagrams)
+    anonymize           Replace names and text with placeholders for bug
+                        reports (--strategy sequential|hash)
+    lsp                 Run a Language Server over stdio (formatting,
+                        range formatting, lint diagnostics)
+    daemon              Serve newline-delimited JSON requests over stdio
+                        ({"method":"format","text":...,"config":...})
 
 ARGS:
     <FILE>    Input file (reads from stdin if not provided)
 
 OPTIONS:
     -w, --write         Write result to source file instead of stdout
+    --check             Exit with status 1 if the input is not formatted,
+                        naming the first line that would change
     --indent <N>        Number of spaces for indentation (default: 4)
     --tabs              Use tabs instead of spaces
+    --flush-left        Keep top-level statements at column 0 under the
+                        declaration; blocks still indent their bodies
+    --style <conservative|standard|aggressive>
+                        Rule groups to apply: whitespace only (as
+                        --lossless), the defaults, or also alignment,
+                        class member sorting, and arrow spacing
+    --lossless          Change only indentation, trailing whitespace, and
+                        blank lines; other formatting options are ignored
+    --section-blank-lines
+                        Blank line after participant declarations, closed
+                        blocks, and before gantt/journey/timeline sections
+    --align-messages    Align sequence message texts within message runs
+    --space-arrows      Single spaces around flowchart link operators
+    --expand-chains     Split A --> B --> C into one edge per line
+    --hoist-directives  Move %%{...}%% directives above the diagram
+                        declaration
+    --dedupe-labels     Drop flowchart node labels repeated after the first
+                        definition (A[Foo] ... A[Foo] -> A[Foo] ... A)
+    --wrap-edge-labels <br|quoted>
+                        Break edge labels of lines wider than --max-width
+                        with <br/> (quoted: inside "...")
+    --align-arrows      Align flowchart arrows and targets within edge runs
+    --align-edge-labels Like --align-arrows, with |labels| in their own column
+    --align-er-attributes
+                        Align ER attribute type/name/key/comment columns
+    --align-gantt-tasks Align gantt task colons and fields per section
+    --align-quadrant-points
+                        Align quadrant point labels, coordinates, and styles
+    --max-width <N>     Skip alignment padding for rows wider than N
+                        (default: 80)
+    --group-participants
+                        Move sequence participant declarations to the top
+    --participant-order <source|first-use|alphabetical>
+                        Sort grouped declarations (implies
+                        --group-participants)
+    --declare-participants
+                        Insert declarations for implicit sequence
+                        participants, in first-use order
+    --sort-class-members
+                        Order class members: annotations, fields, methods,
+                        each by visibility (+ # ~ -)
+    --alphabetize-class-members
+                        Like --sort-class-members, also sorting by name
+    --group-styles      Move flowchart classDef/class/style/linkStyle/click
+                        statements below the nodes and edges
+    --group-gantt-settings
+                        Move gantt settings such as tickInterval and
+                        todayMarker above the first section
+    --reindent-mindmaps Indent mindmap nodes by tree depth, with icon and
+                        class lines under their node
+    --stdin             Read from stdin even when it is a terminal
+    --stream            Format stdin to stdout line by line, for very large
+                        generated diagrams in pipes
+    --cursor-offset <N> Print (to stderr) where a cursor at offset N moves
+                        after formatting (not for Markdown)
+    --source-map <FILE> Write the input→output line map as JSON
+                        ({"lineMap":[1,2,null,...]}, not for Markdown)
+    --trace             Print (to stderr) each formatting rule that changed
+                        a line, with the text before and after
     -h, --help          Print help information
     -v, --version       Print version information
 
@@ -72,12 +421,45 @@ EXAMPLES:
     # Format file in-place
     mermaidfmt -w diagram.mmd
 
+    # Fail in CI when a file is not formatted
+    mermaidfmt --check diagram.mmd
+
     # Format from stdin
     echo "sequenceDiagram" | mermaidfmt
 
     # Custom indent
     mermaidfmt --indent 2 diagram.mmd
     mermaidfmt --tabs diagram.mmd
+
+    # Diagram metrics as JSON
+    mermaidfmt stats --json diagram.mmd
+
+    # Lint, treating empty blocks as errors
+    mermaidfmt lint --deny empty-block diagram.mmd
+
+    # Fix and format in place
+    mermaidfmt fix -w diagram.mmd
+
+    # Split Markdown diagrams into diagrams/<heading>.mmd
+    mermaidfmt extract README.md --out-dir diagrams/
+
+    # Sync edited diagrams back into the Markdown file
+    mermaidfmt embed -w README.md --dir diagrams/
+
+    # Migrate a legacy graph to flowchart
+    mermaidfmt convert --to flowchart -w old.mmd
+
+    # Round-trip through the JSON AST
+    mermaidfmt ast diagram.mmd > ast.json
+    mermaidfmt ast --from-json ast.json
+
+    # Render a flowchart with Graphviz
+    mermaidfmt export --to dot flow.mmd | dot -Tsvg > flow.svg
+    mermaidfmt export --to plantuml sequence.mmd > sequence.puml
+    mermaidfmt export --to d2 flow.mmd > flow.d2
+    mermaidfmt import --from plantuml sequence.puml > sequence.mmd
+    mermaidfmt import --from dot graph.dot > graph.mmd
+    mermaidfmt anonymize diagram.mmd > report.mmd
 `);
 }
 
@@ -105,47 +487,455 @@ async function readStdin(): Promise<string> {
   });
 }
 
-async function main(): Promise<void> {
-  const args = parseArgs(process.argv.slice(2));
+/**
+ * Read input from a file, or from stdin when no file is given.
+ * Prints help and exits when stdin is an interactive terminal.
+ */
+async function readInput(file?: string, forceStdin = false): Promise<string> {
+  if (file) {
+    try {
+      return readFileSync(file, 'utf-8');
+    } catch (_err) {
+      console.error(`Error reading file: ${file}`);
+      process.exit(1);
+    }
+  }
 
-  if (args.help) {
+  // Check if stdin is a TTY (no piped input), unless forced with --stdin
+  if (process.stdin.isTTY && !forceStdin) {
+    console.error(
+      'No input file given and stdin is a terminal. ' +
+        'Pass a file, pipe input, or use --stdin to type it.'
+    );
     printHelp();
     process.exit(0);
   }
+  return readStdin();
+}
 
-  if (args.version) {
-    printVersion();
-    process.exit(0);
+// ============================================================================
+// Subcommands
+// ============================================================================
+
+type Command = (argv: string[]) => Promise<void>;
+
+const COMMANDS: Record<string, Command> = {
+  stats: runStats,
+  lint: runLint,
+  fix: runFix,
+  extract: runExtract,
+  embed: runEmbed,
+  convert: runConvert,
+  ast: runAst,
+  export: runExport,
+  import: runImport,
+  anonymize: runAnonymize,
+  lsp: runLsp,
+  daemon: runDaemon,
+};
+
+async function runStats(argv: string[]): Promise<void> {
+  const json = argv.includes('--json');
+  const file = argv.find((arg) => !arg.startsWith('-'));
+  const input = await readInput(file, argv.includes('--stdin'));
+  const stats = getDiagramStats(input);
+
+  if (json) {
+    console.log(JSON.stringify(stats, null, 2));
+    return;
   }
 
-  const options: FormatOptions = {
-    indentSize: args.indent,
-    useTabs: args.tabs,
-  };
+  const rows: [string, string | number][] = [
+    ['diagram type', stats.diagramType],
+    ['statements', stats.statements],
+    ['nodes', stats.nodes],
+    ['edges', stats.edges],
+    ['participants', stats.participants],
+    ['messages', stats.messages],
+    ['max depth', stats.maxDepth],
+    [
+      'longest line',
+      `${stats.longestLine.length} (line ${stats.longestLine.line})`,
+    ],
+  ];
+  for (const [label, value] of rows) {
+    console.log(`${`${label}:`.padEnd(15)}${value}`);
+  }
+}
 
-  let input: string;
+const RULE_LEVEL_FLAGS: Record<string, RuleLevel> = {
+  '--deny': 'deny',
+  '--warn': 'warn',
+  '--allow': 'allow',
+};
 
-  if (args.file) {
-    try {
-      input = readFileSync(args.file, 'utf-8');
-    } catch (_err) {
-      console.error(`Error reading file: ${args.file}`);
+/**
+ * Extract --deny/--warn/--allow <RULE> flags; other arguments are returned.
+ */
+function parseRuleLevels(argv: string[]): {
+  rules: Record<string, RuleLevel>;
+  rest: string[];
+} {
+  const rules: Record<string, RuleLevel> = {};
+  const rest: string[] = [];
+
+  for (let i = 0; i < argv.length; i++) {
+    const [flag, inline] = argv[i].split('=', 2);
+    if (!Object.hasOwn(RULE_LEVEL_FLAGS, flag)) {
+      rest.push(argv[i]);
+      continue;
+    }
+    const ruleId = inline ?? argv[++i];
+    if (!LINT_RULES.some((rule) => rule.id === ruleId)) {
+      console.error(`Unknown lint rule: ${ruleId}`);
       process.exit(1);
     }
+    rules[ruleId] = RULE_LEVEL_FLAGS[flag];
+  }
+
+  return { rules, rest };
+}
+
+async function runLint(argv: string[]): Promise<void> {
+  const { rules, rest } = parseRuleLevels(argv);
+  const file = rest.find((arg) => !arg.startsWith('-'));
+  const input = await readInput(file, rest.includes('--stdin'));
+  const diagnostics = lint(input, { rules });
+
+  if (rest.includes('--json')) {
+    console.log(JSON.stringify(diagnostics, null, 2));
+  } else {
+    const source = file ?? '<stdin>';
+    for (const d of diagnostics) {
+      const { line, column } = d.span.start;
+      console.log(
+        `${source}:${line}:${column}: ${d.severity}[${d.ruleId}]: ${d.message}`
+      );
+    }
+  }
+
+  if (diagnostics.some((d) => d.severity === 'error')) {
+    process.exit(1);
+  }
+}
+
+async function runFix(argv: string[]): Promise<void> {
+  const { rules, rest } = parseRuleLevels(argv);
+  const args = parseArgs(rest);
+  const input = await readInput(args.file, args.stdin);
+  const { output, applied } = fixMermaid(
+    input,
+    { rules },
+    toFormatOptions(args)
+  );
+
+  // Applied fixes go to stderr so stdout stays the fixed diagram
+  const source = args.file ?? '<stdin>';
+  for (const d of applied) {
+    const { line } = d.span.start;
+    console.error(`${source}:${line}: fixed[${d.ruleId}]: ${d.message}`);
+  }
+
+  if (args.write && args.file) {
+    writeFileSync(args.file, output, 'utf-8');
+  } else {
+    process.stdout.write(output);
+  }
+}
+
+/**
+ * Extract a "--name VALUE" or "--name=VALUE" option; other arguments are
+ * returned.
+ */
+function takeOption(
+  argv: string[],
+  name: string
+): { value?: string; rest: string[] } {
+  let value: string | undefined;
+  const rest: string[] = [];
+  for (let i = 0; i < argv.length; i++) {
+    if (argv[i] === name) {
+      value = argv[++i];
+    } else if (argv[i].startsWith(`${name}=`)) {
+      value = argv[i].slice(name.length + 1);
+    } else {
+      rest.push(argv[i]);
+    }
+  }
+  return { value, rest };
+}
+
+async function runExtract(argv: string[]): Promise<void> {
+  const { value: outDir = '.', rest } = takeOption(argv, '--out-dir');
+  const file = rest.find((arg) => !arg.startsWith('-'));
+  const input = await readInput(file, rest.includes('--stdin'));
+  const fences = findMermaidFences(input);
+  const names = fenceFileNames(fences);
+  mkdirSync(outDir, { recursive: true });
+
+  fences.forEach((fence, index) => {
+    const path = join(outDir, `${names[index]}.mmd`);
+    writeFileSync(path, fence.content, 'utf-8');
+    console.log(`${path} (line ${fence.line})`);
+  });
+}
+
+async function runEmbed(argv: string[]): Promise<void> {
+  const { value: dir, rest } = takeOption(argv, '--dir');
+  const args = parseArgs(rest);
+  const options = toFormatOptions(args);
+  const input = await readInput(args.file, args.stdin);
+  const baseDir = args.file ? dirname(args.file) : '.';
+  const names = fenceFileNames(findMermaidFences(input));
+
+  // Include markers win; otherwise match fence names against --dir files
+  const output = replaceMermaidFences(input, (fence, index) => {
+    let path: string;
+    if (fence.include) {
+      path = join(baseDir, fence.include);
+      if (!existsSync(path)) {
+        console.error(`Included file not found: ${path} (line ${fence.line})`);
+        process.exit(1);
+      }
+    } else {
+      if (!dir) return undefined;
+      path = join(dir, `${names[index]}.mmd`);
+      if (!existsSync(path)) return undefined;
+    }
+    return formatMermaid(readFileSync(path, 'utf-8'), options);
+  });
+
+  if (args.write && args.file) {
+    writeFileSync(args.file, output, 'utf-8');
+  } else {
+    process.stdout.write(output);
+  }
+}
+
+async function runConvert(argv: string[]): Promise<void> {
+  const { value: to, rest } = takeOption(argv, '--to');
+  if (!to || !isConversionTarget(to)) {
+    console.error('Usage: mermaidfmt convert --to <flowchart|stateDiagram-v2>');
+    process.exit(1);
+  }
+  const args = parseArgs(rest);
+  const input = await readInput(args.file, args.stdin);
+
+  let output: string;
+  try {
+    // In Markdown, convert only fences of another type that can be converted
+    output = args.file?.endsWith('.md')
+      ? replaceMermaidFences(input, (fence) => {
+          if (detectDiagramType(fence.content) === to) return undefined;
+          try {
+            return convertDiagram(fence.content, to);
+          } catch {
+            return undefined;
+          }
+        })
+      : convertSource(input, to, args.file ?? '<stdin>');
+  } catch (err) {
+    console.error(err instanceof Error ? err.message : err);
+    process.exit(1);
+  }
+
+  if (args.write && args.file) {
+    writeFileSync(args.file, output, 'utf-8');
   } else {
-    // Check if stdin is a TTY (no piped input)
-    if (process.stdin.isTTY) {
-      printHelp();
-      process.exit(0);
+    process.stdout.write(output);
+  }
+}
+
+async function runAst(argv: string[]): Promise<void> {
+  const fromJson = argv.includes('--from-json');
+  const structure = argv.includes('--structure');
+  const args = parseArgs(
+    argv.filter((arg) => arg !== '--from-json' && arg !== '--structure')
+  );
+  const input = await readInput(args.file, args.stdin);
+
+  try {
+    let output: string;
+    if (fromJson) {
+      output = format(diagramFromJSON(input), toFormatOptions(args));
+    } else if (structure) {
+      output = JSON.stringify(diagramStructure(parse(input)), null, 2) + '\n';
+    } else {
+      output = diagramToJSON(parse(input)) + '\n';
+    }
+    process.stdout.write(output);
+  } catch (err) {
+    console.error(err instanceof Error ? err.message : err);
+    process.exit(1);
+  }
+}
+
+async function runExport(argv: string[]): Promise<void> {
+  const { value: to, rest } = takeOption(argv, '--to');
+  if (!to || !isExportFormat(to)) {
+    console.error('Usage: mermaidfmt export --to <d2|dot|plantuml>');
+    process.exit(1);
+  }
+  const args = parseArgs(rest);
+  const input = await readInput(args.file, args.stdin);
+
+  try {
+    process.stdout.write(exportDiagram(parse(input), to));
+  } catch (err) {
+    console.error(err instanceof Error ? err.message : err);
+    process.exit(1);
+  }
+}
+
+async function runImport(argv: string[]): Promise<void> {
+  const { value: from, rest } = takeOption(argv, '--from');
+  if (!from || !isImportFormat(from)) {
+    console.error('Usage: mermaidfmt import --from <dot|plantuml>');
+    process.exit(1);
+  }
+  const args = parseArgs(rest);
+  const input = await readInput(args.file, args.stdin);
+
+  try {
+    const diagram = importDiagram(input, from);
+    process.stdout.write(format(diagram, toFormatOptions(args)));
+  } catch (err) {
+    console.error(err instanceof Error ? err.message : err);
+    process.exit(1);
+  }
+}
+
+async function runAnonymize(argv: string[]): Promise<void> {
+  const { value, rest } = takeOption(argv, '--strategy');
+  const strategy = toAnonymizeStrategy(value ?? 'sequential');
+  const args = parseArgs(rest);
+  const input = await readInput(args.file, args.stdin);
+
+  try {
+    const diagram = anonymizeDiagram(parse(input), { strategy });
+    process.stdout.write(format(diagram, toFormatOptions(args)));
+  } catch (err) {
+    console.error(err instanceof Error ? err.message : err);
+    process.exit(1);
+  }
+}
+
+/**
+ * Convert one diagram, listing lines to review on stderr.
+ */
+function convertSource(
+  input: string,
+  to: ConversionTarget,
+  source: string
+): string {
+  const { output, warnings } = convertDiagramWithWarnings(input, to);
+  for (const { line, message } of warnings) {
+    console.error(`${source}:${line}: warning: ${message}`);
+  }
+  return output;
+}
+
+/**
+ * List lines the formatter keeps as written because it does not model
+ * their syntax.
+ */
+function warnUnknownStatements(input: string, source: string): void {
+  parse(input).statements.forEach((stmt, index) => {
+    if (stmt.type !== 'unknown') return;
+    console.error(
+      `${source}:${index + 1}: warning: unrecognized syntax kept as written`
+    );
+  });
+}
+
+/**
+ * Format stdin to stdout as it arrives, waiting when stdout is full.
+ */
+async function streamStdin(options: FormatOptions): Promise<void> {
+  try {
+    for await (const chunk of formatStream(process.stdin, options)) {
+      if (!process.stdout.write(chunk)) await once(process.stdout, 'drain');
     }
-    input = await readStdin();
+  } catch (err) {
+    console.error(
+      `Error formatting: ${err instanceof Error ? err.message : err}`
+    );
+    process.exit(1);
+  }
+}
+
+async function runLsp(): Promise<void> {
+  await startLanguageServer(process.stdin, process.stdout);
+  process.exit(0);
+}
+
+async function runDaemon(): Promise<void> {
+  await startDaemon(process.stdin, process.stdout);
+}
+
+// ============================================================================
+// Main
+// ============================================================================
+
+async function main(): Promise<void> {
+  const argv = process.argv.slice(2);
+  if (argv.length > 0 && Object.hasOwn(COMMANDS, argv[0])) {
+    await COMMANDS[argv[0]](argv.slice(1));
+    return;
+  }
+
+  const args = parseArgs(argv);
+
+  if (args.help) {
+    printHelp();
+    process.exit(0);
   }
 
+  if (args.version) {
+    printVersion();
+    process.exit(0);
+  }
+
+  const options = toFormatOptions(args);
+
+  if (args.stream) {
+    await streamStdin(options);
+    return;
+  }
+
+  const input = await readInput(args.file, args.stdin);
+
   try {
     const isMarkdown = args.file?.endsWith('.md');
-    const formatted = isMarkdown
-      ? formatMarkdownMermaidBlocks(input, options)
-      : formatMermaid(input, options);
+    let formatted: string;
+    if (args.cursorOffset !== undefined && !isMarkdown) {
+      // Like prettier, report the moved cursor on stderr
+      const result = formatWithCursor(input, args.cursorOffset, options);
+      formatted = result.formatted;
+      console.error(result.cursorOffset);
+    } else if (args.sourceMap && !isMarkdown) {
+      const result = formatWithSourceMap(input, options);
+      formatted = result.formatted;
+      const json = JSON.stringify({ lineMap: result.lineMap });
+      writeFileSync(args.sourceMap, json + '\n', 'utf-8');
+    } else {
+      formatted = isMarkdown
+        ? formatMarkdownMermaidBlocks(input, options)
+        : formatMermaid(input, options);
+    }
+
+    if (!isMarkdown) warnUnknownStatements(input, args.file ?? '<stdin>');
+
+    if (args.check) {
+      if (formatted === input) return;
+      const source = args.file ?? '<stdin>';
+      const { firstDifference } = isMarkdown
+        ? { firstDifference: undefined }
+        : checkFormatted(input, options);
+      const location = firstDifference ? `:${firstDifference.line}` : '';
+      console.error(`${source}${location}: not formatted`);
+      process.exit(1);
+    }
 
     if (args.write && args.file) {
       writeFileSync(args.file, formatted, 'utf-8');
@@ -0,0 +1,148 @@
+/**
+ * Diagram-type migrations (e.g., legacy `graph` to `flowchart`).
+ * Conversions rewrite only the lines that differ; everything else is kept
+ * byte-for-byte so the result can be reviewed as a minimal diff.
+ */
+
+import type { DiagramType } from './types.js';
+import { parse } from './parser.js';
+
+/** Diagram types that can be produced by a conversion */
+export type ConversionTarget = 'flowchart' | 'stateDiagram-v2';
+
+/** A line the conversion left as-is but could not verify */
+export interface ConversionWarning {
+  /** 1-based input line */
+  line: number;
+  message: string;
+}
+
+/** Converted source and the lines that need manual review */
+export interface ConversionResult {
+  output: string;
+  warnings: ConversionWarning[];
+}
+
+interface Conversion {
+  from: DiagramType;
+  /** Rewrite the diagram declaration line */
+  header: (line: string) => string;
+  /** Rewrite a statement line whose meaning differs in the target */
+  line?: (line: string) => string;
+  /** Forms known to mean the same in the target; others are flagged */
+  knownForms?: RegExp[];
+}
+
+/**
+ * A link directly followed by "o" or "x", as in "A---oB": in graph the
+ * node is "oB", but flowchart reads "---o" as a circle edge to "B".
+ * Quoted labels are matched first so they are kept as written.
+ */
+const LEGACY_LINK_PATTERN = /"[^"]*"|(-{3,}|={3,})([ox][^\s|;&])/g;
+
+/**
+ * stateDiagram statements that stateDiagram-v2 reads the same way:
+ * transitions, descriptions, state declarations, concurrency separators,
+ * note ends, direction, and styling.
+ */
+const STATE_FORMS = [
+  /^(?:\[\*\]|[\w.-]+)\s*-->/,
+  /^[\w.-]+\s*:/,
+  /^state\s/,
+  /^--$/,
+  /^end note$/i,
+  /^direction\s/,
+  /^(?:classDef|class|style)\s/,
+  /^[\w.-]+(?::::[\w-]+)?;?$/,
+];
+
+/**
+ * Supported conversions, keyed by target type.
+ */
+const CONVERSIONS: Record<ConversionTarget, Conversion> = {
+  flowchart: {
+    from: 'graph',
+    header: (line) => line.replace(/\bgraph\b/, 'flowchart'),
+    // Keep the node name by separating it from the link
+    line: (line) =>
+      line.replace(
+        LEGACY_LINK_PATTERN,
+        (match, link: string | undefined, node: string) =>
+          link === undefined ? match : `${link} ${node}`
+      ),
+  },
+  'stateDiagram-v2': {
+    from: 'stateDiagram',
+    header: (line) => line.replace(/\bstateDiagram\b/, 'stateDiagram-v2'),
+    knownForms: STATE_FORMS,
+  },
+};
+
+/**
+ * Check whether a string names a conversion target.
+ */
+export function isConversionTarget(value: string): value is ConversionTarget {
+  return Object.hasOwn(CONVERSIONS, value);
+}
+
+/**
+ * Convert a diagram to another diagram type.
+ * Input that already has the target type is returned unchanged.
+ *
+ * @param input - Mermaid diagram source code
+ * @param to - Target diagram type
+ * @returns Converted source
+ * @throws Error when the diagram type cannot be converted to the target
+ */
+export function convertDiagram(input: string, to: ConversionTarget): string {
+  return convertDiagramWithWarnings(input, to).output;
+}
+
+/**
+ * Convert a diagram like `convertDiagram`, also reporting lines that were
+ * kept as-is but are not known to mean the same in the target type.
+ *
+ * @param input - Mermaid diagram source code
+ * @param to - Target diagram type
+ * @returns Converted source and warnings for lines to review by hand
+ * @throws Error when the diagram type cannot be converted to the target
+ */
+export function convertDiagramWithWarnings(
+  input: string,
+  to: ConversionTarget
+): ConversionResult {
+  const diagram = parse(input);
+  if (diagram.type === to) return { output: input, warnings: [] };
+
+  const conversion = CONVERSIONS[to];
+  if (diagram.type !== conversion.from) {
+    throw new Error(`Cannot convert ${diagram.type} to ${to}`);
+  }
+
+  const lines = input.split('\n');
+  const index = diagram.statements.findIndex((s) => s.type === 'diagram-decl');
+  lines[index] = conversion.header(lines[index]);
+
+  const { line, knownForms } = conversion;
+  const warnings: ConversionWarning[] = [];
+  let inNote = false;
+  diagram.statements.forEach((stmt, i) => {
+    // Multi-line note bodies are free text up to "end note"
+    if (stmt.type === 'note' && !stmt.content.includes(':')) inNote = true;
+    if (stmt.type !== 'generic-line' && stmt.type !== 'unknown') return;
+    if (inNote) {
+      inNote = !/^end note$/i.test(stmt.content);
+      return;
+    }
+    if (line) lines[i] = line(lines[i]);
+    if (knownForms && !knownForms.some((form) => form.test(stmt.content))) {
+      warnings.push({
+        line: i + 1,
+        message:
+          `Unrecognized ${conversion.from} statement kept as-is; ` +
+          `check it in ${to}`,
+      });
+    }
+  });
+  return { output: lines.join('\n'), warnings };
+}
@@ -0,0 +1,110 @@
+/**
+ * Long-running formatter daemon speaking newline-delimited JSON over stdio.
+ * For editors without LSP support: one process serves many requests.
+ *
+ * Request:  {"id":1,"method":"format","text":"...","config":{"indentSize":2}}
+ * Response: {"id":1,"result":"..."} or {"id":1,"error":{"code":"...",...}}
+ */
+
+import { createInterface } from 'node:readline';
+import type { Readable, Writable } from 'node:stream';
+import type { FormatOptions } from './types.js';
+import type { LintOptions } from './lint.js';
+import { lint } from './lint.js';
+import {
+  checkFormatted,
+  formatMermaid,
+  formatMarkdownMermaidBlocks,
+} from './index.js';
+
+/** A daemon request */
+export interface DaemonRequest {
+  /** Echoed back in the response */
+  id?: number | string;
+  method: string;
+  text?: string;
+  config?: FormatOptions;
+  /** Treat text as Markdown and format its mermaid fences */
+  markdown?: boolean;
+  /** Lint rule levels for the lint method */
+  rules?: LintOptions['rules'];
+}
+
+/** Error codes returned in `error.code` */
+export type DaemonErrorCode =
+  | 'invalid-json'
+  | 'invalid-request'
+  | 'unknown-method'
+  | 'format-failed';
+
+/** A daemon response: exactly one of result or error is set */
+export interface DaemonResponse {
+  id?: number | string;
+  result?: unknown;
+  error?: { code: DaemonErrorCode; message: string };
+}
+
+const METHODS: Record<string, (request: DaemonRequest) => unknown> = {
+  format: ({ text = '', config, markdown }) =>
+    markdown
+      ? formatMarkdownMermaidBlocks(text, config)
+      : formatMermaid(text, config),
+  check: ({ text = '', config }) => checkFormatted(text, config),
+  lint: ({ text = '', rules }) => lint(text, { rules }),
+};
+
+/**
+ * Handle one request line and return the response object.
+ */
+export function handleDaemonRequest(line: string): DaemonResponse {
+  let request: DaemonRequest;
+  try {
+    request = JSON.parse(line) as DaemonRequest;
+  } catch (err) {
+    return { error: { code: 'invalid-json', message: errorMessage(err) } };
+  }
+
+  if (typeof request !== 'object' || request === null) {
+    return {
+      error: { code: 'invalid-request', message: 'Request must be an object' },
+    };
+  }
+  const { id } = request;
+  if (typeof request.text !== 'string' && request.text !== undefined) {
+    return {
+      id,
+      error: { code: 'invalid-request', message: '"text" must be a string' },
+    };
+  }
+  if (!Object.hasOwn(METHODS, request.method)) {
+    return {
+      id,
+      error: {
+        code: 'unknown-method',
+        message: `Unknown method: ${request.method}`,
+      },
+    };
+  }
+
+  try {
+    return { id, result: METHODS[request.method](request) };
+  } catch (err) {
+    return { id, error: { code: 'format-failed', message: errorMessage(err) } };
+  }
+}
+
+/**
+ * Serve requests line by line until the input ends.
+ */
+export function startDaemon(input: Readable, output: Writable): Promise<void> {
+  const lines = createInterface({ input, crlfDelay: Infinity });
+  lines.on('line', (line) => {
+    if (!line.trim()) return;
+    output.write(JSON.stringify(handleDaemonRequest(line)) + '\n');
+  });
+  return new Promise((resolve) => lines.on('close', resolve));
+}
+
+function errorMessage(err: unknown): string {
+  return err instanceof Error ? err.message : String(err);
+}
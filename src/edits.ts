@@ -0,0 +1,150 @@
+/**
+ * Minimal text edits between two versions of a document, so editors can
+ * apply formatting as small changes that keep the cursor and undo history.
+ */
+
+/** Replace `text.slice(start, end)` with `newText` (string offsets) */
+export interface TextEdit {
+  start: number;
+  end: number;
+  newText: string;
+}
+
+/** Line diffs beyond this many cells fall back to one edit per hunk */
+const MAX_DIFF_CELLS = 4_000_000;
+
+/**
+ * Compute non-overlapping edits, in document order, that turn `before`
+ * into `after`. Unchanged lines are never touched, and each changed run of
+ * lines is trimmed to the characters that differ.
+ *
+ * @example
+ * ```ts
+ * diffEdits('a\n  b\n', 'a\n    b\n');
+ * // [{ start: 4, end: 4, newText: '  ' }]
+ * ```
+ */
+export function diffEdits(before: string, after: string): TextEdit[] {
+  const a = splitLines(before);
+  const b = splitLines(after);
+  const starts = [0];
+  for (const line of a) starts.push(starts[starts.length - 1] + line.length);
+
+  const edits: TextEdit[] = [];
+  for (const [i0, i1, j0, j1] of changedRuns(a, b)) {
+    const edit = trimEdit(
+      a.slice(i0, i1).join(''),
+      b.slice(j0, j1).join(''),
+      starts[i0]
+    );
+    if (edit) edits.push(edit);
+  }
+  return edits;
+}
+
+/**
+ * Apply edits produced by `diffEdits` (sorted, non-overlapping).
+ */
+export function applyEdits(text: string, edits: TextEdit[]): string {
+  let result = '';
+  let pos = 0;
+  for (const edit of edits) {
+    result += text.slice(pos, edit.start) + edit.newText;
+    pos = edit.end;
+  }
+  return result + text.slice(pos);
+}
+
+/**
+ * Split text into lines that keep their "\n", so joining restores it.
+ */
+function splitLines(text: string): string[] {
+  return text.match(/[^\n]*\n|[^\n]+$/g) ?? [];
+}
+
+/**
+ * Runs of differing lines as [aStart, aEnd, bStart, bEnd], from a longest
+ * common subsequence of lines between the common prefix and suffix.
+ */
+function changedRuns(
+  a: string[],
+  b: string[]
+): [number, number, number, number][] {
+  let prefix = 0;
+  while (prefix < a.length && prefix < b.length && a[prefix] === b[prefix]) {
+    prefix++;
+  }
+  let suffix = 0;
+  while (
+    suffix < a.length - prefix &&
+    suffix < b.length - prefix &&
+    a[a.length - 1 - suffix] === b[b.length - 1 - suffix]
+  ) {
+    suffix++;
+  }
+
+  const n = a.length - prefix - suffix;
+  const m = b.length - prefix - suffix;
+  if (n === 0 && m === 0) return [];
+  if (n === 0 || m === 0 || n * m > MAX_DIFF_CELLS) {
+    return [[prefix, prefix + n, prefix, prefix + m]];
+  }
+
+  // lcs[i][j]: common subsequence length of a[prefix+i..] and b[prefix+j..]
+  const lcs = Array.from({ length: n + 1 }, () => new Uint32Array(m + 1));
+  for (let i = n - 1; i >= 0; i--) {
+    for (let j = m - 1; j >= 0; j--) {
+      lcs[i][j] =
+        a[prefix + i] === b[prefix + j]
+          ? lcs[i + 1][j + 1] + 1
+          : Math.max(lcs[i + 1][j], lcs[i][j + 1]);
+    }
+  }
+
+  const runs: [number, number, number, number][] = [];
+  let i = 0;
+  let j = 0;
+  while (i < n || j < m) {
+    if (i < n && j < m && a[prefix + i] === b[prefix + j]) {
+      i++;
+      j++;
+      continue;
+    }
+    const [i0, j0] = [i, j];
+    while (
+      (i < n || j < m) &&
+      !(i < n && j < m && a[prefix + i] === b[prefix + j])
+    ) {
+      if (j >= m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1])) i++;
+      else j++;
+    }
+    runs.push([prefix + i0, prefix + i, prefix + j0, prefix + j]);
+  }
+  return runs;
+}
+
+/**
+ * Shrink a replacement to the span between the common prefix and suffix.
+ */
+function trimEdit(
+  before: string,
+  after: string,
+  offset: number
+): TextEdit | null {
+  if (before === after) return null;
+  let prefix = 0;
+  const max = Math.min(before.length, after.length);
+  while (prefix < max && before[prefix] === after[prefix]) prefix++;
+  let suffix = 0;
+  while (
+    suffix < max - prefix &&
+    before[before.length - 1 - suffix] === after[after.length - 1 - suffix]
+  ) {
+    suffix++;
+  }
+  return {
+    start: offset + prefix,
+    end: offset + before.length - suffix,
+    newText: after.slice(prefix, after.length - suffix),
+  };
+}
@@ -0,0 +1,718 @@
+/**
+ * Exports to other diagram languages, for toolchains where Mermaid is not
+ * available. Each exporter covers the diagram types its target can
+ * represent and throws for the others.
+ */
+
+import type { BlockKind, Diagram, Statement } from './types.js';
+import { diagramStructure } from './structure.js';
+import type { StructureNode } from './structure.js';
+import { CLASS_DECL_PATTERN, matchParticipantDecl } from './rules.js';
+
+/** Languages a diagram can be exported to */
+export type ExportFormat = 'd2' | 'dot' | 'plantuml';
+
+const EXPORTERS: Record<ExportFormat, (diagram: Diagram) => string> = {
+  d2: diagramToD2,
+  dot: diagramToDot,
+  plantuml: diagramToPlantUML,
+};
+
+/**
+ * Check whether a string names a supported export format.
+ */
+export function isExportFormat(value: string): value is ExportFormat {
+  return Object.hasOwn(EXPORTERS, value);
+}
+
+/**
+ * Export a diagram to another diagram language.
+ *
+ * @throws Error when the format cannot represent the diagram type
+ */
+export function exportDiagram(diagram: Diagram, format: ExportFormat): string {
+  return EXPORTERS[format](diagram);
+}
+
+// ============================================================================
+// Flowchart structure
+// ============================================================================
+
+/** A subgraph and what was first defined inside it, in source order */
+interface Cluster {
+  id: string;
+  label: string;
+  items: (Cluster | StructureNode)[];
+}
+
+/**
+ * The direction (TB, BT, LR, or RL) and subgraph tree of a flowchart,
+ * each node placed in the subgraph where it is first mentioned.
+ */
+function flowchartClusters(diagram: Diagram): {
+  direction: string;
+  root: Cluster;
+} {
+  const { nodes } = diagramStructure(diagram);
+  const nodesByLine = new Map<number, StructureNode[]>();
+  for (const node of nodes) {
+    nodesByLine.set(node.line, [...(nodesByLine.get(node.line) ?? []), node]);
+  }
+
+  const root: Cluster = { id: '', label: '', items: [] };
+  const stack = [root];
+  let direction = 'TB';
+  diagram.statements.forEach((stmt, index) => {
+    const current = stack[stack.length - 1];
+    if (stmt.type === 'diagram-decl') {
+      const match = stmt.content.match(/\s(TB|TD|BT|LR|RL)\b/);
+      if (match) direction = match[1] === 'TD' ? 'TB' : match[1];
+    } else if (stmt.type === 'block-start' && stmt.blockKind === 'subgraph') {
+      const cluster = { ...subgraphTitle(stmt.label, index), items: [] };
+      current.items.push(cluster);
+      stack.push(cluster);
+      return;
+    } else if (stmt.type === 'block-end' && stack.length > 1) {
+      stack.pop();
+      return;
+    }
+    current.items.push(...(nodesByLine.get(index + 1) ?? []));
+  });
+  return { direction, root };
+}
+
+/**
+ * ID and title of "subgraph ID [Title]", "subgraph ID", or
+ * "subgraph Some title" (which gets a generated ID).
+ */
+function subgraphTitle(
+  label: string | undefined,
+  index: number
+): { id: string; label: string } {
+  const text = label ?? '';
+  const titled = text.match(/^(\S+)\s*\[(.*)\]$/);
+  if (titled) return { id: titled[1], label: unquote(titled[2]) };
+  if (/^\S+$/.test(text)) return { id: text, label: text };
+  return { id: `subgraph${index + 1}`, label: unquote(text) };
+}
+
+// ============================================================================
+// Graphviz DOT
+// ============================================================================
+
+/** DOT node attributes for each flowchart shape opener */
+const DOT_SHAPES: Record<string, string> = {
+  '[': 'shape=box',
+  '(': 'shape=box, style=rounded',
+  '([': 'shape=box, style=rounded',
+  '[[': 'shape=box, peripheries=2',
+  '[(': 'shape=cylinder',
+  '((': 'shape=circle',
+  '(((': 'shape=doublecircle',
+  '{': 'shape=diamond',
+  '{{': 'shape=hexagon',
+  '>': 'shape=cds',
+  '[/': 'shape=parallelogram',
+  '[\\': 'shape=parallelogram',
+};
+
+/**
+ * Export a flowchart to Graphviz DOT. Nodes keep their labels and shapes,
+ * edges their labels and line styles, and subgraphs become clusters
+ * holding the nodes first mentioned inside them.
+ *
+ * @throws Error for diagrams other than flowchart and graph
+ *
+ * @example
+ * ```ts
+ * diagramToDot(parse('flowchart LR\nA[Start] --> B'));
+ * // 'digraph {\n  rankdir=LR;\n  node [shape=box];\n  "A" [label="Start"...'
+ * ```
+ */
+export function diagramToDot(diagram: Diagram): string {
+  if (diagram.type !== 'flowchart' && diagram.type !== 'graph') {
+    throw new Error(
+      `Cannot export ${diagram.type} to DOT: only flowchart and graph ` +
+        'diagrams are supported'
+    );
+  }
+  const { edges } = diagramStructure(diagram);
+  const { direction, root } = flowchartClusters(diagram);
+  const lines = [
+    'digraph {',
+    `  rankdir=${direction};`,
+    '  node [shape=box];',
+  ];
+  const render = (cluster: Cluster, indent: string) => {
+    for (const item of cluster.items) {
+      if ('items' in item) {
+        lines.push(`${indent}subgraph ${dotString(`cluster_${item.id}`)} {`);
+        lines.push(`${indent}  label=${dotString(item.label)};`);
+        render(item, `${indent}  `);
+        lines.push(`${indent}}`);
+      } else {
+        lines.push(`${indent}${dotString(item.id)}${dotNodeAttributes(item)};`);
+      }
+    }
+  };
+  render(root, '  ');
+
+  for (const edge of edges) {
+    const attributes = dotEdgeAttributes(edge.arrow);
+    if (edge.label) attributes.unshift(`label=${dotString(edge.label)}`);
+    const list = attributes.length > 0 ? ` [${attributes.join(', ')}]` : '';
+    lines.push(`  ${dotString(edge.from)} -> ${dotString(edge.to)}${list};`);
+  }
+  lines.push('}');
+  return lines.join('\n') + '\n';
+}
+
+function dotNodeAttributes(node: StructureNode): string {
+  const attributes: string[] = [];
+  if (node.label !== undefined) {
+    attributes.push(`label=${dotString(node.label)}`);
+  }
+  const shape = node.shape && DOT_SHAPES[node.shape.open];
+  if (shape && shape !== 'shape=box') attributes.push(shape);
+  return attributes.length > 0 ? ` [${attributes.join(', ')}]` : '';
+}
+
+/**
+ * DOT attributes for a flowchart link operator's style and heads.
+ */
+function dotEdgeAttributes(arrow: string): string[] {
+  if (/^~+$/.test(arrow)) return ['style=invis'];
+  const attributes: string[] = [];
+  if (arrow.includes('.')) attributes.push('style=dashed');
+  if (arrow.includes('=')) attributes.push('penwidth=2');
+  const head = arrow[arrow.length - 1];
+  if (head === 'o') attributes.push('arrowhead=odot');
+  else if (head === 'x') attributes.push('arrowhead=tee');
+  else if (head !== '>') attributes.push('arrowhead=none');
+  if (arrow.startsWith('<')) attributes.push('dir=both');
+  return attributes;
+}
+
+/** Quote a DOT string; Mermaid <br> breaks and #quot; are translated */
+function dotString(text: string): string {
+  const escaped = text
+    .replace(/#quot;/g, '"')
+    .replace(/\\/g, '\\\\')
+    .replace(/"/g, '\\"')
+    .replace(/<br\s*\/?>/gi, '\\n');
+  return `"${escaped}"`;
+}
+
+// ============================================================================
+// PlantUML
+// ============================================================================
+
+/** PlantUML arrows for sequence message arrows */
+const PLANTUML_ARROWS: Record<string, string> = {
+  '->>': '->',
+  '-->>': '-->',
+  '->': '->',
+  '-->': '-->',
+  '-x': '->x',
+  '--x': '-->x',
+  '-)': '->>',
+  '--)': '-->>',
+  '<<->>': '<->',
+  '<<-->>': '<-->',
+};
+
+/** PlantUML `left to right direction` and friends for class diagrams */
+const PLANTUML_DIRECTIONS: Record<string, string> = {
+  LR: 'left to right direction',
+  RL: 'left to right direction',
+  TB: 'top to bottom direction',
+  BT: 'top to bottom direction',
+};
+
+/**
+ * Export a sequence or class diagram to PlantUML.
+ *
+ * Sequence diagrams keep participants (with aliases), messages,
+ * activations, notes, and alt/loop/opt/par/critical/break groups; boxes
+ * become PlantUML boxes. Class diagrams keep classes with their members,
+ * annotations as stereotypes, namespaces, and relationships with
+ * cardinalities and labels. Lines without a PlantUML equivalent are kept
+ * as `'` comments so nothing is dropped silently.
+ *
+ * @throws Error for other diagram types
+ */
+export function diagramToPlantUML(diagram: Diagram): string {
+  let body: string[];
+  if (diagram.type === 'sequenceDiagram') {
+    body = plantUMLSequence(diagram.statements);
+  } else if (diagram.type === 'classDiagram') {
+    body = plantUMLClasses(diagram.statements);
+  } else {
+    throw new Error(
+      `Cannot export ${diagram.type} to PlantUML: only sequenceDiagram and ` +
+        'classDiagram are supported'
+    );
+  }
+  return ['@startuml', ...body, '@enduml'].join('\n') + '\n';
+}
+
+function plantUMLSequence(statements: Statement[]): string[] {
+  const lines: string[] = [];
+  // Open blocks; rect has no PlantUML equivalent and adds no indentation
+  const blocks: BlockKind[] = [];
+  const indent = (offset = 0) => {
+    const depth = blocks.filter((kind) => kind !== 'rect').length;
+    return '  '.repeat(Math.max(0, depth + offset));
+  };
+
+  for (const stmt of statements) {
+    switch (stmt.type) {
+      case 'diagram-decl':
+      case 'blank-line':
+        continue;
+      case 'participant': {
+        const decl = matchParticipantDecl(stmt.content);
+        if (!decl) break;
+        const { kind, id, alias } = decl;
+        const name =
+          alias === undefined ? id : `${plantUMLString(alias)} as ${id}`;
+        lines.push(`${indent()}${kind} ${name}`);
+        continue;
+      }
+      case 'autonumber':
+        lines.push(`${indent()}autonumber`);
+        continue;
+      case 'activate':
+      case 'deactivate':
+        lines.push(`${indent()}${stmt.type} ${stmt.participant}`);
+        continue;
+      case 'arrow-message': {
+        const activation = stmt.arrow.match(/[+-]$/)?.[0];
+        const base = activation ? stmt.arrow.slice(0, -1) : stmt.arrow;
+        const arrow = PLANTUML_ARROWS[base] ?? '->';
+        const marker = activation ? ` ${activation}${activation}` : '';
+        const text = stmt.message ? ` : ${plantUMLText(stmt.message)}` : '';
+        lines.push(
+          `${indent()}${stmt.from} ${arrow} ${stmt.to}${marker}${text}`
+        );
+        continue;
+      }
+      case 'note':
+        if (!stmt.position || !stmt.participants) break;
+        lines.push(
+          `${indent()}note ${stmt.position} ${stmt.participants.join(', ')}` +
+            ` : ${plantUMLText(stmt.text ?? '')}`
+        );
+        continue;
+      case 'block-start': {
+        const { blockKind, label = '', color } = stmt;
+        if (blockKind === 'box') {
+          const fill = color ? ` ${plantUMLColor(color)}` : '';
+          lines.push(`${indent()}box ${plantUMLString(label)}${fill}`);
+        } else if (blockKind !== 'rect') {
+          lines.push(`${indent()}${blockKind} ${label}`.trimEnd());
+        }
+        blocks.push(blockKind);
+        continue;
+      }
+      case 'block-else':
+      case 'block-and':
+      case 'block-option': {
+        const label = stmt.label ? ` ${stmt.label}` : '';
+        lines.push(`${indent(-1)}else${label}`);
+        continue;
+      }
+      case 'block-end': {
+        const kind = blocks.pop();
+        if (kind === 'box') lines.push(`${indent()}end box`);
+        else if (kind !== 'rect') lines.push(`${indent()}end`);
+        continue;
+      }
+      case 'comment':
+        lines.push(`${indent()}' ${stmt.content.replace(/^%%\s*/, '')}`);
+        continue;
+    }
+    lines.push(`${indent()}' ${stmt.content}`);
+  }
+  return lines;
+}
+
+function plantUMLClasses(statements: Statement[]): string[] {
+  const stereotypes = classStereotypes(statements);
+  const declared = new Set<string>();
+  for (const stmt of statements) {
+    if (stmt.type === 'brace-block-start' && stmt.blockKind === 'class') {
+      declared.add(stmt.name);
+    } else if (stmt.type === 'generic-line') {
+      const match = stmt.content.match(CLASS_DECL_PATTERN);
+      if (match) declared.add(match[1]);
+    }
+  }
+  const header = (name: string) => {
+    const tags = (stereotypes.get(name) ?? []).map((tag) => ` <<${tag}>>`);
+    return `class ${plantUMLGeneric(name)}${tags.join('')}`;
+  };
+
+  const lines: string[] = [];
+  let depth = 0;
+  const indent = () => '  '.repeat(depth);
+  // Whether each open brace block is a class body
+  const bodies: boolean[] = [];
+
+  for (const stmt of statements) {
+    switch (stmt.type) {
+      case 'diagram-decl':
+      case 'blank-line':
+        continue;
+      case 'brace-block-start':
+        if (stmt.blockKind === 'class') {
+          lines.push(`${indent()}${header(stmt.name)} {`);
+        } else {
+          lines.push(`${indent()}${stmt.blockKind} ${stmt.name} {`);
+        }
+        bodies.push(stmt.blockKind === 'class');
+        depth++;
+        continue;
+      case 'brace-block-end':
+        bodies.pop();
+        depth = Math.max(0, depth - 1);
+        lines.push(`${indent()}}`);
+        continue;
+      case 'annotation':
+        // Stereotypes are written on the class declaration
+        if (stmt.target && !declared.has(stmt.target)) {
+          declared.add(stmt.target);
+          lines.push(`${indent()}${header(stmt.target)}`);
+        }
+        continue;
+      case 'class-relation': {
+        const { from, to, arrow, label } = stmt;
+        const parts = [
+          plantUMLGeneric(from),
+          stmt.fromCardinality === undefined ? '' : `"${stmt.fromCardinality}"`,
+          arrow,
+          stmt.toCardinality === undefined ? '' : `"${stmt.toCardinality}"`,
+          plantUMLGeneric(to),
+        ];
+        const text = label ? ` : ${label}` : '';
+        lines.push(`${indent()}${parts.filter(Boolean).join(' ')}${text}`);
+        continue;
+      }
+      case 'comment':
+        lines.push(`${indent()}' ${stmt.content.replace(/^%%\s*/, '')}`);
+        continue;
+      case 'generic-line': {
+        const line = plantUMLClassLine(stmt.content, bodies.at(-1), header);
+        if (line === null) break;
+        lines.push(`${indent()}${line}`);
+        continue;
+      }
+    }
+    lines.push(`${indent()}' ${stmt.content}`);
+  }
+  return lines;
+}
+
+/**
+ * A class diagram line other than relations and blocks: members, class
+ * declarations, "A : member" lines, notes, and direction.
+ */
+function plantUMLClassLine(
+  content: string,
+  inBody: boolean | undefined,
+  header: (name: string) => string
+): string | null {
+  if (inBody) return plantUMLGeneric(content);
+  const decl = content.match(/^class\s+([\w-]+(?:~[^~]+~)?)\s*$/);
+  if (decl) return header(decl[1]);
+  const member = content.match(/^([\w-]+)\s*:\s*(.+)$/);
+  if (member) return `${member[1]} : ${plantUMLGeneric(member[2])}`;
+  const note = content.match(/^note\s+for\s+([\w-]+)\s+"(.*)"$/);
+  if (note) return `note right of ${note[1]} : ${plantUMLText(note[2])}`;
+  const direction = content.match(/^direction\s+(TB|BT|LR|RL)$/);
+  if (direction) return PLANTUML_DIRECTIONS[direction[1]];
+  return null;
+}
+
+/**
+ * Stereotypes of each class, from "<<interface>> A" lines and
+ * annotations inside class bodies.
+ */
+function classStereotypes(statements: Statement[]): Map<string, string[]> {
+  const stereotypes = new Map<string, string[]>();
+  const add = (name: string, tag: string) => {
+    stereotypes.set(name, [...(stereotypes.get(name) ?? []), tag]);
+  };
+  const classes: (string | null)[] = [];
+  for (const stmt of statements) {
+    if (stmt.type === 'brace-block-start') {
+      classes.push(stmt.blockKind === 'class' ? stmt.name : null);
+    } else if (stmt.type === 'brace-block-end') {
+      classes.pop();
+    } else if (stmt.type === 'annotation') {
+      const target = stmt.target ?? classes.at(-1);
+      if (target) add(target, stmt.name);
+    }
+  }
+  return stereotypes;
+}
+
+/** Mermaid generics ("List~int~") in PlantUML form ("List<int>") */
+function plantUMLGeneric(text: string): string {
+  return text.replace(/~([^~]+)~/g, '<$1>');
+}
+
+/** Message and note text with Mermaid <br> breaks as PlantUML \n */
+function plantUMLText(text: string): string {
+  return text.replace(/<br\s*\/?>/gi, '\\n');
+}
+
+function plantUMLString(text: string): string {
+  return '"' + text.replace(/"/g, '\\"') + '"';
+}
+
+/** PlantUML colors are "#name" or "#rrggbb" */
+function plantUMLColor(color: string): string {
+  const rgb = color.match(/^rgba?\((\d+),\s*(\d+),\s*(\d+)/);
+  if (!rgb) return color.startsWith('#') ? color : `#${color}`;
+  const hex = rgb
+    .slice(1, 4)
+    .map((n) => Number(n).toString(16).padStart(2, '0'));
+  return `#${hex.join('')}`;
+}
+
+// ============================================================================
+// D2
+// ============================================================================
+
+/** D2 shape for each flowchart shape opener; absent ones are rectangles */
+const D2_SHAPES: Record<string, string> = {
+  '([': 'shape: oval',
+  '((': 'shape: circle',
+  '(((': 'shape: circle; style.double-border: true',
+  '(': 'style.border-radius: 8',
+  '[[': 'style.double-border: true',
+  '[(': 'shape: cylinder',
+  '{': 'shape: diamond',
+  '{{': 'shape: hexagon',
+  '[/': 'shape: parallelogram',
+  '[\\': 'shape: parallelogram',
+};
+
+/** D2 directions for flowchart directions */
+const D2_DIRECTIONS: Record<string, string> = {
+  TB: 'down',
+  BT: 'up',
+  LR: 'right',
+  RL: 'left',
+};
+
+/**
+ * Export a flowchart or sequence diagram to D2.
+ *
+ * Flowchart nodes keep their labels and shapes, edges their labels and
+ * line styles, and subgraphs become containers; edges are written at the
+ * top level with container paths such as `work.C`. Sequence diagrams
+ * become a `shape: sequence_diagram` with actors, messages, notes, and
+ * groups for alt/loop/opt/par/critical/break blocks and their branches.
+ * Lines without a D2 equivalent are kept as `#` comments.
+ *
+ * @throws Error for other diagram types
+ *
+ * @example
+ * ```ts
+ * diagramToD2(parse('flowchart LR\nA[Start] --> B'));
+ * // "direction: right\nA: Start\nB\nA -> B\n"
+ * ```
+ */
+export function diagramToD2(diagram: Diagram): string {
+  let lines: string[];
+  if (diagram.type === 'flowchart' || diagram.type === 'graph') {
+    lines = d2Flowchart(diagram);
+  } else if (diagram.type === 'sequenceDiagram') {
+    lines = d2Sequence(diagram.statements);
+  } else {
+    throw new Error(
+      `Cannot export ${diagram.type} to D2: only flowchart, graph, and ` +
+        'sequenceDiagram are supported'
+    );
+  }
+  return lines.join('\n') + '\n';
+}
+
+function d2Flowchart(diagram: Diagram): string[] {
+  const { edges } = diagramStructure(diagram);
+  const { direction, root } = flowchartClusters(diagram);
+  const lines = [`direction: ${D2_DIRECTIONS[direction]}`];
+  // Container path of each node, for edges written at the top level
+  const paths = new Map<string, string>();
+
+  const render = (cluster: Cluster, indent: string, prefix: string) => {
+    for (const item of cluster.items) {
+      const key = d2Key(item.id);
+      if ('items' in item) {
+        const label = item.label === item.id ? '' : ` ${d2String(item.label)}`;
+        lines.push(`${indent}${key}:${label} {`);
+        render(item, `${indent}  `, `${prefix}${key}.`);
+        lines.push(`${indent}}`);
+        continue;
+      }
+      paths.set(item.id, `${prefix}${key}`);
+      const shape = item.shape && D2_SHAPES[item.shape.open];
+      const label = item.label === undefined ? '' : ` ${d2String(item.label)}`;
+      const map = shape ? ` {${shape}}` : '';
+      lines.push(`${indent}${key}${label || map ? ':' : ''}${label}${map}`);
+    }
+  };
+  render(root, '', '');
+
+  for (const edge of edges) {
+    const from = paths.get(edge.from) ?? d2Key(edge.from);
+    const to = paths.get(edge.to) ?? d2Key(edge.to);
+    const { connection, style } = d2Connection(edge.arrow);
+    const label = edge.label ? ` ${d2String(edge.label)}` : '';
+    const map = style.length > 0 ? ` {${style.join('; ')}}` : '';
+    const value = label || map ? `:${label}${map}` : '';
+    lines.push(`${from} ${connection} ${to}${value}`);
+  }
+  return lines;
+}
+
+/**
+ * D2 connection operator and style for a flowchart link operator.
+ */
+function d2Connection(arrow: string): {
+  connection: string;
+  style: string[];
+} {
+  if (/^~+$/.test(arrow)) {
+    return { connection: '--', style: ['style.opacity: 0'] };
+  }
+  const style: string[] = [];
+  if (arrow.includes('.')) style.push('style.stroke-dash: 3');
+  if (arrow.includes('=')) style.push('style.stroke-width: 4');
+  const head = arrow[arrow.length - 1];
+  const tail = arrow[0];
+  const headed = head === '>' || head === 'o' || head === 'x';
+  const both = headed && (tail === '<' || tail === 'o' || tail === 'x');
+  if (head === 'o') style.push('target-arrowhead.shape: circle');
+  else if (head === 'x') style.push('target-arrowhead.shape: cross');
+  if (both && tail === 'o') style.push('source-arrowhead.shape: circle');
+  else if (both && tail === 'x') style.push('source-arrowhead.shape: cross');
+  const connection = both ? '<->' : headed ? '->' : '--';
+  return { connection, style };
+}
+
+function d2Sequence(statements: Statement[]): string[] {
+  const lines = ['shape: sequence_diagram'];
+  // Open D2 groups; rect and box wrappers open none
+  const groups: (BlockKind | null)[] = [];
+  const counts = new Map<string, number>();
+  const indent = (offset = 0) => {
+    const depth = groups.filter((group) => group !== null).length;
+    return '  '.repeat(Math.max(0, depth + offset));
+  };
+  // Group keys must be unique within their container
+  const group = (kind: string, label: string | undefined) => {
+    const count = (counts.get(kind) ?? 0) + 1;
+    counts.set(kind, count);
+    const title = label ? `${kind} ${label}` : kind;
+    return `${kind}${count}: ${d2String(title)} {`;
+  };
+
+  for (const stmt of statements) {
+    switch (stmt.type) {
+      case 'diagram-decl':
+      case 'blank-line':
+        continue;
+      case 'participant': {
+        const decl = matchParticipantDecl(stmt.content);
+        if (!decl) break;
+        const key = d2Key(decl.id);
+        const { alias } = decl;
+        const label = alias === undefined ? '' : ` ${d2String(alias)}`;
+        const shape = decl.kind === 'actor' ? ' {shape: person}' : '';
+        const value = label || shape ? `:${label}${shape}` : '';
+        lines.push(`${indent()}${key}${value}`);
+        continue;
+      }
+      case 'arrow-message': {
+        const base = stmt.arrow.replace(/[+-]$/, '');
+        const connection = base.startsWith('<<') ? '<->' : '->';
+        const dashed = base.replace(/^<</, '').startsWith('--');
+        const label = stmt.message ? ` ${d2String(stmt.message)}` : '';
+        const map = dashed ? ' {style.stroke-dash: 3}' : '';
+        const value = label || map ? `:${label}${map}` : '';
+        const from = d2Key(stmt.from);
+        const to = d2Key(stmt.to);
+        lines.push(`${indent()}${from} ${connection} ${to}${value}`);
+        continue;
+      }
+      case 'note': {
+        // D2 notes belong to one actor
+        const target = stmt.participants?.[0];
+        if (!target) break;
+        const count = (counts.get('note') ?? 0) + 1;
+        counts.set('note', count);
+        const text = d2String(stmt.text ?? '');
+        lines.push(`${indent()}${d2Key(target)}.note${count}: ${text}`);
+        continue;
+      }
+      case 'block-start': {
+        const { blockKind, label } = stmt;
+        if (blockKind === 'rect' || blockKind === 'box') {
+          if (blockKind === 'box' && label) {
+            lines.push(`${indent()}# box ${label}`);
+          }
+          groups.push(null);
+          continue;
+        }
+        lines.push(`${indent()}${group(blockKind, label)}`);
+        groups.push(blockKind);
+        continue;
+      }
+      case 'block-else':
+      case 'block-and':
+      case 'block-option': {
+        // A branch closes the group so far and opens a sibling group
+        const kind = stmt.type.slice('block-'.length);
+        lines.push(`${indent(-1)}}`);
+        lines.push(`${indent(-1)}${group(kind, stmt.label)}`);
+        continue;
+      }
+      case 'block-end':
+        if (groups.pop()) lines.push(`${indent()}}`);
+        continue;
+      case 'comment':
+        lines.push(`${indent()}# ${stmt.content.replace(/^%%\s*/, '')}`);
+        continue;
+    }
+    lines.push(`${indent()}# ${stmt.content}`);
+  }
+  return lines;
+}
+
+/** A D2 key, quoted unless it is a plain identifier */
+function d2Key(id: string): string {
+  return /^[\w-]+$/.test(id) ? id : d2String(id);
+}
+
+/**
+ * A D2 label, quoted when it has D2 syntax characters; Mermaid <br>
+ * breaks and #quot; are translated.
+ */
+function d2String(text: string): string {
+  const value = text.replace(/#quot;/g, '"');
+  if (/^[\w .,!?()'-]*$/.test(value) && value.trim() === value && value) {
+    return value;
+  }
+  const escaped = value
+    .replace(/\\/g, '\\\\')
+    .replace(/"/g, '\\"')
+    .replace(/<br\s*\/?>/gi, '\\n');
+  return '"' + escaped + '"';
+}
+
+function unquote(text: string): string {
+  return text.replace(/^"(.*)"$/, '$1');
+}
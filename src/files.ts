@@ -0,0 +1,76 @@
+/**
+ * Batch formatting of files on disk for build-tool integrations, so they
+ * do not reimplement reading, per-file options, and writing.
+ *
+ * Exposed as the `mermaid-formatter/files` entry point, since it needs
+ * Node.js file system access.
+ */
+
+import { readFileSync, writeFileSync } from 'node:fs';
+import type { FormatOptions } from './types.js';
+import { formatMarkdownMermaidBlocks, formatMermaid } from './index.js';
+
+/** How `formatFiles` treats each path */
+export interface FormatFilesOptions {
+  /** Write changed files back in place (default: false) */
+  write?: boolean;
+  /**
+   * Options for one path, merged over the shared options; e.g. to apply
+   * per-directory settings.
+   */
+  resolveOptions?: (path: string) => FormatOptions;
+}
+
+/** Outcome of formatting one file */
+export interface FileResult {
+  path: string;
+  /** Whether formatting changes the file's content */
+  changed: boolean;
+  /** Whether the file was rewritten */
+  written: boolean;
+  /** Formatted content; absent when the file could not be processed */
+  output?: string;
+  /** Why reading, formatting, or writing failed */
+  error?: string;
+}
+
+/**
+ * Format files, Markdown (`.md`) fences included, and report on each.
+ * A failing file is reported in its result and does not stop the batch.
+ *
+ * @param paths - Files to format
+ * @param options - Formatting options shared by all files
+ * @param batch - Writing and per-path option resolution
+ * @returns One result per path, in order
+ *
+ * @example
+ * ```ts
+ * const results = formatFiles(['a.mmd', 'README.md'], {}, { write: true });
+ * const failed = results.filter((result) => result.error);
+ * ```
+ */
+export function formatFiles(
+  paths: string[],
+  options: FormatOptions = {},
+  batch: FormatFilesOptions = {}
+): FileResult[] {
+  return paths.map((path) => {
+    const result: FileResult = { path, changed: false, written: false };
+    try {
+      const input = readFileSync(path, 'utf-8');
+      const fileOptions = { ...options, ...batch.resolveOptions?.(path) };
+      const output = path.endsWith('.md')
+        ? formatMarkdownMermaidBlocks(input, fileOptions)
+        : formatMermaid(input, fileOptions);
+      result.output = output;
+      result.changed = output !== input;
+      if (batch.write && result.changed) {
+        writeFileSync(path, output, 'utf-8');
+        result.written = true;
+      }
+    } catch (err) {
+      result.error = err instanceof Error ? err.message : String(err);
+    }
+    return result;
+  });
+}
@@ -0,0 +1,321 @@
+/**
+ * Structural parsing of flowchart statement lines.
+ * Splits "A[Start] & B --> |yes| C" into node groups and links.
+ */
+
+import type {
+  FlowchartChain,
+  FlowchartLink,
+  FlowchartNode,
+  Statement,
+} from './types.js';
+import { parseAttributes } from './rules.js';
+
+/**
+ * Node shape delimiters, longest openers first.
+ * Each opener lists the closers it accepts (trapezoids mix slashes).
+ */
+const SHAPE_DELIMITERS: [string, string[]][] = [
+  ['(((', [')))']],
+  ['((', ['))']],
+  ['([', ['])']],
+  ['[[', [']]']],
+  ['[(', [')]']],
+  ['[/', ['/]', '\\]']],
+  ['[\\', ['\\]', '/]']],
+  ['{{', ['}}']],
+  ['(', [')']],
+  ['[', [']']],
+  ['{', ['}']],
+  ['>', [']']],
+];
+
+/** Characters allowed in node IDs (hyphens handled separately) */
+const ID_CHAR = /[^\s\-=~.<>&|[\](){}:;,"'@]/;
+
+/**
+ * Link operators without inline text, e.g. -->, ---, -.->, ==>, ~~~, <-->, --x.
+ */
+const LINK_PATTERN =
+  /^<?(?:-{2,}>|-{3,}|-{2,}[ox](?=[\s|]|$)|={2,}>|={3,}|={2,}[ox](?=[\s|]|$)|-\.+->?|~{3,})/;
+
+/**
+ * Link operators with inline text, e.g. "-- text -->" or "-. text .->".
+ */
+const TEXT_LINK_PATTERN =
+  /^(<?(?:--|==|-\.))(?![-=.>])\s*(.+?)\s*(-{2,}[>ox]?|={2,}[>ox]?|\.-+>?)(?=[\s\w"'[({]|$)/;
+
+/**
+ * Parse a flowchart statement line into node groups and links.
+ * Returns null when the line is not a node/edge statement (keywords,
+ * style statements, or syntax this parser does not model).
+ */
+export function parseFlowchartLine(line: string): FlowchartChain | null {
+  const text = line.trim().replace(/;$/, '');
+  if (!text) return null;
+
+  const groups: FlowchartNode[][] = [];
+  const links: FlowchartLink[] = [];
+  let pos = 0;
+
+  const skipSpaces = () => {
+    while (pos < text.length && /\s/.test(text[pos])) pos++;
+  };
+
+  for (;;) {
+    // A group of nodes joined by '&'
+    const group: FlowchartNode[] = [];
+    for (;;) {
+      skipSpaces();
+      const node = readNode(text, pos);
+      if (!node) return null;
+      group.push(node.node);
+      pos = node.end;
+      skipSpaces();
+      if (text[pos] !== '&') break;
+      pos++;
+    }
+    groups.push(group);
+
+    if (pos >= text.length) break;
+
+    const link = readLink(text, pos);
+    if (!link) return null;
+    links.push(link.link);
+    pos = link.end;
+  }
+
+  return { groups, links };
+}
+
+/**
+ * Read a node reference: ID, optional shape, attribute map, and class.
+ */
+/** Last chain parsed for each statement and the text it was parsed from */
+const STATEMENT_CHAINS = new WeakMap<
+  Statement,
+  { line: string; chain: FlowchartChain | null }
+>();
+
+/**
+ * `parseFlowchartLine` of a statement's text (default: its content),
+ * remembered per statement, so the parser's check and the formatter's
+ * passes over an unchanged line parse it once. The chain is shared, so
+ * callers must not modify it.
+ */
+export function statementChain(
+  stmt: Statement,
+  line: string = stmt.content
+): FlowchartChain | null {
+  const cached = STATEMENT_CHAINS.get(stmt);
+  if (cached?.line === line) return cached.chain;
+  const chain = parseFlowchartLine(line);
+  STATEMENT_CHAINS.set(stmt, { line, chain });
+  return chain;
+}
+
+function readNode(
+  text: string,
+  start: number
+): { node: FlowchartNode; end: number } | null {
+  let pos = start;
+  while (pos < text.length) {
+    const ch = text[pos];
+    // Hyphens belong to the ID only when followed by another ID character
+    if (ch === '-' && pos > start && ID_CHAR.test(text[pos + 1] ?? '')) {
+      pos++;
+      continue;
+    }
+    if (!ID_CHAR.test(ch)) break;
+    pos++;
+  }
+  if (pos === start) return null;
+
+  const node: FlowchartNode = { id: text.slice(start, pos) };
+
+  const shape = readShape(text, pos);
+  if (shape) {
+    node.shape = shape.shape;
+    pos = shape.end;
+  } else if (text.startsWith('@{', pos)) {
+    const close = findClosing(text, pos + 2, '}');
+    if (close < 0) return null;
+    node.attributes = text.slice(pos + 2, close).trim();
+    pos = close + 1;
+  }
+
+  const classMatch = text.slice(pos).match(/^:::([\w-]+)/);
+  if (classMatch) {
+    node.className = classMatch[1];
+    pos += classMatch[0].length;
+  }
+
+  const { icon, image } = nodeImagery(node);
+  if (icon) node.icon = icon;
+  if (image) node.image = image;
+
+  return { node, end: pos };
+}
+
+/** Leading FontAwesome token of a label, e.g. "fa:fa-car" or "fab:fa-x" */
+const LABEL_ICON_PATTERN = /^"?(fa[a-z]?:fa-[\w-]+)/;
+
+/**
+ * Icon and image of a node: a label starting with an icon token, or the
+ * `icon` and `img` attributes of an attribute map.
+ */
+export function nodeImagery(node: FlowchartNode): {
+  icon?: string;
+  image?: string;
+} {
+  if (node.shape) {
+    const match = node.shape.text.trim().match(LABEL_ICON_PATTERN);
+    return match ? { icon: match[1] } : {};
+  }
+  if (!node.attributes) return {};
+  const attributes = parseAttributes(node.attributes) ?? [];
+  const value = (key: string) => {
+    const attribute = attributes.find((a) => a.key === key);
+    return attribute?.value.replace(/^"(.*)"$/, '$1');
+  };
+  return { icon: value('icon'), image: value('img') };
+}
+
+/**
+ * Read a shape suffix such as [text], ((text)), or {{text}}.
+ */
+function readShape(
+  text: string,
+  start: number
+): { shape: NonNullable<FlowchartNode['shape']>; end: number } | null {
+  for (const [open, closers] of SHAPE_DELIMITERS) {
+    if (!text.startsWith(open, start)) continue;
+    const inner = start + open.length;
+    for (const close of closers) {
+      const end = findClosing(text, inner, close);
+      if (end < 0) continue;
+      return {
+        shape: { open, text: text.slice(inner, end), close },
+        end: end + close.length,
+      };
+    }
+    return null;
+  }
+  return null;
+}
+
+/**
+ * Find the next occurrence of a closing delimiter outside double quotes.
+ */
+function findClosing(text: string, start: number, close: string): number {
+  let inQuote = false;
+  for (let i = start; i < text.length; i++) {
+    if (text[i] === '"') inQuote = !inQuote;
+    else if (!inQuote && text.startsWith(close, i)) return i;
+  }
+  return -1;
+}
+
+/**
+ * Read a link: optional edge ID, operator, and optional |label|.
+ */
+function readLink(
+  text: string,
+  start: number
+): { link: FlowchartLink; end: number } | null {
+  let pos = start;
+  const link: FlowchartLink = { arrow: '' };
+
+  const edgeId = text.slice(pos).match(/^([\w-]+)@(?=[<\-=~])/);
+  if (edgeId) {
+    link.edgeId = edgeId[1];
+    pos += edgeId[0].length;
+  }
+
+  const rest = text.slice(pos);
+  const textLink = rest.match(TEXT_LINK_PATTERN);
+  const plain = rest.match(LINK_PATTERN);
+  if (plain) {
+    link.arrow = plain[0];
+    pos += plain[0].length;
+  } else if (textLink) {
+    link.inlineOpen = textLink[1];
+    link.label = textLink[2];
+    link.arrow = textLink[3];
+    pos += textLink[0].length;
+  } else {
+    return null;
+  }
+
+  const pipe = text.slice(pos).match(/^\s*\|([^|]*)\|/);
+  if (pipe) {
+    link.label = pipe[1].trim();
+    pos += pipe[0].length;
+  }
+
+  return { link, end: pos };
+}
+
+/**
+ * Collect node IDs referenced by a chain, in order of appearance.
+ */
+export function chainNodeIds(chain: FlowchartChain): string[] {
+  return chain.groups.flatMap((group) => group.map((node) => node.id));
+}
+
+/**
+ * Count the edges a chain produces ("A & B --> C" is two edges).
+ */
+export function chainEdgeCount(chain: FlowchartChain): number {
+  let count = 0;
+  for (let i = 0; i < chain.links.length; i++) {
+    count += chain.groups[i].length * chain.groups[i + 1].length;
+  }
+  return count;
+}
+
+/**
+ * Render a node reference back to source, e.g. "A[Start]:::done".
+ */
+export function formatFlowchartNode(node: FlowchartNode): string {
+  let text = node.id;
+  if (node.shape) {
+    text += node.shape.open + node.shape.text + node.shape.close;
+  } else if (node.attributes !== undefined) {
+    text += node.attributes ? `@{ ${node.attributes} }` : '@{}';
+  }
+  if (node.className) text += `:::${node.className}`;
+  return text;
+}
+
+/**
+ * Render a node group, e.g. "A & B".
+ */
+export function formatFlowchartGroup(group: FlowchartNode[]): string {
+  return group.map(formatFlowchartNode).join(' & ');
+}
+
+/**
+ * Render a chain with single spaces around each link, e.g. "A --> B & C".
+ */
+export function formatFlowchartChain(chain: FlowchartChain): string {
+  return chain.groups
+    .map((group, i) => {
+      const link = chain.links[i];
+      const text = formatFlowchartGroup(group);
+      return link ? `${text} ${formatFlowchartLink(link)}` : text;
+    })
+    .join(' ');
+}
+
+/**
+ * Render a link back to source, e.g. "e1@-->|yes|" or "-- text -->".
+ */
+export function formatFlowchartLink(link: FlowchartLink): string {
+  const edgeId = link.edgeId ? `${link.edgeId}@` : '';
+  if (link.inlineOpen) {
+    return `${edgeId}${link.inlineOpen} ${link.label} ${link.arrow}`;
+  }
+  const label = link.label === undefined ? '' : `|${link.label}|`;
+  return `${edgeId}${link.arrow}${label}`;
+}
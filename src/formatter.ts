@@ -4,14 +4,58 @@
  */
 
 import type {
+  AnnotationStatement,
+  Attribute,
+  AttributeMapStatement,
+  ClassRelationStatement,
   Diagram,
+  DiagramType,
   Statement,
   StatementType,
   FormatOptions,
+  ParticipantOrder,
+  StyleLevel,
   ArrowMessageStatement,
+  ActivateStatement,
+  DeactivateStatement,
+  NoteStatement,
   BlockStartStatement,
   BraceBlockStartStatement,
+  LinkStatement,
+  StateStereotypeStatement,
+  GanttSettingStatement,
+  TimelineEventStatement,
+  EdgeLabelWrap,
+  FlowchartChain,
+  FlowchartLink,
+  FlowchartNode,
+  TraceEvent,
 } from './types.js';
+import {
+  CLASS_VISIBILITIES,
+  EDGE_ID_PATTERN,
+  isDeclarableParticipant,
+  isIndentSensitive,
+  isMindmapDeclaration,
+  matchClassMember,
+  matchErAttribute,
+  matchGanttTask,
+  matchParticipantDecl,
+  matchParticipantId,
+  matchQuadrantPoint,
+  matchRequirementRelation,
+  parseAttributes,
+  STYLE_STATEMENT_PATTERN,
+} from './rules.js';
+import type { QuadrantPoint } from './rules.js';
+import { displayWidth, padEndToWidth } from './width.js';
+import {
+  formatFlowchartChain,
+  formatFlowchartGroup,
+  formatFlowchartNode,
+  formatFlowchartLink,
+  statementChain,
+} from './flowchart.js';
 
 // ============================================================================
 // Configuration
@@ -20,22 +64,143 @@ import type {
 const DEFAULT_OPTIONS: Required<FormatOptions> = {
   indentSize: 4,
   useTabs: false,
+  indentTopLevel: true,
+  styleLevel: 'standard',
+  lossless: false,
+  alignMessages: false,
+  alignArrows: false,
+  alignEdgeLabels: false,
+  alignErAttributes: false,
+  alignGanttTasks: false,
+  alignQuadrantPoints: false,
+  maxWidth: 80,
+  groupParticipants: false,
+  participantOrder: 'source',
+  declareParticipants: false,
+  sortClassMembers: false,
+  alphabetizeClassMembers: false,
+  groupStyleStatements: false,
+  groupGanttSettings: false,
+  reindentMindmaps: false,
+  spaceArrows: false,
+  dedupeNodeLabels: false,
+  wrapEdgeLabels: 'off',
+  blankLineBetweenSections: false,
+  expandChains: false,
+  hoistDirectives: false,
+  statementHooks: [],
+  limits: {},
+  trace: null,
 };
 
+/** Options that rewrite, reorder, or add statements, as lossless sets them */
+const LOSSLESS_OPTIONS: FormatOptions = {
+  alignMessages: false,
+  alignArrows: false,
+  alignEdgeLabels: false,
+  alignErAttributes: false,
+  alignGanttTasks: false,
+  alignQuadrantPoints: false,
+  groupParticipants: false,
+  declareParticipants: false,
+  sortClassMembers: false,
+  groupStyleStatements: false,
+  groupGanttSettings: false,
+  spaceArrows: false,
+  dedupeNodeLabels: false,
+  wrapEdgeLabels: 'off',
+  expandChains: false,
+  hoistDirectives: false,
+  statementHooks: [],
+};
+
+/** Defaults each style level puts over `DEFAULT_OPTIONS` */
+const STYLE_LEVEL_OPTIONS: Record<StyleLevel, FormatOptions> = {
+  conservative: { lossless: true },
+  standard: {},
+  aggressive: {
+    alignMessages: true,
+    alignArrows: true,
+    alignEdgeLabels: true,
+    alignErAttributes: true,
+    alignGanttTasks: true,
+    alignQuadrantPoints: true,
+    sortClassMembers: true,
+    spaceArrows: true,
+  },
+};
+
+/** Options with defaults filled in and lossless mode applied */
+export function resolveOptions(
+  options: FormatOptions
+): Required<FormatOptions> {
+  const level = options.styleLevel ?? DEFAULT_OPTIONS.styleLevel;
+  const opts = {
+    ...DEFAULT_OPTIONS,
+    ...STYLE_LEVEL_OPTIONS[level],
+    ...options,
+  };
+  return opts.lossless ? { ...opts, ...LOSSLESS_OPTIONS } : opts;
+}
+
 // ============================================================================
 // Statement Formatters - Strategy pattern
 // ============================================================================
 
-type StatementFormatter = (stmt: Statement) => string;
+type StatementFormatter = (
+  stmt: Statement,
+  diagramType: DiagramType
+) => string;
 
 const STATEMENT_FORMATTERS: Partial<Record<StatementType, StatementFormatter>> =
   {
     'block-start': (stmt) => {
       const s = stmt as BlockStartStatement;
-      return s.label ? `${s.blockKind} ${s.label}` : s.blockKind;
+      if (s.blockKind === 'block') {
+        return s.label ? `block:${s.label}` : 'block';
+      }
+      let text: string = s.blockKind;
+      if (s.color) text += ` ${formatColor(s.color)}`;
+      if (s.label) text += ` ${s.label}`;
+      return text;
+    },
+    // The payload is kept as written: JSON spacing is the author's
+    link: (stmt) => {
+      const s = stmt as LinkStatement;
+      const base = `${s.keyword} ${s.participant}:`;
+      return s.payload ? `${base} ${s.payload}` : base;
+    },
+    'state-stereotype': (stmt) => {
+      const s = stmt as StateStereotypeStatement;
+      return `state ${s.name} <<${s.stereotype}>>`;
+    },
+    // Quoted aliases are kept as written, spacing included
+    participant: (stmt) => {
+      const decl = matchParticipantDecl(stmt.content);
+      if (!decl) return normalizeContent(stmt.content);
+      const { kind, id, alias, aliasQuoted } = decl;
+      if (alias === undefined) return `${kind} ${id}`;
+      const name = aliasQuoted ? alias : normalizeContent(alias);
+      return `${kind} ${id} as ${name}`;
+    },
+    'state-separator': () => '--',
+    // Syntax the parser does not model is kept as written
+    unknown: (stmt) => stmt.content,
+    frontmatter: (stmt) => stmt.content,
+    // Shape markers and node text are kept as written
+    'mindmap-node': (stmt) => stmt.content,
+    'mindmap-decoration': (stmt) => stmt.content,
+    'timeline-event': (stmt) =>
+      `: ${(stmt as TimelineEventStatement).events.join(' : ')}`,
+    // The value is kept as written: dates and formats are the author's
+    'gantt-setting': (stmt) => {
+      const s = stmt as GanttSettingStatement;
+      return s.value ? `${s.keyword} ${s.value}` : s.keyword;
     },
     'brace-block-start': (stmt) => {
       const s = stmt as BraceBlockStartStatement;
+      // ER entity blocks have no leading keyword
+      if (s.blockKind === 'entity') return `${s.name} {`;
       return `${s.blockKind} ${s.name} {`;
     },
     'block-option': (stmt) => {
@@ -50,6 +215,39 @@ const STATEMENT_FORMATTERS: Partial<Record<StatementType, StatementFormatter>> =
       const label = (stmt as { label?: string }).label;
       return label ? `and ${label}` : 'and';
     },
+    activate: (stmt) => `activate ${(stmt as ActivateStatement).participant}`,
+    deactivate: (stmt) =>
+      `deactivate ${(stmt as DeactivateStatement).participant}`,
+    note: (stmt, diagramType) => {
+      const s = stmt as NoteStatement;
+      if (!s.position || !s.participants) return normalizeContent(s.content);
+      // Sequence diagrams conventionally capitalize the keyword
+      const keyword = diagramType === 'sequenceDiagram' ? 'Note' : 'note';
+      const base = `${keyword} ${s.position} ${s.participants.join(',')}`;
+      if (s.text === undefined) return base;
+      const text = s.text.replace(/  +/g, ' ');
+      return text ? `${base}: ${text}` : `${base}:`;
+    },
+    annotation: (stmt) => {
+      const s = stmt as AnnotationStatement;
+      return s.target ? `<<${s.name}>> ${s.target}` : `<<${s.name}>>`;
+    },
+    'class-relation': (stmt) => {
+      const s = stmt as ClassRelationStatement;
+      const parts = [
+        s.from,
+        s.fromCardinality === undefined ? '' : `"${s.fromCardinality}"`,
+        s.arrow,
+        s.toCardinality === undefined ? '' : `"${s.toCardinality}"`,
+        s.to,
+      ];
+      const base = parts.filter(Boolean).join(' ');
+      return s.label ? `${base}: ${s.label.replace(/  +/g, ' ')}` : base;
+    },
+    'attribute-map': (stmt) => {
+      const s = stmt as AttributeMapStatement;
+      return `${s.id}@${formatAttributes(s.attributes)}`;
+    },
     'arrow-message': (stmt) => {
       const s = stmt as ArrowMessageStatement;
       const base = `${s.from} ${s.arrow} ${s.to}`;
@@ -61,34 +259,104 @@ const STATEMENT_FORMATTERS: Partial<Record<StatementType, StatementFormatter>> =
   };
 
 // Statements that need content normalization
-const NORMALIZABLE_TYPES: StatementType[] = [
-  'generic-line',
-  'participant',
-  'note',
-];
+const NORMALIZABLE_TYPES: StatementType[] = ['generic-line', 'autonumber'];
+
+// Diagrams whose lines are free text, kept as written
+const FREE_TEXT_DIAGRAMS: DiagramType[] = ['timeline'];
+
+function isNormalizable(stmt: Statement, diagramType: DiagramType): boolean {
+  return (
+    NORMALIZABLE_TYPES.includes(stmt.type) &&
+    !FREE_TEXT_DIAGRAMS.includes(diagramType)
+  );
+}
 
 // ============================================================================
 // Content Normalizers - Pipeline pattern
 // ============================================================================
 
-type ContentNormalizer = (content: string) => string;
+/** A named rewrite step; names are reported by the `trace` option */
+interface ContentNormalizer {
+  name: string;
+  apply: (content: string) => string;
+}
 
+// Normalizers check for the characters they rewrite before running a
+// pattern, so most lines pass through without a scan or a new string
 const CONTENT_NORMALIZERS: ContentNormalizer[] = [
   // Collapse multiple spaces to single space
-  (content) => content.replace(/  +/g, ' '),
+  {
+    name: 'collapse-spaces',
+    apply: (content) =>
+      content.includes('  ') ? content.replace(/  +/g, ' ') : content,
+  },
+  // Normalize inline attribute maps: A@{shape:rect} -> A@{ shape: rect }
+  {
+    name: 'attribute-maps',
+    apply: (content) => {
+      if (!content.includes('@{')) return content;
+      return content.replace(/@\{([^{}]*)\}/g, (match, body: string) => {
+        const attributes = parseAttributes(body);
+        return attributes ? `@${formatAttributes(attributes)}` : match;
+      });
+    },
+  },
+  // Normalize edge IDs: A e1@-->B -> A e1@--> B
+  {
+    name: 'edge-ids',
+    apply: (content) => {
+      if (!content.includes('@')) return content;
+      return content.replace(
+        EDGE_ID_PATTERN,
+        (match, edge: string, pipe: string | undefined, offset: number) => {
+          if (pipe) return `${edge}|`;
+          return offset + match.length < content.length ? `${edge} ` : edge;
+        }
+      );
+    },
+  },
   // Normalize bracket padding: [ text ] -> [text]
-  (content) => normalizeBracketPair(content, '[', ']'),
+  {
+    name: 'bracket-padding',
+    apply: (content) => normalizeBracketPair(content, '[', ']'),
+  },
   // Normalize brace padding: { text } -> {text}
-  (content) => normalizeBracketPair(content, '{', '}'),
+  {
+    name: 'brace-padding',
+    apply: (content) => normalizeBracketPair(content, '{', '}'),
+  },
   // Normalize paren padding: ( text ) -> (text)
-  (content) => normalizeBracketPair(content, '(', ')'),
+  {
+    name: 'paren-padding',
+    apply: (content) => normalizeBracketPair(content, '(', ')'),
+  },
   // Normalize pipe labels: | text | -> |text|
-  (content) =>
-    content.replace(/\|\s+([^|]*?)\s*\|/g, (_, inner) => `|${inner.trim()}|`),
+  {
+    name: 'pipe-labels',
+    apply: (content) => {
+      if (!content.includes('|')) return content;
+      return content.replace(
+        /\|\s+([^|]*?)\s*\|/g,
+        (_, inner: string) => `|${inner.trim()}|`
+      );
+    },
+  },
 ];
 
+/** Diagram-specific rewrites of generic lines, after the normalizers */
+const DIAGRAM_LINE_NORMALIZERS: Partial<
+  Record<DiagramType, ContentNormalizer>
+> = {
+  'block-beta': { name: 'block-widths', apply: joinBlockWidths },
+  quadrantChart: { name: 'quadrant-points', apply: formatQuadrantPoint },
+  requirementDiagram: {
+    name: 'requirement-relations',
+    apply: formatRequirementRelation,
+  },
+};
+
 function normalizeContent(content: string): string {
-  return CONTENT_NORMALIZERS.reduce((acc, fn) => fn(acc), content);
+  return CONTENT_NORMALIZERS.reduce((acc, step) => step.apply(acc), content);
 }
 
 // ============================================================================
@@ -97,12 +365,16 @@ function normalizeContent(content: string): string {
 
 function getIndentDepth(
   stmt: Statement,
-  seenDiagramDecl: boolean,
+  indentBody: boolean,
   braceBlockDepth: number,
   blockDepth: number
 ): number {
-  // Diagram declaration and directives: always at column 0
-  if (stmt.type === 'diagram-decl' || stmt.type === 'directive') {
+  // Frontmatter, diagram declaration, and directives: always at column 0
+  if (
+    stmt.type === 'frontmatter' ||
+    stmt.type === 'diagram-decl' ||
+    stmt.type === 'directive'
+  ) {
     return 0;
   }
 
@@ -124,7 +396,7 @@ function getIndentDepth(
   }
 
   // Base indentation level
-  const base = seenDiagramDecl ? 1 : 0;
+  const base = indentBody ? 1 : 0;
 
   // Block else/option: same level as block-start
   if (
@@ -135,6 +407,19 @@ function getIndentDepth(
     return base + blockDepth - 1;
   }
 
+  // Mindmap nodes by tree depth; icons and classes under their node
+  if (stmt.type === 'mindmap-node') {
+    return base + stmt.depth;
+  }
+  if (stmt.type === 'mindmap-decoration') {
+    return base + stmt.depth + 1;
+  }
+
+  // Timeline event continuations: one level below their period
+  if (stmt.type === 'timeline-event') {
+    return base + blockDepth + 1;
+  }
+
   // All other statements (including block-start, block-end): base + blockDepth
   return base + blockDepth;
 }
@@ -147,9 +432,19 @@ function getIndentDepth(
 const BLANK_BEFORE_BLOCK_TYPES: StatementType[] = [
   'diagram-decl',
   'generic-line',
+  'unknown',
   'arrow-message',
   'participant',
+  'autonumber',
+  'link',
+  'activate',
+  'deactivate',
   'note',
+  'attribute-map',
+  'annotation',
+  'class-relation',
+  'state-stereotype',
+  'gantt-setting',
   'block-end',
   'brace-block-end',
 ];
@@ -168,6 +463,855 @@ function shouldInsertBlankBefore(
   return false;
 }
 
+/** Diagrams whose top level is divided by "section" lines */
+const SECTIONED_DIAGRAMS: DiagramType[] = ['gantt', 'journey', 'timeline'];
+
+/** Statements that continue the enclosing block rather than start content */
+const BLOCK_CONTINUATION_TYPES: StatementType[] = [
+  'block-end',
+  'brace-block-end',
+  'block-else',
+  'block-option',
+  'block-and',
+  'state-separator',
+];
+
+/** Statements kept with the participant declarations they follow */
+const DECLARATION_TYPES: StatementType[] = ['participant', 'link', 'comment'];
+
+/**
+ * Whether a top-level statement starts a new logical section: the first
+ * statement after the participant declarations, the first after a closed
+ * block, or a gantt/journey/timeline "section" line.
+ */
+function isSectionBoundary(
+  stmt: Statement,
+  lastNonBlankType: StatementType | null,
+  diagramType: DiagramType,
+  nested: boolean
+): boolean {
+  if (nested || !lastNonBlankType || lastNonBlankType === 'diagram-decl') {
+    return false;
+  }
+  if (BLOCK_CONTINUATION_TYPES.includes(stmt.type)) return false;
+
+  // Links to participant menus belong with the declarations
+  if (lastNonBlankType === 'participant' || lastNonBlankType === 'link') {
+    return !DECLARATION_TYPES.includes(stmt.type);
+  }
+  if (
+    lastNonBlankType === 'block-end' ||
+    lastNonBlankType === 'brace-block-end'
+  ) {
+    return true;
+  }
+  return (
+    SECTIONED_DIAGRAMS.includes(diagramType) &&
+    stmt.type === 'generic-line' &&
+    /^section\b/.test(stmt.content)
+  );
+}
+
+// ============================================================================
+// Column Alignment Rules
+// ============================================================================
+
+/**
+ * Splits a statement's formatted content into columns, or returns null when
+ * the statement does not take part in alignment. Columns joined by single
+ * spaces must equal the unaligned output.
+ */
+type ColumnSplitter = (
+  stmt: Statement,
+  diagramType: DiagramType,
+  opts: Required<FormatOptions>
+) => string[] | null;
+
+interface ColumnAligner {
+  /** Rule name reported by the `trace` option */
+  name: string;
+  enabled: (opts: Required<FormatOptions>, diagramType: DiagramType) => boolean;
+  split: ColumnSplitter;
+}
+
+const COLUMN_ALIGNERS: ColumnAligner[] = [
+  // Sequence messages: "A ->> B:" prefixes padded so texts line up
+  {
+    name: 'align-messages',
+    enabled: (opts, diagramType) =>
+      opts.alignMessages && diagramType === 'sequenceDiagram',
+    split: (stmt, diagramType, opts) => {
+      if (stmt.type !== 'arrow-message' || !stmt.message) return null;
+      const head = `${stmt.from} ${stmt.arrow} ${stmt.to}:`;
+      const content = formatStatement(stmt, diagramType, opts);
+      return [head, content.slice(head.length + 1)];
+    },
+  },
+  // Flowchart edges: "A --> B" rows with arrows and targets in columns
+  {
+    name: 'align-arrows',
+    enabled: (opts, diagramType) =>
+      opts.alignArrows && !opts.alignEdgeLabels && isFlowchartType(diagramType),
+    split: (stmt, diagramType, opts) =>
+      splitEdge(stmt, diagramType, opts, false),
+  },
+  {
+    name: 'align-edge-labels',
+    enabled: (opts, diagramType) =>
+      opts.alignArrows && opts.alignEdgeLabels && isFlowchartType(diagramType),
+    split: (stmt, diagramType, opts) =>
+      splitEdge(stmt, diagramType, opts, true),
+  },
+  // ER attributes: type, name, keys, and comment columns
+  {
+    name: 'align-er-attributes',
+    enabled: (opts, diagramType) =>
+      opts.alignErAttributes && diagramType === 'erDiagram',
+    split: (stmt) => {
+      if (stmt.type !== 'generic-line') return null;
+      const attribute = matchErAttribute(normalizeContent(stmt.content));
+      if (!attribute) return null;
+      const { type, name, keys, comment } = attribute;
+      return [type, name, keys?.join(', ') ?? '', comment ?? ''];
+    },
+  },
+  // Gantt tasks: "Name : id, start, duration" with colon and fields aligned
+  {
+    name: 'align-gantt-tasks',
+    enabled: (opts, diagramType) =>
+      opts.alignGanttTasks && diagramType === 'gantt',
+    split: (stmt) => {
+      if (stmt.type !== 'generic-line') return null;
+      const task = matchGanttTask(normalizeContent(stmt.content));
+      if (!task) return null;
+      const fields = task.fields.map((field, k) =>
+        k < task.fields.length - 1 ? `${field},` : field
+      );
+      return [task.name, ':', ...fields];
+    },
+  },
+  // Quadrant points: "Name:", "[x, y]", and style columns
+  {
+    name: 'align-quadrant-points',
+    enabled: (opts, diagramType) =>
+      opts.alignQuadrantPoints && diagramType === 'quadrantChart',
+    split: (stmt) => {
+      if (stmt.type !== 'generic-line') return null;
+      const point = matchQuadrantPoint(normalizeContent(stmt.content));
+      return point ? quadrantPointCells(point) : null;
+    },
+  },
+];
+
+function isFlowchartType(diagramType: DiagramType): boolean {
+  return diagramType === 'flowchart' || diagramType === 'graph';
+}
+
+/**
+ * Split a single-link edge statement into [source, link, target], or
+ * [source, arrow, |label|, target] when labels get their own column.
+ * Chains of several links are rows of one cell, kept as formatted.
+ */
+function splitEdge(
+  stmt: Statement,
+  diagramType: DiagramType,
+  opts: Required<FormatOptions>,
+  labelColumn: boolean
+): string[] | null {
+  if (stmt.type !== 'generic-line') return null;
+  const content = normalizeContent(stmt.content);
+  const chain = statementChain(stmt, content);
+  if (!chain || chain.links.length === 0) return null;
+  if (chain.links.length > 1) return [formatStatement(stmt, diagramType, opts)];
+
+  const [from, to] = chain.groups.map(formatFlowchartGroup);
+  const target = content.endsWith(';') ? `${to};` : to;
+  const link = chain.links[0];
+  if (!labelColumn) return [from, formatFlowchartLink(link), target];
+  // Inline-text labels are part of the operator and stay in its column
+  if (link.inlineOpen || link.label === undefined) {
+    return [from, formatFlowchartLink(link), '', target];
+  }
+  const arrow = formatFlowchartLink({ ...link, label: undefined });
+  return [from, arrow, `|${link.label}|`, target];
+}
+
+/**
+ * Align columns across runs of consecutive output lines that the splitter
+ * accepts and that share indentation. Any other line ends a run, so each
+ * block is aligned on its own.
+ */
+function alignColumns(
+  lines: string[],
+  statements: (Statement | null)[],
+  diagramType: DiagramType,
+  split: ColumnSplitter,
+  opts: Required<FormatOptions>
+): void {
+  let run: AlignedRow[] = [];
+  const flush = () => {
+    if (run.length > 1) renderAlignedRun(lines, run, opts.maxWidth);
+    run = [];
+  };
+
+  lines.forEach((line, index) => {
+    const stmt = statements[index];
+    const cells = stmt && split(stmt, diagramType, opts);
+    if (!cells) {
+      flush();
+      return;
+    }
+    // Trailing comments follow the last column without a column of their own
+    if (stmt.trailingComment) {
+      cells.push(`${cells.pop() ?? ''} ${stmt.trailingComment}`);
+    }
+    const indent = line.slice(0, line.length - line.trimStart().length);
+    if (run.length > 0 && run[0].indent !== indent) flush();
+    run.push({ index, indent, cells });
+  });
+  flush();
+}
+
+interface AlignedRow {
+  index: number;
+  indent: string;
+  cells: string[];
+}
+
+/**
+ * Pad the rows of a run into columns. Rows that would exceed maxWidth once
+ * padded keep single spacing and do not widen the columns of the others.
+ */
+function renderAlignedRun(
+  lines: string[],
+  run: AlignedRow[],
+  maxWidth: number
+): void {
+  // Each row's own widths give its single-spaced form and natural width
+  const sized = run.map((row) => {
+    const own = columnWidths([row]);
+    return { row, own, natural: displayWidth(renderRow(row, own)) };
+  });
+  // Drop the widest rows from alignment until the rest fit
+  const kept = [...sized].sort((a, b) => a.natural - b.natural);
+  let widths = columnWidths(kept.map(({ row }) => row));
+  while (
+    kept.length > 0 &&
+    kept.some(({ row }) => displayWidth(renderRow(row, widths)) > maxWidth)
+  ) {
+    kept.pop();
+    widths = columnWidths(kept.map(({ row }) => row));
+  }
+
+  const aligned = new Set(kept);
+  for (const entry of sized) {
+    const { row, own } = entry;
+    lines[row.index] = renderRow(row, aligned.has(entry) ? widths : own);
+  }
+}
+
+/**
+ * Width of each column among rows where it is not the last cell.
+ */
+function columnWidths(rows: AlignedRow[]): number[] {
+  const widths: number[] = [];
+  for (const { cells } of rows) {
+    cells.slice(0, -1).forEach((cell, k) => {
+      widths[k] = Math.max(widths[k] ?? 0, displayWidth(cell));
+    });
+  }
+  return widths;
+}
+
+function renderRow({ indent, cells }: AlignedRow, widths: number[]): string {
+  // Columns that are empty in every row are dropped
+  const padded = cells
+    .map((cell, k) =>
+      k < cells.length - 1 ? padEndToWidth(cell, widths[k] ?? 0) : cell
+    )
+    .filter((_, k) => k === cells.length - 1 || (widths[k] ?? 0) > 0);
+  return (indent + padded.join(' ')).trimEnd();
+}
+
+// ============================================================================
+// Statement Ordering Rules
+// ============================================================================
+
+/** A statement to emit and its input line, or null when inserted */
+interface OrderedStatement {
+  stmt: Statement;
+  source: number | null;
+}
+
+/** Participant declaration with the comments directly above it */
+interface ParticipantGroup {
+  id: string;
+  entries: OrderedStatement[];
+}
+
+/**
+ * Statements in emission order. Identity unless a reordering option is
+ * enabled for the diagram type.
+ */
+function statementOrder(
+  diagram: Diagram,
+  opts: Required<FormatOptions>
+): OrderedStatement[] {
+  const identity: OrderedStatement[] = diagram.statements.map(
+    (stmt, source) => ({ stmt, source })
+  );
+  let order = identity;
+  if (diagram.type === 'sequenceDiagram') {
+    order = orderParticipants(diagram.statements, identity, opts);
+  } else if (diagram.type === 'classDiagram' && opts.sortClassMembers) {
+    order = betweenDirectives(identity, (entries) =>
+      sortClassMembers(entries, opts.alphabetizeClassMembers)
+    );
+  } else if (isFlowchartType(diagram.type) && opts.groupStyleStatements) {
+    order = betweenDirectives(identity, moveStylesToEnd);
+  } else if (diagram.type === 'gantt' && opts.groupGanttSettings) {
+    order = betweenDirectives(identity, moveSettingsBeforeSections);
+  }
+  return opts.hoistDirectives ? hoistDirectives(order) : order;
+}
+
+/**
+ * Move top-level directives to just after the YAML frontmatter, or to the
+ * top without one. Directives inside blocks stay where they are.
+ */
+function hoistDirectives(order: OrderedStatement[]): OrderedStatement[] {
+  const start = frontmatterLength(order);
+  const directives: OrderedStatement[] = [];
+  const rest: OrderedStatement[] = [];
+  let depth = 0;
+  for (const entry of order.slice(start)) {
+    const { type } = entry.stmt;
+    (type === 'directive' && depth === 0 ? directives : rest).push(entry);
+    if (type === 'block-start' || type === 'brace-block-start') {
+      depth++;
+    } else if ((type === 'block-end' || type === 'brace-block-end') && depth) {
+      depth--;
+    }
+  }
+  return [...order.slice(0, start), ...directives, ...rest];
+}
+
+/**
+ * Statements up to and including the closing `---` of YAML frontmatter
+ * at the start of the diagram, or 0 without frontmatter.
+ */
+function frontmatterLength(order: OrderedStatement[]): number {
+  let length = 0;
+  order.forEach((entry, i) => {
+    if (entry.stmt.type === 'frontmatter') length = i + 1;
+  });
+  return length;
+}
+
+/**
+ * Reorder each run of statements between directives on its own, so no
+ * statement moves across a directive: settings may depend on whether a
+ * directive comes before or after other statements.
+ */
+function betweenDirectives(
+  identity: OrderedStatement[],
+  reorder: (entries: OrderedStatement[]) => OrderedStatement[]
+): OrderedStatement[] {
+  const result: OrderedStatement[] = [];
+  let run: OrderedStatement[] = [];
+  for (const entry of identity) {
+    if (entry.stmt.type !== 'directive') {
+      run.push(entry);
+      continue;
+    }
+    result.push(...reorder(run), entry);
+    run = [];
+  }
+  result.push(...reorder(run));
+  return result;
+}
+
+/**
+ * Group and declare sequence participants as configured.
+ */
+function orderParticipants(
+  statements: Statement[],
+  identity: OrderedStatement[],
+  opts: Required<FormatOptions>
+): OrderedStatement[] {
+  const inserted: ParticipantGroup[] = opts.declareParticipants
+    ? implicitParticipants(statements).map((id) => ({
+        id,
+        entries: [
+          {
+            stmt: {
+              type: 'participant',
+              kind: 'participant',
+              id,
+              content: `participant ${id}`,
+            },
+            source: null,
+          },
+        ],
+      }))
+    : [];
+  if (!opts.groupParticipants && inserted.length === 0) return identity;
+
+  const decl = statements.findIndex((s) => s.type === 'diagram-decl');
+  let insertAt = decl + 1;
+  while (
+    statements[insertAt]?.type === 'autonumber' ||
+    statements[insertAt]?.type === 'directive'
+  ) {
+    insertAt++;
+  }
+
+  const moved = opts.groupParticipants
+    ? participantGroups(statements, insertAt)
+    : [];
+  if (!opts.groupParticipants) {
+    // New declarations go after the ones already at the top
+    for (let i = insertAt; i < statements.length; i++) {
+      const { type } = statements[i];
+      if (type === 'participant') insertAt = i + 1;
+      else if (type !== 'comment') break;
+    }
+  }
+  const groups = opts.groupParticipants
+    ? sortParticipantGroups(
+        [...moved, ...inserted],
+        statements,
+        opts.participantOrder
+      )
+    : inserted;
+
+  const movedSources = new Set(
+    moved.flatMap((group) => group.entries.map((e) => e.source))
+  );
+  return [
+    ...identity.slice(0, insertAt),
+    ...groups.flatMap((group) => group.entries),
+    ...identity.slice(insertAt).filter((e) => !movedSources.has(e.source)),
+  ];
+}
+
+/**
+ * Top-level participant declarations after `insertAt`, with the comments
+ * directly above them. Declarations inside blocks (e.g. box) and below a
+ * later directive stay put.
+ */
+function participantGroups(
+  statements: Statement[],
+  insertAt: number
+): ParticipantGroup[] {
+  const groups: ParticipantGroup[] = [];
+  const directive = statements.findIndex(
+    (stmt, index) => index >= insertAt && stmt.type === 'directive'
+  );
+  const end = directive < 0 ? statements.length : directive;
+  let depth = 0;
+  statements.slice(0, end).forEach((stmt, index) => {
+    if (stmt.type === 'block-start') depth++;
+    if (stmt.type === 'block-end' && depth > 0) depth--;
+    if (stmt.type !== 'participant' || depth > 0 || index < insertAt) return;
+    let first = index;
+    while (first > insertAt && statements[first - 1].type === 'comment') {
+      first--;
+    }
+    const entries = statements
+      .slice(first, index + 1)
+      .map((s, k) => ({ stmt: s, source: first + k }));
+    groups.push({ id: matchParticipantId(stmt.content) ?? '', entries });
+  });
+  return groups;
+}
+
+/**
+ * Participants used by messages, notes, or activations but never declared,
+ * in first-use order. IDs a declaration cannot spell are left implicit.
+ */
+function implicitParticipants(statements: Statement[]): string[] {
+  const declared = new Set<string>();
+  for (const stmt of statements) {
+    // "create participant X" declares X where it is first used
+    const content =
+      stmt.type === 'generic-line'
+        ? stmt.content.replace(/^create\s+/, '')
+        : stmt.type === 'participant'
+          ? stmt.content
+          : '';
+    const id = matchParticipantId(content);
+    if (id) declared.add(id);
+  }
+
+  const implicit: string[] = [];
+  for (const stmt of statements) {
+    for (const id of participantsUsed(stmt)) {
+      if (declared.has(id) || !isDeclarableParticipant(id)) continue;
+      declared.add(id);
+      implicit.push(id);
+    }
+  }
+  return implicit;
+}
+
+function sortParticipantGroups(
+  groups: ParticipantGroup[],
+  statements: Statement[],
+  participantOrder: ParticipantOrder
+): ParticipantGroup[] {
+  if (participantOrder === 'alphabetical') {
+    return [...groups].sort((a, b) => (a.id < b.id ? -1 : a.id > b.id ? 1 : 0));
+  }
+  if (participantOrder !== 'first-use') return groups;
+
+  // Unused declarations go last, in source order
+  const firstUse = new Map<string, number>();
+  statements.forEach((stmt, index) => {
+    for (const id of participantsUsed(stmt)) {
+      if (!firstUse.has(id)) firstUse.set(id, index);
+    }
+  });
+  const rank = (id: string) => firstUse.get(id) ?? Infinity;
+  return [...groups].sort((a, b) => rank(a.id) - rank(b.id));
+}
+
+function participantsUsed(stmt: Statement): string[] {
+  switch (stmt.type) {
+    case 'arrow-message':
+      return [stmt.from, stmt.to].map((id) => id.replace(/^[+-]/, ''));
+    case 'note':
+      return stmt.participants ?? [];
+    case 'activate':
+    case 'deactivate':
+    case 'link':
+      return [stmt.participant];
+    default:
+      return [];
+  }
+}
+
+/**
+ * Reorder the members of each class body: annotations, then fields, then
+ * methods, each by visibility (+ # ~ -, then none) and optionally by name.
+ * Comments directly above a member move with it; blank lines split a body
+ * into sections that are sorted separately.
+ */
+function sortClassMembers(
+  identity: OrderedStatement[],
+  alphabetize: boolean
+): OrderedStatement[] {
+  const result: OrderedStatement[] = [];
+  let section: { key: MemberSortKey; entries: OrderedStatement[] }[] = [];
+  let comments: OrderedStatement[] = [];
+  let inClass = false;
+
+  const flush = () => {
+    section.sort((a, b) => compareMembers(a.key, b.key, alphabetize));
+    result.push(...section.flatMap((member) => member.entries), ...comments);
+    section = [];
+    comments = [];
+  };
+
+  for (const entry of identity) {
+    const { stmt } = entry;
+    if (!inClass) {
+      result.push(entry);
+      inClass = stmt.type === 'brace-block-start' && stmt.blockKind === 'class';
+      continue;
+    }
+    if (stmt.type === 'comment') {
+      comments.push(entry);
+      continue;
+    }
+    if (stmt.type === 'blank-line' || stmt.type === 'brace-block-end') {
+      flush();
+      result.push(entry);
+      inClass = stmt.type !== 'brace-block-end';
+      continue;
+    }
+    section.push({
+      key: memberSortKey(stmt),
+      entries: [...comments, entry],
+    });
+    comments = [];
+  }
+  flush();
+  return result;
+}
+
+interface MemberSortKey {
+  group: number;
+  visibility: number;
+  name: string;
+}
+
+function memberSortKey(stmt: Statement): MemberSortKey {
+  if (stmt.type === 'annotation') {
+    return { group: 0, visibility: 0, name: stmt.name };
+  }
+  const member = matchClassMember(normalizeContent(stmt.content));
+  return {
+    group: member?.kind === 'method' ? 2 : 1,
+    // Members without a visibility marker follow the marked ones
+    visibility: member?.visibility
+      ? CLASS_VISIBILITIES.indexOf(member.visibility)
+      : CLASS_VISIBILITIES.length,
+    name: member?.name ?? '',
+  };
+}
+
+function compareMembers(
+  a: MemberSortKey,
+  b: MemberSortKey,
+  alphabetize: boolean
+): number {
+  if (a.group !== b.group) return a.group - b.group;
+  if (a.visibility !== b.visibility) return a.visibility - b.visibility;
+  if (!alphabetize || a.name === b.name) return 0;
+  return a.name < b.name ? -1 : 1;
+}
+
+/**
+ * Move classDef/class/style/linkStyle/click statements, with the comments
+ * directly above them, below everything else, keeping their relative order.
+ * A blank line separates them from the structure.
+ */
+function moveStylesToEnd(identity: OrderedStatement[]): OrderedStatement[] {
+  const structure: OrderedStatement[] = [];
+  const styles: OrderedStatement[] = [];
+  let comments: OrderedStatement[] = [];
+
+  for (const entry of identity) {
+    const { stmt } = entry;
+    if (stmt.type === 'comment') {
+      comments.push(entry);
+      continue;
+    }
+    const isStyle =
+      stmt.type === 'generic-line' &&
+      STYLE_STATEMENT_PATTERN.test(normalizeContent(stmt.content));
+    (isStyle ? styles : structure).push(...comments, entry);
+    comments = [];
+  }
+  structure.push(...comments);
+
+  if (styles.length === 0) return identity;
+  const separator: OrderedStatement = {
+    stmt: { type: 'blank-line', content: '' },
+    source: null,
+  };
+  return [...structure, separator, ...styles];
+}
+
+/**
+ * Move gantt settings that follow the first section, with the comments
+ * directly above them, to just before it, keeping their relative order.
+ */
+function moveSettingsBeforeSections(
+  identity: OrderedStatement[]
+): OrderedStatement[] {
+  const first = identity.findIndex(
+    ({ stmt }) =>
+      stmt.type === 'generic-line' && /^section\b/.test(stmt.content)
+  );
+  if (first === -1) return identity;
+
+  const sections: OrderedStatement[] = [];
+  const settings: OrderedStatement[] = [];
+  let comments: OrderedStatement[] = [];
+  for (const entry of identity.slice(first)) {
+    if (entry.stmt.type === 'comment') {
+      comments.push(entry);
+      continue;
+    }
+    const isSetting = entry.stmt.type === 'gantt-setting';
+    (isSetting ? settings : sections).push(...comments, entry);
+    comments = [];
+  }
+  sections.push(...comments);
+
+  if (settings.length === 0) return identity;
+  return [...identity.slice(0, first), ...settings, ...sections];
+}
+
+/**
+ * Split chained flowchart edges into one statement per link:
+ * "A[Start] --> B --> C" becomes "A[Start] --> B" and "B --> C". Nodes that
+ * continue the chain are referenced by bare ID on the following line.
+ */
+function expandChains(order: OrderedStatement[]): OrderedStatement[] {
+  return order.flatMap((entry) => {
+    const { stmt } = entry;
+    if (stmt.type !== 'generic-line') return [entry];
+    const content = normalizeContent(stmt.content);
+    const chain = statementChain(stmt, content);
+    if (!chain || chain.links.length < 2) return [entry];
+
+    const end = content.endsWith(';') ? ';' : '';
+    // A trailing comment follows the last edge of the chain
+    const edge = { ...stmt, trailingComment: undefined };
+    const last = chain.links.length - 1;
+    return chain.links.map((link, i) => {
+      const from =
+        i === 0
+          ? formatFlowchartGroup(chain.groups[0])
+          : chain.groups[i].map((node) => node.id).join(' & ');
+      const to = formatFlowchartGroup(chain.groups[i + 1]);
+      const line = `${from} ${formatFlowchartLink(link)} ${to}${end}`;
+      const base = i === last ? stmt : edge;
+      return { ...entry, stmt: { ...base, content: line } };
+    });
+  });
+}
+
+/**
+ * Drop a flowchart node's label when it repeats the node's current label,
+ * e.g. the second "A[Foo]" becomes "A". A different label is a redefinition
+ * and is kept.
+ */
+function dedupeNodeLabels(order: OrderedStatement[]): OrderedStatement[] {
+  const labels = new Map<string, string>();
+  return order.map((entry) => {
+    const { stmt } = entry;
+    if (stmt.type !== 'generic-line') return entry;
+    const content = normalizeContent(stmt.content);
+    const chain = statementChain(stmt, content);
+    if (!chain) return entry;
+
+    const repeated: FlowchartNode[] = [];
+    for (const node of chain.groups.flat()) {
+      if (!node.shape) continue;
+      const label = formatFlowchartNode({ id: '', shape: node.shape });
+      if (labels.get(node.id) === label) repeated.push(node);
+      labels.set(node.id, label);
+    }
+    if (repeated.length === 0) return entry;
+    const stripped = rewriteChainText(content, chain, {
+      node: (node) =>
+        repeated.includes(node)
+          ? formatFlowchartNode({ ...node, shape: undefined })
+          : undefined,
+    });
+    return stripped === null
+      ? entry
+      : { ...entry, stmt: { ...stmt, content: stripped } };
+  });
+}
+
+/**
+ * Rewrite node texts and link labels in a chain line while keeping the rest
+ * of the line as written. Each node and label is located in order;
+ * replacers return undefined to keep the original. Returns null if a part
+ * cannot be located.
+ */
+function rewriteChainText(
+  content: string,
+  chain: FlowchartChain,
+  replace: {
+    node?: (node: FlowchartNode) => string | undefined;
+    label?: (link: FlowchartLink) => string | undefined;
+  }
+): string | null {
+  let result = '';
+  let pos = 0;
+  const rewrite = (text: string, replacement: string | undefined) => {
+    const start = content.indexOf(text, pos);
+    if (start < 0) return false;
+    result += content.slice(pos, start) + (replacement ?? text);
+    pos = start + text.length;
+    return true;
+  };
+
+  for (let i = 0; i < chain.groups.length; i++) {
+    for (const node of chain.groups[i]) {
+      if (!rewrite(formatFlowchartNode(node), replace.node?.(node))) {
+        return null;
+      }
+    }
+    const link = chain.links[i];
+    if (link?.label === undefined) continue;
+    if (!rewrite(link.label, replace.label?.(link))) return null;
+  }
+  return result + content.slice(pos);
+}
+
+// ============================================================================
+// Label Wrapping Rules
+// ============================================================================
+
+/** Narrowest segment a wrapped label is broken into */
+const MIN_LABEL_WIDTH = 16;
+
+/** Line breaks Mermaid accepts inside labels */
+const LABEL_BREAK_PATTERN = /(<br\s*\/?>)/i;
+
+/**
+ * Break the edge labels of a line wider than maxWidth with <br/> so each
+ * segment fits beside the rest of the line. The available width ignores
+ * the labels themselves, so wrapping an already wrapped line is a no-op.
+ * The line's indent is measured as emitted, so tabs count like in the
+ * other width checks.
+ */
+function wrapEdgeLabels(
+  stmt: Statement,
+  indent: string,
+  diagramType: DiagramType,
+  opts: Required<FormatOptions>
+): Statement {
+  if (stmt.type !== 'generic-line') return stmt;
+  const indentWidth = displayWidth(indent);
+  const content = formatStatement(stmt, diagramType, opts);
+  if (indentWidth + displayWidth(content) <= opts.maxWidth) return stmt;
+  const chain = statementChain(stmt, content);
+  if (!chain) return stmt;
+
+  const labelsWidth = chain.links.reduce(
+    (sum, link) => sum + displayWidth(link.label ?? ''),
+    0
+  );
+  const rest = indentWidth + displayWidth(content) - labelsWidth;
+  const width = Math.max(MIN_LABEL_WIDTH, opts.maxWidth - rest);
+
+  const wrapped = rewriteChainText(content, chain, {
+    label: ({ label = '' }) =>
+      wrapLabel(label, width, opts.wrapEdgeLabels),
+  });
+  if (wrapped === null || wrapped === content) return stmt;
+  return { ...stmt, content: wrapped };
+}
+
+function wrapLabel(label: string, width: number, wrap: EdgeLabelWrap): string {
+  const quoted = /^".*"$/.test(label);
+  const text = quoted ? label.slice(1, -1) : label;
+  const lines = text
+    .split(LABEL_BREAK_PATTERN)
+    .map((part, k) => (k % 2 === 1 ? part : wrapWords(part, width)));
+  const result = lines.join('');
+  // Only labels that were broken get quotes, so rewrapping is a no-op
+  const broken = result !== text;
+  return quoted || (broken && wrap === 'quoted') ? `"${result}"` : result;
+}
+
+/**
+ * Greedily join words into segments no wider than width, joined by <br/>.
+ */
+function wrapWords(text: string, width: number): string {
+  const segments: string[] = [];
+  for (const word of text.split(' ')) {
+    const last = segments.length - 1;
+    if (
+      last >= 0 &&
+      displayWidth(segments[last]) + 1 + displayWidth(word) <= width
+    ) {
+      segments[last] += ` ${word}`;
+    } else {
+      segments.push(word);
+    }
+  }
+  return segments.join('<br/>');
+}
+
 // ============================================================================
 // Main Format Function
 // ============================================================================
@@ -176,98 +1320,445 @@ function shouldInsertBlankBefore(
  * Format a parsed diagram AST back to string.
  */
 export function format(diagram: Diagram, options: FormatOptions = {}): string {
-  const opts = { ...DEFAULT_OPTIONS, ...options };
-  const indentStr = opts.useTabs ? '\t' : ' '.repeat(opts.indentSize);
+  return formatLines(diagram, options).lines.join('\n') + '\n';
+}
 
-  const lines: string[] = [];
-  let braceBlockDepth = 0;
-  let blockDepth = 0;
-  let seenDiagramDecl = false;
-  let lastNonBlankType: StatementType | null = null;
+/**
+ * Format one statement on its own, without indentation. Options that need
+ * the surrounding diagram (alignment, reordering, wrapping) do not apply.
+ *
+ * @example
+ * ```ts
+ * const [, message] = parse('sequenceDiagram\nA->>B:hi').statements;
+ * formatStatementText(message, 'sequenceDiagram'); // 'A ->> B: hi'
+ * ```
+ */
+export function formatStatementText(
+  stmt: Statement,
+  diagramType: DiagramType = 'unknown',
+  options: FormatOptions = {}
+): string {
+  const opts = resolveOptions(options);
+  if (opts.lossless) return sourceText(stmt);
+  const content = formatStatement(stmt, diagramType, opts);
+  const hooked = applyStatementHooks(stmt, diagramType, content, opts);
+  return withTrailingComment(stmt, hooked ?? content);
+}
 
-  for (let i = 0; i < diagram.statements.length; i++) {
-    const stmt = diagram.statements[i];
+/**
+ * Format a parsed diagram into output lines, recording for each output line
+ * the index of the statement (= input line) that produced it, or null for
+ * inserted blank lines.
+ */
+export function formatLines(
+  diagram: Diagram,
+  options: FormatOptions = {}
+): { lines: string[]; sources: (number | null)[] } {
+  const opts = resolveOptions(options);
+  if (opts.trace) opts.trace = skipFinalNewline(diagram, opts.trace);
+  const emitter = new LineEmitter(diagram.type, opts);
 
-    // Handle blank lines: collapse consecutive blanks, skip trailing
-    if (stmt.type === 'blank-line') {
-      if (
-        lastNonBlankType === 'blank-line' ||
-        i === diagram.statements.length - 1
-      ) {
-        continue;
-      }
-      if (lines.length === 0) {
-        continue;
+  let order = statementOrder(diagram, opts);
+  if (opts.expandChains && isFlowchartType(diagram.type)) {
+    order = expandChains(order);
+  }
+  if (opts.dedupeNodeLabels && isFlowchartType(diagram.type)) {
+    order = dedupeNodeLabels(order);
+  }
+  for (const { stmt, source } of order) emitter.emit(stmt, source);
+
+  const { lines, sources, emitted } = emitter;
+  const { trace } = opts;
+  for (const aligner of COLUMN_ALIGNERS) {
+    if (aligner.enabled(opts, diagram.type)) {
+      const before = trace ? [...lines] : null;
+      alignColumns(lines, emitted, diagram.type, aligner.split, opts);
+      if (trace && before) {
+        lines.forEach((line, i) => {
+          const stmt = emitted[i];
+          if (line === before[i] || !stmt) return;
+          trace({
+            line: lineNumber(sources[i]),
+            statementType: stmt.type,
+            rule: aligner.name,
+            before: before[i].trimStart(),
+            after: line.trimStart(),
+          });
+        });
       }
-      lines.push('');
-      lastNonBlankType = 'blank-line';
-      continue;
     }
+  }
+
+  // Remove trailing blank lines
+  while (lines.length > 0 && lines[lines.length - 1] === '') {
+    lines.pop();
+    const source = sources.pop() ?? null;
+    trace?.({
+      line: lineNumber(source),
+      statementType: 'blank-line',
+      rule: 'trailing-blank-lines',
+      before: '',
+      after: null,
+    });
+  }
+
+  return { lines, sources };
+}
+
+/**
+ * Drop trace events for the empty last line that a final newline leaves,
+ * which every formatted file has.
+ */
+function skipFinalNewline(
+  diagram: Diagram,
+  trace: (event: TraceEvent) => void
+): (event: TraceEvent) => void {
+  const last = diagram.statements.length;
+  if (diagram.statements[last - 1]?.type !== 'blank-line') return trace;
+  return (event) => {
+    if (event.line !== last || event.statementType !== 'blank-line') {
+      trace(event);
+    }
+  };
+}
+
+/**
+ * Whether a diagram can be formatted one statement at a time. Options that
+ * align runs of lines, reorder statements, or remember earlier nodes need
+ * the whole diagram.
+ */
+export function isStreamable(
+  diagramType: DiagramType,
+  options: FormatOptions = {}
+): boolean {
+  const opts = resolveOptions(options);
+  if (
+    opts.hoistDirectives ||
+    COLUMN_ALIGNERS.some((aligner) => aligner.enabled(opts, diagramType))
+  ) {
+    return false;
+  }
+  switch (diagramType) {
+    case 'sequenceDiagram':
+      return !opts.groupParticipants && !opts.declareParticipants;
+    case 'classDiagram':
+      return !opts.sortClassMembers;
+    case 'flowchart':
+    case 'graph':
+      return !opts.groupStyleStatements && !opts.dedupeNodeLabels;
+    case 'gantt':
+      return !opts.groupGanttSettings;
+    default:
+      return true;
+  }
+}
 
-    // Insert blank line before block-start if needed
-    if (shouldInsertBlankBefore(stmt, lastNonBlankType)) {
-      if (lines.length > 0 && lines[lines.length - 1] !== '') {
-        lines.push('');
+/**
+ * Whether a diagram is kept as written instead of formatted: indent-
+ * sensitive diagrams are, except built-in mindmaps with reindentMindmaps.
+ *
+ * @param header - Diagram declaration line, e.g. "mindmap"
+ */
+export function keepsLayout(
+  diagramType: DiagramType,
+  header: string | null,
+  options: FormatOptions = {}
+): boolean {
+  if (!isIndentSensitive(diagramType)) return false;
+  return !options.reindentMindmaps || !isMindmapDeclaration(header ?? '');
+}
+
+/**
+ * Statements to emit for one input statement when streaming: the statement
+ * itself, or one per link when chains are expanded.
+ */
+export function streamStatements(
+  stmt: Statement,
+  diagramType: DiagramType,
+  opts: Required<FormatOptions>
+): Statement[] {
+  if (!opts.expandChains || !isFlowchartType(diagramType)) return [stmt];
+  return expandChains([{ stmt, source: null }]).map((entry) => entry.stmt);
+}
+
+/**
+ * Indentation and blank-line state while emitting statements in order.
+ * Output accumulates in `lines`; blank lines at the end are left for the
+ * caller to drop, since a later statement may still follow them.
+ */
+export class LineEmitter {
+  readonly lines: string[] = [];
+  /** Input line behind each output line, null for inserted blank lines */
+  readonly sources: (number | null)[] = [];
+  /** Statement behind each output line, null for blank lines */
+  readonly emitted: (Statement | null)[] = [];
+  private readonly diagramType: DiagramType;
+  private readonly opts: Required<FormatOptions>;
+  private readonly indentStr: string;
+  /** Indentation strings by depth, built on first use */
+  private readonly indents: string[] = [''];
+  private braceBlockDepth = 0;
+  private blockDepth = 0;
+  private seenDiagramDecl = false;
+  private lastNonBlankType: StatementType | null = null;
+  /** Last line emitted, kept across `take` calls */
+  private lastLine: string | null = null;
+
+  constructor(diagramType: DiagramType, options: FormatOptions = {}) {
+    this.diagramType = diagramType;
+    this.opts = resolveOptions(options);
+    this.indentStr = this.opts.useTabs
+      ? '\t'
+      : ' '.repeat(this.opts.indentSize);
+  }
+
+  /** Format a statement and append its output lines */
+  emit(stmt: Statement, source: number | null): void {
+    const { opts, diagramType } = this;
+
+    // Handle blank lines: collapse consecutive blanks, skip leading ones
+    if (stmt.type === 'blank-line') {
+      if (this.lastNonBlankType === 'blank-line' || this.lastLine === null) {
+        opts.trace?.({
+          line: lineNumber(source),
+          statementType: stmt.type,
+          rule:
+            this.lastLine === null
+              ? 'leading-blank-lines'
+              : 'collapse-blank-lines',
+          before: '',
+          after: null,
+        });
+        return;
       }
+      this.push('', source, null);
+      this.lastNonBlankType = 'blank-line';
+      return;
+    }
+
+    // Insert blank line before block-start or a new section if needed
+    const blankRule = this.blankRuleBefore(stmt);
+    if (blankRule && this.lastLine !== null && this.lastLine !== '') {
+      this.push('', null, null);
+      opts.trace?.({
+        line: lineNumber(source),
+        statementType: stmt.type,
+        rule: blankRule,
+        before: null,
+        after: '',
+      });
     }
 
     // Decrement brace depth before formatting brace-block-end
-    if (stmt.type === 'brace-block-end' && braceBlockDepth > 0) {
-      braceBlockDepth--;
+    if (stmt.type === 'brace-block-end' && this.braceBlockDepth > 0) {
+      this.braceBlockDepth--;
     }
 
     // Decrement block depth before formatting block-end
-    if (stmt.type === 'block-end' && blockDepth > 0) {
-      blockDepth--;
+    if (stmt.type === 'block-end' && this.blockDepth > 0) {
+      this.blockDepth--;
     }
 
     // Calculate indentation depth
     const depth = getIndentDepth(
       stmt,
-      seenDiagramDecl,
-      braceBlockDepth,
-      blockDepth
+      this.seenDiagramDecl && opts.indentTopLevel,
+      this.braceBlockDepth,
+      this.blockDepth
     );
 
-    // Format the statement
-    const content = formatStatement(stmt);
-    const formatted = depth > 0 ? indentStr.repeat(depth) + content : content;
-    lines.push(formatted);
+    const { content, target } = opts.lossless
+      ? { content: sourceText(stmt), target: stmt }
+      : this.formatText(stmt, depth, source);
+    const formatted = depth > 0 ? this.indent(depth) + content : content;
+    this.push(formatted, source, target);
 
     // Update state
     if (stmt.type === 'diagram-decl') {
-      seenDiagramDecl = true;
+      this.seenDiagramDecl = true;
     }
     if (stmt.type === 'brace-block-start') {
-      braceBlockDepth++;
+      this.braceBlockDepth++;
     }
     if (stmt.type === 'block-start') {
-      blockDepth++;
+      this.blockDepth++;
     }
-    lastNonBlankType = stmt.type;
+    this.lastNonBlankType = stmt.type;
   }
 
-  // Remove trailing blank lines
-  while (lines.length > 0 && lines[lines.length - 1] === '') {
-    lines.pop();
+  /**
+   * Format a statement's text. Wrapped statements are kept for alignment;
+   * hooked lines are kept as returned, so they take no part in it.
+   */
+  private formatText(
+    stmt: Statement,
+    depth: number,
+    source: number | null
+  ): { content: string; target: Statement | null } {
+    const { opts, diagramType } = this;
+    const target =
+      opts.wrapEdgeLabels !== 'off' && isFlowchartType(diagramType)
+        ? wrapEdgeLabels(stmt, this.indent(depth), diagramType, opts)
+        : stmt;
+    const standard = formatStatement(target, diagramType, opts);
+    const hooked = applyStatementHooks(target, diagramType, standard, opts);
+    if (opts.trace) {
+      traceStatement(stmt, target, standard, hooked, diagramType, opts, {
+        line: lineNumber(source),
+        trace: opts.trace,
+      });
+    }
+    return {
+      content: withTrailingComment(stmt, hooked ?? standard),
+      target: hooked === undefined ? target : null,
+    };
+  }
+
+  /**
+   * Remove and return the lines emitted so far, except trailing blank lines
+   * that are only kept if more content follows.
+   */
+  take(): string[] {
+    let end = this.lines.length;
+    while (end > 0 && this.lines[end - 1] === '') end--;
+    this.sources.splice(0, end);
+    this.emitted.splice(0, end);
+    return this.lines.splice(0, end);
   }
 
-  // Ensure single trailing newline
-  return lines.join('\n') + '\n';
+  /** Rule that asks for a blank line before a statement, if any */
+  private blankRuleBefore(stmt: Statement): string | null {
+    if (shouldInsertBlankBefore(stmt, this.lastNonBlankType)) {
+      return 'blank-before-block';
+    }
+    const nested = this.braceBlockDepth + this.blockDepth > 0;
+    if (
+      this.opts.blankLineBetweenSections &&
+      isSectionBoundary(stmt, this.lastNonBlankType, this.diagramType, nested)
+    ) {
+      return 'blank-between-sections';
+    }
+    return null;
+  }
+
+  private indent(depth: number): string {
+    this.indents[depth] ??= this.indentStr.repeat(depth);
+    return this.indents[depth];
+  }
+
+  private push(
+    line: string,
+    source: number | null,
+    stmt: Statement | null
+  ): void {
+    this.lines.push(line);
+    this.sources.push(source);
+    this.emitted.push(stmt);
+    this.lastLine = line;
+  }
+}
+
+/** Color function arguments separated by ", ": rgb(1,2 ,3) -> rgb(1, 2, 3) */
+function formatColor(color: string): string {
+  const match = color.match(/^(\w+)\(\s*(.*?)\s*\)$/);
+  if (!match) return color;
+  const args = match[2].split(',').map((arg) => arg.trim());
+  return `${match[1]}(${args.join(', ')})`;
+}
+
+/** Statement text with its trailing comment, one space before `%%` */
+function withTrailingComment(stmt: Statement, text: string): string {
+  return stmt.trailingComment ? `${text} ${stmt.trailingComment}` : text;
+}
+
+/** Statement text as written, for lossless formatting */
+function sourceText(stmt: Statement): string {
+  return stmt.raw ?? withTrailingComment(stmt, stmt.content);
+}
+
+/** 1-based input line for a statement index, keeping null */
+function lineNumber(source: number | null): number | null {
+  return source === null ? null : source + 1;
+}
+
+/**
+ * Report the rules that took a statement from its source text to its
+ * output: content normalizers or its statement formatter, then edge label
+ * wrapping and statement hooks.
+ */
+function traceStatement(
+  stmt: Statement,
+  target: Statement,
+  standard: string,
+  hooked: string | undefined,
+  diagramType: DiagramType,
+  opts: Required<FormatOptions>,
+  context: { line: number | null; trace: (event: TraceEvent) => void }
+): void {
+  const report = (rule: string, before: string, after: string) => {
+    if (before === after) return;
+    const { line, trace } = context;
+    trace({ line, statementType: stmt.type, rule, before, after });
+  };
+
+  let text = stmt.content;
+  if (STATEMENT_FORMATTERS[stmt.type]) {
+    text = formatStatement(stmt, diagramType, opts);
+    report(stmt.type, stmt.content, text);
+  } else if (isNormalizable(stmt, diagramType)) {
+    for (const step of CONTENT_NORMALIZERS) {
+      const next = step.apply(text);
+      report(step.name, text, next);
+      text = next;
+    }
+    if (
+      opts.spaceArrows &&
+      stmt.type === 'generic-line' &&
+      isFlowchartType(diagramType)
+    ) {
+      const spaced = spaceFlowchartLinks(stmt, text);
+      report('space-arrows', text, spaced);
+      text = spaced;
+    }
+    const diagramStep = DIAGRAM_LINE_NORMALIZERS[diagramType];
+    if (stmt.type === 'generic-line' && diagramStep) {
+      const next = diagramStep.apply(text);
+      report(diagramStep.name, text, next);
+      text = next;
+    }
+  }
+  if (target !== stmt) report('wrap-edge-labels', text, standard);
+  if (hooked !== undefined) report('statement-hook', standard, hooked);
 }
 
 /**
  * Format a single statement's content.
  */
-function formatStatement(stmt: Statement): string {
+function formatStatement(
+  stmt: Statement,
+  diagramType: DiagramType,
+  opts: Required<FormatOptions> = DEFAULT_OPTIONS
+): string {
   // Use custom formatter if available
   const formatter = STATEMENT_FORMATTERS[stmt.type];
   if (formatter) {
-    return formatter(stmt);
+    return formatter(stmt, diagramType);
+  }
+
+  if (
+    opts.spaceArrows &&
+    stmt.type === 'generic-line' &&
+    isFlowchartType(diagramType)
+  ) {
+    return spaceFlowchartLinks(stmt, normalizeContent(stmt.content));
+  }
+  const diagramStep = DIAGRAM_LINE_NORMALIZERS[diagramType];
+  if (stmt.type === 'generic-line' && diagramStep) {
+    return diagramStep.apply(normalizeContent(stmt.content));
   }
 
   // Normalize content for specific types
-  if (NORMALIZABLE_TYPES.includes(stmt.type)) {
+  if (isNormalizable(stmt, diagramType)) {
     return normalizeContent(stmt.content);
   }
 
@@ -275,48 +1766,165 @@ function formatStatement(stmt: Statement): string {
   return stmt.content;
 }
 
+/**
+ * Text from the first matching statement hook that returns one; a veto
+ * (null) keeps the statement's source text.
+ */
+function applyStatementHooks(
+  stmt: Statement,
+  diagramType: DiagramType,
+  formatted: string,
+  opts: Required<FormatOptions>
+): string | undefined {
+  for (const hook of opts.statementHooks) {
+    if (hook.types && !hook.types.includes(stmt.type)) continue;
+    if (hook.diagramTypes && !hook.diagramTypes.includes(diagramType)) {
+      continue;
+    }
+    const result = hook.format(stmt, { diagramType, formatted });
+    if (result === null) return stmt.content;
+    if (result !== undefined) return result;
+  }
+  return undefined;
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
+/**
+ * Attach block-beta width suffixes to their block: "a : 2" and
+ * "space :2" become "a:2" and "space:2". Labels in quotes or brackets and
+ * style statements ("style a stroke-width: 4") are kept as written.
+ */
+function joinBlockWidths(content: string): string {
+  if (STYLE_STATEMENT_PATTERN.test(content)) return content;
+  // A width follows a block: its ID, label, or shape
+  return content.replace(
+    /"[^"]*"|\[[^\]]*\]|(?<=[\w")\]])\s*:\s*(\d+)(?![\w.])/g,
+    (match, width: string | undefined) =>
+      width === undefined ? match : `:${width}`
+  );
+}
+
+/**
+ * Canonical quadrant point: "Name: [x, y]" followed by its style.
+ */
+function formatQuadrantPoint(content: string): string {
+  const point = matchQuadrantPoint(content);
+  return point ? quadrantPointCells(point).filter(Boolean).join(' ') : content;
+}
+
+/** Label with colon, coordinates, and style of a quadrant point */
+function quadrantPointCells({
+  name,
+  className,
+  x,
+  y,
+  style,
+}: QuadrantPoint): [string, string, string] {
+  const label = className ? `${name}:::${className}:` : `${name}:`;
+  return [label, `[${x}, ${y}]`, style ?? ''];
+}
+
+/**
+ * Space a requirement relationship as "a - satisfies -> b", or
+ * "b <- satisfies - a" when written target first.
+ */
+function formatRequirementRelation(content: string): string {
+  const relation = matchRequirementRelation(content);
+  if (!relation) return content;
+  const { source, target, relation: keyword, reversed } = relation;
+  return reversed
+    ? `${target} <- ${keyword} - ${source}`
+    : `${source} - ${keyword} -> ${target}`;
+}
+
+/**
+ * Put single spaces around flowchart link operators: "A-->B" -> "A --> B".
+ * Lines the flowchart parser does not model are returned unchanged, so
+ * labels and keywords are never rewritten.
+ */
+function spaceFlowchartLinks(stmt: Statement, content: string): string {
+  const chain = statementChain(stmt, content);
+  if (!chain || chain.links.length === 0) return content;
+  const text = formatFlowchartChain(chain);
+  return content.endsWith(';') ? `${text};` : text;
+}
+
+/**
+ * Format attribute map braces: { key: value, key: value }
+ */
+function formatAttributes(attributes: Attribute[]): string {
+  if (attributes.length === 0) return '{}';
+  const body = attributes.map(({ key, value }) => `${key}: ${value}`);
+  return `{ ${body.join(', ')} }`;
+}
+
+/** Two-character shape openers and their closers, e.g. the stadium `([` */
+const SHAPE_TOKENS: Record<string, string[]> = {
+  '((': ['))'],
+  '([': ['])'],
+  '[[': [']]'],
+  '[(': [')]'],
+  '{{': ['}}'],
+  '[/': ['/]', '\\]'],
+  '[\\': ['\\]', '/]'],
+};
+
 /**
  * Normalize padding inside bracket pairs.
  * Only normalizes when there's space after opening bracket.
+ * Runs in linear time: matching brackets are paired in one scan up front,
+ * so unbalanced input like "[ [ [ ..." is not rescanned per bracket.
  */
 function normalizeBracketPair(
   content: string,
   open: string,
   close: string
 ): string {
-  const chars = [...content];
-  const result: string[] = [];
-  let i = 0;
-
-  while (i < chars.length) {
-    if (chars[i] === open && chars[i + 1] === ' ') {
-      // Find matching close bracket
-      let depth = 1;
-      let j = i + 1;
-      while (j < chars.length && depth > 0) {
-        if (chars[j] === open) depth++;
-        else if (chars[j] === close) depth--;
-        j++;
-      }
+  if (!content.includes(open + ' ')) return content;
 
-      if (depth === 0) {
-        // Extract inner content (excluding brackets)
-        const inner = chars
-          .slice(i + 1, j - 1)
-          .join('')
-          .trim();
-        result.push(open, inner, close);
-        i = j;
-        continue;
-      }
+  // Matching close bracket for each open bracket that has one; brackets
+  // inside quoted labels are text
+  const matches = new Map<number, number>();
+  const stack: number[] = [];
+  let quoted = false;
+  for (let i = 0; i < content.length; i++) {
+    if (content[i] === '"') quoted = !quoted;
+    else if (quoted) continue;
+    else if (content[i] === open) stack.push(i);
+    else if (content[i] === close) {
+      const start = stack.pop();
+      if (start !== undefined) matches.set(start, i);
+    }
+  }
+
+  let result = '';
+  let copied = 0;
+  for (let i = 0; i < content.length; i++) {
+    // Attribute maps (A@{ ... }) keep their canonical inner padding
+    const isAttributeMap = open === '{' && content[i - 1] === '@';
+    const end = matches.get(i);
+    if (end === undefined || content[i + 1] !== ' ' || isAttributeMap) {
+      continue;
     }
-    result.push(chars[i]);
-    i++;
+    const inner = content.slice(i + 1, end).trim();
+    // Trimming must not fuse a bracket and the text into another shape,
+    // as in G( [x] ), a round node that would become the stadium G([x]).
+    // A closer alone fuses into nothing: A[x [ y ] ] becomes A[x [ y ]]
+    const closers = SHAPE_TOKENS[open + inner[0]];
+    const head = closers ? ' ' : '';
+    const tail =
+      closers &&
+      content[end - 1] === ' ' &&
+      closers.includes(inner.slice(-1) + close)
+        ? ' '
+        : '';
+    result += content.slice(copied, i) + open + head + inner + tail + close;
+    copied = end + 1;
+    i = end;
   }
 
-  return result.join('');
+  return copied === 0 ? content : result + content.slice(copied);
 }
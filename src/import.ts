@@ -0,0 +1,753 @@
+/**
+ * Imports from other diagram languages, for teams migrating documentation
+ * to Mermaid. Each importer builds Mermaid source for the constructs it
+ * understands and parses it, so the result is an ordinary Diagram.
+ */
+
+import type { Diagram } from './types.js';
+import { parse } from './parser.js';
+
+/** Languages a diagram can be imported from */
+export type ImportFormat = 'dot' | 'plantuml';
+
+const IMPORTERS: Record<ImportFormat, (text: string) => Diagram> = {
+  dot: diagramFromDot,
+  plantuml: diagramFromPlantUML,
+};
+
+/**
+ * Check whether a string names an import format.
+ */
+export function isImportFormat(value: string): value is ImportFormat {
+  return Object.hasOwn(IMPORTERS, value);
+}
+
+/**
+ * Import a diagram from another diagram language.
+ *
+ * @throws Error when the input is not a diagram the importer supports
+ */
+export function importDiagram(text: string, format: ImportFormat): Diagram {
+  return IMPORTERS[format](text);
+}
+
+// ============================================================================
+// Graphviz DOT
+// ============================================================================
+
+/** Flowchart shape delimiters for DOT node shapes */
+const FLOWCHART_SHAPES: Record<string, [string, string]> = {
+  box: ['[', ']'],
+  rect: ['[', ']'],
+  rectangle: ['[', ']'],
+  square: ['[', ']'],
+  ellipse: ['([', '])'],
+  oval: ['([', '])'],
+  cylinder: ['[(', ')]'],
+  circle: ['((', '))'],
+  doublecircle: ['(((', ')))'],
+  diamond: ['{', '}'],
+  hexagon: ['{{', '}}'],
+  cds: ['>', ']'],
+  parallelogram: ['[/', '/]'],
+};
+
+/** Flowchart directions for DOT rankdir values */
+const RANKDIRS: Record<string, string> = {
+  TB: 'TB',
+  BT: 'BT',
+  LR: 'LR',
+  RL: 'RL',
+};
+
+/** An unquoted DOT ID: a name or a numeral */
+const DOT_ID_PATTERN =
+  /^(?:[A-Za-z_\u0080-\uffff][\w\u0080-\uffff]*|-?(?:\.\d+|\d+(?:\.\d*)?))/;
+
+interface DotToken {
+  text: string;
+  /** Quoted and HTML strings are never keywords or punctuation */
+  quoted: boolean;
+  line: number;
+}
+
+type DotAttributes = Record<string, string>;
+
+/**
+ * Import a Graphviz DOT graph or digraph as a flowchart.
+ *
+ * Nodes keep their labels and shapes (box, rounded, circle, diamond,
+ * cylinder, ...), edges their labels and styles (dashed, bold, invisible,
+ * undirected), and `cluster` subgraphs become subgraphs with their label.
+ * Default `node` and `edge` attributes apply as in DOT, and `rankdir`
+ * sets the direction. IDs that are not valid flowchart IDs are rewritten,
+ * keeping the original as the label.
+ *
+ * @throws Error when the input is not valid DOT
+ *
+ * @example
+ * ```ts
+ * format(diagramFromDot('digraph { rankdir=LR; a -> b [label="go"] }'));
+ * // "flowchart LR\n    a -->|go| b\n"
+ * ```
+ */
+export function diagramFromDot(text: string): Diagram {
+  return parse(new DotImporter(dotTokens(text)).run().join('\n'));
+}
+
+/**
+ * Recursive-descent reader of DOT statements that writes flowchart lines
+ * as it goes. Clusters are written once their body, which may set their
+ * label anywhere, has been read.
+ */
+class DotImporter {
+  private readonly tokens: DotToken[];
+  private pos = 0;
+  private directed = true;
+  /** Flowchart ID for each DOT node ID */
+  private readonly ids = new Map<string, string>();
+  /** Nodes already written with their shape and label */
+  private readonly written = new Set<string>();
+  /** DOT IDs mentioned in each subgraph being read, innermost last */
+  private readonly members: Set<string>[] = [];
+
+  constructor(tokens: DotToken[]) {
+    this.tokens = tokens;
+  }
+
+  run(): string[] {
+    if (this.keyword('strict')) this.pos++;
+    const kind = this.next('graph or digraph');
+    if (!kind.quoted && /^(?:di)?graph$/i.test(kind.text)) {
+      this.directed = kind.text.toLowerCase() === 'digraph';
+    } else {
+      this.fail(kind, 'graph or digraph');
+    }
+    // The graph's own ID has no flowchart equivalent
+    if (!this.punct('{')) this.pos++;
+    this.expect('{');
+    const graph: DotAttributes = {};
+    const body = this.statements('    ', {}, {}, graph);
+    const direction = RANKDIRS[graph.rankdir?.toUpperCase() ?? 'TB'] ?? 'TB';
+    return [`flowchart ${direction}`, ...body];
+  }
+
+  /**
+   * Statements up to the closing brace, with the defaults of the
+   * enclosing scope; `graph` receives the scope's graph attributes.
+   */
+  private statements(
+    indent: string,
+    nodeDefaults: DotAttributes,
+    edgeDefaults: DotAttributes,
+    graph: DotAttributes
+  ): string[] {
+    const lines: string[] = [];
+    const scope = { node: { ...nodeDefaults }, edge: { ...edgeDefaults } };
+    while (!this.punct('}')) {
+      if (this.punct(';') || this.punct(',')) {
+        this.pos++;
+        continue;
+      }
+      const kind = ['graph', 'node', 'edge'].find((k) => this.keyword(k));
+      if (kind && this.peek(1)?.text === '[') {
+        this.pos++;
+        const attributes = this.attributes();
+        Object.assign(kind === 'graph' ? graph : scope[kind], attributes);
+        continue;
+      }
+      if (!this.peek()?.quoted && this.peek(1)?.text === '=') {
+        const key = this.next('attribute').text;
+        this.pos++;
+        graph[key] = this.next('attribute value').text;
+        continue;
+      }
+      lines.push(...this.statement(indent, scope.node, scope.edge));
+    }
+    this.expect('}');
+    return lines;
+  }
+
+  /** A node, edge, or subgraph statement */
+  private statement(
+    indent: string,
+    nodeDefaults: DotAttributes,
+    edgeDefaults: DotAttributes
+  ): string[] {
+    const lines: string[] = [];
+    const operands: string[][] = [];
+    const operand = () => {
+      if (!this.keyword('subgraph') && !this.punct('{')) {
+        return [this.nodeId()];
+      }
+      const subgraph = this.subgraph(indent, nodeDefaults, edgeDefaults);
+      lines.push(...subgraph.lines);
+      return subgraph.ids;
+    };
+
+    const isSubgraph = this.keyword('subgraph') || this.punct('{');
+    operands.push(operand());
+    while (this.punct('->') || this.punct('--')) {
+      this.pos++;
+      operands.push(operand());
+    }
+    const attributes = this.punct('[') ? this.attributes() : {};
+
+    if (operands.length === 1) {
+      if (isSubgraph) return lines;
+      const [id] = operands[0];
+      lines.push(indent + this.node(id, { ...nodeDefaults, ...attributes }));
+      return lines;
+    }
+
+    // Bare node lines of "a -> {b c}" add nothing to the edge line
+    const bare = new Set(operands.flat().map((id) => this.flowchartId(id)));
+    const kept = lines.filter((line) => !bare.has(line.slice(indent.length)));
+    const link = this.link({ ...edgeDefaults, ...attributes });
+    const group = (ids: string[]) =>
+      ids.map((id) => this.node(id, nodeDefaults)).join(' & ');
+    const chain = operands
+      .map(group)
+      .reduce((line, next) => `${line} ${link} ${next}`);
+    return [...kept, indent + chain];
+  }
+
+  /**
+   * A subgraph; clusters become flowchart subgraphs, other subgraphs only
+   * group their statements. Returns the DOT IDs of the nodes inside.
+   */
+  private subgraph(
+    indent: string,
+    nodeDefaults: DotAttributes,
+    edgeDefaults: DotAttributes
+  ): { ids: string[]; lines: string[] } {
+    let name = '';
+    if (this.keyword('subgraph')) {
+      this.pos++;
+      if (!this.punct('{')) name = this.next('subgraph name').text;
+    }
+    this.expect('{');
+    const graph: DotAttributes = {};
+    const cluster = name.startsWith('cluster');
+    const inner = cluster ? `${indent}    ` : indent;
+    this.members.push(new Set());
+    const body = this.statements(inner, nodeDefaults, edgeDefaults, graph);
+    const ids = [...(this.members.pop() ?? [])];
+    if (!cluster) return { ids, lines: body };
+
+    const id = this.flowchartId(name.replace(/^cluster_?/, '') || name);
+    const title =
+      graph.label === undefined ? '' : ` [${flowchartText(graph.label)}]`;
+    return {
+      ids,
+      lines: [`${indent}subgraph ${id}${title}`, ...body, `${indent}end`],
+    };
+  }
+
+  /**
+   * A node reference, its shape and label written at the first mention
+   * that has them.
+   */
+  private node(dotId: string, attributes: DotAttributes): string {
+    const id = this.flowchartId(dotId);
+    const shape = FLOWCHART_SHAPES[attributes.shape?.toLowerCase() ?? ''];
+    let label = attributes.label?.replace(/\\N/g, dotId);
+    if (label === undefined && id !== dotId) label = dotId;
+    if (this.written.has(id)) return id;
+    let [open, close] = shape ?? ['[', ']'];
+    if (open === '[' && /\brounded\b/.test(attributes.style ?? '')) {
+      [open, close] = ['(', ')'];
+    } else if (open === '[' && attributes.peripheries === '2') {
+      [open, close] = ['[[', ']]'];
+    }
+    // A plain rectangle without a label is the flowchart default
+    if (open === '[' && label === undefined) return id;
+    this.written.add(id);
+    return `${id}${open}${flowchartText(label ?? dotId)}${close}`;
+  }
+
+  /** The flowchart link for an edge's attributes */
+  private link(attributes: DotAttributes): string {
+    const style = attributes.style ?? '';
+    const head = attributes.arrowhead;
+    const dir = attributes.dir ?? (this.directed ? 'forward' : 'none');
+    if (/\binvis\b/.test(style)) return '~~~';
+
+    // Line body and its tip, e.g. "-." and ">" for a dashed arrow
+    const line = /\b(?:dashed|dotted)\b/.test(style)
+      ? '-.-'
+      : /\bbold\b/.test(style) || Number(attributes.penwidth) >= 2
+        ? '=='
+        : '--';
+    const tip =
+      dir === 'none' || head === 'none'
+        ? ''
+        : head === 'odot' || head === 'dot'
+          ? 'o'
+          : head === 'tee'
+            ? 'x'
+            : '>';
+    let arrow = tip ? line + tip : line === '-.-' ? line : line + line[0];
+    if (dir === 'both' && tip) arrow = (tip === '>' ? '<' : tip) + arrow;
+    const label = attributes.label;
+    return label === undefined ? arrow : `${arrow}|${flowchartText(label)}|`;
+  }
+
+  /** "id", "id:port", or "id:port:compass" */
+  private nodeId(): string {
+    const token = this.next('node ID');
+    if (!token.quoted && /^[{}[\];,=]$|^-[->]$/.test(token.text)) {
+      this.fail(token, 'node ID');
+    }
+    while (this.punct(':')) this.pos += 2;
+    this.flowchartId(token.text);
+    for (const members of this.members) members.add(token.text);
+    return token.text;
+  }
+
+  /** A flowchart ID for a DOT ID, unique among the IDs given so far */
+  private flowchartId(dotId: string): string {
+    const existing = this.ids.get(dotId);
+    if (existing) return existing;
+    // "end" would close a subgraph
+    const name = dotId.replace(/[^\w-]/g, '_') || '_';
+    const base = name === 'end' ? 'End' : name;
+    const taken = new Set(this.ids.values());
+    let id = base;
+    for (let n = 2; taken.has(id); n++) id = `${base}_${n}`;
+    this.ids.set(dotId, id);
+    return id;
+  }
+
+  /** "[a=b, c=d; ...]", possibly repeated as "[...][...]" */
+  private attributes(): DotAttributes {
+    const attributes: DotAttributes = {};
+    while (this.punct('[')) {
+      this.pos++;
+      while (!this.punct(']')) {
+        if (this.punct(',') || this.punct(';')) {
+          this.pos++;
+          continue;
+        }
+        const key = this.next('attribute').text;
+        this.expect('=');
+        attributes[key] = this.next('attribute value').text;
+      }
+      this.pos++;
+    }
+    return attributes;
+  }
+
+  private peek(offset = 0): DotToken | undefined {
+    return this.tokens[this.pos + offset];
+  }
+
+  private punct(text: string): boolean {
+    const token = this.peek();
+    return token !== undefined && !token.quoted && token.text === text;
+  }
+
+  private keyword(text: string): boolean {
+    const token = this.peek();
+    return (
+      token !== undefined &&
+      !token.quoted &&
+      token.text.toLowerCase() === text
+    );
+  }
+
+  private next(expected: string): DotToken {
+    const token = this.peek();
+    if (!token) {
+      throw new Error(`Cannot import DOT: expected ${expected} at end`);
+    }
+    this.pos++;
+    return token;
+  }
+
+  private expect(text: string): void {
+    const token = this.next(`"${text}"`);
+    if (token.quoted || token.text !== text) this.fail(token, `"${text}"`);
+  }
+
+  private fail(token: DotToken, expected: string): never {
+    throw new Error(
+      `Cannot import DOT: expected ${expected} at line ${token.line}, ` +
+        `found "${token.text}"`
+    );
+  }
+}
+
+/**
+ * Split DOT source into IDs, strings, and punctuation, dropping comments.
+ */
+function dotTokens(source: string): DotToken[] {
+  const tokens: DotToken[] = [];
+  let line = 1;
+  let i = 0;
+  const push = (text: string, quoted: boolean) =>
+    tokens.push({ text, quoted, line });
+
+  while (i < source.length) {
+    const char = source[i];
+    const rest = source.slice(i, i + 2);
+    if (char === '\n') {
+      line++;
+      i++;
+    } else if (/\s/.test(char)) {
+      i++;
+    } else if (rest === '//' || (char === '#' && atLineStart(source, i))) {
+      while (i < source.length && source[i] !== '\n') i++;
+    } else if (rest === '/*') {
+      const end = source.indexOf('*/', i + 2);
+      const stop = end === -1 ? source.length : end + 2;
+      line += source.slice(i, stop).split('\n').length - 1;
+      i = stop;
+    } else if (char === '"') {
+      let value = '';
+      const start = line;
+      for (i++; i < source.length && source[i] !== '"'; i++) {
+        if (source[i] === '\\' && source[i + 1] === '"') {
+          value += '"';
+          i++;
+        } else if (source[i] === '\\' && source[i + 1] === '\n') {
+          line++;
+          i++;
+        } else {
+          if (source[i] === '\n') line++;
+          value += source[i];
+        }
+      }
+      i++;
+      tokens.push({ text: value, quoted: true, line: start });
+    } else if (char === '<') {
+      // HTML string: balanced angle brackets, kept without the outer pair
+      let depth = 0;
+      const start = i;
+      for (; i < source.length; i++) {
+        if (source[i] === '<') depth++;
+        else if (source[i] === '>' && --depth === 0) break;
+      }
+      const html = source.slice(start + 1, i);
+      i++;
+      push(html, true);
+      line += html.split('\n').length - 1;
+    } else if (rest === '->' || rest === '--') {
+      push(rest, false);
+      i += 2;
+    } else if ('{}[];,=:'.includes(char)) {
+      push(char, false);
+      i++;
+    } else {
+      const match = source.slice(i).match(DOT_ID_PATTERN);
+      if (!match) {
+        throw new Error(
+          `Cannot import DOT: unexpected "${char}" at line ${line}`
+        );
+      }
+      push(match[0], false);
+      i += match[0].length;
+    }
+  }
+  return tokens;
+}
+
+function atLineStart(source: string, index: number): boolean {
+  const start = source.lastIndexOf('\n', index - 1) + 1;
+  return source.slice(start, index).trim() === '';
+}
+
+/**
+ * Flowchart text for a DOT label: line breaks become <br/>, and text
+ * with flowchart syntax characters is quoted.
+ */
+function flowchartText(label: string): string {
+  const value = label
+    .replace(/\\[nlr]/g, '<br/>')
+    .replace(/\\(.)/g, '$1')
+    .replace(/"/g, '#quot;');
+  return /^[\w .,!?'-]*$/.test(value) ? value : `"${value}"`;
+}
+
+// ============================================================================
+// PlantUML
+// ============================================================================
+
+/** Mermaid arrows for PlantUML sequence arrows, without color brackets */
+const MERMAID_ARROWS: Record<string, string> = {
+  '->': '->>',
+  '-->': '-->>',
+  '->>': '-)',
+  '-->>': '--)',
+  '->x': '-x',
+  '-->x': '--x',
+  '->o': '->>',
+  '-->o': '-->>',
+  '<->': '<<->>',
+  '<-->': '<<-->>',
+};
+
+/** Reversed PlantUML arrows ("B <- A"), by the arrow they reverse */
+const REVERSED_ARROWS: Record<string, string> = {
+  '<-': '->',
+  '<--': '-->',
+  '<<-': '->>',
+  '<<--': '-->>',
+  'x<-': '->x',
+  'x<--': '-->x',
+};
+
+/** PlantUML participant kinds; Mermaid has participant and actor */
+const PARTICIPANT_KINDS =
+  /^(participant|actor|boundary|control|entity|database|collections|queue)\s+(.+)$/;
+
+/** A participant name: a quoted string or a bare word */
+const NAME = String.raw`"[^"]*"|[\w.@]+`;
+
+const MESSAGE_PATTERN = new RegExp(
+  String.raw`^(${NAME})\s*([<x]?<?-{1,2}(?:\[[^\]]*\])?-?>?>?[xo]?)\s*(${NAME})` +
+    String.raw`\s*((?:\+\+|--|\*\*|!!)\s*)?(?::\s*(.*))?$`
+);
+
+const NOTE_PATTERN =
+  /^(?:r|h)?note\s+(left of|right of|over)\s+([^:]+?)\s*(?::\s*(.*))?$/i;
+
+/** Groups, with the Mermaid keyword they open and what `else` becomes */
+const GROUPS: Record<string, { open: string; branch: string }> = {
+  alt: { open: 'alt', branch: 'else' },
+  loop: { open: 'loop', branch: 'else' },
+  opt: { open: 'opt', branch: 'else' },
+  par: { open: 'par', branch: 'and' },
+  break: { open: 'break', branch: 'else' },
+  critical: { open: 'critical', branch: 'option' },
+};
+
+/**
+ * Import a PlantUML sequence diagram.
+ *
+ * Participants (with display names), messages with their arrow styles
+ * and activation shorthands, activations, notes (single and multi-line),
+ * autonumbering, groups (alt/else, loop, opt, par, break, critical), and
+ * boxes are converted. Other PlantUML groups become highlighted rects
+ * with their label as a comment.
+ * Lines without a Mermaid equivalent, such as separators, delays, and
+ * skinparams, are kept as `%%` comments so nothing is dropped silently.
+ *
+ * @throws Error for PlantUML documents other than sequence diagrams
+ *
+ * @example
+ * ```ts
+ * format(diagramFromPlantUML('@startuml\nA -> B : hi\n@enduml'));
+ * // "sequenceDiagram\n    A ->> B: hi\n"
+ * ```
+ */
+export function diagramFromPlantUML(text: string): Diagram {
+  const lines = ['sequenceDiagram'];
+  // Mermaid keyword closed by each open "end"; null for boxes
+  const groups: (string | null)[] = [];
+  // Lines of a multi-line note, once its header is read
+  let note: { header: string; text: string[] } | null = null;
+  let blockComment = false;
+  const indent = () => '  '.repeat(groups.length);
+  const keep = (line: string) => lines.push(`${indent()}%% ${line}`);
+
+  for (const raw of text.split(/\r?\n/)) {
+    const line = raw.trim();
+
+    if (blockComment) {
+      const close = line.indexOf("'/");
+      const content = close === -1 ? line : line.slice(0, close).trim();
+      if (content) keep(content);
+      if (close !== -1) blockComment = false;
+      continue;
+    }
+    if (note) {
+      if (/^end\s*note$/i.test(line)) {
+        lines.push(`${indent()}${note.header}: ${note.text.join('<br/>')}`);
+        note = null;
+      } else {
+        note.text.push(line);
+      }
+      continue;
+    }
+
+    if (!line) {
+      lines.push('');
+      continue;
+    }
+    const start = line.match(/^@start(\w+)/);
+    if (start) {
+      if (start[1] !== 'uml') {
+        throw new Error(
+          `Cannot import @start${start[1]}: only PlantUML sequence diagrams ` +
+            'are supported'
+        );
+      }
+      continue;
+    }
+    if (line.startsWith('@end')) continue;
+    if (line.startsWith("/'")) {
+      const rest = line.slice(2);
+      const close = rest.indexOf("'/");
+      const content = (close === -1 ? rest : rest.slice(0, close)).trim();
+      if (content) keep(content);
+      blockComment = close === -1;
+      continue;
+    }
+    if (line.startsWith("'")) {
+      lines.push(`${indent()}%% ${line.slice(1).trim()}`);
+      continue;
+    }
+
+    const converted = plantUMLStatement(line, groups);
+    if (converted === null) {
+      keep(line);
+      continue;
+    }
+    if (converted.noteHeader !== undefined) {
+      note = { header: converted.noteHeader, text: [] };
+      continue;
+    }
+    // Branches and ends sit at the indentation of their block's start
+    const depth = groups.length - (converted.outdent ?? 0);
+    lines.push(`${'  '.repeat(Math.max(0, depth))}${converted.line}`);
+    if (converted.open !== undefined) groups.push(converted.open);
+    if (converted.comment) keep(converted.comment);
+  }
+
+  if (note) {
+    lines.push(`${indent()}${note.header}: ${note.text.join('<br/>')}`);
+  }
+  return parse(lines.join('\n'));
+}
+
+interface ConvertedLine {
+  line: string;
+  /** Mermaid block opened by this line */
+  open?: string | null;
+  /** Comment to write as the first line of the opened block */
+  comment?: string;
+  /** Levels to outdent by, 1 for branches like else */
+  outdent?: number;
+  /** Start of a multi-line note, written once "end note" is read */
+  noteHeader?: string;
+}
+
+/**
+ * Convert one PlantUML sequence statement, updating the open groups.
+ * Returns null for lines without a Mermaid equivalent.
+ */
+function plantUMLStatement(
+  line: string,
+  groups: (string | null)[]
+): ConvertedLine | null {
+  const participant = line.match(PARTICIPANT_KINDS);
+  if (participant) return plantUMLParticipant(participant[1], participant[2]);
+
+  const message = line.match(MESSAGE_PATTERN);
+  if (message) {
+    const [, from, arrow, to, activation, text] = message;
+    return { line: plantUMLMessage(from, arrow, to, activation, text) };
+  }
+
+  const note = line.match(NOTE_PATTERN);
+  if (note) {
+    const [, position, targets, text] = note;
+    const participants = targets.split(/\s*,\s*/).map(participantId);
+    const header = `Note ${position.toLowerCase()} ${participants.join(',')}`;
+    return text === undefined
+      ? { line: '', noteHeader: header }
+      : { line: `${header}: ${text}` };
+  }
+
+  const [keyword, ...words] = line.split(/\s+/);
+  const label = words.join(' ');
+  const lower = keyword.toLowerCase();
+  if (Object.hasOwn(GROUPS, lower)) {
+    const { open } = GROUPS[lower];
+    return { line: `${open} ${label}`.trimEnd(), open };
+  }
+  if (lower === 'group') {
+    const line = 'rect rgba(0, 0, 0, 0.05)';
+    return { line, open: 'rect', comment: label };
+  }
+  if (lower === 'else') {
+    const group = groups.at(-1);
+    const branch = Object.hasOwn(GROUPS, group ?? '')
+      ? GROUPS[group as string].branch
+      : 'else';
+    return { line: `${branch} ${label}`.trimEnd(), outdent: 1 };
+  }
+  if (lower === 'box') return plantUMLBox(label);
+  if (lower === 'end') {
+    if (groups.length === 0) return null;
+    groups.pop();
+    return { line: 'end' };
+  }
+  if (lower === 'autonumber') return { line: 'autonumber' };
+  if (lower === 'activate' || lower === 'deactivate' || lower === 'destroy') {
+    if (words.length !== 1) return null;
+    return { line: `${lower} ${participantId(words[0])}` };
+  }
+  if (lower === 'title' && label) return { line: `title ${label}` };
+  return null;
+}
+
+/**
+ * "participant "Long name" as A", "actor A as "Long name"",
+ * "database Db #red", ...
+ */
+function plantUMLParticipant(kind: string, rest: string): ConvertedLine {
+  const keyword = kind === 'actor' ? 'actor' : 'participant';
+  // Drop trailing ordering and color, which Mermaid has no syntax for
+  const decl = rest.replace(/\s+(?:order\s+\d+|#\S+)\s*$/g, '').trim();
+  const alias = decl.match(new RegExp(`^(${NAME})\\s+as\\s+(${NAME})$`));
+  if (!alias) return { line: `${keyword} ${participantId(decl)}` };
+  const [, first, second] = alias;
+  // Either side may be the quoted display name
+  const [id, name] = first.startsWith('"') ? [second, first] : [first, second];
+  const display = unquote(name);
+  const line =
+    display === id ? `${keyword} ${id}` : `${keyword} ${id} as ${display}`;
+  return { line };
+}
+
+function plantUMLMessage(
+  from: string,
+  arrow: string,
+  to: string,
+  activation: string | undefined,
+  text: string | undefined
+): string {
+  // Colors ("-[#red]>") and the short "-" form of dotted arrows go
+  let base = arrow.replace(/\[[^\]]*\]/g, '');
+  let [source, target] = [participantId(from), participantId(to)];
+  if (Object.hasOwn(REVERSED_ARROWS, base)) {
+    base = REVERSED_ARROWS[base];
+    [source, target] = [target, source];
+  }
+  const mermaid = MERMAID_ARROWS[base] ?? '->>';
+  const marker =
+    activation?.trim() === '++' ? '+' : activation?.trim() === '--' ? '-' : '';
+  const message = text === undefined ? '' : ` ${text.replace(/\\n/g, '<br/>')}`;
+  return `${source}${mermaid}${marker}${target}:${message}`;
+}
+
+/** "box "Label" #color": hex colors keep their "#", named colors drop it */
+function plantUMLBox(rest: string): ConvertedLine {
+  const match = rest.match(/^(?:"([^"]*)"|([^#]*?))?\s*(#\S+)?$/);
+  const label = (match?.[1] ?? match?.[2] ?? '').trim();
+  const color = match?.[3];
+  const hex = color !== undefined && /^#[\da-f]{3,8}$/i.test(color);
+  const parts = ['box', hex ? color : color?.slice(1), label].filter(Boolean);
+  return { line: parts.join(' '), open: null };
+}
+
+/** Mermaid IDs cannot be quoted, so spaces in quoted names become "_" */
+function participantId(name: string): string {
+  return unquote(name.trim()).replace(/\s+/g, '_');
+}
+
+function unquote(text: string): string {
+  return text.replace(/^"(.*)"$/, '$1');
+}
@@ -6,25 +6,143 @@
 
 // Type exports
 export type {
+  CheckResult,
   FormatOptions,
+  ParseLimits,
+  ParticipantOrder,
+  StatementHook,
+  StatementHookContext,
+  StyleLevel,
+  TraceEvent,
   DiagramType,
   Statement,
   Diagram,
   StatementType,
   BlockKind,
   BraceBlockKind,
+  FlowchartChain,
+  FlowchartLink,
+  FlowchartNode,
 } from './types.js';
+export type { DiagramStats } from './stats.js';
+export type { EdgeOptions, NodeShape, SequenceBlockKind } from './builder.js';
+export type {
+  BlockOpener,
+  Transformer,
+  VisitContext,
+  Visitor,
+} from './visit.js';
+export type {
+  ConversionResult,
+  ConversionTarget,
+  ConversionWarning,
+} from './convert.js';
+export type { RenameResult } from './refactor.js';
+export type { TextEdit } from './edits.js';
+export type { FenceError, MermaidBlock } from './markdown.js';
+export type { StreamChunk } from './stream.js';
+export type { DiagramBehavior } from './rules.js';
+export type {
+  DiagramStructure,
+  StructureEdge,
+  StructureMessage,
+  StructureNode,
+  StructureParticipant,
+} from './structure.js';
+export type {
+  ClassDiagram,
+  ClassStatement,
+  CommonStatement,
+  DiagramFamily,
+  ErAttributeStatement,
+  ErDiagram,
+  ErRelationStatement,
+  ErStatement,
+  FlowchartChainStatement,
+  FlowchartDiagram,
+  FlowchartStatement,
+  GenericDiagram,
+  SequenceDiagram,
+  SequenceStatement,
+  StateDiagram,
+  StateStatement,
+  StateTransitionStatement,
+  TypedDiagram,
+} from './typed.js';
+export type { ExportFormat } from './export.js';
+export type { ImportFormat } from './import.js';
+export type { MergeConflict, MergeResult } from './merge.js';
+export type { AnonymizeOptions, AnonymizeStrategy } from './anonymize.js';
+export type {
+  Diagnostic,
+  FixResult,
+  LineEdit,
+  LintOptions,
+  LintRule,
+  RuleLevel,
+  Severity,
+  Span,
+  Position,
+} from './lint.js';
 
 // Function exports
-export { parse, detectDiagramType } from './parser.js';
-export { format } from './formatter.js';
-export { isIndentSensitive, INDENT_SENSITIVE_DIAGRAMS } from './rules.js';
+export {
+  parse,
+  parseStrict,
+  ParseError,
+  LimitExceededError,
+  detectDiagramType,
+} from './parser.js';
+export { format, formatStatementText } from './formatter.js';
+export { formatStream } from './stream.js';
+export {
+  isIndentSensitive,
+  INDENT_SENSITIVE_DIAGRAMS,
+  registerDiagramType,
+  unregisterDiagramType,
+} from './rules.js';
+export { parseFlowchartLine } from './flowchart.js';
+export { getDiagramStats } from './stats.js';
+export { lint, lintDiagram, LINT_RULES } from './lint.js';
+export {
+  convertDiagram,
+  convertDiagramWithWarnings,
+  isConversionTarget,
+} from './convert.js';
+export { walkDiagram, transformDiagram } from './visit.js';
+export { diagramToJSON, diagramFromJSON } from './json.js';
+export { diagramStructure } from './structure.js';
+export { typedDiagram, untypedDiagram } from './typed.js';
+export {
+  diagramToD2,
+  diagramToDot,
+  diagramToPlantUML,
+  exportDiagram,
+  isExportFormat,
+} from './export.js';
+export {
+  diagramFromDot,
+  diagramFromPlantUML,
+  importDiagram,
+  isImportFormat,
+} from './import.js';
+export { mergeDiagrams } from './merge.js';
+export { anonymizeDiagram } from './anonymize.js';
+export { renameId } from './refactor.js';
+export { diffEdits, applyEdits } from './edits.js';
+export { MarkdownFormatError, extractDiagrams } from './markdown.js';
+export { FlowchartBuilder, SequenceDiagramBuilder } from './builder.js';
 
 // Internal imports
-import { parse, detectDiagramType } from './parser.js';
-import { format } from './formatter.js';
-import { isIndentSensitive } from './rules.js';
-import type { FormatOptions } from './types.js';
+import { parse, detectDiagramType, diagramHeader } from './parser.js';
+import { format, formatLines, keepsLayout } from './formatter.js';
+import { applyFixes } from './lint.js';
+import { MarkdownFormatError, replaceMermaidFences } from './markdown.js';
+import type { FenceError } from './markdown.js';
+import { diffEdits } from './edits.js';
+import type { TextEdit } from './edits.js';
+import type { FixResult, LintOptions } from './lint.js';
+import type { CheckResult, FormatOptions } from './types.js';
 
 // ============================================================================
 // Main API
@@ -53,14 +171,186 @@ export function formatMermaid(input: string, options?: FormatOptions): string {
   const diagramType = detectDiagramType(input);
 
   // Policy: skip formatting for indent-sensitive diagrams
-  if (isIndentSensitive(diagramType)) {
+  if (keepsLayout(diagramType, diagramHeader(input), options)) {
+    enforceLimits(input, options);
     return ensureTrailingNewline(input);
   }
 
-  const diagram = parse(input);
+  const diagram = parse(input, options?.limits);
   return format(diagram, options);
 }
 
+/**
+ * Check whether Mermaid source is already formatted, for check modes in
+ * build tools and CI.
+ *
+ * @param input - Mermaid diagram source code
+ * @param options - Formatting options
+ * @returns Whether the input is formatted, and its first differing line
+ *
+ * @example
+ * ```ts
+ * checkFormatted('sequenceDiagram\nA->>B: hi\n');
+ * // { formatted: false,
+ * //   firstDifference: { line: 2, actual: 'A->>B: hi',
+ * //                      expected: '    A ->> B: hi' } }
+ * ```
+ */
+export function checkFormatted(
+  input: string,
+  options?: FormatOptions
+): CheckResult {
+  const output = formatMermaid(input, options);
+  if (output === input) return { formatted: true };
+
+  const actual = input.split('\n');
+  const expected = output.split('\n');
+  let index = 0;
+  while (actual[index] === expected[index]) index++;
+  return {
+    formatted: false,
+    firstDifference: {
+      line: index + 1,
+      actual: actual[index] ?? null,
+      expected: expected[index] ?? null,
+    },
+  };
+}
+
+/**
+ * Format Mermaid source and map a cursor position into the output, so
+ * editors can keep the caret in place after format-on-save.
+ *
+ * Offsets are string indices, as in prettier's `formatWithCursor`. The
+ * cursor keeps its position relative to the non-whitespace characters of its
+ * line; a cursor on a removed blank line moves to the next kept line.
+ *
+ * @param input - Mermaid diagram source code
+ * @param cursorOffset - Cursor offset into the input
+ * @param options - Formatting options
+ * @returns Formatted code and the cursor offset into it
+ *
+ * @example
+ * ```ts
+ * formatWithCursor('sequenceDiagram\nA->>B:hi', 23);
+ * // { formatted: 'sequenceDiagram\n    A ->> B: hi\n', cursorOffset: 30 }
+ * ```
+ */
+export function formatWithCursor(
+  input: string,
+  cursorOffset: number,
+  options?: FormatOptions
+): { formatted: string; cursorOffset: number } {
+  if (keepsLayout(detectDiagramType(input), diagramHeader(input), options)) {
+    enforceLimits(input, options);
+    const formatted = ensureTrailingNewline(input);
+    return {
+      formatted,
+      cursorOffset: Math.min(Math.max(cursorOffset, 0), formatted.length),
+    };
+  }
+
+  const diagram = parse(input, options?.limits);
+  const { lines, sources } = formatLines(diagram, options);
+  return {
+    formatted: lines.join('\n') + '\n',
+    cursorOffset: mapCursorOffset(input, cursorOffset, lines, sources),
+  };
+}
+
+/**
+ * Format Mermaid source and report where each input line ended up.
+ *
+ * `lineMap[i]` is the 1-based output line of input line `i + 1`, or null
+ * when the line was removed (collapsed or trailing blank lines).
+ *
+ * @param input - Mermaid diagram source code
+ * @param options - Formatting options
+ * @returns Formatted code and the input→output line map
+ *
+ * @example
+ * ```ts
+ * formatWithSourceMap('sequenceDiagram\n\n\nA->>B: hi\n');
+ * // lineMap: [1, 2, null, 3, null]
+ * ```
+ */
+export function formatWithSourceMap(
+  input: string,
+  options?: FormatOptions
+): { formatted: string; lineMap: (number | null)[] } {
+  const inputLineCount = input.split('\n').length;
+
+  if (keepsLayout(detectDiagramType(input), diagramHeader(input), options)) {
+    enforceLimits(input, options);
+    // Lines are kept as-is; only the segment after a final newline has none
+    const lineMap = Array.from({ length: inputLineCount }, (_, i) =>
+      i === inputLineCount - 1 && input.endsWith('\n') ? null : i + 1
+    );
+    return { formatted: ensureTrailingNewline(input), lineMap };
+  }
+
+  const diagram = parse(input, options?.limits);
+  const { lines, sources } = formatLines(diagram, options);
+  const lineMap: (number | null)[] = new Array(inputLineCount).fill(null);
+  sources.forEach((source, index) => {
+    // Expanded statements map to their first output line
+    if (source !== null && lineMap[source] === null) {
+      lineMap[source] = index + 1;
+    }
+  });
+  return { formatted: lines.join('\n') + '\n', lineMap };
+}
+
+/**
+ * Format Mermaid source and return the changes as minimal text edits
+ * instead of the full output, for editors that apply edits in place.
+ *
+ * @param input - Mermaid diagram source code
+ * @param options - Formatting options
+ * @returns Sorted, non-overlapping edits; empty when already formatted
+ *
+ * @example
+ * ```ts
+ * formatEdits('sequenceDiagram\nA ->> B: hi\n');
+ * // [{ start: 16, end: 16, newText: '    ' }]
+ * ```
+ */
+export function formatEdits(
+  input: string,
+  options?: FormatOptions
+): TextEdit[] {
+  return diffEdits(input, formatMermaid(input, options));
+}
+
+/**
+ * Apply safe lint fixes, then format the result.
+ *
+ * @param input - Mermaid diagram source code
+ * @param lintOptions - Rule level overrides; allowed rules are not fixed
+ * @param options - Formatting options
+ * @returns Formatted output and the diagnostics that were fixed
+ *
+ * @example
+ * ```ts
+ * import { fixMermaid } from 'mermaid-formatter';
+ *
+ * const { output, applied } = fixMermaid(`
+ * sequenceDiagram
+ *     loop Every minute
+ *         A->>B: ping
+ * `);
+ * // output ends with "    end"
+ * ```
+ */
+export function fixMermaid(
+  input: string,
+  lintOptions: LintOptions = {},
+  options?: FormatOptions
+): FixResult {
+  const { output, applied } = applyFixes(input, lintOptions);
+  return { output: formatMermaid(output, options), applied };
+}
+
 /**
  * Format Mermaid code blocks in Markdown.
  *
@@ -102,6 +392,47 @@ export function formatMarkdownMermaidBlocks(
   );
 }
 
+/**
+ * Format every mermaid fence in a Markdown document, reporting failures
+ * per fence. Fence indentation and line endings are preserved.
+ *
+ * @param markdown - Markdown content
+ * @param options - Formatting options
+ * @returns Markdown with formatted Mermaid fences
+ * @throws MarkdownFormatError listing each fence that failed, by line
+ *
+ * @example
+ * ```ts
+ * try {
+ *   html = render(formatMarkdown(page));
+ * } catch (err) {
+ *   if (err instanceof MarkdownFormatError) {
+ *     for (const { line, message } of err.errors) warn(line, message);
+ *   }
+ * }
+ * ```
+ */
+export function formatMarkdown(
+  markdown: string,
+  options?: FormatOptions
+): string {
+  const errors: FenceError[] = [];
+  const output = replaceMermaidFences(markdown, (fence) => {
+    try {
+      return formatMermaid(fence.content, options);
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err);
+      errors.push({ line: fence.line, message });
+      return undefined;
+    }
+  });
+  // Fences are replaced last to first
+  if (errors.length > 0) {
+    throw new MarkdownFormatError(errors.reverse(), output);
+  }
+  return output;
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
@@ -113,6 +444,79 @@ function ensureTrailingNewline(input: string): string {
   return input.endsWith('\n') ? input : input + '\n';
 }
 
+/**
+ * Check `options.limits` for input that is passed through unparsed.
+ */
+function enforceLimits(input: string, options?: FormatOptions): void {
+  if (options?.limits) parse(input, options.limits);
+}
+
+/**
+ * Map an input offset to an output offset using the output line sources.
+ */
+function mapCursorOffset(
+  input: string,
+  offset: number,
+  lines: string[],
+  sources: (number | null)[]
+): number {
+  // Locate the cursor's input line and column
+  const inputLines = input.split('\n');
+  let line = 0;
+  let lineStart = 0;
+  while (
+    line < inputLines.length - 1 &&
+    lineStart + inputLines[line].length < offset
+  ) {
+    lineStart += inputLines[line].length + 1;
+    line++;
+  }
+  const column = Math.max(
+    0,
+    Math.min(offset - lineStart, inputLines[line].length)
+  );
+
+  let target = sources.indexOf(line);
+  let outputColumn: number;
+  if (target !== -1) {
+    const text = inputLines[line];
+    const visible = text.slice(0, column).replace(/\s/g, '').length;
+    // Stick to the next character when the cursor is right before one
+    const beforeChar = column < text.length && /\S/.test(text[column]);
+    outputColumn = visibleColumn(lines[target], visible, beforeChar);
+  } else {
+    // Dropped line: move to the start of the next kept line
+    target = sources.findIndex((source) => source !== null && source > line);
+    if (target === -1) return lines.join('\n').length;
+    outputColumn = lines[target].length - lines[target].trimStart().length;
+  }
+
+  const targetStart = lines
+    .slice(0, target)
+    .reduce((sum, text) => sum + text.length + 1, 0);
+  return targetStart + outputColumn;
+}
+
+/**
+ * Column after the first `visible` non-whitespace characters of a line:
+ * right before the next one when `beforeChar` is set (or nothing precedes),
+ * otherwise right after the last one.
+ */
+function visibleColumn(
+  text: string,
+  visible: number,
+  beforeChar: boolean
+): number {
+  let seen = 0;
+  for (let i = 0; i < text.length; i++) {
+    if (/\s/.test(text[i])) continue;
+    if (seen === visible && (beforeChar || visible === 0)) return i;
+    seen++;
+    if (seen === visible && !beforeChar) return i + 1;
+  }
+  return text.length;
+}
+
 /**
  * Apply indentation to each non-empty line.
  */
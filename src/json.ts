@@ -0,0 +1,118 @@
+/**
+ * JSON interchange for the diagram AST, so external tools (visualizers,
+ * linters, code generators) can consume parsed diagrams or produce ones
+ * for the formatter to print.
+ */
+
+import type { Diagram, DiagramType, StatementType } from './types.js';
+import { DIAGRAM_PATTERNS } from './rules.js';
+
+/** Every statement type; keyed so a new type fails to compile until added */
+const STATEMENT_TYPES: Record<StatementType, true> = {
+  'diagram-decl': true,
+  directive: true,
+  participant: true,
+  autonumber: true,
+  link: true,
+  activate: true,
+  deactivate: true,
+  'arrow-message': true,
+  'block-start': true,
+  'brace-block-start': true,
+  'block-option': true,
+  'block-else': true,
+  'block-and': true,
+  'block-end': true,
+  'brace-block-end': true,
+  note: true,
+  comment: true,
+  'attribute-map': true,
+  annotation: true,
+  'class-relation': true,
+  'state-stereotype': true,
+  'state-separator': true,
+  'gantt-setting': true,
+  'timeline-event': true,
+  'mindmap-node': true,
+  'mindmap-decoration': true,
+  'generic-line': true,
+  unknown: true,
+  frontmatter: true,
+  'blank-line': true,
+};
+
+const DIAGRAM_TYPES = new Set<DiagramType>([
+  ...DIAGRAM_PATTERNS.map(([, type]) => type),
+  'unknown',
+]);
+
+/**
+ * Serialize a diagram AST to JSON.
+ *
+ * @param diagram - Parsed or built diagram
+ * @param indent - Spaces of indentation (default: 2; 0 for one line)
+ */
+export function diagramToJSON(diagram: Diagram, indent = 2): string {
+  return JSON.stringify(diagram, null, indent || undefined);
+}
+
+/**
+ * Read a diagram AST from JSON text or an already parsed value.
+ *
+ * Checks the diagram type, that every statement has a known `type` and a
+ * string `content`; other fields are passed through as given.
+ *
+ * @throws Error naming the first invalid field
+ *
+ * @example
+ * ```ts
+ * format(diagramFromJSON(diagramToJSON(parse(input))));
+ * ```
+ */
+export function diagramFromJSON(json: string | unknown): Diagram {
+  const value: unknown = typeof json === 'string' ? JSON.parse(json) : json;
+  if (!isObject(value)) {
+    throw new Error('Invalid diagram JSON: expected an object');
+  }
+  if (!DIAGRAM_TYPES.has(value.type as DiagramType)) {
+    throw new Error(
+      `Invalid diagram JSON: unknown diagram type ${JSON.stringify(value.type)}`
+    );
+  }
+  if (!Array.isArray(value.statements)) {
+    throw new Error('Invalid diagram JSON: "statements" must be an array');
+  }
+
+  value.statements.forEach((stmt: unknown, index) => {
+    const at = `statements[${index}]`;
+    if (!isObject(stmt)) {
+      throw new Error(`Invalid diagram JSON: ${at} must be an object`);
+    }
+    if (!Object.hasOwn(STATEMENT_TYPES, String(stmt.type))) {
+      throw new Error(
+        `Invalid diagram JSON: ${at}.type ${JSON.stringify(stmt.type)} ` +
+          'is not a statement type'
+      );
+    }
+    if (typeof stmt.content !== 'string') {
+      throw new Error(`Invalid diagram JSON: ${at}.content must be a string`);
+    }
+    if (
+      stmt.trailingComment !== undefined &&
+      typeof stmt.trailingComment !== 'string'
+    ) {
+      throw new Error(
+        `Invalid diagram JSON: ${at}.trailingComment must be a string`
+      );
+    }
+    if (stmt.raw !== undefined && typeof stmt.raw !== 'string') {
+      throw new Error(`Invalid diagram JSON: ${at}.raw must be a string`);
+    }
+  });
+
+  return value as unknown as Diagram;
+}
+
+function isObject(value: unknown): value is Record<string, unknown> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
@@ -0,0 +1,1169 @@
+/**
+ * Semantic checks beyond formatting.
+ * Each rule has a stable ID and a default level that callers can override.
+ */
+
+import type { Diagram, Statement } from './types.js';
+import { matchBlocks, parse } from './parser.js';
+import {
+  isDeclarableParticipant,
+  matchGanttTask,
+  matchInitDirective,
+  matchJourneyTask,
+  matchParticipantDecl,
+  matchParticipantId,
+  matchRequirementRelation,
+  REQUIREMENT_RELATIONS,
+} from './rules.js';
+import { parseFlowchartLine } from './flowchart.js';
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/** Severity of a reported diagnostic */
+export type Severity = 'error' | 'warning';
+
+/** Configured level of a rule: allow (off), warn, or deny (error) */
+export type RuleLevel = 'allow' | 'warn' | 'deny';
+
+/** 1-based line and column */
+export interface Position {
+  line: number;
+  column: number;
+}
+
+/** Source range of a diagnostic (end column is exclusive) */
+export interface Span {
+  start: Position;
+  end: Position;
+}
+
+/** Replace `deleteCount` source lines at `index` with `insert` */
+export interface LineEdit {
+  /** 0-based line index; the line count appends */
+  index: number;
+  deleteCount: number;
+  insert: string[];
+}
+
+/** A lint finding with its rule ID, severity, and location */
+export interface Diagnostic {
+  ruleId: string;
+  severity: Severity;
+  message: string;
+  span: Span;
+  /** Safe automatic fix, when the rule provides one */
+  fix?: LineEdit;
+}
+
+/** Fixed source and the diagnostics whose fixes were applied */
+export interface FixResult {
+  output: string;
+  applied: Diagnostic[];
+}
+
+/** Lint configuration */
+export interface LintOptions {
+  /** Per-rule level overrides, keyed by rule ID */
+  rules?: Record<string, RuleLevel>;
+}
+
+/** Input available to every rule */
+export interface LintContext {
+  diagram: Diagram;
+  /** Source lines; statements[i] was parsed from lines[i] */
+  lines: string[];
+}
+
+/** A rule's raw finding before severity is applied */
+export interface Finding {
+  message: string;
+  /** 0-based statement index (same as line index) */
+  index: number;
+  /** 0-based offsets into the statement content; the whole line if absent */
+  range?: { start: number; end: number };
+  fix?: LineEdit;
+}
+
+/** Lint rule definition */
+export interface LintRule {
+  id: string;
+  description: string;
+  defaultLevel: RuleLevel;
+  check: (context: LintContext) => Finding[];
+}
+
+// ============================================================================
+// Rules
+// ============================================================================
+
+const missingDiagramType: LintRule = {
+  id: 'missing-diagram-type',
+  description: 'Diagram has no recognized type declaration',
+  defaultLevel: 'deny',
+  check: ({ diagram }) => {
+    if (diagram.type !== 'unknown') return [];
+    const index = diagram.statements.findIndex(
+      (s) => s.type !== 'blank-line' && s.type !== 'comment'
+    );
+    return [
+      {
+        message: 'No diagram type declaration (e.g., "flowchart TD") found',
+        index: Math.max(index, 0),
+      },
+    ];
+  },
+};
+
+const emptyBlock: LintRule = {
+  id: 'empty-block',
+  description: 'Block or brace block has no content',
+  defaultLevel: 'warn',
+  check: ({ diagram }) => {
+    const findings: Finding[] = [];
+    const { statements } = diagram;
+    statements.forEach((stmt, index) => {
+      if (stmt.type !== 'block-start' && stmt.type !== 'brace-block-start') {
+        return;
+      }
+      const next = nextContentIndex(statements, index + 1);
+      const closer =
+        stmt.type === 'block-start' ? 'block-end' : 'brace-block-end';
+      if (next !== -1 && statements[next].type === closer) {
+        findings.push({ message: `Empty '${stmt.blockKind}' block`, index });
+      }
+    });
+    return findings;
+  },
+};
+
+const undeclaredParticipant: LintRule = {
+  id: 'undeclared-participant',
+  description:
+    'Sequence message, note, or activation uses an undeclared participant',
+  defaultLevel: 'warn',
+  check: ({ diagram, lines }) => {
+    if (diagram.type !== 'sequenceDiagram') return [];
+    const declared = new Set<string>();
+    let lastDeclaration = -1;
+    diagram.statements.forEach((stmt, index) => {
+      if (stmt.type !== 'participant') return;
+      const id = matchParticipantId(stmt.content);
+      if (id) declared.add(id);
+      lastDeclaration = index;
+    });
+    // Implicit participants are only flagged when some are declared
+    if (lastDeclaration === -1) return [];
+
+    const indent = leadingWhitespace(lines[lastDeclaration]);
+    const findings: Finding[] = [];
+    diagram.statements.forEach((stmt, index) => {
+      for (const id of referencedParticipants(stmt)) {
+        if (declared.has(id)) continue;
+        declared.add(id);
+        // IDs a declaration cannot spell are reported without a fix
+        const fix = isDeclarableParticipant(id)
+          ? {
+              index: lastDeclaration + 1,
+              deleteCount: 0,
+              insert: [`${indent}participant ${id}`],
+            }
+          : undefined;
+        findings.push({
+          message: `Participant '${id}' is used but not declared`,
+          index,
+          fix,
+        });
+      }
+    });
+    return findings;
+  },
+};
+
+/**
+ * Participants a sequence statement refers to.
+ */
+function referencedParticipants(stmt: Statement): string[] {
+  switch (stmt.type) {
+    case 'arrow-message':
+      return [stmt.from, stmt.to];
+    case 'note':
+      return stmt.participants ?? [];
+    case 'activate':
+    case 'deactivate':
+    case 'link':
+      return [stmt.participant];
+    default:
+      return [];
+  }
+}
+
+/** Style class definition, e.g. "classDef warn,error fill:#f00" */
+const CLASS_DEF_PATTERN = /^classDef\s+([\w-]+(?:\s*,\s*[\w-]+)*)/;
+
+/** Class usages: "A:::name", "class A,B name", 'cssClass "A,B" name' */
+const CLASS_USAGE_PATTERNS = [
+  /:::([\w-]+)/g,
+  /^class\s+\S+\s+([\w-]+);?$/g,
+  /^cssClass\s+"[^"]*"\s+([\w-]+)/g,
+];
+
+const unusedClassDef: LintRule = {
+  id: 'unused-class-def',
+  description: 'classDef is never applied to any node',
+  defaultLevel: 'warn',
+  check: ({ diagram }) => {
+    const used = new Set<string>(['default']);
+    const definitions: { names: string[]; index: number }[] = [];
+    diagram.statements.forEach((stmt, index) => {
+      if (stmt.type !== 'generic-line') return;
+      const def = stmt.content.match(CLASS_DEF_PATTERN);
+      if (def) {
+        definitions.push({ names: def[1].split(/\s*,\s*/), index });
+        return;
+      }
+      for (const pattern of CLASS_USAGE_PATTERNS) {
+        for (const match of stmt.content.matchAll(pattern)) {
+          used.add(match[1]);
+        }
+      }
+    });
+
+    // Only whole definitions are removable; "classDef a,b" with one used stays
+    return definitions
+      .filter(({ names }) => names.every((name) => !used.has(name)))
+      .map(({ names, index }) => ({
+        message: `classDef '${names.join(',')}' is never used`,
+        index,
+        fix: { index, deleteCount: 1, insert: [] },
+      }));
+  },
+};
+
+const unclosedBlock: LintRule = {
+  id: 'unclosed-block',
+  description: 'Block or brace block is missing its closing line',
+  defaultLevel: 'deny',
+  check: ({ diagram, lines }) => {
+    const { open } = matchBlocks(diagram.statements);
+
+    // Close after the last content line, innermost block first
+    const end = lastContentIndex(lines) + 1;
+    return open.reverse().map((index) => {
+      const stmt = diagram.statements[index];
+      const closer = stmt.type === 'block-start' ? 'end' : '}';
+      const kind = stmt.type === 'block-start' ? stmt.blockKind : '{';
+      return {
+        message: `'${kind}' block is never closed`,
+        index,
+        fix: {
+          index: end,
+          deleteCount: 0,
+          insert: [leadingWhitespace(lines[index]) + closer],
+        },
+      };
+    });
+  },
+};
+
+const conflictingDefinition: LintRule = {
+  id: 'conflicting-definition',
+  description:
+    'Flowchart node or sequence participant redefined with a different label',
+  defaultLevel: 'warn',
+  check: ({ diagram }) => {
+    // First definition of each ID: its label and 0-based line
+    const first = new Map<string, { label: string; index: number }>();
+    const findings: Finding[] = [];
+    const define = (
+      id: string,
+      label: string,
+      index: number,
+      what: string
+    ) => {
+      const previous = first.get(id);
+      if (!previous) {
+        first.set(id, { label, index });
+      } else if (previous.label !== label) {
+        findings.push({
+          message:
+            `${what} '${id}' is redefined as ${label} ` +
+            `(first defined as ${previous.label} on line ${previous.index + 1})`,
+          index,
+        });
+      }
+    };
+
+    diagram.statements.forEach((stmt, index) => {
+      if (diagram.type === 'sequenceDiagram' && stmt.type === 'participant') {
+        const decl = matchParticipantDecl(stmt.content);
+        if (decl?.alias) {
+          define(decl.id, `'${decl.alias}'`, index, 'Participant');
+        }
+      } else if (
+        (diagram.type === 'flowchart' || diagram.type === 'graph') &&
+        stmt.type === 'generic-line'
+      ) {
+        const nodes = parseFlowchartLine(stmt.content)?.groups.flat() ?? [];
+        for (const node of nodes) {
+          if (!node.shape) continue;
+          const { open, text, close } = node.shape;
+          define(node.id, `${open}${text.trim()}${close}`, index, 'Node');
+        }
+      }
+    });
+    return findings;
+  },
+};
+
+const endNodeId: LintRule = {
+  id: 'end-node-id',
+  description: "Flowchart node named 'end', which Mermaid reads as a keyword",
+  defaultLevel: 'warn',
+  check: ({ diagram }) => {
+    if (diagram.type !== 'flowchart' && diagram.type !== 'graph') return [];
+    const findings: Finding[] = [];
+    diagram.statements.forEach((stmt, index) => {
+      if (stmt.type !== 'generic-line') return;
+      const nodes = parseFlowchartLine(stmt.content)?.groups.flat() ?? [];
+      if (nodes.some((node) => node.id === 'end')) {
+        findings.push({
+          message:
+            "Node 'end' is read as a keyword; capitalize the ID and quote " +
+            'the label instead, e.g. End["end"]',
+          index,
+        });
+      }
+    });
+    return findings;
+  },
+};
+
+const invalidGanttDate: LintRule = {
+  id: 'invalid-gantt-date',
+  description: "Gantt task date does not match the diagram's dateFormat",
+  defaultLevel: 'warn',
+  check: ({ diagram }) => {
+    const format = ganttDateFormat(diagram);
+    const matches = dateFormatMatcher(format);
+    if (!matches) return [];
+    return ganttTasks(diagram).flatMap(({ index, start, end }) => {
+      const findings: Finding[] = [];
+      const report = (field: GanttField, what: string) =>
+        findings.push({
+          message:
+            `${what} '${field.text}' does not match ` +
+            `dateFormat '${format}'`,
+          index,
+          range: fieldRange(field),
+        });
+      if (start?.text && !/^after\s/.test(start.text) && !matches(start.text)) {
+        report(start, 'Start date');
+      }
+      if (
+        end.text &&
+        !/^until\s/.test(end.text) &&
+        !DURATION_PATTERN.test(end.text) &&
+        !DURATION_LIKE_PATTERN.test(end.text) &&
+        !matches(end.text)
+      ) {
+        report(end, 'End date');
+      }
+      return findings;
+    });
+  },
+};
+
+const invalidGanttDuration: LintRule = {
+  id: 'invalid-gantt-duration',
+  description: 'Gantt task duration has an unknown unit',
+  defaultLevel: 'warn',
+  check: ({ diagram }) => {
+    const matches = dateFormatMatcher(ganttDateFormat(diagram));
+    return ganttTasks(diagram)
+      .filter(
+        ({ end }) =>
+          DURATION_LIKE_PATTERN.test(end.text) &&
+          !DURATION_PATTERN.test(end.text) &&
+          !matches?.(end.text)
+      )
+      .map(({ index, end }) => ({
+        message:
+          `Duration '${end.text}' has an unknown unit; ` +
+          'use ms, s, m, h, d, w, M, or y (e.g. 3d)',
+        index,
+        range: fieldRange(end),
+      }));
+  },
+};
+
+const undefinedGanttTask: LintRule = {
+  id: 'undefined-gantt-task',
+  description: "Gantt 'after' or 'until' refers to an undefined task ID",
+  defaultLevel: 'warn',
+  check: ({ diagram }) => {
+    const tasks = ganttTasks(diagram);
+    // Tasks may refer to tasks defined further down
+    const ids = new Set(tasks.flatMap(({ id }) => (id ? [id.text] : [])));
+    return tasks.flatMap(({ index, start, end }) =>
+      [start, end].flatMap((field) => {
+        const match = field?.text.match(/^(after|until)\s+(.*)$/);
+        if (!field || !match) return [];
+        const [, keyword, list] = match;
+        let offset = field.start + field.text.length - list.length;
+        return list.split(/(\s+)/).flatMap((word) => {
+          const start = offset;
+          offset += word.length;
+          if (!word.trim() || ids.has(word)) return [];
+          return [
+            {
+              message: `Task '${word}' in '${keyword}' is not defined`,
+              index,
+              range: { start, end: offset },
+            },
+          ];
+        });
+      })
+    );
+  },
+};
+
+const invalidJourneyScore: LintRule = {
+  id: 'invalid-journey-score',
+  description: 'Journey task score is not a whole number from 1 to 7',
+  defaultLevel: 'warn',
+  check: ({ diagram }) =>
+    journeyTasks(diagram)
+      .filter(({ score }) => !/^[1-7]$/.test(score))
+      .map(({ index, content, name, score }) => {
+        if (!score) return { message: `Task '${name}' has no score`, index };
+        const start = content.indexOf(score, content.indexOf(':') + 1);
+        return {
+          message: `Score '${score}' of task '${name}' is not 1-7`,
+          index,
+          range: { start, end: start + score.length },
+        };
+      }),
+};
+
+const missingJourneyActors: LintRule = {
+  id: 'missing-journey-actors',
+  description: 'Journey task lists no actors',
+  defaultLevel: 'warn',
+  check: ({ diagram }) =>
+    journeyTasks(diagram)
+      .filter(({ actors }) => actors.length === 0)
+      .map(({ index, name }) => ({
+        message:
+          `Task '${name}' has no actors; ` +
+          `list them after the score, e.g. '${name}: 5: Me'`,
+        index,
+      })),
+};
+
+/** Journey task line with its statement index and content */
+interface JourneyTask {
+  index: number;
+  content: string;
+  name: string;
+  score: string;
+  actors: string[];
+}
+
+function journeyTasks(diagram: Diagram): JourneyTask[] {
+  if (diagram.type !== 'journey') return [];
+  return diagram.statements.flatMap((stmt, index) => {
+    if (stmt.type !== 'generic-line') return [];
+    const task = matchJourneyTask(stmt.content);
+    return task ? [{ index, content: stmt.content, ...task }] : [];
+  });
+}
+
+const unknownRequirementRelation: LintRule = {
+  id: 'unknown-requirement-relation',
+  description: 'Requirement relationship uses an unknown keyword',
+  defaultLevel: 'warn',
+  check: ({ diagram }) => {
+    if (diagram.type !== 'requirementDiagram') return [];
+    return diagram.statements.flatMap((stmt, index) => {
+      if (stmt.type !== 'generic-line') return [];
+      const relation = matchRequirementRelation(stmt.content)?.relation;
+      if (!relation) return [];
+      if (REQUIREMENT_RELATIONS.includes(relation.toLowerCase())) return [];
+      const start = stmt.content.search(new RegExp(`\\b${relation}\\b`));
+      return [
+        {
+          message:
+            `Unknown requirement relationship '${relation}'; expected ` +
+            `one of ${REQUIREMENT_RELATIONS.join(', ')}`,
+          index,
+          range: { start, end: start + relation.length },
+        },
+      ];
+    });
+  },
+};
+
+const invalidInitDirective: LintRule = {
+  id: 'invalid-init-directive',
+  description: 'Init directive config is not a JSON object',
+  defaultLevel: 'warn',
+  check: ({ diagram }) =>
+    initDirectives(diagram)
+      .filter(({ config }) => config === null)
+      .map(({ index }) => ({
+        message: 'Init directive config is not a valid JSON object',
+        index,
+      })),
+};
+
+const invalidConfigValue: LintRule = {
+  id: 'invalid-config-value',
+  description: 'Init directive sets a known config key to an invalid value',
+  defaultLevel: 'warn',
+  check: ({ diagram }) =>
+    configIssues(diagram)
+      .filter((issue) => issue.expected !== undefined)
+      .map(({ index, content, offset, path, expected, value }) => ({
+        message:
+          `Config '${path.join('.')}' must be ${expected}, ` +
+          `got ${JSON.stringify(value)}`,
+        index,
+        range: configKeyRange(content, offset, path),
+      })),
+};
+
+const unknownConfigKey: LintRule = {
+  id: 'unknown-config-key',
+  description: 'Init directive sets a config key Mermaid does not know',
+  defaultLevel: 'allow',
+  check: ({ diagram }) =>
+    configIssues(diagram)
+      .filter((issue) => issue.expected === undefined)
+      .map(({ index, content, offset, path }) => ({
+        message: `Unknown config key '${path.join('.')}'`,
+        index,
+        range: configKeyRange(content, offset, path),
+      })),
+};
+
+/** Init directive with its statement index and content */
+interface InitDirectiveLine {
+  index: number;
+  content: string;
+  offset: number;
+  config: Record<string, unknown> | null;
+}
+
+function initDirectives(diagram: Diagram): InitDirectiveLine[] {
+  return diagram.statements.flatMap((stmt, index) => {
+    if (stmt.type !== 'directive') return [];
+    const directive = matchInitDirective(stmt.content);
+    return directive ? [{ index, content: stmt.content, ...directive }] : [];
+  });
+}
+
+/** Unknown key (no `expected`) or invalid value of a known key */
+interface ConfigIssue extends InitDirectiveLine {
+  path: string[];
+  expected?: string;
+  value?: unknown;
+}
+
+function configIssues(diagram: Diagram): ConfigIssue[] {
+  return initDirectives(diagram).flatMap((directive) => {
+    const issues: ConfigIssue[] = [];
+    const visit = (
+      config: Record<string, unknown>,
+      schema: ConfigSchema,
+      path: string[]
+    ) => {
+      for (const [key, value] of Object.entries(config)) {
+        const at = [...path, key];
+        const type = Object.hasOwn(schema, key) ? schema[key] : undefined;
+        if (type === undefined) {
+          issues.push({ ...directive, path: at });
+          continue;
+        }
+        if (isConfigSchema(type) && isConfigObject(value)) {
+          visit(value, type, at);
+          continue;
+        }
+        const expected = configMismatch(type, value);
+        if (expected) issues.push({ ...directive, path: at, expected, value });
+      }
+    };
+    if (directive.config) visit(directive.config, CONFIG_SCHEMA, []);
+    return issues;
+  });
+}
+
+const unknownStatement: LintRule = {
+  id: 'unknown-statement',
+  description: 'Line uses syntax the formatter does not model',
+  defaultLevel: 'warn',
+  check: ({ diagram }) =>
+    diagram.statements.flatMap((stmt, index) =>
+      stmt.type === 'unknown'
+        ? [
+            {
+              message:
+                `Unrecognized ${diagram.type} syntax; ` +
+                'the formatter keeps this line as written',
+              index,
+            },
+          ]
+        : []
+    ),
+};
+
+const unmatchedClose: LintRule = {
+  id: 'unmatched-close',
+  description: "Stray 'end' or '}' without a matching block opener",
+  defaultLevel: 'deny',
+  check: ({ diagram }) =>
+    matchBlocks(diagram.statements).stray.map((index) => ({
+      message: `'${diagram.statements[index].content}' has no matching opener`,
+      index,
+    })),
+};
+
+/**
+ * All built-in rules, in reporting order.
+ */
+export const LINT_RULES: readonly LintRule[] = [
+  missingDiagramType,
+  emptyBlock,
+  undeclaredParticipant,
+  unusedClassDef,
+  conflictingDefinition,
+  endNodeId,
+  invalidGanttDate,
+  invalidGanttDuration,
+  undefinedGanttTask,
+  invalidJourneyScore,
+  missingJourneyActors,
+  unknownRequirementRelation,
+  invalidInitDirective,
+  invalidConfigValue,
+  unknownConfigKey,
+  unknownStatement,
+  unclosedBlock,
+  unmatchedClose,
+];
+
+// ============================================================================
+// Main Lint Function
+// ============================================================================
+
+/**
+ * Lint Mermaid diagram source.
+ *
+ * @param input - Mermaid diagram source code
+ * @param options - Rule level overrides
+ * @returns Diagnostics sorted by line
+ */
+export function lint(input: string, options: LintOptions = {}): Diagnostic[] {
+  return sortByLine(runRules(input.split('\n'), options));
+}
+
+/**
+ * Lint a parsed or built diagram. Without source text, each statement's
+ * content stands in for its line, so spans and fixes refer to those lines.
+ *
+ * @param diagram - Diagram AST
+ * @param options - Rule level overrides
+ * @returns Diagnostics sorted by line
+ */
+export function lintDiagram(
+  diagram: Diagram,
+  options: LintOptions = {}
+): Diagnostic[] {
+  const lines = diagram.statements.map((stmt) => stmt.content);
+  return sortByLine(runRules(lines, options, diagram));
+}
+
+/**
+ * Apply the safe fixes of all enabled rules. The output is not formatted.
+ *
+ * @param input - Mermaid diagram source code
+ * @param options - Rule level overrides; allowed rules are not fixed
+ * @returns Fixed source and the diagnostics that were fixed
+ */
+export function applyFixes(
+  input: string,
+  options: LintOptions = {}
+): FixResult {
+  const lines = input.split('\n');
+  const applied = runRules(lines, options).filter((d) => d.fix);
+  const edits = applied.flatMap((d) => (d.fix ? [d.fix] : []));
+  return {
+    output: applyLineEdits(lines, edits).join('\n'),
+    applied: sortByLine(applied),
+  };
+}
+
+/**
+ * Run enabled rules in rule order, so fixes apply in a stable order.
+ */
+function runRules(
+  lines: string[],
+  options: LintOptions,
+  diagram: Diagram = parse(lines.join('\n'))
+): Diagnostic[] {
+  const context: LintContext = { diagram, lines };
+  const diagnostics: Diagnostic[] = [];
+
+  for (const rule of LINT_RULES) {
+    const level = options.rules?.[rule.id] ?? rule.defaultLevel;
+    if (level === 'allow') continue;
+    const severity: Severity = level === 'deny' ? 'error' : 'warning';
+
+    for (const finding of rule.check(context)) {
+      diagnostics.push({
+        ruleId: rule.id,
+        severity,
+        message: finding.message,
+        span: finding.range
+          ? rangeSpan(lines, finding.index, finding.range)
+          : lineSpan(lines, finding.index),
+        ...(finding.fix && { fix: finding.fix }),
+      });
+    }
+  }
+
+  return diagnostics;
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+/**
+ * Span covering the trimmed content of a line.
+ */
+function lineSpan(lines: string[], index: number): Span {
+  const text = (lines[index] ?? '').replace(/\s+$/, '');
+  const column = text.length - text.trimStart().length + 1;
+  return {
+    start: { line: index + 1, column },
+    end: { line: index + 1, column: text.length + 1 },
+  };
+}
+
+/**
+ * Span of offsets into the statement content of a line, which starts at
+ * the line's first non-blank character.
+ */
+function rangeSpan(
+  lines: string[],
+  index: number,
+  range: { start: number; end: number }
+): Span {
+  const { start } = lineSpan(lines, index);
+  return {
+    start: { line: index + 1, column: start.column + range.start },
+    end: { line: index + 1, column: start.column + range.end },
+  };
+}
+
+function sortByLine(diagnostics: Diagnostic[]): Diagnostic[] {
+  return [...diagnostics].sort(
+    (a, b) => a.span.start.line - b.span.start.line
+  );
+}
+
+/**
+ * Apply line edits against the original line indices.
+ * Insertions at the same index keep their order.
+ */
+function applyLineEdits(lines: string[], edits: LineEdit[]): string[] {
+  const deleted = new Set<number>();
+  for (const edit of edits) {
+    for (let i = 0; i < edit.deleteCount; i++) deleted.add(edit.index + i);
+  }
+
+  const output: string[] = [];
+  for (let i = 0; i <= lines.length; i++) {
+    for (const edit of edits) {
+      if (edit.index === i) output.push(...edit.insert);
+    }
+    if (i < lines.length && !deleted.has(i)) output.push(lines[i]);
+  }
+  return output;
+}
+
+function leadingWhitespace(line: string): string {
+  return line.match(/^\s*/)?.[0] ?? '';
+}
+
+function lastContentIndex(lines: string[]): number {
+  for (let i = lines.length - 1; i >= 0; i--) {
+    if (lines[i].trim()) return i;
+  }
+  return -1;
+}
+
+/**
+ * Index of the next statement that is not blank or a comment, or -1.
+ */
+function nextContentIndex(statements: Statement[], from: number): number {
+  for (let i = from; i < statements.length; i++) {
+    const type = statements[i].type;
+    if (type !== 'blank-line' && type !== 'comment') return i;
+  }
+  return -1;
+}
+
+// ============================================================================
+// Gantt Tasks
+// ============================================================================
+
+/** A comma-separated task field and its offset in the statement content */
+interface GanttField {
+  text: string;
+  start: number;
+}
+
+/** Task fields by role: "Name : id, start, end" after leading tags */
+interface GanttTask {
+  index: number;
+  id?: GanttField;
+  start?: GanttField;
+  end: GanttField;
+}
+
+/** Task tags that come before the ID and dates */
+const GANTT_TAGS = new Set(['active', 'done', 'crit', 'milestone', 'vert']);
+
+/** Durations Mermaid accepts, e.g. "3d", "1.5h", or "250ms" */
+const DURATION_PATTERN = /^\d+(?:\.\d+)?(?:ms|[smhdwMy])$/;
+
+/** A number with a unit, e.g. "3days" */
+const DURATION_LIKE_PATTERN = /^\d+(?:\.\d+)?\s*[a-zA-Z]+$/;
+
+/**
+ * Declared dateFormat of a gantt diagram; the last one applies to all
+ * tasks, and Mermaid's default is YYYY-MM-DD.
+ */
+function ganttDateFormat(diagram: Diagram): string {
+  let format = 'YYYY-MM-DD';
+  for (const stmt of diagram.statements) {
+    if (stmt.type === 'gantt-setting' && stmt.keyword === 'dateFormat') {
+      format = stmt.value;
+    }
+  }
+  return format;
+}
+
+/**
+ * Tasks of a gantt diagram with their fields assigned as Mermaid does: one
+ * field is the end, two are start and end, three are ID, start, and end.
+ */
+function ganttTasks(diagram: Diagram): GanttTask[] {
+  if (diagram.type !== 'gantt') return [];
+  const tasks: GanttTask[] = [];
+  diagram.statements.forEach((stmt, index) => {
+    if (stmt.type !== 'generic-line' || !matchGanttTask(stmt.content)) return;
+    const colon = stmt.content.indexOf(':');
+    const fields: GanttField[] = [];
+    let offset = colon + 1;
+    for (const part of stmt.content.slice(colon + 1).split(',')) {
+      const text = part.trim();
+      fields.push({ text, start: offset + part.indexOf(text) });
+      offset += part.length + 1;
+    }
+    while (fields.length > 1 && GANTT_TAGS.has(fields[0].text)) {
+      fields.shift();
+    }
+    const [first, second, third] = fields;
+    if (!first.text) return;
+    if (fields.length === 1) tasks.push({ index, end: first });
+    else if (fields.length === 2) {
+      tasks.push({ index, start: first, end: second });
+    } else tasks.push({ index, id: first, start: second, end: third });
+  });
+  return tasks;
+}
+
+function fieldRange({ text, start }: GanttField): {
+  start: number;
+  end: number;
+} {
+  return { start, end: start + text.length };
+}
+
+/** dayjs format tokens and the values they accept */
+const DATE_TOKENS: [string, string, [number, number]?][] = [
+  ['YYYY', '\\d{4}'],
+  ['YY', '\\d{2}'],
+  ['MMMM', '[A-Za-z]+'],
+  ['MMM', '[A-Za-z]{3}'],
+  ['MM', '\\d{2}', [1, 12]],
+  ['M', '\\d{1,2}', [1, 12]],
+  ['Do', '\\d{1,2}(?:st|nd|rd|th)'],
+  ['DD', '\\d{2}', [1, 31]],
+  ['D', '\\d{1,2}', [1, 31]],
+  ['dddd', '[A-Za-z]+'],
+  ['ddd', '[A-Za-z]{3}'],
+  ['HH', '\\d{2}', [0, 23]],
+  ['H', '\\d{1,2}', [0, 23]],
+  ['hh', '\\d{2}', [1, 12]],
+  ['h', '\\d{1,2}', [1, 12]],
+  ['mm', '\\d{2}', [0, 59]],
+  ['m', '\\d{1,2}', [0, 59]],
+  ['ss', '\\d{2}', [0, 59]],
+  ['s', '\\d{1,2}', [0, 59]],
+  ['SSS', '\\d{3}'],
+  ['SS', '\\d{2}'],
+  ['S', '\\d'],
+  ['A', '(?:AM|PM)'],
+  ['a', '(?:am|pm)'],
+  ['ZZ', '[+-]\\d{4}'],
+  ['Z', '(?:Z|[+-]\\d{2}:\\d{2})'],
+  ['X', '-?\\d+(?:\\.\\d+)?'],
+  ['x', '-?\\d+'],
+];
+
+/**
+ * Test for dates written in a dayjs format such as "YYYY-MM-DD HH:mm", or
+ * null when the format uses tokens this check does not know.
+ */
+function dateFormatMatcher(format: string): ((date: string) => boolean) | null {
+  let source = '';
+  const ranges: ([number, number] | undefined)[] = [];
+  let rest = format.trim();
+  while (rest) {
+    const literal = rest.match(/^\[([^\]]*)\]/);
+    const token = DATE_TOKENS.find(([name]) => rest.startsWith(name));
+    if (literal) {
+      source += escapeRegExp(literal[1]);
+      rest = rest.slice(literal[0].length);
+    } else if (token) {
+      source += `(${token[1]})`;
+      ranges.push(token[2]);
+      rest = rest.slice(token[0].length);
+    } else if (/^[a-zA-Z]/.test(rest)) {
+      return null;
+    } else {
+      source += escapeRegExp(rest[0]);
+      rest = rest.slice(1);
+    }
+  }
+  const pattern = new RegExp(`^${source}$`);
+  return (date) => {
+    const match = date.match(pattern);
+    return (
+      match !== null &&
+      ranges.every((range, k) => {
+        if (!range) return true;
+        const value = Number(match[k + 1]);
+        return value >= range[0] && value <= range[1];
+      })
+    );
+  };
+}
+
+function escapeRegExp(text: string): string {
+  return text.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+}
+
+// ============================================================================
+// Config Schema
+// ============================================================================
+
+/**
+ * Expected type of a config value: a primitive type, one of a list of
+ * strings, or a nested schema. 'object' is a free-form map.
+ */
+type ConfigType =
+  | 'string'
+  | 'number'
+  | 'boolean'
+  | 'object'
+  | 'any'
+  | readonly string[]
+  | ConfigSchema;
+
+interface ConfigSchema {
+  readonly [key: string]: ConfigType;
+}
+
+/** Settings shared by every diagram section */
+const DIAGRAM_CONFIG = {
+  useMaxWidth: 'boolean',
+  useWidth: 'number',
+  titleTopMargin: 'number',
+} as const;
+
+/**
+ * Curated subset of the Mermaid config schema: top-level keys and the
+ * sections of the diagram types this formatter parses.
+ */
+const CONFIG_SCHEMA: ConfigSchema = {
+  theme: ['default', 'base', 'dark', 'forest', 'neutral', 'null'],
+  themeVariables: 'object',
+  themeCSS: 'string',
+  look: ['classic', 'handDrawn'],
+  layout: ['dagre', 'elk'],
+  handDrawnSeed: 'number',
+  darkMode: 'boolean',
+  fontFamily: 'string',
+  fontSize: 'number',
+  altFontFamily: 'string',
+  logLevel: 'any',
+  securityLevel: ['strict', 'loose', 'antiscript', 'sandbox'],
+  startOnLoad: 'boolean',
+  arrowMarkerAbsolute: 'boolean',
+  htmlLabels: 'boolean',
+  markdownAutoWrap: 'boolean',
+  wrap: 'boolean',
+  maxTextSize: 'number',
+  maxEdges: 'number',
+  deterministicIds: 'boolean',
+  deterministicIDSeed: 'string',
+  secure: 'any',
+  legacyMathML: 'boolean',
+  forceLegacyMathML: 'boolean',
+  suppressErrorRendering: 'boolean',
+  elk: 'object',
+  flowchart: {
+    ...DIAGRAM_CONFIG,
+    defaultRenderer: ['dagre-d3', 'dagre-wrapper', 'elk'],
+    curve: 'string',
+    htmlLabels: 'boolean',
+    diagramPadding: 'number',
+    nodeSpacing: 'number',
+    rankSpacing: 'number',
+    padding: 'number',
+    wrappingWidth: 'number',
+    subGraphTitleMargin: 'object',
+    arrowMarkerAbsolute: 'boolean',
+    inheritDir: 'boolean',
+  },
+  sequence: {
+    ...DIAGRAM_CONFIG,
+    arrowMarkerAbsolute: 'boolean',
+    hideUnusedParticipants: 'boolean',
+    activationWidth: 'number',
+    diagramMarginX: 'number',
+    diagramMarginY: 'number',
+    actorMargin: 'number',
+    width: 'number',
+    height: 'number',
+    boxMargin: 'number',
+    boxTextMargin: 'number',
+    noteMargin: 'number',
+    messageMargin: 'number',
+    messageAlign: ['left', 'center', 'right'],
+    mirrorActors: 'boolean',
+    forceMenus: 'boolean',
+    bottomMarginAdj: 'number',
+    rightAngles: 'boolean',
+    showSequenceNumbers: 'boolean',
+    actorFontSize: 'any',
+    actorFontFamily: 'string',
+    actorFontWeight: 'any',
+    noteFontSize: 'any',
+    noteFontFamily: 'string',
+    noteFontWeight: 'any',
+    noteAlign: ['left', 'center', 'right'],
+    messageFontSize: 'any',
+    messageFontFamily: 'string',
+    messageFontWeight: 'any',
+    wrap: 'boolean',
+    wrapPadding: 'number',
+    labelBoxWidth: 'number',
+    labelBoxHeight: 'number',
+  },
+  gantt: {
+    ...DIAGRAM_CONFIG,
+    barHeight: 'number',
+    barGap: 'number',
+    topPadding: 'number',
+    rightPadding: 'number',
+    leftPadding: 'number',
+    gridLineStartPadding: 'number',
+    fontSize: 'number',
+    sectionFontSize: 'any',
+    numberSectionStyles: 'number',
+    axisFormat: 'string',
+    tickInterval: 'string',
+    topAxis: 'boolean',
+    displayMode: ['', 'compact'],
+    weekday: [
+      'monday',
+      'tuesday',
+      'wednesday',
+      'thursday',
+      'friday',
+      'saturday',
+      'sunday',
+    ],
+  },
+  class: 'object',
+  state: 'object',
+  er: 'object',
+  journey: 'object',
+  timeline: 'object',
+  mindmap: 'object',
+  gitGraph: 'object',
+  pie: 'object',
+  quadrantChart: 'object',
+  xyChart: 'object',
+  requirement: 'object',
+  sankey: 'object',
+  block: 'object',
+  packet: 'object',
+  architecture: 'object',
+  kanban: 'object',
+  radar: 'object',
+  c4: 'object',
+};
+
+function isConfigSchema(type: ConfigType): type is ConfigSchema {
+  return typeof type === 'object' && !Array.isArray(type);
+}
+
+function isConfigObject(value: unknown): value is Record<string, unknown> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
+
+/**
+ * Describe the expected value when `value` does not fit `type`.
+ */
+function configMismatch(type: ConfigType, value: unknown): string | null {
+  if (type === 'any') return null;
+  if (isConfigSchema(type) || type === 'object') {
+    return isConfigObject(value) ? null : 'an object';
+  }
+  if (Array.isArray(type)) {
+    return type.includes(value as string)
+      ? null
+      : `one of ${type.map((option) => `'${option}'`).join(', ')}`;
+  }
+  return typeof value === type ? null : `a ${type}`;
+}
+
+/**
+ * Range of the last key of `path` in the directive, found by searching
+ * for each quoted key after its parent; the whole line if not found.
+ */
+function configKeyRange(
+  content: string,
+  offset: number,
+  path: string[]
+): { start: number; end: number } | undefined {
+  let start = offset;
+  let length = 0;
+  for (const key of path) {
+    const found = [`"${key}"`, `'${key}'`]
+      .map((quoted) => content.indexOf(quoted, start + length))
+      .filter((at) => at >= 0);
+    if (found.length === 0) return undefined;
+    start = Math.min(...found);
+    length = key.length + 2;
+  }
+  return { start, end: start + length };
+}
@@ -0,0 +1,283 @@
+/**
+ * Minimal Language Server Protocol server over stdio.
+ * Supports full-document sync, formatting, range formatting, and lint
+ * diagnostics, so editors can integrate without spawning a process per save.
+ */
+
+import type { Readable, Writable } from 'node:stream';
+import type { Diagnostic as LintDiagnostic } from './lint.js';
+import type { FormatOptions } from './types.js';
+import { lint } from './lint.js';
+import { diffEdits } from './edits.js';
+import { formatMermaid, formatMarkdownMermaidBlocks } from './index.js';
+
+// ============================================================================
+// Protocol Types
+// ============================================================================
+
+/** JSON-RPC message (request, response, or notification) */
+export interface RpcMessage {
+  jsonrpc: '2.0';
+  id?: number | string | null;
+  method?: string;
+  params?: unknown;
+  result?: unknown;
+  error?: { code: number; message: string };
+}
+
+interface LspPosition {
+  line: number;
+  character: number;
+}
+
+interface LspRange {
+  start: LspPosition;
+  end: LspPosition;
+}
+
+interface TextEdit {
+  range: LspRange;
+  newText: string;
+}
+
+interface TextDocumentParams {
+  textDocument: { uri: string; text?: string };
+  contentChanges?: { text: string }[];
+  options?: { tabSize: number; insertSpaces: boolean };
+}
+
+/** JSON-RPC error codes used by the server */
+const PARSE_ERROR = -32700;
+const METHOD_NOT_FOUND = -32601;
+const REQUEST_FAILED = -32803;
+
+/** LSP DiagnosticSeverity values */
+const SEVERITY_ERROR = 1;
+const SEVERITY_WARNING = 2;
+
+// ============================================================================
+// Server
+// ============================================================================
+
+/**
+ * Create a message handler. Responses and notifications are passed to `send`.
+ */
+export function createLanguageServer(
+  send: (message: RpcMessage) => void
+): (message: RpcMessage) => void {
+  const documents = new Map<string, string>();
+
+  const publishDiagnostics = (uri: string) => {
+    const text = documents.get(uri) ?? '';
+    // Markdown documents are formatted but not linted as a single diagram
+    const diagnostics = isMarkdown(uri) ? [] : lint(text).map(toLspDiagnostic);
+    send({
+      jsonrpc: '2.0',
+      method: 'textDocument/publishDiagnostics',
+      params: { uri, diagnostics },
+    });
+  };
+
+  const formatDocument = (params: TextDocumentParams): TextEdit[] => {
+    const { uri } = params.textDocument;
+    const text = documents.get(uri);
+    if (text === undefined) return [];
+    const options: FormatOptions = params.options
+      ? {
+          indentSize: params.options.tabSize,
+          useTabs: !params.options.insertSpaces,
+        }
+      : {};
+    const formatted = isMarkdown(uri)
+      ? formatMarkdownMermaidBlocks(text, options)
+      : formatMermaid(text, options);
+    return diffEdits(text, formatted).map((edit) => ({
+      range: {
+        start: positionAt(text, edit.start),
+        end: positionAt(text, edit.end),
+      },
+      newText: edit.newText,
+    }));
+  };
+
+  const handlers: Record<string, (params: TextDocumentParams) => unknown> = {
+    initialize: () => ({
+      capabilities: {
+        textDocumentSync: 1,
+        documentFormattingProvider: true,
+        documentRangeFormattingProvider: true,
+      },
+      serverInfo: { name: 'mermaidfmt' },
+    }),
+    shutdown: () => null,
+    'textDocument/formatting': formatDocument,
+    // Indentation depends on enclosing blocks, so ranges format the whole
+    // document
+    'textDocument/rangeFormatting': formatDocument,
+  };
+
+  const notifications: Record<string, (params: TextDocumentParams) => void> = {
+    'textDocument/didOpen': ({ textDocument }) => {
+      documents.set(textDocument.uri, textDocument.text ?? '');
+      publishDiagnostics(textDocument.uri);
+    },
+    'textDocument/didChange': ({ textDocument, contentChanges }) => {
+      const change = contentChanges?.[contentChanges.length - 1];
+      if (!change) return;
+      documents.set(textDocument.uri, change.text);
+      publishDiagnostics(textDocument.uri);
+    },
+    'textDocument/didClose': ({ textDocument }) => {
+      documents.delete(textDocument.uri);
+      send({
+        jsonrpc: '2.0',
+        method: 'textDocument/publishDiagnostics',
+        params: { uri: textDocument.uri, diagnostics: [] },
+      });
+    },
+  };
+
+  return (message) => {
+    const { id, method } = message;
+    if (!method) return;
+    const params = message.params as TextDocumentParams;
+
+    // Notifications have no id and never get a response
+    if (id === undefined) {
+      if (Object.hasOwn(notifications, method)) notifications[method](params);
+      return;
+    }
+
+    if (!Object.hasOwn(handlers, method)) {
+      send({
+        jsonrpc: '2.0',
+        id,
+        error: { code: METHOD_NOT_FOUND, message: `Unknown method: ${method}` },
+      });
+      return;
+    }
+
+    try {
+      send({ jsonrpc: '2.0', id, result: handlers[method](params) });
+    } catch (err) {
+      const text = err instanceof Error ? err.message : String(err);
+      send({
+        jsonrpc: '2.0',
+        id,
+        error: { code: REQUEST_FAILED, message: text },
+      });
+    }
+  };
+}
+
+/**
+ * Serve LSP over a byte stream pair (stdin/stdout for the CLI).
+ * Resolves when the client sends `exit`.
+ */
+export function startLanguageServer(
+  input: Readable,
+  output: Writable
+): Promise<void> {
+  return new Promise((resolve) => {
+    const send = (message: RpcMessage) => output.write(encodeMessage(message));
+    const handle = createLanguageServer(send);
+    const parse = createMessageParser((message) => {
+      if (message.method === 'exit') {
+        resolve();
+        return;
+      }
+      handle(message);
+    }, send);
+    input.on('data', (chunk: Buffer) => parse(chunk));
+    input.on('end', () => resolve());
+  });
+}
+
+// ============================================================================
+// Framing
+// ============================================================================
+
+/**
+ * Frame a message with a Content-Length header.
+ */
+export function encodeMessage(message: RpcMessage): string {
+  const body = JSON.stringify(message);
+  return `Content-Length: ${Buffer.byteLength(body, 'utf-8')}\r\n\r\n${body}`;
+}
+
+/**
+ * Create a parser that accepts arbitrary chunks and emits whole messages.
+ * A body that is not valid JSON is answered with a parse error through
+ * `onError`, and parsing continues with the next message.
+ */
+export function createMessageParser(
+  onMessage: (message: RpcMessage) => void,
+  onError: (response: RpcMessage) => void = () => {}
+): (chunk: Buffer) => void {
+  let buffer = Buffer.alloc(0);
+
+  return (chunk) => {
+    buffer = Buffer.concat([buffer, chunk]);
+    for (;;) {
+      const headerEnd = buffer.indexOf('\r\n\r\n');
+      if (headerEnd < 0) return;
+      const header = buffer.subarray(0, headerEnd).toString('ascii');
+      const length = Number(header.match(/Content-Length:\s*(\d+)/i)?.[1]);
+      const start = headerEnd + 4;
+      if (Number.isNaN(length)) {
+        // Drop a malformed header and resynchronize on the next one
+        buffer = buffer.subarray(start);
+        continue;
+      }
+      if (buffer.length < start + length) return;
+      const body = buffer.subarray(start, start + length).toString('utf-8');
+      buffer = buffer.subarray(start + length);
+      let message: RpcMessage;
+      try {
+        message = JSON.parse(body) as RpcMessage;
+      } catch {
+        onError({
+          jsonrpc: '2.0',
+          id: null,
+          error: { code: PARSE_ERROR, message: 'Parse error' },
+        });
+        continue;
+      }
+      onMessage(message);
+    }
+  };
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+function isMarkdown(uri: string): boolean {
+  return /\.(md|markdown)$/i.test(uri);
+}
+
+/**
+ * Convert a lint diagnostic (1-based) to an LSP diagnostic (0-based).
+ */
+function toLspDiagnostic(d: LintDiagnostic) {
+  const { start, end } = d.span;
+  return {
+    range: {
+      start: { line: start.line - 1, character: start.column - 1 },
+      end: { line: end.line - 1, character: end.column - 1 },
+    },
+    severity: d.severity === 'error' ? SEVERITY_ERROR : SEVERITY_WARNING,
+    code: d.ruleId,
+    source: 'mermaidfmt',
+    message: d.message,
+  };
+}
+
+/**
+ * Convert a string offset to a 0-based LSP position.
+ */
+function positionAt(text: string, offset: number): LspPosition {
+  const before = text.slice(0, offset);
+  const line = before.split('\n').length - 1;
+  return { line, character: offset - (before.lastIndexOf('\n') + 1) };
+}
@@ -0,0 +1,219 @@
+/**
+ * Mermaid fence discovery and replacement in Markdown documents.
+ * Used by `formatMarkdown` and the CLI `extract` and `embed` subcommands.
+ */
+
+/**
+ * A ```mermaid fence found in a Markdown document.
+ */
+export interface MermaidFence {
+  /** Diagram source with the fence indentation removed */
+  content: string;
+  /** Indentation before the opening fence */
+  indent: string;
+  /** 1-based line of the opening fence */
+  line: number;
+  /** 1-based line of the closing fence (last line when unterminated) */
+  endLine: number;
+  /** Text of the nearest preceding heading, if any */
+  heading?: string;
+  /** Path from a preceding "<!-- mermaidfmt:include PATH -->" marker */
+  include?: string;
+}
+
+/**
+ * A mermaid fence with its position in the document, for tools that
+ * reuse the formatter's fence detection. Offsets are string indices.
+ */
+export interface MermaidBlock {
+  /** Diagram source with the fence indentation removed */
+  source: string;
+  /** Opening through closing fence line, without the final line break */
+  range: { start: number; end: number };
+  /** The diagram lines between the fences (end exclusive) */
+  contentRange: { start: number; end: number };
+  /** Indentation before the opening fence */
+  indent: string;
+  /** Text after the opening backticks, e.g. "mermaid" */
+  infoString: string;
+  /** 1-based line of the opening fence */
+  line: number;
+}
+
+/** A fence that failed to format */
+export interface FenceError {
+  /** 1-based line of the opening fence in the document */
+  line: number;
+  message: string;
+}
+
+/**
+ * Thrown by `formatMarkdown` when some fences fail to format. `output`
+ * holds the document with every other fence formatted.
+ */
+export class MarkdownFormatError extends Error {
+  readonly errors: FenceError[];
+  readonly output: string;
+
+  constructor(errors: FenceError[], output: string) {
+    const lines = errors.map((e) => `line ${e.line}`).join(', ');
+    super(`Failed to format mermaid fences at ${lines}: ${errors[0].message}`);
+    this.name = 'MarkdownFormatError';
+    this.errors = errors;
+    this.output = output;
+  }
+}
+
+const FENCE_OPEN_PATTERN = /^([ \t]*)```mermaid\s*$/;
+const FENCE_PATTERN = /^[ \t]*(```|~~~)/;
+const HEADING_PATTERN = /^#{1,6}\s+(.+?)(?:\s+#+)?\s*$/;
+const INCLUDE_PATTERN = /^[ \t]*<!--\s*mermaidfmt:include\s+(\S+)\s*-->\s*$/;
+
+/**
+ * Find all mermaid fences, skipping headings inside other code blocks.
+ * An unterminated fence runs to the end of the document.
+ */
+export function findMermaidFences(markdown: string): MermaidFence[] {
+  const lines = markdown.split(/\r?\n/);
+  const fences: MermaidFence[] = [];
+  let heading: string | undefined;
+  let include: string | undefined;
+
+  for (let i = 0; i < lines.length; i++) {
+    const marker = lines[i].match(INCLUDE_PATTERN);
+    if (marker) {
+      include = marker[1];
+      continue;
+    }
+
+    const open = lines[i].match(FENCE_OPEN_PATTERN);
+    const other = !open && lines[i].match(FENCE_PATTERN);
+
+    if (open) {
+      const indent = open[1];
+      const body: string[] = [];
+      let j = i + 1;
+      for (; j < lines.length && !/^[ \t]*```/.test(lines[j]); j++) {
+        const line = lines[j];
+        body.push(line.startsWith(indent) ? line.slice(indent.length) : line);
+      }
+      fences.push({
+        content: body.length ? body.join('\n') + '\n' : '',
+        indent,
+        line: i + 1,
+        endLine: Math.min(j + 1, lines.length),
+        heading,
+        ...(include && { include }),
+      });
+      i = j;
+    } else if (other) {
+      // Skip non-mermaid code blocks up to the matching fence
+      const fence = other[1];
+      let j = i + 1;
+      while (j < lines.length && !lines[j].trimStart().startsWith(fence)) j++;
+      i = j;
+    } else {
+      const match = lines[i].match(HEADING_PATTERN);
+      if (match) heading = match[1];
+      // A marker applies to the next fence, across blank lines only
+      if (lines[i].trim()) include = undefined;
+      continue;
+    }
+    include = undefined;
+  }
+
+  return fences;
+}
+
+/**
+ * Find all mermaid fences with their offsets in the document, using the
+ * same detection as `formatMarkdown`.
+ *
+ * @example
+ * ```ts
+ * extractDiagrams('Intro\n```mermaid\npie\n```\n')[0].range;
+ * // { start: 6, end: 24 }
+ * ```
+ */
+export function extractDiagrams(markdown: string): MermaidBlock[] {
+  // Start and end (before the line break) offsets of each line
+  const starts = [0];
+  const ends: number[] = [];
+  for (let i = markdown.indexOf('\n'); i !== -1; ) {
+    ends.push(markdown[i - 1] === '\r' ? i - 1 : i);
+    starts.push(i + 1);
+    i = markdown.indexOf('\n', i + 1);
+  }
+  ends.push(markdown.length);
+  const lineText = (line: number) =>
+    markdown.slice(starts[line - 1], ends[line - 1]);
+
+  return findMermaidFences(markdown).map((fence) => {
+    const closed =
+      fence.endLine > fence.line && /^[ \t]*```/.test(lineText(fence.endLine));
+    const contentStart = starts[fence.line] ?? markdown.length;
+    return {
+      source: fence.content,
+      range: { start: starts[fence.line - 1], end: ends[fence.endLine - 1] },
+      contentRange: {
+        start: contentStart,
+        end: closed ? starts[fence.endLine - 1] : markdown.length,
+      },
+      indent: fence.indent,
+      infoString: lineText(fence.line).trim().slice(3),
+      line: fence.line,
+    };
+  });
+}
+
+/**
+ * Replace fence contents. `replace` returns the new diagram source for a
+ * fence, or undefined to keep it. Fence indentation and line endings are
+ * preserved.
+ */
+export function replaceMermaidFences(
+  markdown: string,
+  replace: (fence: MermaidFence, index: number) => string | undefined
+): string {
+  const eol = markdown.includes('\r\n') ? '\r\n' : '\n';
+  const lines = markdown.split(/\r?\n/);
+  const fences = findMermaidFences(markdown);
+
+  // Replace from the end so earlier line numbers stay valid
+  for (let index = fences.length - 1; index >= 0; index--) {
+    const fence = fences[index];
+    const content = replace(fence, index);
+    if (content === undefined) continue;
+    const source = content.replace(/\n$/, '');
+    const body = source
+      ? source.split('\n').map((line) => (line ? fence.indent + line : line))
+      : [];
+    const closed = /^[ \t]*```/.test(lines[fence.endLine - 1] ?? '');
+    const bodyEnd = closed ? fence.endLine - 1 : fence.endLine;
+    lines.splice(fence.line, bodyEnd - fence.line, ...body);
+  }
+
+  return lines.join(eol);
+}
+
+/**
+ * File names (without extension) for extracted fences: the heading slug,
+ * suffixed with -2, -3, ... on repeats, or "diagram-N" without a heading.
+ */
+export function fenceFileNames(fences: MermaidFence[]): string[] {
+  const seen = new Map<string, number>();
+  return fences.map((fence, index) => {
+    const base =
+      (fence.heading && slugify(fence.heading)) || `diagram-${index + 1}`;
+    const count = (seen.get(base) ?? 0) + 1;
+    seen.set(base, count);
+    return count === 1 ? base : `${base}-${count}`;
+  });
+}
+
+function slugify(text: string): string {
+  return text
+    .toLowerCase()
+    .replace(/[^a-z0-9]+/g, '-')
+    .replace(/^-+|-+$/g, '');
+}
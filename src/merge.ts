@@ -0,0 +1,407 @@
+/**
+ * Merging of same-type diagrams, e.g. sequence diagrams generated per
+ * service combined into one system view. The first diagram is kept as
+ * is; the second contributes what the first does not already have.
+ */
+
+import type {
+  Diagram,
+  FlowchartChain,
+  FlowchartLink,
+  FlowchartNode,
+  Statement,
+} from './types.js';
+import { isIndentSensitive, matchParticipantDecl } from './rules.js';
+import { formatFlowchartChain, parseFlowchartLine } from './flowchart.js';
+
+/** A definition in the second diagram that disagrees with the first */
+export interface MergeConflict {
+  /** 1-based line in the second diagram */
+  line: number;
+  message: string;
+}
+
+/** Merged diagram and the conflicting definitions that were dropped */
+export interface MergeResult {
+  diagram: Diagram;
+  conflicts: MergeConflict[];
+}
+
+/** A definition in the first diagram: its text and 1-based line */
+interface Definition {
+  text: string;
+  line: number;
+}
+
+/**
+ * Merge two diagrams of the same type.
+ *
+ * Flowchart nodes and sequence participants are unioned; new participant
+ * declarations of the second diagram go after those of the first. Edges,
+ * messages, and other statements that already appear in the first
+ * diagram are dropped, as are whole blocks (loops, subgraphs, ...) with
+ * the same content. A node or participant the second diagram defines
+ * differently is reported as a conflict and keeps its first definition.
+ * The first diagram's frontmatter and directives configure the result;
+ * those of the second are dropped, and reported when they differ.
+ *
+ * @param a - First diagram, kept as is
+ * @param b - Diagram to merge into it
+ * @returns Merged diagram and conflicts
+ * @throws Error when the diagrams have different types, or the type is
+ *   indent-sensitive (e.g. mindmap)
+ *
+ * @example
+ * ```ts
+ * const { diagram } = mergeDiagrams(
+ *   parse('sequenceDiagram\nA->>B: order'),
+ *   parse('sequenceDiagram\nA->>B: order\nB->>C: pay')
+ * );
+ * format(diagram);
+ * // "sequenceDiagram\n    A ->> B: order\n\n    B ->> C: pay\n"
+ * ```
+ */
+export function mergeDiagrams(a: Diagram, b: Diagram): MergeResult {
+  if (a.type !== b.type) {
+    throw new Error(
+      `Cannot merge ${b.type} into ${a.type}: diagrams must have the ` +
+        'same type'
+    );
+  }
+  if (isIndentSensitive(a.type)) {
+    throw new Error(`Cannot merge ${a.type} diagrams`);
+  }
+  return new DiagramMerger(a).merge(b);
+}
+
+/**
+ * Merge state: what the first diagram defines, and the merged statements
+ * built so far.
+ */
+class DiagramMerger {
+  private readonly type: Diagram['type'];
+  private readonly statements: Statement[];
+  private readonly conflicts: MergeConflict[] = [];
+  /** Signatures of top-level statements and blocks */
+  private readonly signatures = new Set<string>();
+  /** Node shapes and participant aliases by ID */
+  private readonly definitions = new Map<string, Definition>();
+  /** Flowchart node IDs and edge signatures */
+  private readonly nodes = new Set<string>();
+  private readonly edges = new Set<string>();
+  /** Where the next new participant declaration goes */
+  private participantIndex: number;
+  /** Frontmatter and directive lines of the first diagram */
+  private readonly configuration = new Set<string>();
+
+  constructor(a: Diagram) {
+    this.type = a.type;
+    this.statements = [...a.statements];
+    // Content from the second diagram follows the first's last line
+    while (this.statements.at(-1)?.type === 'blank-line') {
+      this.statements.pop();
+    }
+
+    const declaration = this.statements.findIndex(
+      (stmt) => stmt.type === 'diagram-decl'
+    );
+    this.participantIndex = declaration + 1;
+    for (const [start, end] of topLevelItems(this.statements)) {
+      const item = this.statements.slice(start, end);
+      this.signatures.add(itemSignature(item));
+      if (item[0].type === 'participant') this.participantIndex = end;
+    }
+    this.statements.forEach((stmt, index) => this.learn(stmt, index + 1));
+  }
+
+  merge(b: Diagram): MergeResult {
+    let separated = false;
+    for (const [start, end] of topLevelItems(b.statements)) {
+      const item = b.statements.slice(start, end);
+      const first = item[0];
+      if (first.type === 'diagram-decl') continue;
+      if (first.type === 'frontmatter' || first.type === 'directive') {
+        this.dropConfiguration(first, start + 1);
+        continue;
+      }
+      if (first.type === 'blank-line') {
+        if (this.statements.at(-1)?.type !== 'blank-line') {
+          this.statements.push(first);
+        }
+        continue;
+      }
+      const kept =
+        item.length === 1
+          ? this.mergeStatement(first, start + 1)
+          : this.mergeBlock(item, start + 1);
+      if (kept.length === 0) continue;
+      // Set the second diagram's content apart from the first's
+      if (!separated && this.statements.at(-1)?.type !== 'blank-line') {
+        this.statements.push({ type: 'blank-line', content: '' });
+      }
+      separated = true;
+      this.statements.push(...kept);
+    }
+    return {
+      diagram: { type: this.type, statements: this.statements },
+      conflicts: this.conflicts,
+    };
+  }
+
+  /**
+   * Drop a frontmatter or directive line of the second diagram, reporting
+   * it when the first diagram does not have it.
+   */
+  private dropConfiguration(stmt: Statement, line: number): void {
+    const text = stmt.content.trim();
+    if (text === '---' || this.configuration.has(text)) return;
+    const what = stmt.type === 'directive' ? 'Directive' : 'Frontmatter line';
+    this.conflicts.push({
+      line,
+      message:
+        `${what} '${text}' is dropped; the first diagram's configuration ` +
+        'is kept',
+    });
+  }
+
+  /** Statements to keep for one top-level statement of the second diagram */
+  private mergeStatement(stmt: Statement, line: number): Statement[] {
+    const signature = itemSignature([stmt]);
+    if (this.signatures.has(signature)) return [];
+    this.signatures.add(signature);
+
+    if (stmt.type === 'participant') {
+      const decl = matchParticipantDecl(stmt.content);
+      if (decl) {
+        // Redeclaring without an alias changes nothing
+        const known = this.definitions.has(decl.id);
+        if (decl.alias === undefined && known) return [];
+        if (!this.define(decl.id, participantText(decl), line)) return [];
+      }
+      this.statements.splice(this.participantIndex++, 0, stmt);
+      return [];
+    }
+    if (stmt.type === 'generic-line' && this.isFlowchart()) {
+      const chain = parseFlowchartLine(stmt.content);
+      if (chain) return this.mergeChain(stmt, chain, line);
+    }
+    return [stmt];
+  }
+
+  /**
+   * A block is kept whole unless the first diagram has the same block;
+   * conflicting node definitions inside it still lose their shape.
+   */
+  private mergeBlock(item: Statement[], line: number): Statement[] {
+    const signature = itemSignature(item);
+    if (this.signatures.has(signature)) return [];
+    this.signatures.add(signature);
+    return item.map((stmt, offset) => {
+      if (stmt.type !== 'generic-line' || !this.isFlowchart()) return stmt;
+      const chain = parseFlowchartLine(stmt.content);
+      if (!chain) return stmt;
+      const changed = this.resolveShapes(chain, line + offset);
+      this.addChain(chain);
+      return changed ? flowchartStatement(chain) : stmt;
+    });
+  }
+
+  /**
+   * Flowchart line: drop known edges and redundant node definitions,
+   * splitting a chain into single edges when only some edges are new.
+   */
+  private mergeChain(
+    stmt: Statement,
+    chain: FlowchartChain,
+    line: number
+  ): Statement[] {
+    const changed = this.resolveShapes(chain, line);
+    const edges = chainEdges(chain);
+    const fresh = edges.filter(
+      ([from, to, link]) =>
+        !this.edges.has(edgeSignature(from.id, to.id, link))
+    );
+    const nodes = chain.groups.flat();
+    const newNodes = nodes.filter((node) => !this.nodes.has(node.id));
+    this.addChain(chain);
+
+    if (edges.length === 0) {
+      // A node declaration line adds nothing once its nodes are known
+      const defines = nodes.some((node) => node.shape);
+      if (newNodes.length === 0 && !defines) return [];
+      return [changed ? flowchartStatement(chain) : stmt];
+    }
+    if (fresh.length === edges.length) {
+      return [changed ? flowchartStatement(chain) : stmt];
+    }
+
+    // New edges one per line, each shape on its node's first mention
+    const shaped = new Set<FlowchartNode>();
+    const keep = (node: FlowchartNode): FlowchartNode => {
+      if (!node.shape || shaped.has(node)) return { id: node.id };
+      shaped.add(node);
+      return node;
+    };
+    const kept = fresh.map(([from, to, link]) =>
+      flowchartStatement({ groups: [[keep(from)], [keep(to)]], links: [link] })
+    );
+    // Definitions whose edges were all dropped still count
+    for (const node of nodes) {
+      if (node.shape && !shaped.has(node)) {
+        shaped.add(node);
+        kept.push(flowchartStatement({ groups: [[node]], links: [] }));
+      }
+    }
+    return kept;
+  }
+
+  /**
+   * Drop node shapes that repeat or contradict a known definition,
+   * reporting contradictions. Returns whether any shape was dropped.
+   */
+  private resolveShapes(chain: FlowchartChain, line: number): boolean {
+    let changed = false;
+    for (const group of chain.groups) {
+      group.forEach((node, i) => {
+        if (!node.shape || this.define(node.id, shapeText(node.shape), line)) {
+          return;
+        }
+        group[i] = { ...node, shape: undefined };
+        changed = true;
+      });
+    }
+    return changed;
+  }
+
+  /**
+   * Record a node or participant definition from the second diagram.
+   * Returns false when the ID is already defined, reporting a conflict
+   * if the definitions differ.
+   */
+  private define(id: string, text: string, line: number): boolean {
+    const previous = this.definitions.get(id);
+    if (!previous) {
+      this.definitions.set(id, { text, line });
+      return true;
+    }
+    if (previous.text !== text) {
+      const what = this.isFlowchart() ? 'Node' : 'Participant';
+      this.conflicts.push({
+        line,
+        message:
+          `${what} '${id}' is defined as ${text}, but as ${previous.text} ` +
+          `on line ${previous.line} of the first diagram; keeping the first`,
+      });
+    }
+    return false;
+  }
+
+  /**
+   * Record the definitions, nodes, and edges of a first-diagram line;
+   * the first diagram's own redefinitions are not conflicts.
+   */
+  private learn(stmt: Statement, line: number): void {
+    const first = (id: string, text: string) => {
+      if (!this.definitions.has(id)) this.definitions.set(id, { text, line });
+    };
+    if (stmt.type === 'frontmatter' || stmt.type === 'directive') {
+      this.configuration.add(stmt.content.trim());
+      return;
+    }
+    if (stmt.type === 'participant') {
+      const decl = matchParticipantDecl(stmt.content);
+      if (decl) first(decl.id, participantText(decl));
+      return;
+    }
+    if (stmt.type !== 'generic-line' || !this.isFlowchart()) return;
+    const chain = parseFlowchartLine(stmt.content);
+    if (!chain) return;
+    for (const node of chain.groups.flat()) {
+      if (node.shape) first(node.id, shapeText(node.shape));
+    }
+    this.addChain(chain);
+  }
+
+  private addChain(chain: FlowchartChain): void {
+    for (const node of chain.groups.flat()) this.nodes.add(node.id);
+    for (const [from, to, link] of chainEdges(chain)) {
+      this.edges.add(edgeSignature(from.id, to.id, link));
+    }
+  }
+
+  private isFlowchart(): boolean {
+    return this.type === 'flowchart' || this.type === 'graph';
+  }
+}
+
+/**
+ * [start, end) ranges of top-level statements, a block with its body
+ * and closer counting as one.
+ */
+function topLevelItems(statements: Statement[]): [number, number][] {
+  const items: [number, number][] = [];
+  let depth = 0;
+  let start = 0;
+  statements.forEach((stmt, index) => {
+    if (depth === 0) start = index;
+    if (stmt.type === 'block-start' || stmt.type === 'brace-block-start') {
+      depth++;
+    } else if (
+      (stmt.type === 'block-end' || stmt.type === 'brace-block-end') &&
+      depth > 0
+    ) {
+      depth--;
+    }
+    if (depth === 0) items.push([start, index + 1]);
+  });
+  // An unclosed block runs to the end
+  if (depth > 0) items.push([start, statements.length]);
+  return items;
+}
+
+/**
+ * Identity of a statement or block for deduplication: messages by their
+ * parts, everything else by content with whitespace collapsed.
+ */
+function itemSignature(item: Statement[]): string {
+  return item
+    .map((stmt) =>
+      stmt.type === 'arrow-message'
+        ? `${stmt.from}${stmt.arrow}${stmt.to}:${stmt.message.trim()}`
+        : stmt.content.trim().replace(/\s+/g, ' ')
+    )
+    .join('\n');
+}
+
+/** A participant definition as conflicts show it: 'Alice' or A */
+function participantText(decl: { id: string; alias?: string }): string {
+  return decl.alias === undefined ? decl.id : `'${decl.alias}'`;
+}
+
+/** A node definition as conflicts show it, e.g. [Start] */
+function shapeText(shape: NonNullable<FlowchartNode['shape']>): string {
+  return `${shape.open}${shape.text.trim()}${shape.close}`;
+}
+
+type ChainEdge = [FlowchartNode, FlowchartNode, FlowchartLink];
+
+/** Every edge of a chain ("A & B --> C" has two) */
+function chainEdges(chain: FlowchartChain): ChainEdge[] {
+  return chain.links.flatMap((link, i) =>
+    chain.groups[i].flatMap((from) =>
+      chain.groups[i + 1].map((to): ChainEdge => [from, to, link])
+    )
+  );
+}
+
+function edgeSignature(
+  from: string,
+  to: string,
+  link: FlowchartLink
+): string {
+  return `${from} ${link.arrow} ${to} ${link.label?.trim() ?? ''}`;
+}
+
+function flowchartStatement(chain: FlowchartChain): Statement {
+  return { type: 'generic-line', content: formatFlowchartChain(chain) };
+}
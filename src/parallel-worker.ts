@@ -0,0 +1,21 @@
+/**
+ * Worker thread entry point for `formatMarkdownParallel`: formats the
+ * diagram sources it is sent and posts back one result per source, in
+ * order. Only loaded as a worker, so importing `mermaid-formatter/parallel`
+ * never listens on a thread's message port.
+ */
+
+import { parentPort } from 'node:worker_threads';
+import { registerDiagramType } from './index.js';
+import { formatSources } from './parallel.js';
+import type { WorkerRequest } from './parallel.js';
+
+parentPort?.on(
+  'message',
+  ({ sources, options, diagramTypes }: WorkerRequest) => {
+    for (const [keyword, behavior] of diagramTypes) {
+      registerDiagramType(keyword, behavior);
+    }
+    parentPort?.postMessage(formatSources(sources, options));
+  }
+);
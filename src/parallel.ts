@@ -0,0 +1,151 @@
+/**
+ * Markdown formatting on worker threads, for documents with many
+ * diagrams, such as architecture handbooks.
+ *
+ * Exposed as the `mermaid-formatter/parallel` entry point, since it needs
+ * Node.js worker threads.
+ */
+
+import { cpus } from 'node:os';
+import { Worker } from 'node:worker_threads';
+import type { FormatOptions } from './types.js';
+import type { FenceError } from './markdown.js';
+import type { DiagramBehavior } from './rules.js';
+import {
+  findMermaidFences,
+  MarkdownFormatError,
+  replaceMermaidFences,
+} from './markdown.js';
+import { registeredDiagramTypes } from './rules.js';
+import { formatMermaid } from './index.js';
+
+/** Formatted fence content, or why formatting failed */
+export type SourceResult = { output: string } | { error: string };
+
+/** What the calling thread sends each worker */
+export interface WorkerRequest {
+  sources: string[];
+  options: FormatOptions;
+  /** Diagram types registered on the calling thread */
+  diagramTypes: [string, DiagramBehavior][];
+}
+
+/** How `formatMarkdownParallel` spreads the work */
+export interface ParallelOptions {
+  /** Worker threads to use at most (default: one per CPU) */
+  threads?: number;
+}
+
+/**
+ * Like `formatMarkdown`, but formats the fences on worker threads. The
+ * output is the same as `formatMarkdown` gives, whatever the thread count.
+ * With one thread or fewer than two fences, formats on the calling thread.
+ *
+ * Diagram types registered with `registerDiagramType` are registered on
+ * the workers too. `statementHooks` and `trace` cannot be sent to other
+ * threads, so options with them are rejected.
+ *
+ * @param markdown - Markdown content
+ * @param options - Formatting options
+ * @param parallel - Thread count
+ * @returns Markdown with formatted Mermaid fences
+ * @throws MarkdownFormatError listing each fence that failed, by line
+ *
+ * @example
+ * ```ts
+ * const output = await formatMarkdownParallel(handbook, { indentSize: 2 });
+ * ```
+ */
+export async function formatMarkdownParallel(
+  markdown: string,
+  options: FormatOptions = {},
+  parallel: ParallelOptions = {}
+): Promise<string> {
+  if (options.statementHooks?.length) {
+    throw new Error('statementHooks cannot be used with worker threads');
+  }
+  if (options.trace) {
+    throw new Error('trace cannot be used with worker threads');
+  }
+  const fences = findMermaidFences(markdown);
+  const sources = fences.map((fence) => fence.content);
+  const threads = Math.min(parallel.threads ?? cpus().length, sources.length);
+  const results =
+    threads > 1
+      ? await formatInWorkers(sources, options, threads)
+      : formatSources(sources, options);
+
+  const errors: FenceError[] = [];
+  results.forEach((result, index) => {
+    if ('error' in result) {
+      errors.push({ line: fences[index].line, message: result.error });
+    }
+  });
+  const output = replaceMermaidFences(markdown, (_, index) => {
+    const result = results[index];
+    return 'output' in result ? result.output : undefined;
+  });
+  if (errors.length > 0) throw new MarkdownFormatError(errors, output);
+  return output;
+}
+
+/**
+ * Format each source, catching failures so one bad fence does not lose
+ * the results of the others.
+ */
+export function formatSources(
+  sources: string[],
+  options: FormatOptions
+): SourceResult[] {
+  return sources.map((source) => {
+    try {
+      return { output: formatMermaid(source, options) };
+    } catch (err) {
+      return { error: err instanceof Error ? err.message : String(err) };
+    }
+  });
+}
+
+/**
+ * Deal the sources out to `threads` workers round-robin and put the
+ * results back in source order. Rejects if a worker fails or exits
+ * without posting its results.
+ */
+async function formatInWorkers(
+  sources: string[],
+  options: FormatOptions,
+  threads: number
+): Promise<SourceResult[]> {
+  const results: SourceResult[] = new Array(sources.length);
+  const url = new URL('./parallel-worker.js', import.meta.url);
+  const diagramTypes = registeredDiagramTypes();
+
+  await Promise.all(
+    Array.from({ length: threads }, async (_, thread) => {
+      const indexes = sources
+        .map((_, index) => index)
+        .filter((index) => index % threads === thread);
+      const request: WorkerRequest = {
+        sources: indexes.map((index) => sources[index]),
+        options,
+        diagramTypes,
+      };
+      const worker = new Worker(url);
+      try {
+        const output = await new Promise<SourceResult[]>((resolve, reject) => {
+          worker.once('message', resolve);
+          worker.once('error', reject);
+          // Ignored once the results arrived, when terminate ends the worker
+          worker.once('exit', (code) =>
+            reject(new Error(`Worker stopped with exit code ${code}`))
+          );
+          worker.postMessage(request);
+        });
+        indexes.forEach((index, i) => (results[index] = output[i]));
+      } finally {
+        await worker.terminate();
+      }
+    })
+  );
+  return results;
+}
@@ -3,43 +3,410 @@
  * Converts source code into an AST for formatting.
  */
 
-import type { BlockKind, Diagram, DiagramType, Statement } from './types.js';
+import type {
+  BlockKind,
+  Diagram,
+  DiagramType,
+  ParseLimits,
+  Statement,
+} from './types.js';
+import type { Span } from './lint.js';
+import { parseFlowchartLine, statementChain } from './flowchart.js';
 import {
   matchDiagramType,
   matchBlockKeyword,
   matchBraceBlockStart,
   matchArrowMessage,
+  matchActivation,
+  matchLink,
+  matchStateStereotype,
+  matchGanttSetting,
+  matchBetaBlock,
+  matchTimelinePeriod,
+  isMindmapDeclaration,
+  splitTimelineEvents,
+  splitBoxLabel,
+  matchNote,
+  matchParticipantDecl,
+  matchAttributeMap,
+  matchAnnotation,
+  matchClassRelation,
+  splitTrailingComment,
+  FLOWCHART_KEYWORD_PATTERN,
+  SEQUENCE_KEYWORD_PATTERN,
+  STYLE_STATEMENT_PATTERN,
 } from './rules.js';
 
+/**
+ * Error from `parseStrict`, with the location and what was expected there.
+ */
+export class ParseError extends Error {
+  readonly span: Span;
+  /** What would have been valid at the span (e.g., "end") */
+  readonly expected: string[];
+
+  constructor(message: string, span: Span, expected: string[] = []) {
+    super(`${message} (line ${span.start.line}, column ${span.start.column})`);
+    this.name = 'ParseError';
+    this.span = span;
+    this.expected = expected;
+  }
+
+  /** 1-based line of the error */
+  get line(): number {
+    return this.span.start.line;
+  }
+
+  /** 1-based column of the error */
+  get column(): number {
+    return this.span.start.column;
+  }
+}
+
+/**
+ * Thrown when input exceeds one of the `ParseLimits`. The span points at
+ * the line that went over the limit.
+ */
+export class LimitExceededError extends ParseError {
+  /** The limit that was exceeded */
+  readonly limit: keyof ParseLimits;
+  /** The configured maximum */
+  readonly max: number;
+
+  constructor(
+    message: string,
+    span: Span,
+    limit: keyof ParseLimits,
+    max: number
+  ) {
+    super(message, span);
+    this.name = 'LimitExceededError';
+    this.limit = limit;
+    this.max = max;
+  }
+}
+
 /**
  * Parse Mermaid diagram source into AST.
+ *
+ * @throws LimitExceededError when the input exceeds `limits`
  */
-export function parse(input: string): Diagram {
-  const lines = input.split('\n');
-  const statements: Statement[] = [];
-  let diagramType: DiagramType = 'unknown';
-  const openBlocks: BlockKind[] = [];
+export function parse(input: string, limits: ParseLimits = {}): Diagram {
+  // Reject oversized input before splitting it; the line parser only
+  // finds out once it reaches the line that goes over
+  const { maxInputLength } = limits;
+  if (maxInputLength !== undefined && input.length > maxInputLength) {
+    throw inputLengthError(maxInputLength, 1, 1);
+  }
+  const parser = new LineParser(limits);
+  const statements = input.split('\n').map((line) => parser.next(line));
+  return { type: parser.diagramType, statements };
+}
+
+/**
+ * Parser state for reading a diagram one line at a time; `parse` feeds it
+ * every line of the input. Each line yields exactly one statement.
+ */
+export class LineParser {
+  /** Type from the first diagram declaration seen so far */
+  diagramType: DiagramType = 'unknown';
+  private readonly openBlocks: BlockKind[] = [];
+  private readonly limits: ParseLimits;
+  /** Lines, characters, and open blocks of both kinds seen so far */
+  private lines = 0;
+  private length = 0;
+  private depth = 0;
+  /** One copy of each identifier and arrow, shared by all statements */
+  private readonly names = new Map<string, string>();
+  /** Timeline period that ": event" continuation lines belong to */
+  private period: string | null = null;
+  /** Inside a multi-line "accDescr {" description */
+  private inDescription = false;
+  /** Position relative to YAML frontmatter at the start of the input */
+  private frontmatter: FrontmatterState = 'start';
+  /** Whether the diagram is a built-in mindmap, whose nodes get depths */
+  private mindmap = false;
+  /** Indentation widths of the current mindmap node and its ancestors */
+  private outline: number[] = [];
 
-  for (const line of lines) {
+  constructor(limits: ParseLimits = {}) {
+    this.limits = limits;
+  }
+
+  /** Parse the next input line */
+  next(line: string): Statement {
+    this.lines++;
+    this.checkLimits(line);
     const trimmed = line.trim();
-    const statement = parseLine(trimmed, diagramType, openBlocks);
+    const [state, inFrontmatter] = frontmatterStep(this.frontmatter, trimmed);
+    this.frontmatter = state;
+    // YAML is kept as written, indentation included
+    if (inFrontmatter) return { type: 'frontmatter', content: line.trimEnd() };
+
+    const [code, comment] = splitTrailingComment(trimmed);
+    const statement = this.markUnknown(
+      this.continueTimeline(
+        this.outlineMindmap(
+          parseLine(code, this.diagramType, this.openBlocks),
+          line
+        )
+      )
+    );
+    if (comment) statement.trailingComment = comment;
+    if (trimmed !== statement.content) statement.raw = trimmed;
+    this.checkDepth(statement);
+    this.internNames(statement);
+
+    // Track diagram type from first declaration
+    if (statement.type === 'diagram-decl' && this.diagramType === 'unknown') {
+      this.diagramType = statement.diagramType;
+      this.mindmap = isMindmapDeclaration(statement.content);
+    }
+    if (statement.type === 'block-start') {
+      this.openBlocks.push(statement.blockKind);
+    } else if (statement.type === 'block-end' && this.openBlocks.length > 0) {
+      this.openBlocks.pop();
+    }
+    return statement;
+  }
+
+  /**
+   * Replace the identifier fields of a statement with their first seen
+   * copy, so generated diagrams that repeat the same IDs on thousands of
+   * lines hold each ID in memory once.
+   * Flowchart node IDs are interned in the chain remembered for the line.
+   */
+  private internNames(statement: Statement): void {
+    switch (statement.type) {
+      case 'arrow-message':
+      case 'class-relation':
+        statement.from = this.intern(statement.from);
+        statement.to = this.intern(statement.to);
+        statement.arrow = this.intern(statement.arrow);
+        break;
+      case 'activate':
+      case 'deactivate':
+      case 'link':
+        statement.participant = this.intern(statement.participant);
+        break;
+      case 'note':
+        if (statement.participants) {
+          statement.participants = statement.participants.map((name) =>
+            this.intern(name)
+          );
+        }
+        break;
+      case 'participant':
+        if (statement.id) statement.id = this.intern(statement.id);
+        break;
+      case 'annotation':
+        if (statement.target) statement.target = this.intern(statement.target);
+        break;
+      case 'brace-block-start':
+      case 'state-stereotype':
+        statement.name = this.intern(statement.name);
+        break;
+      case 'attribute-map':
+        statement.id = this.intern(statement.id);
+        break;
+      case 'generic-line':
+        if (this.diagramType === 'flowchart' || this.diagramType === 'graph') {
+          for (const node of statementChain(statement)?.groups.flat() ?? []) {
+            node.id = this.intern(node.id);
+          }
+        }
+        break;
+    }
+  }
+
+  private intern(name: string): string {
+    const known = this.names.get(name);
+    if (known !== undefined) return known;
+    this.names.set(name, name);
+    return name;
+  }
 
-    if (statement) {
-      // Track diagram type from first declaration
-      if (statement.type === 'diagram-decl' && diagramType === 'unknown') {
-        diagramType = statement.diagramType;
-      }
-      statements.push(statement);
+  /**
+   * Check the start of the next line before all of it has arrived, so a
+   * stream without newlines fails at the length limits instead of being
+   * buffered whole.
+   */
+  checkPartialLine(lineLength: number): void {
+    const line = this.lines + 1;
+    const length = this.length + lineLength + (line > 1 ? 1 : 0);
+    this.checkLength(lineLength, line, length);
+  }
+
+  private checkLimits(line: string): void {
+    const { maxStatements } = this.limits;
+    // Every line but the first follows a newline
+    this.length += line.length + (this.lines > 1 ? 1 : 0);
+    this.checkLength(line.length, this.lines, this.length);
+    if (maxStatements !== undefined && this.lines > maxStatements) {
+      const message = `More than ${maxStatements} statements`;
+      throw limitError(message, this.lines, 1, 'maxStatements', maxStatements);
+    }
+  }
+
+  /** Check a line's length and the input length up to its end */
+  private checkLength(lineLength: number, line: number, length: number): void {
+    const { maxInputLength, maxLineLength } = this.limits;
+    if (maxInputLength !== undefined && length > maxInputLength) {
+      const column = lineLength - (length - maxInputLength) + 1;
+      throw inputLengthError(maxInputLength, line, Math.max(column, 1));
+    }
+    if (maxLineLength !== undefined && lineLength > maxLineLength) {
+      const max = maxLineLength;
+      const message = `Line is ${lineLength} characters, over ${max}`;
+      throw limitError(message, line, max + 1, 'maxLineLength', max);
+    }
+  }
+
+  /**
+   * Turn mindmap lines into nodes with their tree depth, taken from the
+   * indentation of the raw line, and icon/class lines into decorations of
+   * the node above them. Mindmap text is never a block keyword.
+   */
+  private outlineMindmap(statement: Statement, line: string): Statement {
+    if (
+      !this.mindmap ||
+      statement.type === 'blank-line' ||
+      statement.type === 'comment' ||
+      statement.type === 'directive'
+    ) {
+      return statement;
+    }
+    const { content } = statement;
+    const decoration = content.match(/^(?:::icon\((.*)\)|:::(.*))$/);
+    if (decoration) {
+      return {
+        type: 'mindmap-decoration',
+        kind: decoration[1] === undefined ? 'class' : 'icon',
+        value: (decoration[1] ?? decoration[2]).trim(),
+        depth: Math.max(this.outline.length - 1, 0),
+        content,
+      };
+    }
+    const width = line.length - line.trimStart().length;
+    const { outline } = this;
+    while (outline.length > 0 && outline[outline.length - 1] >= width) {
+      outline.pop();
+    }
+    outline.push(width);
+    return { type: 'mindmap-node', depth: outline.length - 1, content };
+  }
+
+  /**
+   * Attach timeline ": event" lines to the period above them. Without a
+   * period, e.g. right after a section line, they stay generic lines.
+   */
+  private continueTimeline(statement: Statement): Statement {
+    if (this.diagramType !== 'timeline' || statement.type !== 'generic-line') {
+      return statement;
+    }
+    const { content } = statement;
+    if (!content.startsWith(':')) {
+      this.period = matchTimelinePeriod(content)?.period ?? null;
+      return statement;
+    }
+    if (this.period === null) return statement;
+    return {
+      type: 'timeline-event',
+      period: this.period,
+      events: splitTimelineEvents(content.slice(1)),
+      content,
+    };
+  }
 
-      if (statement.type === 'block-start') {
-        openBlocks.push(statement.blockKind);
-      } else if (statement.type === 'block-end' && openBlocks.length > 0) {
-        openBlocks.pop();
-      }
+  /**
+   * Turn generic lines of sequence diagrams and flowcharts that match no
+   * modelled syntax into unknown statements, so they are kept as written.
+   * Lines of a multi-line accessible description are free text.
+   */
+  private markUnknown(statement: Statement): Statement {
+    const { content } = statement;
+    if (this.inDescription) {
+      this.inDescription = !content.endsWith('}');
+      return statement;
+    }
+    if (/^accDescr\s*\{/.test(content) && !content.endsWith('}')) {
+      this.inDescription = true;
+      return statement;
     }
+    // A stray "end" is a closer without an opener, reported by lint
+    if (
+      statement.type !== 'generic-line' ||
+      content === 'end' ||
+      isModelledLine(this.diagramType, statement)
+    ) {
+      return statement;
+    }
+    return { type: 'unknown', content };
   }
 
-  return { type: diagramType, statements };
+  private checkDepth(statement: Statement): void {
+    if (
+      statement.type === 'block-start' ||
+      statement.type === 'brace-block-start'
+    ) {
+      this.depth++;
+    } else if (
+      statement.type === 'block-end' ||
+      statement.type === 'brace-block-end'
+    ) {
+      this.depth = Math.max(this.depth - 1, 0);
+    }
+    const { maxDepth } = this.limits;
+    if (maxDepth !== undefined && this.depth > maxDepth) {
+      const message = `Blocks are nested more than ${maxDepth} deep`;
+      throw limitError(message, this.lines, 1, 'maxDepth', maxDepth);
+    }
+  }
+}
+
+/**
+ * Whether a generic line is syntax the parser models. Only sequence
+ * diagrams and flowcharts are checked; in other diagram types every line
+ * is modelled as a generic line.
+ */
+function isModelledLine(
+  diagramType: DiagramType,
+  statement: Statement
+): boolean {
+  const { content } = statement;
+  switch (diagramType) {
+    case 'sequenceDiagram':
+      return SEQUENCE_KEYWORD_PATTERN.test(content);
+    case 'flowchart':
+    case 'graph':
+      return (
+        FLOWCHART_KEYWORD_PATTERN.test(content) ||
+        STYLE_STATEMENT_PATTERN.test(content) ||
+        statementChain(statement) !== null
+      );
+    default:
+      return true;
+  }
+}
+
+function inputLengthError(
+  max: number,
+  line: number,
+  column: number
+): LimitExceededError {
+  const message = `Input is over the limit of ${max} characters`;
+  return limitError(message, line, column, 'maxInputLength', max);
+}
+
+function limitError(
+  message: string,
+  line: number,
+  column: number,
+  limit: keyof ParseLimits,
+  max: number
+): LimitExceededError {
+  const span = { start: { line, column }, end: { line, column } };
+  return new LimitExceededError(message, span, limit, max);
 }
 
 /**
@@ -65,9 +432,10 @@ function parseLine(
     return { type: 'directive', content: trimmed };
   }
 
-  // Diagram declaration
-  const detectedType = matchDiagramType(trimmed);
-  if (detectedType && currentDiagramType === 'unknown') {
+  // Diagram declaration (only the first one counts)
+  const detectedType =
+    currentDiagramType === 'unknown' ? matchDiagramType(trimmed) : null;
+  if (detectedType) {
     return {
       type: 'diagram-decl',
       diagramType: detectedType,
@@ -106,9 +474,38 @@ function parseLine(
     return { type: 'block-and', label, content: trimmed };
   }
 
+  // Block-beta composite blocks; their nodes and widths are generic lines,
+  // where "b:2" after an arrow is a width rather than a message
+  if (currentDiagramType === 'block-beta') {
+    const block = matchBetaBlock(trimmed);
+    if (block) {
+      return {
+        type: 'block-start',
+        blockKind: 'block',
+        ...block,
+        content: trimmed,
+      };
+    }
+    return { type: 'generic-line', content: trimmed };
+  }
+
   // Block start with 'end' keyword
   const blockKind = matchBlockKeyword(trimmed);
-  if (blockKind) {
+  if (
+    (blockKind === 'box' || blockKind === 'rect') &&
+    currentDiagramType === 'sequenceDiagram'
+  ) {
+    const rest = trimmed.slice(blockKind.length).trim();
+    const { color, label } = splitBoxLabel(rest);
+    return {
+      type: 'block-start',
+      blockKind,
+      color,
+      label,
+      content: trimmed,
+    };
+  }
+  if (blockKind && blockKind !== 'box') {
     const label = trimmed.slice(blockKind.length).trim() || undefined;
     return {
       type: 'block-start',
@@ -118,8 +515,8 @@ function parseLine(
     };
   }
 
-  // Brace block start (state/class/namespace with {)
-  const braceBlock = matchBraceBlockStart(trimmed);
+  // Brace block start (state/class/namespace with {, ER entity blocks)
+  const braceBlock = matchBraceBlockStart(trimmed, currentDiagramType);
   if (braceBlock) {
     return {
       type: 'brace-block-start',
@@ -131,14 +528,92 @@ function parseLine(
 
   // Participant declaration (sequence diagram)
   if (/^participant\b/.test(trimmed) || /^actor\b/.test(trimmed)) {
-    return { type: 'participant', content: trimmed };
+    const decl = matchParticipantDecl(trimmed);
+    return { type: 'participant', ...decl, content: trimmed };
+  }
+
+  // Sequence diagram statements: autonumber, activate, deactivate
+  if (currentDiagramType === 'sequenceDiagram') {
+    if (/^autonumber\b/.test(trimmed)) {
+      return { type: 'autonumber', content: trimmed };
+    }
+
+    const activation = matchActivation(trimmed);
+    if (activation) {
+      return {
+        type: activation.kind,
+        participant: activation.participant,
+        content: trimmed,
+      };
+    }
+
+    const link = matchLink(trimmed);
+    if (link) {
+      return { type: 'link', ...link, content: trimmed };
+    }
   }
 
   // Note
   if (/^note\b/i.test(trimmed)) {
+    const note = matchNote(trimmed);
+    if (note) {
+      return { type: 'note', ...note, content: trimmed };
+    }
     return { type: 'note', content: trimmed };
   }
 
+  // Class diagram annotations and relationships
+  if (currentDiagramType === 'classDiagram') {
+    const annotation = matchAnnotation(trimmed);
+    if (annotation) {
+      return { type: 'annotation', ...annotation, content: trimmed };
+    }
+
+    const relation = matchClassRelation(trimmed);
+    if (relation) {
+      return { type: 'class-relation', ...relation, content: trimmed };
+    }
+  }
+
+  // Attribute map (flowchart v11 node/edge metadata)
+  if (currentDiagramType === 'flowchart' || currentDiagramType === 'graph') {
+    const attributeMap = matchAttributeMap(trimmed);
+    if (attributeMap) {
+      return {
+        type: 'attribute-map',
+        id: attributeMap.id,
+        attributes: attributeMap.attributes,
+        content: trimmed,
+      };
+    }
+    // Colons inside node labels ("B[fa:fa-car Car]") are not messages
+    if (trimmed.includes(':') && parseFlowchartLine(trimmed)) {
+      return { type: 'generic-line', content: trimmed };
+    }
+  }
+
+  // State diagram stereotypes and concurrency separators
+  if (
+    currentDiagramType === 'stateDiagram' ||
+    currentDiagramType === 'stateDiagram-v2'
+  ) {
+    if (trimmed === '--') {
+      return { type: 'state-separator', content: trimmed };
+    }
+    const stereotype = matchStateStereotype(trimmed);
+    if (stereotype) {
+      return { type: 'state-stereotype', ...stereotype, content: trimmed };
+    }
+  }
+
+  // Gantt settings, kept apart from tasks
+  if (currentDiagramType === 'gantt') {
+    const setting = matchGanttSetting(trimmed);
+    if (setting) {
+      return { type: 'gantt-setting', ...setting, content: trimmed };
+    }
+  }
+
   // Arrow message (all diagram types when syntax matches)
   const arrowMatch = matchArrowMessage(trimmed);
   if (arrowMatch) {
@@ -161,15 +636,146 @@ function topBlockKind(openBlocks: readonly BlockKind[]): BlockKind | null {
 }
 
 /**
- * Detect diagram type from source code.
+ * Parse strictly: like `parse`, but throw a `ParseError` for input the
+ * lenient parser would format with wrong indentation or treat as plain
+ * text (no diagram declaration, unclosed blocks, stray closers).
+ *
+ * @throws ParseError at the first problem
  */
-export function detectDiagramType(input: string): DiagramType {
+export function parseStrict(input: string, limits?: ParseLimits): Diagram {
+  const diagram = parse(input, limits);
   const lines = input.split('\n');
-  for (const line of lines) {
-    const trimmed = line.trim();
-    if (trimmed === '' || trimmed.startsWith('%%')) continue;
-    const type = matchDiagramType(trimmed);
-    if (type) return type;
+  const { statements } = diagram;
+
+  const first = statements.findIndex(
+    (s) => s.type !== 'blank-line' && s.type !== 'comment'
+  );
+  if (first !== -1 && diagram.type === 'unknown') {
+    throw lineError(lines, first, 'Expected a diagram type declaration', [
+      'diagram type',
+    ]);
+  }
+
+  const { open, stray } = matchBlocks(statements);
+  if (stray.length > 0 && (open.length === 0 || stray[0] < open[0])) {
+    const closer = statements[stray[0]].content;
+    throw lineError(lines, stray[0], `Unexpected '${closer}'`, ['statement']);
+  }
+  if (open.length > 0) {
+    const index = open[open.length - 1];
+    const stmt = statements[index];
+    if (stmt.type === 'block-start') {
+      const message = `'${stmt.blockKind}' block is never closed`;
+      throw lineError(lines, index, message, ['end']);
+    }
+    throw lineError(lines, index, "'{' block is never closed", ['}']);
+  }
+  return diagram;
+}
+
+/**
+ * ParseError spanning the trimmed content of a line.
+ */
+function lineError(
+  lines: string[],
+  index: number,
+  message: string,
+  expected: string[]
+): ParseError {
+  const text = (lines[index] ?? '').trimEnd();
+  const column = text.length - text.trimStart().length + 1;
+  const span = {
+    start: { line: index + 1, column },
+    end: { line: index + 1, column: text.length + 1 },
+  };
+  return new ParseError(message, span, expected);
+}
+
+/**
+ * Pair block openers with closers of their own kind ("end" or "}").
+ * Returns openers left unclosed, outermost first, and closers with no
+ * opener. A bare "end" outside any block is parsed as a generic line.
+ */
+export function matchBlocks(statements: Statement[]): {
+  open: number[];
+  stray: number[];
+} {
+  const open: number[] = [];
+  const stray: number[] = [];
+  statements.forEach((stmt, index) => {
+    if (stmt.type === 'block-start' || stmt.type === 'brace-block-start') {
+      open.push(index);
+      return;
+    }
+    const opener =
+      stmt.type === 'block-end' ||
+      (stmt.type === 'generic-line' && stmt.content === 'end')
+        ? 'block-start'
+        : stmt.type === 'brace-block-end'
+          ? 'brace-block-start'
+          : null;
+    if (!opener) return;
+    let match = open.length - 1;
+    while (match >= 0 && statements[open[match]].type !== opener) match--;
+    if (match === -1) stray.push(index);
+    else open.splice(match, 1);
+  });
+  return { open, stray };
+}
+
+/**
+ * Detect diagram type from source code without parsing it, e.g. to route
+ * diagrams to per-type pipelines. Only the lines up to the declaration are
+ * read; see `diagramHeader`.
+ */
+export function detectDiagramType(input: string): DiagramType {
+  const header = diagramHeader(input);
+  return (header !== null && matchDiagramType(header)) || 'unknown';
+}
+
+/**
+ * The trimmed diagram declaration line of the input, or null. Blank lines,
+ * comments, directives, and YAML frontmatter (between `---` lines at the
+ * start) are skipped, so a `flowchart:` config key is not a declaration.
+ */
+export function diagramHeader(input: string): string | null {
+  let start = 0;
+  let frontmatter: FrontmatterState = 'start';
+  while (start <= input.length) {
+    const newline = input.indexOf('\n', start);
+    const end = newline === -1 ? input.length : newline;
+    const trimmed = input.slice(start, end).trim();
+    start = end + 1;
+    const [state, inFrontmatter] = frontmatterStep(frontmatter, trimmed);
+    frontmatter = state;
+    if (inFrontmatter || trimmed === '' || trimmed.startsWith('%%')) continue;
+    if (matchDiagramType(trimmed)) return trimmed;
+  }
+  return null;
+}
+
+/**
+ * Where a line is relative to YAML frontmatter: before it, where only
+ * blank lines may come first, inside it, or past it (or past the point
+ * where it could start).
+ */
+type FrontmatterState = 'start' | 'inside' | 'done';
+
+/**
+ * The frontmatter state after a trimmed line, and whether the line is
+ * part of the frontmatter, `---` delimiters included.
+ */
+function frontmatterStep(
+  state: FrontmatterState,
+  trimmed: string
+): [FrontmatterState, boolean] {
+  switch (state) {
+    case 'start':
+      if (trimmed === '') return ['start', false];
+      return trimmed === '---' ? ['inside', true] : ['done', false];
+    case 'inside':
+      return [trimmed === '---' ? 'done' : 'inside', true];
+    case 'done':
+      return ['done', false];
   }
-  return 'unknown';
 }
@@ -0,0 +1,212 @@
+/**
+ * Source refactorings that understand diagram structure.
+ * Edits touch only identifier positions, so labels, message texts, and
+ * comments that happen to contain the same word are left alone.
+ */
+
+import type { Diagram, DiagramType, Statement } from './types.js';
+import type { Span } from './lint.js';
+import { parse } from './parser.js';
+import { parseFlowchartLine } from './flowchart.js';
+
+/** Renamed source, its parsed diagram, and where the ID was replaced */
+export interface RenameResult {
+  output: string;
+  diagram: Diagram;
+  /** Spans of the replaced occurrences in the input */
+  changes: Span[];
+}
+
+/**
+ * Rename a node, participant, state, or class everywhere it is referenced:
+ * declarations, edges, messages, notes, activations, and style or click
+ * statements.
+ *
+ * @param input - Mermaid diagram source code
+ * @param from - Identifier to rename
+ * @param to - New identifier
+ * @returns Renamed source and the spans that changed
+ *
+ * @example
+ * ```ts
+ * renameId('sequenceDiagram\nA->>B: hi A', 'A', 'Alice').output;
+ * // 'sequenceDiagram\nAlice->>B: hi A'
+ * ```
+ */
+export function renameId(
+  input: string,
+  from: string,
+  to: string
+): RenameResult {
+  const lines = input.split('\n');
+  const diagram = parse(input);
+  const changes: Span[] = [];
+  if (!from) return { output: input, diagram, changes };
+  // Innermost brace blocks; class and entity bodies hold members, not IDs
+  const braces: string[] = [];
+
+  diagram.statements.forEach((stmt, index) => {
+    if (stmt.type === 'brace-block-end') braces.pop();
+    const inMembers = ['class', 'entity'].includes(braces[braces.length - 1]);
+    if (stmt.type === 'brace-block-start') braces.push(stmt.blockKind);
+    if (inMembers) return;
+
+    const line = lines[index];
+    const indent = line.length - line.trimStart().length;
+    const offsets = identifierOffsets(stmt, diagram.type, from);
+    // Replace right to left so earlier offsets stay valid
+    let renamed = line;
+    for (const offset of [...offsets].reverse()) {
+      const start = indent + offset;
+      renamed =
+        renamed.slice(0, start) + to + renamed.slice(start + from.length);
+    }
+    lines[index] = renamed;
+    for (const offset of offsets) {
+      const column = indent + offset + 1;
+      changes.push({
+        start: { line: index + 1, column },
+        end: { line: index + 1, column: column + from.length },
+      });
+    }
+  });
+
+  const output = lines.join('\n');
+  return { output, diagram: parse(output), changes };
+}
+
+/**
+ * Offsets of an identifier within a statement's content, where it is used
+ * as an ID rather than as text.
+ */
+function identifierOffsets(
+  stmt: Statement,
+  diagramType: DiagramType,
+  id: string
+): number[] {
+  const { content } = stmt;
+  switch (stmt.type) {
+    case 'participant':
+      // "participant A as Alice": the alias is display text
+      return findWord(beforeText(content, /\s+as\s/), id, 1);
+    case 'arrow-message': {
+      const arrow = content.indexOf(stmt.arrow, stmt.from.length);
+      const to = content.indexOf(stmt.to, arrow + stmt.arrow.length);
+      return [
+        ...(stmt.from === id ? [0] : []),
+        ...(stmt.to === id ? [to] : []),
+      ];
+    }
+    case 'note': {
+      // Skip "Note" and the position words ("over", "right of")
+      const keywords = 1 + (stmt.position?.split(' ').length ?? 0);
+      return findWord(beforeText(content, /:/), id, keywords);
+    }
+    case 'activate':
+    case 'deactivate':
+      return stmt.participant === id ? [content.lastIndexOf(id)] : [];
+    case 'link':
+      return findWord(beforeText(content, /:/), id, 1);
+    case 'state-stereotype':
+      return stmt.name === id ? findWord(content, id, 1).slice(0, 1) : [];
+    case 'block-start':
+      // "block:ID:2" (block-beta)
+      if (stmt.blockKind === 'block') {
+        return findWord(content, id)
+          .filter((at) => at > 'block'.length)
+          .slice(0, 1);
+      }
+      // "subgraph ID [Title]"
+      return stmt.blockKind === 'subgraph'
+        ? findWord(content, id, 1).slice(0, 1)
+        : [];
+    case 'brace-block-start':
+      return findWord(beforeText(content, /\{/), id);
+    case 'class-relation':
+    case 'annotation':
+      return findWord(beforeText(content, /:/), id);
+    case 'generic-line':
+      return genericLineOffsets(content, diagramType, id);
+    default:
+      return [];
+  }
+}
+
+/**
+ * Flowchart chains are located node by node; other diagrams keep IDs
+ * before the first colon (e.g., "A --> B : label", "Idle : waiting").
+ */
+function genericLineOffsets(
+  content: string,
+  diagramType: DiagramType,
+  id: string
+): number[] {
+  if (diagramType === 'sequenceDiagram') return [];
+  if (diagramType !== 'flowchart' && diagramType !== 'graph') {
+    return findWord(beforeText(content, /:/), id);
+  }
+
+  // "style A ...", "click A ...": the ID is the second word
+  if (/^(?:style|click)\s/.test(content)) {
+    return findWord(content, id, 1).slice(0, 1);
+  }
+  // "class A,B name": IDs before the class name
+  if (/^class\s/.test(content)) {
+    return findWord(content.replace(/\s+\S+\s*;?$/, ''), id, 1);
+  }
+  if (/^(?:classDef|linkStyle)\s/.test(content)) return [];
+
+  const chain = parseFlowchartLine(content);
+  if (!chain) return [];
+  const offsets: number[] = [];
+  let pos = 0;
+  for (let i = 0; i < chain.groups.length; i++) {
+    for (const node of chain.groups[i]) {
+      const [start] = findWord(content.slice(pos), node.id);
+      if (start === undefined) return offsets;
+      if (node.id === id) offsets.push(pos + start);
+      pos += start + node.id.length;
+      if (node.shape) {
+        const { open, text, close } = node.shape;
+        pos += open.length + text.length + close.length;
+      } else if (node.attributes !== undefined) {
+        pos = content.indexOf('}', pos) + 1;
+      }
+    }
+    // Skip the label so words inside it are not taken for nodes
+    const label = chain.links[i]?.label;
+    if (label) pos = content.indexOf(label, pos) + label.length;
+  }
+  return offsets;
+}
+
+/**
+ * Text before the first match of a separator, or all of it.
+ */
+function beforeText(content: string, separator: RegExp): string {
+  const match = content.match(separator);
+  return match?.index === undefined ? content : content.slice(0, match.index);
+}
+
+/**
+ * Offsets of whole-word occurrences outside double quotes, skipping the
+ * first `skipWords` words (keywords such as "participant" or "Note over").
+ */
+function findWord(text: string, word: string, skipWords = 0): number[] {
+  let from = 0;
+  for (let i = 0; i < skipWords; i++) {
+    const next = text.slice(from).match(/^\s*\S+/);
+    if (!next) return [];
+    from += next[0].length;
+  }
+
+  const offsets: number[] = [];
+  for (let at = text.indexOf(word, from); at !== -1; ) {
+    const before = text[at - 1] ?? '';
+    const after = text[at + word.length] ?? '';
+    const quoted = (text.slice(0, at).match(/"/g)?.length ?? 0) % 2 === 1;
+    if (!/\w/.test(before) && !/\w/.test(after) && !quoted) offsets.push(at);
+    at = text.indexOf(word, at + word.length);
+  }
+  return offsets;
+}
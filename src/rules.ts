@@ -3,7 +3,13 @@
  * All syntax definitions in one place for easy maintenance.
  */
 
-import type { DiagramType, BlockKind, BraceBlockKind } from './types.js';
+import type {
+  Attribute,
+  DiagramType,
+  BlockKind,
+  BraceBlockKind,
+  NotePosition,
+} from './types.js';
 
 /**
  * Patterns for detecting diagram types.
@@ -43,6 +49,7 @@ export const BLOCK_KEYWORDS: BlockKind[] = [
   'opt',
   'break',
   'rect',
+  'box',
   'subgraph',
 ];
 
@@ -60,7 +67,7 @@ export const BRACE_BLOCK_KEYWORDS: BraceBlockKind[] = [
  * Diagram types where indentation represents hierarchy.
  * These should NOT be reformatted as it would change semantics.
  */
-export const INDENT_SENSITIVE_DIAGRAMS: DiagramType[] = ['mindmap', 'timeline'];
+export const INDENT_SENSITIVE_DIAGRAMS: DiagramType[] = ['mindmap'];
 
 /**
  * Check if a diagram type is indent-sensitive.
@@ -69,6 +76,16 @@ export function isIndentSensitive(diagramType: DiagramType): boolean {
   return INDENT_SENSITIVE_DIAGRAMS.includes(diagramType);
 }
 
+/**
+ * Whether a diagram declaration starts a mindmap whose nodes may be
+ * reindented by depth: the built-in header, or a keyword registered with
+ * 'hierarchy'. 'passthrough' diagrams are handled like mindmaps but
+ * never reindented.
+ */
+export function isMindmapDeclaration(line: string): boolean {
+  return /^mindmap\b/.test(line) || registeredBehavior(line) === 'hierarchy';
+}
+
 /**
  * Match diagram type from a line of text.
  */
@@ -78,20 +95,491 @@ export function matchDiagramType(line: string): DiagramType | null {
       return type;
     }
   }
+  const behavior = registeredBehavior(line);
+  return behavior ? BEHAVIOR_TYPES[behavior] : null;
+}
+
+/** Behavior of the registered keyword a line starts with, if any */
+function registeredBehavior(line: string): DiagramBehavior | null {
+  for (const [keyword, behavior] of CUSTOM_DIAGRAMS) {
+    const next = line[keyword.length] ?? '';
+    if (line.startsWith(keyword) && !/[\w-]/.test(next)) return behavior;
+  }
   return null;
 }
 
+/**
+ * How a registered diagram header is handled: formatted like a flowchart,
+ * or kept as written. 'hierarchy' is for diagrams whose indentation is
+ * structure, handled like mindmap, including `reindentMindmaps`;
+ * 'passthrough' for syntax the formatter should not touch at all.
+ */
+export type DiagramBehavior = 'flowchart' | 'hierarchy' | 'passthrough';
+
+/**
+ * Built-in diagram type each behavior is handled as; 'hierarchy' and
+ * 'passthrough' differ in `isMindmapDeclaration`
+ */
+const BEHAVIOR_TYPES: Record<DiagramBehavior, DiagramType> = {
+  flowchart: 'flowchart',
+  hierarchy: 'mindmap',
+  passthrough: 'mindmap',
+};
+
+/** Registered header keywords, checked after the built-in patterns */
+const CUSTOM_DIAGRAMS = new Map<string, DiagramBehavior>();
+
+/**
+ * Register a diagram header the formatter does not know, such as one from
+ * a Mermaid plugin, with the behavior to use for it. Diagrams starting
+ * with the keyword are parsed as its behavior's built-in type; without
+ * registration their lines are all kept at column 0.
+ *
+ * @param keyword - Header keyword, e.g. "zenuml"
+ * @param behavior - How to handle the diagram
+ * @throws Error if the keyword is not a single word or is built in
+ *
+ * @example
+ * ```ts
+ * registerDiagramType('networkGraph', 'flowchart');
+ * formatMermaid('networkGraph\nA-->B'); // 'networkGraph\n    A-->B\n'
+ * ```
+ */
+export function registerDiagramType(
+  keyword: string,
+  behavior: DiagramBehavior
+): void {
+  if (!/^[A-Za-z][\w-]*$/.test(keyword)) {
+    throw new Error(`Invalid diagram keyword: ${JSON.stringify(keyword)}`);
+  }
+  if (DIAGRAM_PATTERNS.some(([pattern]) => pattern.test(keyword))) {
+    throw new Error(`'${keyword}' is a built-in diagram type`);
+  }
+  CUSTOM_DIAGRAMS.set(keyword, behavior);
+}
+
+/**
+ * Keywords added with `registerDiagramType` and their behaviors, in
+ * registration order.
+ */
+export function registeredDiagramTypes(): [string, DiagramBehavior][] {
+  return [...CUSTOM_DIAGRAMS];
+}
+
+/**
+ * Remove a keyword added with `registerDiagramType`.
+ */
+export function unregisterDiagramType(keyword: string): void {
+  CUSTOM_DIAGRAMS.delete(keyword);
+}
+
+/** Any block keyword as the first word, tried in `BLOCK_KEYWORDS` order */
+const BLOCK_KEYWORD_PATTERN = new RegExp(`^(${BLOCK_KEYWORDS.join('|')})\\b`);
+
 /**
  * Match block start keyword (critical, alt, loop, etc.)
  */
 export function matchBlockKeyword(line: string): BlockKind | null {
-  for (const keyword of BLOCK_KEYWORDS) {
-    const pattern = new RegExp(`^${keyword}\\b`);
-    if (pattern.test(line)) {
-      return keyword;
+  const match = line.match(BLOCK_KEYWORD_PATTERN);
+  return match ? (match[1] as BlockKind) : null;
+}
+
+/**
+ * Composite block of a block-beta diagram: "block", "block:ID", or
+ * "block:ID:2" with a column width.
+ */
+const BETA_BLOCK_PATTERN = /^block(?:\s*:\s*([\w-]+)(?:\s*:\s*(\d+))?)?$/;
+
+/**
+ * Match block-beta composite block start; the label is "ID" or "ID:width"
+ */
+export function matchBetaBlock(line: string): { label?: string } | null {
+  const match = line.match(BETA_BLOCK_PATTERN);
+  if (!match) return null;
+  const [, id, width] = match;
+  if (!id) return {};
+  return { label: width ? `${id}:${width}` : id };
+}
+
+/**
+ * CSS named colors accepted as the first word of a sequence diagram box.
+ */
+const CSS_NAMED_COLORS = new Set(
+  (
+    'aliceblue antiquewhite aqua aquamarine azure beige bisque black ' +
+    'blanchedalmond blue blueviolet brown burlywood cadetblue chartreuse ' +
+    'chocolate coral cornflowerblue cornsilk crimson cyan darkblue darkcyan ' +
+    'darkgoldenrod darkgray darkgreen darkgrey darkkhaki darkmagenta ' +
+    'darkolivegreen darkorange darkorchid darkred darksalmon darkseagreen ' +
+    'darkslateblue darkslategray darkslategrey darkturquoise darkviolet ' +
+    'deeppink deepskyblue dimgray dimgrey dodgerblue firebrick floralwhite ' +
+    'forestgreen fuchsia gainsboro ghostwhite gold goldenrod gray green ' +
+    'greenyellow grey honeydew hotpink indianred indigo ivory khaki lavender ' +
+    'lavenderblush lawngreen lemonchiffon lightblue lightcoral lightcyan ' +
+    'lightgoldenrodyellow lightgray lightgreen lightgrey lightpink ' +
+    'lightsalmon lightseagreen lightskyblue lightslategray lightslategrey ' +
+    'lightsteelblue lightyellow lime limegreen linen magenta maroon ' +
+    'mediumaquamarine mediumblue mediumorchid mediumpurple mediumseagreen ' +
+    'mediumslateblue mediumspringgreen mediumturquoise mediumvioletred ' +
+    'midnightblue mintcream mistyrose moccasin navajowhite navy oldlace ' +
+    'olive olivedrab orange orangered orchid palegoldenrod palegreen ' +
+    'paleturquoise palevioletred papayawhip peachpuff peru pink plum ' +
+    'powderblue purple rebeccapurple red rosybrown royalblue saddlebrown ' +
+    'salmon sandybrown seagreen seashell sienna silver skyblue slateblue ' +
+    'slategray slategrey snow springgreen steelblue tan teal thistle tomato ' +
+    'transparent turquoise violet wheat white whitesmoke yellow yellowgreen'
+  ).split(' ')
+);
+
+/**
+ * Leading color token of a box label: rgb()/rgba()/hsl()/hsla(), hex, or a word.
+ */
+const BOX_COLOR_PATTERN =
+  /^((?:rgba?|hsla?)\([^)]*\)|#[0-9a-fA-F]{3,8}\b|[a-zA-Z]+\b)\s*(.*)$/;
+
+/**
+ * Split a box block label into its optional color and remaining label
+ * (e.g., "Purple Alice & John" -> { color: "Purple", label: "Alice & John" })
+ */
+export function splitBoxLabel(label: string): {
+  color?: string;
+  label?: string;
+} {
+  const match = label.match(BOX_COLOR_PATTERN);
+  if (!match) return { label: label || undefined };
+  const [, color, rest] = match;
+  const isColor = /^[a-zA-Z]+$/.test(color)
+    ? CSS_NAMED_COLORS.has(color.toLowerCase())
+    : true;
+  if (!isColor) return { label };
+  return { color, label: rest || undefined };
+}
+
+/**
+ * Participant declaration (sequence diagram), e.g. "participant A as Alice".
+ */
+const PARTICIPANT_PATTERN = /^(?:participant|actor)\s+(\S+)/;
+
+/**
+ * Match participant declaration and return the participant ID
+ */
+export function matchParticipantId(line: string): string | null {
+  const match = line.match(PARTICIPANT_PATTERN);
+  return match ? match[1] : null;
+}
+
+/**
+ * Whether "participant <id>" reads back as the same ID. An ID with spaces,
+ * e.g. from a note's participant list, would declare only its first word.
+ */
+export function isDeclarableParticipant(id: string): boolean {
+  return matchParticipantId(`participant ${id}`) === id;
+}
+
+/**
+ * Full participant declaration: keyword, ID, and optional alias. A quoted
+ * alias may follow `as` without a space, e.g. `participant A as"Alice"`.
+ */
+const PARTICIPANT_DECL_PATTERN =
+  /^(participant|actor)\s+(\S+)(?:\s+as(?:\s+|(?="))(.+))?$/;
+
+/** Participant declaration fields */
+export interface ParticipantDecl {
+  kind: 'participant' | 'actor';
+  id: string;
+  /** Display name as written, quotes included */
+  alias?: string;
+  /** True when the alias is one quoted string, e.g. "Alice in Wonderland" */
+  aliasQuoted?: boolean;
+}
+
+/**
+ * Match a participant declaration with its keyword and alias.
+ */
+export function matchParticipantDecl(line: string): ParticipantDecl | null {
+  const match = line.match(PARTICIPANT_DECL_PATTERN);
+  if (!match) return null;
+  const [, kind, id, alias] = match;
+  const decl: ParticipantDecl = { kind: kind as ParticipantDecl['kind'], id };
+  if (alias === undefined) return decl;
+  return { ...decl, alias, aliasQuoted: /^"[^"]*"$/.test(alias) };
+}
+
+/**
+ * ER relationship: entities, cardinality operator, and optional label,
+ * e.g. "CUSTOMER ||--o{ ORDER : places".
+ */
+export const ER_RELATION_PATTERN =
+  /^([\w-]+)\s*((?:\|o|\|\||\}o|\}\|)(?:--|\.\.)(?:o\||\|\||o\{|\|\{))\s*([\w-]+)(?:\s*:\s*(.*))?/;
+
+/** State transition without label, e.g. "s1 --> s2" */
+export const STATE_TRANSITION_PATTERN = /^(\S+)\s*-->\s*(\S+)$/;
+
+/** State with a stereotype, e.g. "state fork1 <<fork>>" */
+const STATE_STEREOTYPE_PATTERN = /^state\s+([\w-]+)\s*<<\s*(\w+)\s*>>$/;
+
+/**
+ * Match state stereotype statement (e.g., "state c <<choice>>")
+ */
+export function matchStateStereotype(
+  line: string
+): { name: string; stereotype: string } | null {
+  const match = line.match(STATE_STEREOTYPE_PATTERN);
+  return match ? { name: match[1], stereotype: match[2] } : null;
+}
+
+/** State declaration, e.g. "state Idle" or 'state "Long name" as s2' */
+export const STATE_DECL_PATTERN = /^state\s+(?:"[^"]*"\s+as\s+)?([\w-]+)/;
+
+/** Class declaration without body, e.g. "class Animal" */
+export const CLASS_DECL_PATTERN = /^class\s+([\w-]+)/;
+
+/**
+ * Participant links (sequence diagram): "link A: Label @ URL" or
+ * 'links A: {"Label": "URL"}'. The participant holds no arrow, so a
+ * message from a participant named "link" ("link ->> B: hi") is not one.
+ */
+const LINK_PATTERN = /^(links?)\s+((?:(?!-[-x)>]|[<>])[^:])+?)\s*:\s*(.*)$/;
+
+/**
+ * Match participant link statement (e.g., "link A: Docs @ https://x")
+ */
+export function matchLink(line: string): {
+  keyword: 'link' | 'links';
+  participant: string;
+  payload: string;
+} | null {
+  const match = line.match(LINK_PATTERN);
+  if (!match) return null;
+  return {
+    keyword: match[1] as 'link' | 'links',
+    participant: match[2],
+    payload: match[3],
+  };
+}
+
+/**
+ * Split a trailing comment off a statement line:
+ * "A --> B %% main path" -> ["A --> B", "%% main path"]. `%%` inside
+ * quoted text and inline directives (`%%{...}%%`) are not comments, and
+ * lines starting with `%%` are left whole.
+ */
+export function splitTrailingComment(
+  line: string
+): [string, string | undefined] {
+  if (line.startsWith('%%') || !line.includes('%%')) return [line, undefined];
+  let quoted = false;
+  for (let i = 0; i < line.length; i++) {
+    if (line[i] === '"') quoted = !quoted;
+    if (quoted || !line.startsWith('%%', i)) continue;
+    if (line.startsWith('%%{', i)) {
+      const end = line.indexOf('}%%', i + 3);
+      if (end < 0) break;
+      i = end + 2;
+      continue;
     }
+    return [line.slice(0, i).trimEnd(), line.slice(i)];
   }
-  return null;
+  return [line, undefined];
+}
+
+/**
+ * Activation statements (sequence diagram): "activate A", "deactivate A".
+ */
+const ACTIVATION_PATTERN = /^(activate|deactivate)\s+(.+)$/;
+
+/**
+ * Match activation statement (e.g., "activate A", "deactivate Alice")
+ */
+export function matchActivation(
+  line: string
+): { kind: 'activate' | 'deactivate'; participant: string } | null {
+  const match = line.match(ACTIVATION_PATTERN);
+  if (!match) return null;
+  return {
+    kind: match[1] as 'activate' | 'deactivate',
+    participant: match[2].trim(),
+  };
+}
+
+/**
+ * Whole-line attribute map (flowchart), e.g. 'A@{ shape: rounded }'.
+ */
+const ATTRIBUTE_MAP_PATTERN = /^([\w-]+)\s*@\{(.*)\}$/;
+
+/**
+ * Match attribute map statement (e.g., 'A@{ shape: rounded, label: "x" }')
+ */
+export function matchAttributeMap(
+  line: string
+): { id: string; attributes: Attribute[] } | null {
+  const match = line.match(ATTRIBUTE_MAP_PATTERN);
+  if (!match) return null;
+  const attributes = parseAttributes(match[2]);
+  if (!attributes) return null;
+  return { id: match[1], attributes };
+}
+
+/**
+ * Parse the body of an attribute map into key/value pairs.
+ * Commas and colons inside quotes are not separators.
+ * Returns null when any entry is not a "key: value" pair.
+ */
+export function parseAttributes(body: string): Attribute[] | null {
+  const entries = splitOutsideQuotes(body, ',');
+  if (entries.length === 1 && entries[0].trim() === '') return [];
+
+  const attributes: Attribute[] = [];
+  for (const entry of entries) {
+    const [key, ...rest] = splitOutsideQuotes(entry, ':');
+    if (rest.length === 0 || !key.trim()) return null;
+    attributes.push({ key: key.trim(), value: rest.join(':').trim() });
+  }
+  return attributes;
+}
+
+/**
+ * Split text on a separator character, ignoring separators inside quotes.
+ */
+function splitOutsideQuotes(text: string, separator: string): string[] {
+  const parts: string[] = [];
+  let quote: string | null = null;
+  let start = 0;
+
+  for (let i = 0; i < text.length; i++) {
+    const ch = text[i];
+    if (quote) {
+      if (ch === quote) quote = null;
+    } else if (ch === '"' || ch === "'") {
+      quote = ch;
+    } else if (ch === separator) {
+      parts.push(text.slice(start, i));
+      start = i + 1;
+    }
+  }
+  parts.push(text.slice(start));
+  return parts;
+}
+
+/**
+ * Class annotation, e.g. "<<interface>>" or "<< enumeration >> Color".
+ */
+const ANNOTATION_PATTERN = /^<<\s*([^<>]+?)\s*>>\s*(.*)$/;
+
+/**
+ * Match class annotation (e.g., "<<interface>> Shape")
+ */
+export function matchAnnotation(
+  line: string
+): { name: string; target?: string } | null {
+  const match = line.match(ANNOTATION_PATTERN);
+  if (!match) return null;
+  return { name: match[1], target: match[2] || undefined };
+}
+
+/**
+ * Class relationship operators: optional left head, solid or dotted line,
+ * optional right head (e.g., <|--, *--, o--, -->, ..|>, ..>, --, ..).
+ */
+const CLASS_RELATION_PATTERN =
+  /^([^\s"]+?)\s*(?:"([^"]*)"\s*)?((?:<\||\*|o|<|\(\))?(?:--|\.\.)(?:\|>|\*|o|>|\(\))?)\s*(?:"([^"]*)"\s*)?([^\s":]+)\s*(?::\s*(.*))?$/;
+
+/**
+ * Match class relationship (e.g., 'Class01 "1" --> "many" Class02 : has')
+ */
+export function matchClassRelation(line: string): {
+  from: string;
+  fromCardinality?: string;
+  arrow: string;
+  toCardinality?: string;
+  to: string;
+  label?: string;
+} | null {
+  const match = line.match(CLASS_RELATION_PATTERN);
+  if (!match) return null;
+  return {
+    from: match[1],
+    fromCardinality: match[2]?.trim(),
+    arrow: match[3],
+    toCardinality: match[4]?.trim(),
+    to: match[5],
+    label: match[6]?.trim() || undefined,
+  };
+}
+
+/** Member visibility markers (public, protected, package, private) */
+export const CLASS_VISIBILITIES = ['+', '#', '~', '-'] as const;
+
+/**
+ * Class body member, e.g. "+deposit(amount) bool", "-String owner$".
+ */
+const CLASS_MEMBER_PATTERN = /^([+#~-])?\s*(.*?)[$*]?$/;
+
+/**
+ * Match class body member and classify it as a field or method
+ * (a method has parentheses). The name is the identifier before "(" for
+ * methods, and the name before ":" or the last word for fields.
+ */
+export function matchClassMember(line: string): {
+  kind: 'field' | 'method';
+  visibility?: (typeof CLASS_VISIBILITIES)[number];
+  name: string;
+} | null {
+  const match = line.match(CLASS_MEMBER_PATTERN);
+  if (!match || !match[2]) return null;
+  const visibility = match[1] as (typeof CLASS_VISIBILITIES)[number];
+  const body = match[2];
+  const paren = body.indexOf('(');
+  if (paren > 0) {
+    return { kind: 'method', visibility, name: body.slice(0, paren).trim() };
+  }
+  const colon = body.indexOf(':');
+  const name =
+    colon > 0 ? body.slice(0, colon).trim() : body.split(/\s+/).pop() ?? '';
+  return { kind: 'field', visibility, name };
+}
+
+/**
+ * Flowchart styling and interaction statements
+ * (e.g., "classDef warn fill:#f00", "style A stroke:#333", "click A call cb()").
+ */
+export const STYLE_STATEMENT_PATTERN =
+  /^(?:classDef|class|style|linkStyle|click)\s/;
+
+/** Sequence diagram keywords the parser keeps as generic lines */
+export const SEQUENCE_KEYWORD_PATTERN =
+  /^(?:title\b|accTitle\s*:|accDescr\b|create\s|destroy\s)/;
+
+/** Flowchart keywords the parser keeps as generic lines */
+export const FLOWCHART_KEYWORD_PATTERN =
+  /^(?:direction\s|title\b|accTitle\s*:|accDescr\b)/;
+
+/**
+ * Flowchart edge ID prefix with its link operator, e.g. " e1@-->" or " e2@==>".
+ * Requires whitespace before the ID so node IDs are never split.
+ */
+export const EDGE_ID_PATTERN =
+  /(\s[\w-]+@[<ox]?(?:-{2,}|={2,}|-\.+-|~{3,})(?:>|[ox](?=\s))?)\s*(\|)?/g;
+
+/**
+ * Positional note (e.g., "Note right of A: text", "note over A,B").
+ */
+const NOTE_PATTERN =
+  /^note\s+(left\s+of|right\s+of|over)\s+([^:]+?)\s*(?::\s*(.*))?$/i;
+
+/**
+ * Match positional note statement (any keyword casing)
+ */
+export function matchNote(
+  line: string
+): { position: NotePosition; participants: string[]; text?: string } | null {
+  const match = line.match(NOTE_PATTERN);
+  if (!match) return null;
+  return {
+    position: match[1].toLowerCase().replace(/\s+/, ' ') as NotePosition,
+    participants: match[2].split(',').map((p) => p.trim()),
+    text: match[3] === undefined ? undefined : match[3].trim(),
+  };
 }
 
 /**
@@ -108,6 +596,9 @@ const ARROW_PATTERN =
 export function matchArrowMessage(
   line: string
 ): { from: string; arrow: string; to: string; message: string } | null {
+  // Every arrow has a '-' and every message a ':'; most flowchart lines
+  // have neither, so skip the pattern for them
+  if (!line.includes(':') || !line.includes('-')) return null;
   const match = line.match(ARROW_PATTERN);
   if (!match) return null;
   // Keep flowchart class assignment syntax untouched, e.g. A --> B:::warning
@@ -121,17 +612,258 @@ export function matchArrowMessage(
 }
 
 /**
- * Match brace block start (state Name {, class Name {, namespace Name {)
+ * ER entity attribute block start (e.g., "CUSTOMER {", "p[Person] {").
+ * Relationship lines like "CUSTOMER ||--o{ ORDER : places" never match.
+ */
+const ER_ENTITY_BLOCK_PATTERN =
+  /^("[^"]*"|[\w-]+(?:\[[^\]]*\])?)\s*\{\s*$/;
+
+/** Brace block start for the keywords in `BRACE_BLOCK_KEYWORDS` */
+const BRACE_BLOCK_PATTERN = new RegExp(
+  `^(${BRACE_BLOCK_KEYWORDS.join('|')})\\s+(.+?)\\s*\\{\\s*$`
+);
+
+/**
+ * Match brace block start (state Name {, class Name {, namespace Name {),
+ * plus entity attribute blocks in erDiagram.
  */
 export function matchBraceBlockStart(
-  line: string
+  line: string,
+  diagramType: DiagramType = 'unknown'
 ): { kind: BraceBlockKind; name: string } | null {
-  for (const keyword of BRACE_BLOCK_KEYWORDS) {
-    const pattern = new RegExp(`^${keyword}\\s+(.+?)\\s*\\{\\s*$`);
-    const match = line.match(pattern);
-    if (match) {
-      return { kind: keyword, name: match[1].trim() };
-    }
+  if (diagramType === 'erDiagram') {
+    const match = line.match(ER_ENTITY_BLOCK_PATTERN);
+    return match ? { kind: 'entity', name: match[1] } : null;
+  }
+  if (!line.trimEnd().endsWith('{')) return null;
+  const match = line.match(BRACE_BLOCK_PATTERN);
+  return match
+    ? { kind: match[1] as BraceBlockKind, name: match[2].trim() }
+    : null;
+}
+
+/**
+ * ER entity attribute (e.g., 'string name PK "primary"', "int id PK, FK").
+ */
+const ER_ATTRIBUTE_PATTERN =
+  /^([\w-]+(?:\([\d,\s]*\))?(?:\[\])?)\s+([\w-]+)(?:\s+((?:PK|FK|UK)(?:\s*,\s*(?:PK|FK|UK))*))?(?:\s+("[^"]*"))?$/;
+
+/**
+ * Match ER attribute line inside an entity block
+ */
+export function matchErAttribute(
+  line: string
+): { type: string; name: string; keys?: string[]; comment?: string } | null {
+  const match = line.match(ER_ATTRIBUTE_PATTERN);
+  if (!match) return null;
+  return {
+    type: match[1],
+    name: match[2],
+    keys: match[3]?.split(/\s*,\s*/),
+    comment: match[4],
+  };
+}
+
+/**
+ * Gantt settings: "dateFormat YYYY-MM-DD", "tickInterval 1week",
+ * "todayMarker off", or flags such as "inclusiveEndDates".
+ */
+const GANTT_SETTING_PATTERN =
+  /^(dateFormat|axisFormat|tickInterval|todayMarker|excludes|includes|weekday|weekend|inclusiveEndDates|topAxis)(?:\s+(.*))?$/;
+
+/**
+ * Match gantt setting line (e.g., "tickInterval 1week")
+ */
+export function matchGanttSetting(
+  line: string
+): { keyword: string; value: string } | null {
+  const match = line.match(GANTT_SETTING_PATTERN);
+  return match ? { keyword: match[1], value: match[2] ?? '' } : null;
+}
+
+/** Gantt keywords whose lines are not tasks even when they contain a colon */
+const GANTT_KEYWORD_PATTERN =
+  /^(?:dateFormat|axisFormat|tickInterval|title|excludes|includes|todayMarker|weekday|weekend|inclusiveEndDates|topAxis|section|click|accTitle|accDescr)\b/;
+
+/**
+ * Match gantt task line (e.g., "Design :des1, 2014-01-06, 3d")
+ */
+export function matchGanttTask(
+  line: string
+): { name: string; fields: string[] } | null {
+  if (GANTT_KEYWORD_PATTERN.test(line)) return null;
+  const match = line.match(/^([^:]+?)\s*:\s*(.+)$/);
+  if (!match) return null;
+  return { name: match[1], fields: match[2].split(/\s*,\s*/) };
+}
+
+/** Journey keywords whose lines are not tasks even when they contain a colon */
+const JOURNEY_KEYWORD_PATTERN = /^(?:title|section|accTitle|accDescr)\b/;
+
+/**
+ * Match journey task line (e.g., "Make tea: 5: Me, Cat"). The score is
+ * kept as written; actors are empty when the second colon is missing.
+ */
+export function matchJourneyTask(
+  line: string
+): { name: string; score: string; actors: string[] } | null {
+  if (JOURNEY_KEYWORD_PATTERN.test(line)) return null;
+  const match = line.match(/^([^:]+?)\s*:\s*([^:]*?)\s*(?::(.*))?$/);
+  if (!match) return null;
+  const actors = (match[3] ?? '')
+    .split(',')
+    .map((actor) => actor.trim())
+    .filter(Boolean);
+  return { name: match[1], score: match[2], actors };
+}
+
+/** Parsed `%%{init: {...}}%%` directive */
+export interface InitDirective {
+  /** Config object, or null when it is not a valid JSON object */
+  config: Record<string, unknown> | null;
+  /** Offset of the config text within the directive */
+  offset: number;
+}
+
+const INIT_DIRECTIVE_PATTERN = /^%%\{\s*(?:init|initialize)\s*:\s*/;
+
+/** A double-quoted JSON string, or a single-quoted one to rewrite */
+const QUOTED_STRING_PATTERN = /"(?:[^"\\]|\\.)*"|'((?:[^'\\]|\\.)*)'/g;
+
+/**
+ * Match init directive (e.g., `%%{init: {"theme": "dark"}}%%`). Mermaid
+ * also accepts single-quoted strings, so those are read as JSON strings.
+ */
+export function matchInitDirective(content: string): InitDirective | null {
+  const match = content.match(INIT_DIRECTIVE_PATTERN);
+  if (!match) return null;
+  const offset = match[0].length;
+  const text = content.slice(offset).replace(/\}\s*%%$/, '');
+  const json = text.replace(QUOTED_STRING_PATTERN, (quoted, single) =>
+    single === undefined
+      ? quoted
+      : JSON.stringify(single.replace(/\\'/g, "'"))
+  );
+  let config: unknown = null;
+  try {
+    config = JSON.parse(json);
+  } catch {
+    // Reported by lint as a malformed directive
+  }
+  const isObject =
+    typeof config === 'object' && config !== null && !Array.isArray(config);
+  return {
+    config: isObject ? (config as Record<string, unknown>) : null,
+    offset,
+  };
+}
+
+/** Timeline keywords whose lines are not periods even with a colon */
+const TIMELINE_KEYWORD_PATTERN = /^(?:title|section|accTitle|accDescr)\b/;
+
+/**
+ * Match timeline period line (e.g., "2004 : Facebook : Google")
+ */
+export function matchTimelinePeriod(
+  line: string
+): { period: string; events: string[] } | null {
+  if (TIMELINE_KEYWORD_PATTERN.test(line)) return null;
+  const match = line.match(/^([^:]+?)\s*:(.*)$/);
+  if (!match) return null;
+  return { period: match[1], events: splitTimelineEvents(match[2]) };
+}
+
+/**
+ * Events of a timeline line after its first colon, e.g. " A : B". As in
+ * Mermaid, only a colon followed by whitespace separates events, so URLs
+ * and clock times stay in one event.
+ */
+export function splitTimelineEvents(text: string): string[] {
+  return text.split(/\s*:\s+/).map((event) => event.trim());
+}
+
+/** Quadrant chart point, e.g. "Campaign A:::hot: [0.3, 0.6] radius: 10" */
+export interface QuadrantPoint {
+  name: string;
+  className?: string;
+  /** Coordinates as written, e.g. "0.3" */
+  x: string;
+  y: string;
+  /** Style after the coordinates, e.g. "radius: 10, color: #ff3300" */
+  style?: string;
+}
+
+/** Quadrant chart keywords whose lines are not points */
+const QUADRANT_KEYWORD_PATTERN =
+  /^(?:title|x-axis|y-axis|quadrant-[1-4]|classDef|accTitle|accDescr)\b/;
+
+const QUADRANT_POINT_PATTERN =
+  /^(.+?)(?::::([\w-]+))?\s*:\s*\[\s*([^,\]]*?)\s*,\s*([^,\]]*?)\s*\]\s*(.*)$/;
+
+/**
+ * Match quadrant chart point line (e.g., "Campaign A: [0.3, 0.6]")
+ */
+export function matchQuadrantPoint(line: string): QuadrantPoint | null {
+  if (QUADRANT_KEYWORD_PATTERN.test(line)) return null;
+  const match = line.match(QUADRANT_POINT_PATTERN);
+  if (!match) return null;
+  const [, name, className, x, y, style] = match;
+  return {
+    name,
+    ...(className && { className }),
+    x,
+    y,
+    ...(style && { style }),
+  };
+}
+
+/** Relationship keywords of requirement diagrams */
+export const REQUIREMENT_RELATIONS = [
+  'contains',
+  'copies',
+  'derives',
+  'satisfies',
+  'verifies',
+  'refines',
+  'traces',
+];
+
+/**
+ * Requirement diagram relationship, e.g. "test_entity - satisfies -> req".
+ * `reversed` is true for the "req <- satisfies - test_entity" form, where
+ * the target is written first.
+ */
+export interface RequirementRelation {
+  source: string;
+  target: string;
+  /** Keyword as written, e.g. "satisfies" */
+  relation: string;
+  reversed: boolean;
+}
+
+const REQUIREMENT_NAME = '("[^"]*"|[^\\s"<>-]+)';
+const REQUIREMENT_RELATION_PATTERN = new RegExp(
+  `^${REQUIREMENT_NAME}\\s*-\\s*(\\w+)\\s*->\\s*${REQUIREMENT_NAME}$`
+);
+const REVERSED_REQUIREMENT_RELATION_PATTERN = new RegExp(
+  `^${REQUIREMENT_NAME}\\s*<-\\s*(\\w+)\\s*-\\s*${REQUIREMENT_NAME}$`
+);
+
+/**
+ * Match requirement relationship line in either direction
+ */
+export function matchRequirementRelation(
+  line: string
+): RequirementRelation | null {
+  const forward = line.match(REQUIREMENT_RELATION_PATTERN);
+  if (forward) {
+    const [, source, relation, target] = forward;
+    return { source, target, relation, reversed: false };
+  }
+  const reversed = line.match(REVERSED_REQUIREMENT_RELATION_PATTERN);
+  if (reversed) {
+    const [, target, relation, source] = reversed;
+    return { source, target, relation, reversed: true };
   }
   return null;
 }
@@ -0,0 +1,214 @@
+/**
+ * Diagram metrics (node/edge/participant/message counts, depth, line length).
+ * Used by the CLI `stats` subcommand for documentation audits.
+ */
+
+import type { Diagram, DiagramType, Statement } from './types.js';
+import { parse } from './parser.js';
+import {
+  CLASS_DECL_PATTERN,
+  ER_RELATION_PATTERN,
+  matchParticipantId,
+  STATE_DECL_PATTERN,
+  STATE_TRANSITION_PATTERN,
+} from './rules.js';
+import {
+  chainEdgeCount,
+  chainNodeIds,
+  parseFlowchartLine,
+} from './flowchart.js';
+
+/**
+ * Metrics for a single diagram.
+ */
+export interface DiagramStats {
+  diagramType: DiagramType;
+  /** Non-blank statements */
+  statements: number;
+  /** Distinct nodes (flowchart nodes, classes, states, entities) */
+  nodes: number;
+  /** Edges (flowchart links, class/ER relationships, state transitions) */
+  edges: number;
+  /** Distinct sequence diagram participants, declared or implicit */
+  participants: number;
+  /** Sequence diagram messages */
+  messages: number;
+  /** Deepest block/brace nesting level */
+  maxDepth: number;
+  /** Longest source line (1-based line number, 0 for empty input) */
+  longestLine: { line: number; length: number };
+}
+
+/**
+ * Compute metrics for Mermaid diagram source.
+ */
+export function getDiagramStats(input: string): DiagramStats {
+  const diagram = parse(input);
+  const { nodes, edges, participants, messages } = countElements(diagram);
+  const statements = diagram.statements.filter((s) => s.type !== 'blank-line');
+
+  return {
+    diagramType: diagram.type,
+    statements: statements.length,
+    nodes,
+    edges,
+    participants,
+    messages,
+    maxDepth: maxNestingDepth(diagram.statements),
+    longestLine: longestLine(input),
+  };
+}
+
+function countElements(diagram: Diagram): {
+  nodes: number;
+  edges: number;
+  participants: number;
+  messages: number;
+} {
+  const nodes = new Set<string>();
+  const participants = new Set<string>();
+  let edges = 0;
+  let messages = 0;
+
+  switch (diagram.type) {
+    case 'sequenceDiagram':
+      for (const stmt of diagram.statements) {
+        if (stmt.type === 'participant') {
+          const id = matchParticipantId(stmt.content);
+          if (id) participants.add(id);
+        } else if (stmt.type === 'arrow-message') {
+          participants.add(stmt.from);
+          participants.add(stmt.to);
+          messages++;
+        }
+      }
+      break;
+
+    case 'flowchart':
+    case 'graph': {
+      const edgeIds = new Set<string>();
+      const attributeIds: string[] = [];
+      for (const stmt of diagram.statements) {
+        if (stmt.type === 'attribute-map') {
+          attributeIds.push(stmt.id);
+          continue;
+        }
+        if (stmt.type !== 'generic-line') continue;
+        const chain = parseFlowchartLine(stmt.content);
+        if (!chain) continue;
+        chainNodeIds(chain).forEach((id) => nodes.add(id));
+        for (const link of chain.links) {
+          if (link.edgeId) edgeIds.add(link.edgeId);
+        }
+        edges += chainEdgeCount(chain);
+      }
+      // Attribute maps describe nodes unless they target an edge ID
+      attributeIds
+        .filter((id) => !edgeIds.has(id))
+        .forEach((id) => nodes.add(id));
+      break;
+    }
+
+    case 'classDiagram':
+      for (const stmt of diagram.statements) {
+        if (stmt.type === 'class-relation') {
+          nodes.add(stmt.from);
+          nodes.add(stmt.to);
+          edges++;
+        } else if (stmt.type === 'brace-block-start') {
+          if (stmt.blockKind === 'class') nodes.add(stmt.name);
+        } else if (stmt.type === 'annotation' && stmt.target) {
+          nodes.add(stmt.target);
+        } else if (stmt.type === 'generic-line') {
+          const match = stmt.content.match(CLASS_DECL_PATTERN);
+          if (match) nodes.add(match[1]);
+        }
+      }
+      break;
+
+    case 'stateDiagram':
+    case 'stateDiagram-v2':
+      for (const stmt of diagram.statements) {
+        const transition = stateTransition(stmt);
+        if (transition) {
+          transition
+            .filter((state) => state !== '[*]')
+            .forEach((state) => nodes.add(state));
+          edges++;
+        } else if (
+          stmt.type === 'brace-block-start' ||
+          stmt.type === 'state-stereotype'
+        ) {
+          nodes.add(stmt.name);
+        } else if (stmt.type === 'generic-line') {
+          const match = stmt.content.match(STATE_DECL_PATTERN);
+          if (match) nodes.add(match[1]);
+        }
+      }
+      break;
+
+    case 'erDiagram':
+      for (const stmt of diagram.statements) {
+        if (stmt.type === 'brace-block-start') {
+          nodes.add(stmt.name.replace(/\[.*\]$/, ''));
+        } else if (stmt.type === 'generic-line') {
+          const match = stmt.content.match(ER_RELATION_PATTERN);
+          if (match) {
+            nodes.add(match[1]);
+            nodes.add(match[3]);
+            edges++;
+          }
+        }
+      }
+      break;
+  }
+
+  return {
+    nodes: nodes.size,
+    edges,
+    participants: participants.size,
+    messages,
+  };
+}
+
+/**
+ * Endpoints of a state transition statement, labeled or not.
+ */
+function stateTransition(stmt: Statement): [string, string] | null {
+  if (stmt.type === 'arrow-message') {
+    return stmt.arrow === '-->' ? [stmt.from, stmt.to] : null;
+  }
+  if (stmt.type === 'generic-line') {
+    const match = stmt.content.match(STATE_TRANSITION_PATTERN);
+    return match ? [match[1], match[2]] : null;
+  }
+  return null;
+}
+
+function maxNestingDepth(statements: Statement[]): number {
+  let depth = 0;
+  let max = 0;
+  for (const stmt of statements) {
+    if (stmt.type === 'block-start' || stmt.type === 'brace-block-start') {
+      depth++;
+      max = Math.max(max, depth);
+    } else if (
+      (stmt.type === 'block-end' || stmt.type === 'brace-block-end') &&
+      depth > 0
+    ) {
+      depth--;
+    }
+  }
+  return max;
+}
+
+function longestLine(input: string): { line: number; length: number } {
+  let longest = { line: 0, length: 0 };
+  input.split('\n').forEach((line, index) => {
+    const length = line.replace(/\s+$/, '').length;
+    if (length > longest.length) {
+      longest = { line: index + 1, length };
+    }
+  });
+  return longest;
+}
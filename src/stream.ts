@@ -0,0 +1,169 @@
+/**
+ * Streaming formatter for very large generated diagrams and pipes.
+ * Input is parsed and formatted one line at a time, so neither the whole
+ * input nor the whole output is held in memory.
+ */
+
+import type { FormatOptions, Statement } from './types.js';
+import { LineParser, parse } from './parser.js';
+import {
+  format,
+  isStreamable,
+  keepsLayout,
+  LineEmitter,
+  resolveOptions,
+  streamStatements,
+} from './formatter.js';
+
+/** Input chunk: text, or UTF-8 bytes such as a Node.js Buffer */
+export type StreamChunk = string | Uint8Array;
+
+/**
+ * Format Mermaid source read in chunks, yielding the output in chunks.
+ * The output is the same as `formatMermaid` on the whole input.
+ *
+ * Options that look across statements (alignment, participant grouping,
+ * class member sorting, style grouping, label deduplication) need the
+ * whole diagram; with them the input is buffered and formatted at the end.
+ *
+ * @param input - Chunks of Mermaid source, e.g. `process.stdin`
+ * @param options - Formatting options
+ *
+ * @example
+ * ```ts
+ * for await (const chunk of formatStream(process.stdin)) {
+ *   process.stdout.write(chunk);
+ * }
+ * ```
+ */
+export async function* formatStream(
+  input: AsyncIterable<StreamChunk> | Iterable<StreamChunk>,
+  options: FormatOptions = {}
+): AsyncGenerator<string> {
+  const formatter = new StreamFormatter(options);
+  const decoder = new TextDecoder();
+  // Pieces of the line still waiting for its newline; only the new chunk
+  // is searched, and the pieces are joined once the line is complete
+  let partial: string[] = [];
+  let partialLength = 0;
+
+  for await (const chunk of input) {
+    const text =
+      typeof chunk === 'string'
+        ? chunk
+        : decoder.decode(chunk, { stream: true });
+    let output = '';
+    let start = 0;
+    let newline = text.indexOf('\n');
+    while (newline !== -1) {
+      partial.push(text.slice(start, newline));
+      output += formatter.feed(partial.join(''));
+      partial = [];
+      partialLength = 0;
+      start = newline + 1;
+      newline = text.indexOf('\n', start);
+    }
+    if (start < text.length) {
+      partial.push(text.slice(start));
+      partialLength += text.length - start;
+    }
+    formatter.checkPartialLine(partialLength);
+    if (output) yield output;
+  }
+
+  // The text after the last newline is a line too, as in `parse`
+  const output = formatter.end(partial.join('') + decoder.decode());
+  if (output) yield output;
+}
+
+/**
+ * Line-at-a-time formatting state. Lines are held only until the diagram
+ * type is known, which decides between formatting statements as they
+ * arrive, passing indent-sensitive diagrams through, or buffering.
+ */
+class StreamFormatter {
+  /** Options with defaults and the style level applied */
+  private readonly opts: Required<FormatOptions>;
+  private readonly limits: FormatOptions['limits'];
+  private readonly parser: LineParser;
+  private mode: 'detect' | 'stream' | 'raw' | 'buffer' = 'detect';
+  private emitter: LineEmitter | null = null;
+  /** Lines and their statements not yet formatted */
+  private pending: { line: string; stmt: Statement }[] = [];
+  private wrote = false;
+
+  constructor(options: FormatOptions) {
+    this.opts = resolveOptions(options);
+    this.limits = options.limits;
+    this.parser = new LineParser(options.limits);
+  }
+
+  /** Feed one complete input line, returning output that is now final */
+  feed(line: string): string {
+    if (this.mode === 'raw') {
+      // Passed-through lines still count against the limits
+      if (this.limits) this.parser.next(line);
+      return `${line}\n`;
+    }
+    const stmt = this.parser.next(line);
+    if (this.mode === 'stream') {
+      this.emit(stmt);
+      return this.take();
+    }
+    this.pending.push({ line, stmt });
+    const type = this.parser.diagramType;
+    return this.mode === 'detect' && type !== 'unknown' ? this.start() : '';
+  }
+
+  /** Check the line still waiting for its newline against the limits */
+  checkPartialLine(length: number): void {
+    if (this.limits) this.parser.checkPartialLine(length);
+  }
+
+  /** Feed the last line (possibly empty) and return the remaining output */
+  end(line: string): string {
+    if (this.mode === 'raw') {
+      if (this.limits) this.parser.next(line);
+      return line ? `${line}\n` : '';
+    }
+    let output = this.feed(line);
+    if (this.mode === 'detect') output = this.start();
+    if (this.mode === 'buffer') {
+      const source = this.pending.map((entry) => entry.line).join('\n');
+      return format(parse(source, this.limits), this.opts);
+    }
+    // An empty diagram still formats to a single newline
+    return this.mode === 'stream' && !this.wrote ? '\n' : output;
+  }
+
+  private start(): string {
+    const type = this.parser.diagramType;
+    const decl = this.pending.find(({ stmt }) => stmt.type === 'diagram-decl');
+    if (keepsLayout(type, decl?.stmt.content ?? null, this.opts)) {
+      this.mode = 'raw';
+      return this.pending.map((entry) => `${entry.line}\n`).join('');
+    }
+    if (!isStreamable(type, this.opts)) {
+      this.mode = 'buffer';
+      return '';
+    }
+    this.mode = 'stream';
+    this.emitter = new LineEmitter(type, this.opts);
+    for (const { stmt } of this.pending) this.emit(stmt);
+    this.pending = [];
+    return this.take();
+  }
+
+  private emit(stmt: Statement): void {
+    const type = this.parser.diagramType;
+    for (const part of streamStatements(stmt, type, this.opts)) {
+      this.emitter?.emit(part, null);
+    }
+  }
+
+  private take(): string {
+    const lines = this.emitter?.take() ?? [];
+    if (lines.length > 0) this.wrote = true;
+    return lines.map((line) => `${line}\n`).join('');
+  }
+}
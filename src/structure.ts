@@ -0,0 +1,256 @@
+/**
+ * Structural export of diagram content: nodes, edges, participants, and
+ * messages, independent of how the AST represents statements. Meant for
+ * analysis tools, e.g. dependency reports generated from flowcharts.
+ */
+
+import type { Diagram, DiagramType } from './types.js';
+import {
+  CLASS_DECL_PATTERN,
+  matchParticipantDecl,
+  STATE_DECL_PATTERN,
+} from './rules.js';
+import { nodeImagery } from './flowchart.js';
+import { typedDiagram } from './typed.js';
+
+/**
+ * Diagram content as plain data. Every list is in order of first
+ * appearance and every entry records its 1-based source line.
+ * `version` changes only when a field is removed or changes meaning.
+ */
+export interface DiagramStructure {
+  version: 1;
+  diagramType: DiagramType;
+  /** Flowchart nodes, classes, states, or ER entities */
+  nodes: StructureNode[];
+  /** Flowchart links, class and ER relationships, or state transitions */
+  edges: StructureEdge[];
+  /** Sequence diagram participants, declared or implicit */
+  participants: StructureParticipant[];
+  /** Sequence diagram messages */
+  messages: StructureMessage[];
+}
+
+export interface StructureNode {
+  id: string;
+  /** Display text, e.g. "Start" for A[Start] */
+  label?: string;
+  /** Flowchart shape delimiters, e.g. "([" and "])" */
+  shape?: { open: string; close: string };
+  /** FontAwesome icon, e.g. "fa:fa-car" for B[fa:fa-car Car] */
+  icon?: string;
+  /** Image URL from an `img` attribute */
+  image?: string;
+  line: number;
+}
+
+export interface StructureEdge {
+  from: string;
+  to: string;
+  /** Link operator without its label, e.g. "-->" or "||--o{" */
+  arrow: string;
+  label?: string;
+  line: number;
+}
+
+export interface StructureParticipant {
+  id: string;
+  /** Display name from "participant A as Alice" */
+  alias?: string;
+  kind: 'participant' | 'actor';
+  /** False when the participant only appears in messages */
+  declared: boolean;
+  line: number;
+}
+
+export interface StructureMessage {
+  from: string;
+  to: string;
+  arrow: string;
+  text: string;
+  line: number;
+}
+
+/**
+ * Extract the nodes, edges, participants, and messages of a diagram.
+ * Diagram types without such content give empty lists.
+ *
+ * @example
+ * ```ts
+ * diagramStructure(parse('graph TD\nA[Start] -->|go| B')).edges;
+ * // [{ from: 'A', to: 'B', arrow: '-->', label: 'go', line: 2 }]
+ * ```
+ */
+export function diagramStructure(diagram: Diagram): DiagramStructure {
+  const structure: DiagramStructure = {
+    version: 1,
+    diagramType: diagram.type,
+    nodes: [],
+    edges: [],
+    participants: [],
+    messages: [],
+  };
+  const nodes = new Map<string, StructureNode>();
+  const participants = new Map<string, StructureParticipant>();
+  const edgeIds = new Set<string>();
+
+  // Record a node at its first appearance; later labels fill in a bare one
+  const node = (id: string, line: number, label?: string): StructureNode => {
+    let entry = nodes.get(id);
+    if (!entry) {
+      entry = { id, line };
+      nodes.set(id, entry);
+      structure.nodes.push(entry);
+    }
+    if (label !== undefined && entry.label === undefined) entry.label = label;
+    return entry;
+  };
+  const edge = (
+    from: string,
+    to: string,
+    arrow: string,
+    label: string | undefined,
+    line: number
+  ) => {
+    structure.edges.push(
+      label ? { from, to, arrow, label, line } : { from, to, arrow, line }
+    );
+  };
+  const participant = (id: string, line: number) => {
+    if (participants.has(id)) return;
+    const entry: StructureParticipant = {
+      id,
+      kind: 'participant',
+      declared: false,
+      line,
+    };
+    participants.set(id, entry);
+    structure.participants.push(entry);
+  };
+
+  const typed = typedDiagram(diagram);
+  switch (typed.family) {
+    case 'sequence':
+      typed.statements.forEach((stmt, index) => {
+        const line = index + 1;
+        if (stmt.type === 'participant') {
+          const decl = matchParticipantDecl(stmt.content);
+          if (!decl || participants.has(decl.id)) return;
+          const { id, kind, alias } = decl;
+          const entry: StructureParticipant = alias
+            ? { id, alias, kind, declared: true, line }
+            : { id, kind, declared: true, line };
+          participants.set(id, entry);
+          structure.participants.push(entry);
+        } else if (stmt.type === 'arrow-message') {
+          participant(stmt.from, line);
+          participant(stmt.to, line);
+          const { from, to, arrow, message: text } = stmt;
+          structure.messages.push({ from, to, arrow, text, line });
+        }
+      });
+      break;
+
+    case 'flowchart':
+      typed.statements.forEach((stmt, index) => {
+        const line = index + 1;
+        // Attribute maps describe nodes unless they target an edge ID
+        if (stmt.type === 'attribute-map') {
+          if (edgeIds.has(stmt.id)) return;
+          const entry = node(stmt.id, line);
+          const body = stmt.attributes
+            .map(({ key, value }) => `${key}: ${value}`)
+            .join(', ');
+          addImagery(entry, nodeImagery({ id: stmt.id, attributes: body }));
+          return;
+        }
+        if (stmt.type !== 'flowchart-chain') return;
+        const { chain } = stmt;
+        for (const flowNode of chain.groups.flat()) {
+          const { id, shape } = flowNode;
+          const label = shape === undefined ? undefined : unquote(shape.text);
+          const entry = node(id, line, label);
+          if (shape && !entry.shape) {
+            entry.shape = { open: shape.open, close: shape.close };
+          }
+          addImagery(entry, flowNode);
+        }
+        chain.links.forEach((link, i) => {
+          if (link.edgeId) edgeIds.add(link.edgeId);
+          for (const from of chain.groups[i]) {
+            for (const to of chain.groups[i + 1]) {
+              edge(from.id, to.id, link.arrow, link.label, line);
+            }
+          }
+        });
+      });
+      break;
+
+    case 'class':
+      typed.statements.forEach((stmt, index) => {
+        const line = index + 1;
+        if (stmt.type === 'class-relation') {
+          node(stmt.from, line);
+          node(stmt.to, line);
+          edge(stmt.from, stmt.to, stmt.arrow, stmt.label, line);
+        } else if (stmt.type === 'brace-block-start') {
+          if (stmt.blockKind === 'class') node(stmt.name, line);
+        } else if (stmt.type === 'annotation' && stmt.target) {
+          node(stmt.target, line);
+        } else if (stmt.type === 'generic-line') {
+          const match = stmt.content.match(CLASS_DECL_PATTERN);
+          if (match) node(match[1], line);
+        }
+      });
+      break;
+
+    case 'state':
+      typed.statements.forEach((stmt, index) => {
+        const line = index + 1;
+        if (stmt.type === 'state-transition') {
+          const { from, to, label } = stmt;
+          for (const state of [from, to]) {
+            if (state !== '[*]') node(state, line);
+          }
+          edge(from, to, '-->', label, line);
+        } else if (
+          stmt.type === 'brace-block-start' ||
+          stmt.type === 'state-stereotype'
+        ) {
+          node(stmt.name, line);
+        } else if (stmt.type === 'generic-line') {
+          const match = stmt.content.match(STATE_DECL_PATTERN);
+          if (match) node(match[1], line);
+        }
+      });
+      break;
+
+    case 'er':
+      typed.statements.forEach((stmt, index) => {
+        const line = index + 1;
+        if (stmt.type === 'brace-block-start') {
+          node(stmt.name.replace(/\[.*\]$/, ''), line);
+        } else if (stmt.type === 'er-relation') {
+          const { from, to, cardinality, label } = stmt;
+          node(from, line);
+          node(to, line);
+          edge(from, to, cardinality, label, line);
+        }
+      });
+      break;
+  }
+
+  return structure;
+}
+
+function addImagery(
+  entry: StructureNode,
+  { icon, image }: { icon?: string; image?: string }
+): void {
+  if (icon && entry.icon === undefined) entry.icon = icon;
+  if (image && entry.image === undefined) entry.image = image;
+}
+
+function unquote(text: string): string {
+  return text.replace(/^"(.*)"$/, '$1');
+}
@@ -0,0 +1,363 @@
+/**
+ * Helpers for checking formatter stability: an idempotence assertion and a
+ * seeded generator of random valid diagrams and option sets, for property
+ * tests like `format(format(x)) === format(x)`, plus a generator of large
+ * diagrams for benchmarks.
+ *
+ * Exposed as the `mermaid-formatter/testing` entry point.
+ */
+
+import type { FormatOptions } from './types.js';
+import { formatMermaid } from './index.js';
+
+/**
+ * Format input twice and throw when the second pass changes the output.
+ *
+ * @param input - Mermaid diagram source code
+ * @param options - Formatting options
+ * @returns The formatted output
+ * @throws Error naming the first line that differs between passes
+ */
+export function assertIdempotent(
+  input: string,
+  options: FormatOptions = {}
+): string {
+  const once = formatMermaid(input, options);
+  const twice = formatMermaid(once, options);
+  if (once === twice) return once;
+
+  const first = once.split('\n');
+  const second = twice.split('\n');
+  const line = first.findIndex((text, i) => text !== second[i]);
+  throw new Error(
+    `Formatting is not idempotent at line ${line + 1}:\n` +
+      `  first:  ${JSON.stringify(first[line])}\n` +
+      `  second: ${JSON.stringify(second[line])}\n` +
+      `options: ${JSON.stringify(options)}\n` +
+      `input:\n${input}`
+  );
+}
+
+// ============================================================================
+// Random Generation
+// ============================================================================
+
+/** Deterministic pseudo-random source (mulberry32) */
+type Random = () => number;
+
+function createRandom(seed: number): Random {
+  let state = seed >>> 0;
+  return () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+}
+
+function pick<T>(random: Random, items: readonly T[]): T {
+  return items[Math.floor(random() * items.length)];
+}
+
+function int(random: Random, min: number, max: number): number {
+  return min + Math.floor(random() * (max - min + 1));
+}
+
+/** Sloppy but valid whitespace: random indentation and inner spacing */
+function noisy(random: Random, line: string): string {
+  const indent = pick(random, ['', '', ' ', '  ', '\t', '        ']);
+  const spaced = line.replace(/ /g, () => pick(random, [' ', ' ', '  ']));
+  return indent + spaced + pick(random, ['', '', ' ']);
+}
+
+const NAMES = ['Alice', 'Bob', 'Carol', 'Dan', 'Eve', 'API', 'DB'];
+const WORDS = ['start', 'check', 'done', 'retry', 'ok', 'fail', 'load'];
+
+type Generator = (random: Random) => string[];
+
+const GENERATORS: Generator[] = [
+  // Sequence diagram with nested blocks, notes, and activations
+  (random) => {
+    const lines = ['sequenceDiagram'];
+    if (random() < 0.3) lines.push('autonumber');
+    for (let i = int(random, 0, 3); i > 0; i--) {
+      lines.push(`participant ${pick(random, NAMES)}`);
+    }
+    const body = (depth: number): string[] => {
+      const out: string[] = [];
+      for (let i = int(random, 1, 4); i > 0; i--) {
+        const roll = random();
+        const [a, b] = [pick(random, NAMES), pick(random, NAMES)];
+        if (roll < 0.5) {
+          const arrow = pick(random, ['->>', '-->>', '->', '-x', '-)']);
+          out.push(`${a}${arrow}${b}: ${pick(random, WORDS)}`);
+        } else if (roll < 0.65) {
+          out.push(`Note ${pick(random, ['over', 'left of'])} ${a}: hi`);
+        } else if (roll < 0.75) {
+          out.push(`activate ${a}`, `deactivate ${a}`);
+        } else if (roll < 0.8) {
+          out.push('');
+        } else if (depth < 2) {
+          const kind = pick(random, ['loop', 'alt', 'opt', 'par', 'rect']);
+          out.push(`${kind} ${pick(random, WORDS)}`, ...body(depth + 1));
+          if (kind === 'alt') out.push('else other', ...body(depth + 1));
+          if (kind === 'par') out.push('and other', ...body(depth + 1));
+          out.push('end');
+        }
+      }
+      return out;
+    };
+    return [...lines, ...body(0)];
+  },
+  // Flowchart with subgraphs, shapes, labels, and styles
+  (random) => {
+    const lines = [`flowchart ${pick(random, ['TD', 'LR'])}`];
+    const node = () => {
+      const id = pick(random, ['A', 'B', 'C', 'D', 'E']);
+      const word = pick(random, WORDS);
+      return pick(random, [id, `${id}[${word}]`, `${id}(${word})`]);
+    };
+    const body = (depth: number): string[] => {
+      const out: string[] = [];
+      for (let i = int(random, 1, 4); i > 0; i--) {
+        const roll = random();
+        if (roll < 0.6) {
+          const link = pick(random, ['-->', '---', '-.->', '==>']);
+          const label = random() < 0.3 ? `|${pick(random, WORDS)}|` : '';
+          out.push(`${node()}${link}${label}${node()}`);
+        } else if (roll < 0.7) {
+          out.push(`style ${pick(random, ['A', 'B'])} fill:#f9f`);
+        } else if (roll < 0.75) {
+          out.push(`%% ${pick(random, WORDS)}`);
+        } else if (depth < 2) {
+          out.push(`subgraph S${depth}`, ...body(depth + 1), 'end');
+        }
+      }
+      return out;
+    };
+    return [...lines, ...body(0)];
+  },
+  // Class diagram with bodies and relations
+  (random) => {
+    const lines = ['classDiagram'];
+    for (let i = int(random, 1, 3); i > 0; i--) {
+      const name = pick(random, NAMES);
+      lines.push(`class ${name} {`);
+      if (random() < 0.3) lines.push('<<interface>>');
+      for (let j = int(random, 0, 3); j > 0; j--) {
+        const visibility = pick(random, ['+', '-', '#', '']);
+        lines.push(
+          random() < 0.5
+            ? `${visibility}String ${pick(random, WORDS)}`
+            : `${visibility}${pick(random, WORDS)}() bool`
+        );
+      }
+      lines.push('}');
+      const arrow = pick(random, ['<|--', '*--', 'o--', '-->', '..>']);
+      lines.push(`${name} ${arrow} ${pick(random, NAMES)}`);
+    }
+    return lines;
+  },
+  // State diagram with composite states
+  (random) => {
+    const lines = ['stateDiagram-v2', '[*] --> Idle'];
+    const body = (depth: number): string[] => {
+      const out: string[] = [];
+      for (let i = int(random, 1, 3); i > 0; i--) {
+        if (random() < 0.7 || depth >= 2) {
+          const [a, b] = [pick(random, WORDS), pick(random, WORDS)];
+          out.push(`${a} --> ${b}: ${pick(random, WORDS)}`);
+        } else {
+          out.push(`state S${depth} {`, ...body(depth + 1), '}');
+        }
+      }
+      return out;
+    };
+    return [...lines, ...body(0)];
+  },
+  // ER diagram with attribute blocks
+  (random) => {
+    const lines = ['erDiagram'];
+    for (let i = int(random, 1, 3); i > 0; i--) {
+      const entity = pick(random, ['CUSTOMER', 'ORDER', 'ITEM']);
+      lines.push(`${entity} ||--o{ ${pick(random, ['ORDER', 'ITEM'])} : has`);
+      lines.push(`${entity} {`);
+      for (let j = int(random, 1, 3); j > 0; j--) {
+        const type = pick(random, ['string', 'int']);
+        const key = pick(random, ['', ' PK', ' FK']);
+        lines.push(`${type} ${pick(random, WORDS)}${key}`);
+      }
+      lines.push('}');
+    }
+    return lines;
+  },
+  // Gantt chart with sections
+  (random) => {
+    const lines = ['gantt', 'dateFormat YYYY-MM-DD'];
+    for (let i = int(random, 1, 3); i > 0; i--) {
+      lines.push(`section ${pick(random, WORDS)}`);
+      for (let j = int(random, 1, 3); j > 0; j--) {
+        lines.push(`${pick(random, WORDS)} :t${i}${j}, 2024-01-0${j}, ${j}d`);
+      }
+    }
+    return lines;
+  },
+];
+
+/**
+ * Generate a random valid diagram with sloppy whitespace. The same seed
+ * always produces the same diagram.
+ */
+export function randomDiagram(seed: number): string {
+  const random = createRandom(seed);
+  const lines = pick(random, GENERATORS)(random);
+  return lines.map((line) => (line ? noisy(random, line) : line)).join('\n');
+}
+
+/** Syntax fragments spliced into diagrams by `randomInput` */
+const FRAGMENTS = [
+  ...(
+    '[ ] ( ) { } | " : ; & @ @{ ~ << >> ::: [*] --> ->> --- ==> -.- %% %%{ ' +
+    'end else and option subgraph alt loop par box rect note participant ' +
+    'actor as class state style classDef direction <br/> 😀 中文'
+  ).split(' '),
+  'Note over',
+  ' ',
+  '  ',
+  '\t',
+  '\r',
+  '\n',
+  '\u0301',
+  '\u200b',
+];
+
+/**
+ * Generate arbitrary input from a seed: a random diagram with syntax
+ * fragments spliced in, or a random run of fragments after a diagram
+ * header. Mostly invalid, for checking that APIs handle any string.
+ */
+export function randomInput(seed: number): string {
+  const random = createRandom(seed);
+  if (random() < 0.5) {
+    const chars = [...randomDiagram(int(random, 0, 2 ** 31))];
+    for (let k = int(random, 1, 6); k > 0; k--) {
+      const at = int(random, 0, chars.length);
+      chars.splice(at, int(random, 0, 2), pick(random, FRAGMENTS));
+    }
+    return chars.join('');
+  }
+  const header = pick(random, [
+    '',
+    ...(
+      'flowchart graph sequenceDiagram classDiagram stateDiagram-v2 ' +
+      'erDiagram gantt pie mindmap gitGraph'
+    ).split(' '),
+  ]);
+  const fragments = Array.from({ length: int(random, 0, 30) }, () =>
+    pick(random, FRAGMENTS)
+  );
+  return `${header}\n${fragments.join('')}`;
+}
+
+/** Kinds of diagram `largeDiagram` generates */
+export type LargeDiagramKind = 'flowchart' | 'sequence';
+
+/**
+ * Generate a synthetic diagram of exactly `lines` lines, like the output
+ * of diagram generators: thousands of edges or messages with shapes,
+ * labels, nested blocks, and sloppy whitespace for the normalizer. The
+ * same arguments always give the same diagram.
+ *
+ * @example
+ * ```ts
+ * bench('format 50k lines', () => {
+ *   formatMermaid(largeDiagram('flowchart', 50000));
+ * });
+ * ```
+ */
+export function largeDiagram(
+  kind: LargeDiagramKind,
+  lines: number,
+  seed = 1
+): string {
+  const random = createRandom(seed);
+  const flowchart = kind === 'flowchart';
+  const body = flowchart ? largeFlowchart : largeSequence;
+  const out = [flowchart ? 'flowchart TD' : 'sequenceDiagram'];
+  let depth = 0;
+  while (out.length < lines) {
+    // Close blocks once only room for their "end" lines is left
+    const room = lines - out.length;
+    if (depth > 0 && (room <= depth || random() < 0.05)) {
+      out.push('end');
+      depth--;
+    } else if (depth < 3 && room > depth + 2 && random() < 0.05) {
+      out.push(flowchart ? `subgraph G${out.length}` : 'loop retry');
+      depth++;
+    } else {
+      out.push(noisy(random, body(random, out.length)));
+    }
+  }
+  return out.join('\n');
+}
+
+function largeFlowchart(random: Random, n: number): string {
+  const id = (k: number) => `N${k}`;
+  const shape = (k: number) =>
+    pick(random, [id(k), `${id(k)}[Task ${k}]`, `${id(k)}( Step ${k} )`]);
+  const link = pick(random, ['-->', '---', '-.->', '==>']);
+  const label = random() < 0.3 ? `| ${pick(random, WORDS)} |` : '';
+  const target = int(random, Math.max(n - 50, 0), n + 50);
+  return `${shape(n)} ${link}${label} ${shape(target)}`;
+}
+
+function largeSequence(random: Random, n: number): string {
+  const [a, b] = [pick(random, NAMES), pick(random, NAMES)];
+  if (random() < 0.1) return `Note over ${a}: step ${n}`;
+  const arrow = pick(random, ['->>', '-->>', '->', '-)']);
+  return `${a}${arrow}${b}: ${pick(random, WORDS)} ${n}`;
+}
+
+/**
+ * Generate a random set of formatting options from a seed.
+ */
+export function randomFormatOptions(seed: number): FormatOptions {
+  const random = createRandom(seed);
+  const flag = () => random() < 0.5;
+  const options: FormatOptions = {
+    indentSize: pick(random, [2, 4]),
+    useTabs: random() < 0.2,
+    indentTopLevel: random() < 0.8,
+    styleLevel: pick(random, ['conservative', 'standard', 'aggressive']),
+    maxWidth: pick(random, [40, 80, 120]),
+    groupParticipants: flag(),
+    participantOrder: pick(random, ['source', 'first-use', 'alphabetical']),
+    declareParticipants: flag(),
+    alphabetizeClassMembers: flag(),
+    groupStyleStatements: flag(),
+    groupGanttSettings: flag(),
+    reindentMindmaps: flag(),
+    dedupeNodeLabels: flag(),
+    wrapEdgeLabels: pick(random, ['off', 'br', 'quoted']),
+    blankLineBetweenSections: flag(),
+    expandChains: flag(),
+    hoistDirectives: flag(),
+  };
+  // Half the samples leave the options a styleLevel sets unset, so the
+  // level decides them instead of being overridden.
+  if (flag()) {
+    Object.assign(options, {
+      lossless: random() < 0.1,
+      alignMessages: flag(),
+      alignArrows: flag(),
+      alignEdgeLabels: flag(),
+      alignErAttributes: flag(),
+      alignGanttTasks: flag(),
+      alignQuadrantPoints: flag(),
+      sortClassMembers: flag(),
+      spaceArrows: flag(),
+    });
+  }
+  return options;
+}
@@ -0,0 +1,462 @@
+/**
+ * Typed views of a diagram per diagram family. A `TypedDiagram` narrows
+ * the statement list to what the family can contain and turns the generic
+ * lines it understands (flowchart edges, state transitions, ER
+ * relationships and attributes) into typed statements, so analysis and
+ * conversion code can switch on `family` instead of `DiagramType` and
+ * stop matching patterns against `content`.
+ */
+
+import type {
+  ActivateStatement,
+  AnnotationStatement,
+  ArrowMessageStatement,
+  AttributeMapStatement,
+  AutonumberStatement,
+  BlankLineStatement,
+  BlockAndStatement,
+  BlockElseStatement,
+  BlockEndStatement,
+  BlockOptionStatement,
+  BlockStartStatement,
+  BraceBlockEndStatement,
+  BraceBlockStartStatement,
+  ClassRelationStatement,
+  CommentStatement,
+  DeactivateStatement,
+  Diagram,
+  DiagramDeclStatement,
+  DiagramType,
+  DirectiveStatement,
+  FlowchartChain,
+  FrontmatterStatement,
+  GenericLineStatement,
+  LinkStatement,
+  NoteStatement,
+  ParticipantStatement,
+  Statement,
+  StatementType,
+  StateSeparatorStatement,
+  StateStereotypeStatement,
+  UnknownStatement,
+} from './types.js';
+import {
+  ER_RELATION_PATTERN,
+  matchErAttribute,
+  STATE_TRANSITION_PATTERN,
+} from './rules.js';
+import { parseFlowchartLine } from './flowchart.js';
+
+// ============================================================================
+// Family Statements
+// ============================================================================
+
+/** Statements any diagram may contain */
+export type CommonStatement =
+  | DiagramDeclStatement
+  | DirectiveStatement
+  | CommentStatement
+  | BlankLineStatement
+  | GenericLineStatement
+  | UnknownStatement
+  | FrontmatterStatement;
+
+export type SequenceStatement =
+  | CommonStatement
+  | ParticipantStatement
+  | AutonumberStatement
+  | LinkStatement
+  | ActivateStatement
+  | DeactivateStatement
+  | ArrowMessageStatement
+  | NoteStatement
+  | BlockStartStatement
+  | BlockOptionStatement
+  | BlockElseStatement
+  | BlockAndStatement
+  | BlockEndStatement;
+
+/** Flowchart line of nodes and links, e.g. "A --> B & C" */
+export interface FlowchartChainStatement {
+  type: 'flowchart-chain';
+  chain: FlowchartChain;
+  trailingComment?: string;
+  raw?: string;
+  content: string;
+}
+
+export type FlowchartStatement =
+  | CommonStatement
+  | FlowchartChainStatement
+  | AttributeMapStatement
+  | BlockStartStatement
+  | BlockEndStatement;
+
+export type ClassStatement =
+  | CommonStatement
+  | ClassRelationStatement
+  | AnnotationStatement
+  | NoteStatement
+  | BraceBlockStartStatement
+  | BraceBlockEndStatement;
+
+/** State transition, labeled ("s1 --> s2 : go") or not */
+export interface StateTransitionStatement {
+  type: 'state-transition';
+  /** Source state; "[*]" for the start */
+  from: string;
+  /** Target state; "[*]" for the end */
+  to: string;
+  label?: string;
+  trailingComment?: string;
+  raw?: string;
+  content: string;
+}
+
+export type StateStatement =
+  | CommonStatement
+  | StateTransitionStatement
+  | StateStereotypeStatement
+  | StateSeparatorStatement
+  | NoteStatement
+  | BraceBlockStartStatement
+  | BraceBlockEndStatement;
+
+/** ER relationship, e.g. "CUSTOMER ||--o{ ORDER : places" */
+export interface ErRelationStatement {
+  type: 'er-relation';
+  from: string;
+  to: string;
+  /** Cardinality operator, e.g. "||--o{" */
+  cardinality: string;
+  /** Relationship label without quotes */
+  label?: string;
+  trailingComment?: string;
+  raw?: string;
+  content: string;
+}
+
+/** ER attribute inside an entity block, e.g. "string name PK" */
+export interface ErAttributeStatement {
+  type: 'er-attribute';
+  /** Enclosing entity without its alias, e.g. "p" for p[Person] */
+  entity: string;
+  dataType: string;
+  name: string;
+  keys?: string[];
+  /** Comment without quotes */
+  comment?: string;
+  trailingComment?: string;
+  raw?: string;
+  content: string;
+}
+
+export type ErStatement =
+  | CommonStatement
+  | ErRelationStatement
+  | ErAttributeStatement
+  | BraceBlockStartStatement
+  | BraceBlockEndStatement;
+
+// ============================================================================
+// Typed Diagrams
+// ============================================================================
+
+export interface SequenceDiagram {
+  family: 'sequence';
+  type: 'sequenceDiagram';
+  statements: SequenceStatement[];
+}
+
+export interface FlowchartDiagram {
+  family: 'flowchart';
+  type: 'flowchart' | 'graph';
+  statements: FlowchartStatement[];
+}
+
+export interface ClassDiagram {
+  family: 'class';
+  type: 'classDiagram';
+  statements: ClassStatement[];
+}
+
+export interface StateDiagram {
+  family: 'state';
+  type: 'stateDiagram' | 'stateDiagram-v2';
+  statements: StateStatement[];
+}
+
+export interface ErDiagram {
+  family: 'er';
+  type: 'erDiagram';
+  statements: ErStatement[];
+}
+
+/** Every other diagram type, with the statements as parsed */
+export interface GenericDiagram {
+  family: 'generic';
+  type: DiagramType;
+  statements: Statement[];
+}
+
+/**
+ * A diagram with family-specific statements, discriminated by `family`.
+ * Statements stay one per source line, so `statements[i]` is still line
+ * `i + 1`, and every statement keeps its `content`.
+ */
+export type TypedDiagram =
+  | SequenceDiagram
+  | FlowchartDiagram
+  | ClassDiagram
+  | StateDiagram
+  | ErDiagram
+  | GenericDiagram;
+
+/** Family of each typed diagram type; the rest are generic */
+export type DiagramFamily = TypedDiagram['family'];
+
+/** Statement types every family keeps as parsed */
+const COMMON_TYPES: StatementType[] = [
+  'diagram-decl',
+  'directive',
+  'comment',
+  'blank-line',
+  'generic-line',
+  'unknown',
+  'frontmatter',
+];
+
+/** Parsed statement types each family keeps; others become generic lines */
+const FAMILY_TYPES: Record<
+  Exclude<DiagramFamily, 'generic'>,
+  ReadonlySet<StatementType>
+> = {
+  sequence: new Set([
+    ...COMMON_TYPES,
+    'participant',
+    'autonumber',
+    'link',
+    'activate',
+    'deactivate',
+    'arrow-message',
+    'note',
+    'block-start',
+    'block-option',
+    'block-else',
+    'block-and',
+    'block-end',
+  ]),
+  flowchart: new Set([
+    ...COMMON_TYPES,
+    'attribute-map',
+    'block-start',
+    'block-end',
+  ]),
+  class: new Set([
+    ...COMMON_TYPES,
+    'class-relation',
+    'annotation',
+    'note',
+    'brace-block-start',
+    'brace-block-end',
+  ]),
+  state: new Set([
+    ...COMMON_TYPES,
+    'state-stereotype',
+    'state-separator',
+    'note',
+    'brace-block-start',
+    'brace-block-end',
+  ]),
+  er: new Set([...COMMON_TYPES, 'brace-block-start', 'brace-block-end']),
+};
+
+/**
+ * View a diagram as its family's typed diagram. Statements the family does
+ * not know, e.g. a stray `end` in a class diagram, become generic lines.
+ *
+ * @example
+ * ```ts
+ * const typed = typedDiagram(parse('erDiagram\nA ||--o{ B : has'));
+ * if (typed.family === 'er') typed.statements[1];
+ * // { type: 'er-relation', from: 'A', to: 'B', cardinality: '||--o{', ... }
+ * ```
+ */
+export function typedDiagram(diagram: Diagram): TypedDiagram {
+  const { type } = diagram;
+  switch (type) {
+    case 'sequenceDiagram': {
+      const statements = familyStatements(diagram, 'sequence');
+      return {
+        family: 'sequence',
+        type,
+        statements: statements as SequenceStatement[],
+      };
+    }
+    case 'flowchart':
+    case 'graph':
+      return {
+        family: 'flowchart',
+        type,
+        statements: familyStatements(diagram, 'flowchart').map(flowchartChain),
+      };
+    case 'classDiagram':
+      return {
+        family: 'class',
+        type,
+        statements: familyStatements(diagram, 'class') as ClassStatement[],
+      };
+    case 'stateDiagram':
+    case 'stateDiagram-v2':
+      return {
+        family: 'state',
+        type,
+        statements: diagram.statements.map(stateStatement),
+      };
+    case 'erDiagram':
+      return { family: 'er', type, statements: erStatements(diagram) };
+    default:
+      return { family: 'generic', type, statements: diagram.statements };
+  }
+}
+
+/**
+ * Turn a typed diagram back into a plain one: family-specific statements
+ * become generic lines with the same content.
+ */
+export function untypedDiagram(typed: TypedDiagram): Diagram {
+  const statements = (typed.statements as FamilyStatement[]).map((stmt) =>
+    isParsedStatement(stmt) ? stmt : genericLine(stmt)
+  );
+  return { type: typed.type, statements };
+}
+
+/** A statement of any family */
+type FamilyStatement =
+  | Statement
+  | FlowchartChainStatement
+  | StateTransitionStatement
+  | ErRelationStatement
+  | ErAttributeStatement;
+
+const TYPED_ONLY = new Set<FamilyStatement['type']>([
+  'flowchart-chain',
+  'state-transition',
+  'er-relation',
+  'er-attribute',
+]);
+
+function isParsedStatement(stmt: FamilyStatement): stmt is Statement {
+  return !TYPED_ONLY.has(stmt.type);
+}
+
+function familyStatements(
+  diagram: Diagram,
+  family: Exclude<DiagramFamily, 'generic'>
+): Statement[] {
+  const types = FAMILY_TYPES[family];
+  return diagram.statements.map((stmt) =>
+    types.has(stmt.type) ? stmt : genericLine(stmt)
+  );
+}
+
+/** A generic line with the statement's content and trailing comment */
+function genericLine(stmt: FamilyStatement): GenericLineStatement {
+  const { content, trailingComment, raw } = stmt;
+  return {
+    type: 'generic-line',
+    content,
+    ...(trailingComment !== undefined && { trailingComment }),
+    ...(raw !== undefined && { raw }),
+  };
+}
+
+/** Fields a typed statement carries over from the parsed one */
+function carried(stmt: Statement): {
+  trailingComment?: string;
+  raw?: string;
+  content: string;
+} {
+  const { content, trailingComment, raw } = stmt;
+  return {
+    ...(trailingComment !== undefined && { trailingComment }),
+    ...(raw !== undefined && { raw }),
+    content,
+  };
+}
+
+function flowchartChain(stmt: Statement): FlowchartStatement {
+  if (stmt.type !== 'generic-line') return stmt as FlowchartStatement;
+  const chain = parseFlowchartLine(stmt.content);
+  return chain ? { type: 'flowchart-chain', chain, ...carried(stmt) } : stmt;
+}
+
+function stateStatement(stmt: Statement): StateStatement {
+  if (stmt.type === 'arrow-message' && stmt.arrow === '-->') {
+    const { from, to, message } = stmt;
+    return {
+      type: 'state-transition',
+      from,
+      to,
+      ...(message && { label: message }),
+      ...carried(stmt),
+    };
+  }
+  if (stmt.type === 'generic-line') {
+    const match = stmt.content.match(STATE_TRANSITION_PATTERN);
+    if (match) {
+      const [, from, to] = match;
+      return { type: 'state-transition', from, to, ...carried(stmt) };
+    }
+    return stmt;
+  }
+  return FAMILY_TYPES.state.has(stmt.type)
+    ? (stmt as StateStatement)
+    : genericLine(stmt);
+}
+
+function erStatements(diagram: Diagram): ErStatement[] {
+  const entities: string[] = [];
+  return familyStatements(diagram, 'er').map((stmt): ErStatement => {
+    if (stmt.type === 'brace-block-start') {
+      entities.push(stmt.name.replace(/\[.*\]$/, ''));
+      return stmt;
+    }
+    if (stmt.type === 'brace-block-end') {
+      entities.pop();
+      return stmt;
+    }
+    if (stmt.type !== 'generic-line') return stmt as ErStatement;
+
+    const entity = entities[entities.length - 1];
+    if (entity !== undefined) {
+      const attribute = matchErAttribute(stmt.content);
+      if (!attribute) return stmt;
+      const { type: dataType, name, keys, comment } = attribute;
+      return {
+        type: 'er-attribute',
+        entity,
+        dataType,
+        name,
+        ...(keys && { keys }),
+        ...(comment !== undefined && { comment: unquote(comment) }),
+        ...carried(stmt),
+      };
+    }
+    const match = stmt.content.match(ER_RELATION_PATTERN);
+    if (!match) return stmt;
+    const [, from, cardinality, to, label] = match;
+    return {
+      type: 'er-relation',
+      from,
+      to,
+      cardinality,
+      ...(label && { label: unquote(label.trim()) }),
+      ...carried(stmt),
+    };
+  });
+}
+
+function unquote(text: string): string {
+  return text.replace(/^"(.*)"$/, '$1');
+}
@@ -10,6 +10,161 @@ export interface FormatOptions {
   indentSize?: number;
   /** Use tabs instead of spaces (default: false) */
   useTabs?: boolean;
+  /** Indent statements under the diagram declaration (default: true) */
+  indentTopLevel?: boolean;
+  /**
+   * Defaults for groups of rules: 'conservative' turns on lossless,
+   * 'aggressive' turns on alignment, class member sorting, and arrow
+   * spacing. Options given explicitly take precedence (default: 'standard')
+   */
+  styleLevel?: StyleLevel;
+  /**
+   * Change only indentation, trailing whitespace, and blank lines; every
+   * statement keeps its source text and options that rewrite or reorder
+   * statements are ignored (default: false)
+   */
+  lossless?: boolean;
+  /** Align sequence message texts within runs of messages (default: false) */
+  alignMessages?: boolean;
+  /** Align flowchart arrows and targets across edge runs (default: false) */
+  alignArrows?: boolean;
+  /** With alignArrows, also give |labels| their own column (default: false) */
+  alignEdgeLabels?: boolean;
+  /** Align ER attribute type/name/key/comment columns (default: false) */
+  alignErAttributes?: boolean;
+  /** Align gantt task names and metadata within sections (default: false) */
+  alignGanttTasks?: boolean;
+  /** Align quadrant point labels, coordinates, and styles (default: false) */
+  alignQuadrantPoints?: boolean;
+  /** Maximum line width that alignment padding may produce (default: 80) */
+  maxWidth?: number;
+  /** Move sequence participant declarations to the top (default: false) */
+  groupParticipants?: boolean;
+  /** Order of grouped participant declarations (default: 'source') */
+  participantOrder?: ParticipantOrder;
+  /** Insert declarations for implicit sequence participants (default: false) */
+  declareParticipants?: boolean;
+  /** Order class body members: annotations, fields, methods (default: false) */
+  sortClassMembers?: boolean;
+  /** With sortClassMembers, also sort members by name (default: false) */
+  alphabetizeClassMembers?: boolean;
+  /** Move flowchart style/class/click statements to the end (default: false) */
+  groupStyleStatements?: boolean;
+  /** Move gantt settings above the first section (default: false) */
+  groupGanttSettings?: boolean;
+  /** Indent mindmaps by tree depth instead of as written (default: false) */
+  reindentMindmaps?: boolean;
+  /** Single spaces around flowchart link operators (default: false) */
+  spaceArrows?: boolean;
+  /** Drop repeated flowchart node labels after the first (default: false) */
+  dedupeNodeLabels?: boolean;
+  /** Break edge labels of lines wider than maxWidth (default: 'off') */
+  wrapEdgeLabels?: EdgeLabelWrap;
+  /** Insert a blank line between top-level sections (default: false) */
+  blankLineBetweenSections?: boolean;
+  /** Split chained flowchart edges into one edge per line (default: false) */
+  expandChains?: boolean;
+  /** Move top-level %%{...}%% directives up to the top (default: false) */
+  hoistDirectives?: boolean;
+  /** Custom rendering for selected statements, tried in order (default: []) */
+  statementHooks?: StatementHook[];
+  /** Reject input over these sizes while parsing (default: no limits) */
+  limits?: ParseLimits;
+  /** Called for each formatting rule that changes a line (default: null) */
+  trace?: ((event: TraceEvent) => void) | null;
+}
+
+/**
+ * A formatting rule that changed the output, reported to the `trace`
+ * option when debugging why the formatter rewrote a line.
+ */
+export interface TraceEvent {
+  /** Input line (1-based) the rule applied to, or null if there is none */
+  line: number | null;
+  /** Statement type the parser gave the line */
+  statementType: StatementType;
+  /** Rule name, e.g. "collapse-spaces" or "align-messages" */
+  rule: string;
+  /** Text before the rule, without indentation; null for an inserted line */
+  before: string | null;
+  /** Text after the rule, without indentation; null for a removed line */
+  after: string | null;
+}
+
+/**
+ * Size limits for parsing untrusted input, e.g. diagrams submitted to a
+ * formatting service. Exceeding one throws a `LimitExceededError`.
+ */
+export interface ParseLimits {
+  /** Maximum input length in characters */
+  maxInputLength?: number;
+  /** Maximum length of a single line in characters */
+  maxLineLength?: number;
+  /** Maximum number of statements (one per line) */
+  maxStatements?: number;
+  /** Maximum nesting depth of blocks and brace blocks */
+  maxDepth?: number;
+}
+
+/**
+ * Custom rendering for statements, e.g. house style rules. The first hook
+ * that matches a statement and returns a value decides its text; other
+ * hooks and column alignment are skipped for it.
+ */
+export interface StatementHook {
+  /** Statement types the hook applies to (default: all) */
+  types?: StatementType[];
+  /** Diagram types the hook applies to (default: all) */
+  diagramTypes?: DiagramType[];
+  /**
+   * Return the statement's text (without indentation), null to veto
+   * formatting and keep the source text, or undefined for the default.
+   */
+  format: (
+    stmt: Statement,
+    context: StatementHookContext
+  ) => string | null | undefined;
+}
+
+/** What a statement hook knows besides the statement */
+export interface StatementHookContext {
+  diagramType: DiagramType;
+  /** The default rendering, without indentation */
+  formatted: string;
+}
+
+/**
+ * How much the formatter changes: whitespace only, the default rules, or
+ * also alignment, sorting, and arrow spacing.
+ */
+export type StyleLevel = 'conservative' | 'standard' | 'aggressive';
+
+/**
+ * How long flowchart edge labels are wrapped: not at all, with `<br/>`
+ * breaks, or with `<br/>` breaks inside a quoted label.
+ */
+export type EdgeLabelWrap = 'off' | 'br' | 'quoted';
+
+/**
+ * Order of grouped participant declarations: as written, by first use in a
+ * message, note, or activation, or alphabetically by ID.
+ * Mermaid draws participants in declaration order, so sorting moves lanes.
+ */
+export type ParticipantOrder = 'source' | 'first-use' | 'alphabetical';
+
+/**
+ * Whether input is already formatted, and where it first differs if not.
+ */
+export interface CheckResult {
+  formatted: boolean;
+  /** First line (1-based) that formatting would change */
+  firstDifference?: {
+    line: number;
+    /** Input line, or null when the input ends before it */
+    actual: string | null;
+    /** Formatted line, or null when formatting removes it */
+    expected: string | null;
+  };
 }
 
 /**
@@ -48,12 +203,15 @@ export type BlockKind =
   | 'opt'
   | 'break'
   | 'rect'
-  | 'subgraph';
+  | 'box'
+  | 'subgraph'
+  | 'block';
 
 /**
  * Block types that close with '}'.
+ * 'entity' is an erDiagram attribute block (e.g., "CUSTOMER {").
  */
-export type BraceBlockKind = 'state' | 'class' | 'namespace';
+export type BraceBlockKind = 'state' | 'class' | 'namespace' | 'entity';
 
 // ============================================================================
 // AST Node Types - More semantic than raw strings
@@ -62,6 +220,16 @@ export type BraceBlockKind = 'state' | 'class' | 'namespace';
 /** Base interface for all statement nodes */
 interface StatementBase {
   type: string;
+  /**
+   * Comment after the statement on the same line, e.g. "%% main path";
+   * `content` and the other fields exclude it
+   */
+  trailingComment?: string;
+  /**
+   * Source text without indentation, when it differs from `content`
+   * (e.g. the spacing before a trailing comment); used by lossless mode
+   */
+  raw?: string;
 }
 
 /** Diagram type declaration (e.g., "sequenceDiagram", "flowchart TD") */
@@ -80,6 +248,45 @@ export interface DirectiveStatement extends StatementBase {
 /** Participant declaration (sequence diagram) */
 export interface ParticipantStatement extends StatementBase {
   type: 'participant';
+  kind?: 'participant' | 'actor';
+  id?: string;
+  /** Display name after `as`, quotes included */
+  alias?: string;
+  /** Alias is one quoted string, kept as written when formatting */
+  aliasQuoted?: boolean;
+  content: string;
+}
+
+/** Autonumber (sequence diagram), e.g. "autonumber" or "autonumber 10 10" */
+export interface AutonumberStatement extends StatementBase {
+  type: 'autonumber';
+  content: string;
+}
+
+/**
+ * Participant menu links (sequence diagram), e.g. "link A: Docs @ https://x"
+ * or 'links A: {"Docs": "https://x"}'
+ */
+export interface LinkStatement extends StatementBase {
+  type: 'link';
+  keyword: 'link' | 'links';
+  participant: string;
+  /** Text after the colon as written: "Label @ URL" or a JSON object */
+  payload: string;
+  content: string;
+}
+
+/** Activation (sequence diagram), e.g. "activate A" */
+export interface ActivateStatement extends StatementBase {
+  type: 'activate';
+  participant: string;
+  content: string;
+}
+
+/** Deactivation (sequence diagram), e.g. "deactivate A" */
+export interface DeactivateStatement extends StatementBase {
+  type: 'deactivate';
+  participant: string;
   content: string;
 }
 
@@ -87,6 +294,9 @@ export interface ParticipantStatement extends StatementBase {
 export interface BlockStartStatement extends StatementBase {
   type: 'block-start';
   blockKind: BlockKind;
+  /** Background color (box and rect blocks), e.g. "Purple" or "rgb(1,2,3)" */
+  color?: string;
+  /** Text after the keyword; "ID" or "ID:width" for block-beta blocks */
   label?: string;
   content: string;
 }
@@ -132,9 +342,86 @@ export interface BraceBlockEndStatement extends StatementBase {
   content: string;
 }
 
+/**
+ * Note placement relative to participants.
+ */
+export type NotePosition = 'left of' | 'right of' | 'over';
+
 /** Note statement */
 export interface NoteStatement extends StatementBase {
   type: 'note';
+  /** Placement (e.g., "right of"), absent for unstructured notes */
+  position?: NotePosition;
+  /** Participants the note is attached to (e.g., ["A", "B"]) */
+  participants?: string[];
+  /** Note text after the colon */
+  text?: string;
+  content: string;
+}
+
+/** State with a stereotype (state diagram), e.g. "state fork1 <<fork>>" */
+export interface StateStereotypeStatement extends StatementBase {
+  type: 'state-stereotype';
+  name: string;
+  /** e.g. "choice", "fork", or "join" */
+  stereotype: string;
+  content: string;
+}
+
+/** Separator between concurrent regions of a composite state: "--" */
+export interface StateSeparatorStatement extends StatementBase {
+  type: 'state-separator';
+  content: string;
+}
+
+/**
+ * Gantt setting, e.g. "dateFormat YYYY-MM-DD" or "todayMarker off". The
+ * value is kept as written.
+ */
+export interface GanttSettingStatement extends StatementBase {
+  type: 'gantt-setting';
+  /** e.g. "dateFormat", "tickInterval", or "inclusiveEndDates" */
+  keyword: string;
+  /** Text after the keyword, empty for flags like "topAxis" */
+  value: string;
+  content: string;
+}
+
+/**
+ * Timeline line continuing the events of the period above it, e.g.
+ * ": Google" after "2004 : Facebook"
+ */
+export interface TimelineEventStatement extends StatementBase {
+  type: 'timeline-event';
+  /** Period the events belong to, e.g. "2004" */
+  period: string;
+  events: string[];
+  content: string;
+}
+
+/**
+ * Mindmap node line, e.g. "root((mindmap))" or "Origins". Its parent is
+ * the nearest node above it that is indented less.
+ */
+export interface MindmapNodeStatement extends StatementBase {
+  type: 'mindmap-node';
+  /** Tree depth: 0 for the root */
+  depth: number;
+  /** Node text with its shape markers, as written */
+  content: string;
+}
+
+/**
+ * Mindmap icon or class line, e.g. "::icon(fa fa-book)" or ":::urgent",
+ * which decorates the node above it
+ */
+export interface MindmapDecorationStatement extends StatementBase {
+  type: 'mindmap-decoration';
+  kind: 'icon' | 'class';
+  /** e.g. "fa fa-book" for icons or "urgent large" for classes */
+  value: string;
+  /** Tree depth of the decorated node */
+  depth: number;
   content: string;
 }
 
@@ -154,12 +441,70 @@ export interface ArrowMessageStatement extends StatementBase {
   content: string;
 }
 
+/** Class annotation (class diagram), e.g. "<<interface>> Shape" */
+export interface AnnotationStatement extends StatementBase {
+  type: 'annotation';
+  /** Annotation name without angle brackets (e.g., "interface") */
+  name: string;
+  /** Annotated class for inline annotations */
+  target?: string;
+  content: string;
+}
+
+/** Class relationship (class diagram), e.g. 'A "1" --> "many" B : has' */
+export interface ClassRelationStatement extends StatementBase {
+  type: 'class-relation';
+  from: string;
+  /** Cardinality on the "from" side, without quotes */
+  fromCardinality?: string;
+  arrow: string;
+  /** Cardinality on the "to" side, without quotes */
+  toCardinality?: string;
+  to: string;
+  label?: string;
+  content: string;
+}
+
+/** Key/value pair inside an attribute map (e.g., "shape: rounded") */
+export interface Attribute {
+  key: string;
+  value: string;
+}
+
+/** Attribute map (flowchart), e.g. 'A@{ shape: rounded, label: "x" }' */
+export interface AttributeMapStatement extends StatementBase {
+  type: 'attribute-map';
+  id: string;
+  attributes: Attribute[];
+  content: string;
+}
+
 /** Generic line (arrows, relationships, nodes, etc.) */
 export interface GenericLineStatement extends StatementBase {
   type: 'generic-line';
   content: string;
 }
 
+/**
+ * Line of a sequence diagram or flowchart in syntax the parser does not
+ * model, e.g. from a newer Mermaid release. It is kept as written and
+ * reported by the `unknown-statement` lint rule.
+ */
+export interface UnknownStatement extends StatementBase {
+  type: 'unknown';
+  content: string;
+}
+
+/**
+ * Line of the YAML frontmatter between `---` lines at the start of a
+ * diagram, the delimiters included. `content` keeps the line's
+ * indentation, which is YAML structure, and it is kept as written.
+ */
+export interface FrontmatterStatement extends StatementBase {
+  type: 'frontmatter';
+  content: string;
+}
+
 /** Blank line */
 export interface BlankLineStatement extends StatementBase {
   type: 'blank-line';
@@ -173,6 +518,10 @@ export type Statement =
   | DiagramDeclStatement
   | DirectiveStatement
   | ParticipantStatement
+  | AutonumberStatement
+  | LinkStatement
+  | ActivateStatement
+  | DeactivateStatement
   | ArrowMessageStatement
   | BlockStartStatement
   | BraceBlockStartStatement
@@ -183,7 +532,18 @@ export type Statement =
   | BraceBlockEndStatement
   | NoteStatement
   | CommentStatement
+  | AttributeMapStatement
+  | AnnotationStatement
+  | ClassRelationStatement
+  | StateStereotypeStatement
+  | StateSeparatorStatement
+  | GanttSettingStatement
+  | TimelineEventStatement
+  | MindmapNodeStatement
+  | MindmapDecorationStatement
   | GenericLineStatement
+  | UnknownStatement
+  | FrontmatterStatement
   | BlankLineStatement;
 
 /**
@@ -199,5 +559,48 @@ export interface Diagram {
   statements: Statement[];
 }
 
+// ============================================================================
+// Flowchart Structure - Node/edge view of flowchart statement lines
+// ============================================================================
+
+/** Node reference in a flowchart statement (e.g., "A[Start]:::done") */
+export interface FlowchartNode {
+  id: string;
+  /** Shape delimiters and inner text (e.g., "[" + "Start" + "]") */
+  shape?: { open: string; text: string; close: string };
+  /** Raw attribute map body for "A@{ ... }" nodes */
+  attributes?: string;
+  /** Class from ":::name" suffix */
+  className?: string;
+  /**
+   * Icon from a leading FontAwesome label token ("fa:fa-car" in
+   * "B[fa:fa-car Car]") or an `icon` attribute; not written back
+   */
+  icon?: string;
+  /** Image URL from an `img` attribute; not written back */
+  image?: string;
+}
+
+/** Link between node groups (e.g., "-->", "-.->|label|", "-- text -->") */
+export interface FlowchartLink {
+  /** Edge ID from "e1@-->" syntax */
+  edgeId?: string;
+  /** Link operator; the closing operator for inline-text links */
+  arrow: string;
+  /** Opening token of an inline-text link (e.g., "--" in "-- text -->") */
+  inlineOpen?: string;
+  /** Edge label from |label| or inline text */
+  label?: string;
+}
+
+/**
+ * Flowchart statement as node groups joined by links.
+ * "A & B --> C" has groups [[A, B], [C]] and one link.
+ */
+export interface FlowchartChain {
+  groups: FlowchartNode[][];
+  links: FlowchartLink[];
+}
+
 // Re-export from rules for backwards compatibility
 export { INDENT_SENSITIVE_DIAGRAMS } from './rules.js';
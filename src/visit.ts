@@ -0,0 +1,124 @@
+/**
+ * Traversal helpers over the diagram AST.
+ * Statements are a flat list with block start/end markers; the walker
+ * tracks nesting so visitors see each statement with its enclosing blocks.
+ */
+
+import type {
+  BlockStartStatement,
+  BraceBlockStartStatement,
+  Diagram,
+  Statement,
+  StatementType,
+} from './types.js';
+
+/** A statement that opens a nested block */
+export type BlockOpener = BlockStartStatement | BraceBlockStartStatement;
+
+/**
+ * Position of a statement during a walk.
+ */
+export interface VisitContext {
+  diagram: Diagram;
+  /** Index of the statement in `diagram.statements` */
+  index: number;
+  /** Enclosing blocks, outermost first */
+  parents: BlockOpener[];
+}
+
+/** Statement of a given type */
+type StatementOf<K extends StatementType> = Extract<Statement, { type: K }>;
+
+/**
+ * Read-only visitor. Type-named handlers receive statements of that type;
+ * `statement` receives every statement first.
+ */
+export type Visitor = {
+  [K in StatementType]?: (stmt: StatementOf<K>, ctx: VisitContext) => void;
+} & {
+  statement?: (stmt: Statement, ctx: VisitContext) => void;
+  /** Called after a block opener, before its body */
+  enterBlock?: (stmt: BlockOpener, ctx: VisitContext) => void;
+  /** Called at the statement closing a block ("end" or "}") */
+  leaveBlock?: (stmt: BlockOpener, ctx: VisitContext) => void;
+};
+
+/**
+ * Rewriting visitor. A handler returns a replacement statement, several
+ * statements, null to remove the statement, or undefined to keep it.
+ */
+export type Transformer = {
+  [K in StatementType]?: (
+    stmt: StatementOf<K>,
+    ctx: VisitContext
+  ) => TransformResult;
+};
+
+type TransformResult = Statement | Statement[] | null | undefined;
+
+/**
+ * Visit every statement in order, tracking enclosing blocks.
+ *
+ * @example
+ * ```ts
+ * walkDiagram(parse(input), {
+ *   'arrow-message': (msg, { parents }) =>
+ *     console.log(msg.from, msg.to, parents.length),
+ * });
+ * ```
+ */
+export function walkDiagram(diagram: Diagram, visitor: Visitor): void {
+  const parents: BlockOpener[] = [];
+
+  diagram.statements.forEach((stmt, index) => {
+    // Closers are visited at the level of the block they close
+    const opener = isBlockEnd(stmt) ? parents.pop() : undefined;
+    const ctx = { diagram, index, parents: [...parents] };
+    visitor.statement?.(stmt, ctx);
+    handler<void>(visitor, stmt)?.(stmt, ctx);
+    if (opener) visitor.leaveBlock?.(opener, ctx);
+
+    if (stmt.type === 'block-start' || stmt.type === 'brace-block-start') {
+      parents.push(stmt);
+      visitor.enterBlock?.(stmt, ctx);
+    }
+  });
+}
+
+/**
+ * Build a new diagram by passing each statement through the transformer.
+ * Context reflects the original diagram; the input is not modified.
+ */
+export function transformDiagram(
+  diagram: Diagram,
+  transformer: Transformer
+): Diagram {
+  const statements: Statement[] = [];
+  walkDiagram(diagram, {
+    statement: (stmt, ctx) => {
+      const apply = handler<TransformResult>(transformer, stmt);
+      const result = apply?.(stmt, ctx);
+      if (result === undefined) statements.push(stmt);
+      else if (Array.isArray(result)) statements.push(...result);
+      else if (result !== null) statements.push(result);
+    },
+  });
+  return { ...diagram, statements };
+}
+
+function isBlockEnd(stmt: Statement): boolean {
+  return stmt.type === 'block-end' || stmt.type === 'brace-block-end';
+}
+
+/**
+ * Look up the handler for a statement's type. The mapped handler types
+ * cannot be narrowed by a runtime key, so the lookup is typed loosely.
+ */
+function handler<R>(
+  handlers: Partial<Record<StatementType, unknown>>,
+  stmt: Statement
+): ((stmt: Statement, ctx: VisitContext) => R) | undefined {
+  return handlers[stmt.type] as
+    | ((stmt: Statement, ctx: VisitContext) => R)
+    | undefined;
+}
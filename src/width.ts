@@ -0,0 +1,66 @@
+/**
+ * Display width of text in a monospace terminal or editor.
+ * East Asian wide and fullwidth characters take two columns; combining
+ * marks and other zero-width characters take none.
+ */
+
+/** Code point ranges rendered two columns wide */
+const WIDE_RANGES: [number, number][] = [
+  [0x1100, 0x115f], // Hangul Jamo
+  [0x2e80, 0x303e], // CJK radicals, punctuation
+  [0x3041, 0x33ff], // Kana, CJK symbols
+  [0x3400, 0x4dbf], // CJK Extension A
+  [0x4e00, 0x9fff], // CJK Unified Ideographs
+  [0xa000, 0xa4cf], // Yi
+  [0xac00, 0xd7a3], // Hangul Syllables
+  [0xf900, 0xfaff], // CJK Compatibility Ideographs
+  [0xfe10, 0xfe19], // Vertical forms
+  [0xfe30, 0xfe6f], // CJK Compatibility Forms, small form variants
+  [0xff00, 0xff60], // Fullwidth Forms
+  [0xffe0, 0xffe6], // Fullwidth signs
+  [0x1f200, 0x1f2ff], // Enclosed ideographic supplement
+  [0x1f300, 0x1f64f], // Pictographs, emoticons
+  [0x1f680, 0x1f6ff], // Transport and map symbols
+  [0x1f900, 0x1f9ff], // Supplemental symbols and pictographs
+  [0x1fa70, 0x1faff], // Symbols and pictographs extended-A
+  [0x20000, 0x2fffd], // CJK Extensions B-F
+  [0x30000, 0x3fffd], // CJK Extension G
+];
+
+const ZERO_WIDTH_PATTERN = /[\p{Mn}\p{Me}\p{Cf}]/u;
+
+/** Zero-width joiner: the following character joins the previous glyph */
+const ZWJ = '\u200d';
+
+/**
+ * Measure the display width of a string in columns.
+ */
+export function displayWidth(text: string): number {
+  let width = 0;
+  let joined = false;
+  for (const char of text) {
+    if (joined) {
+      joined = false;
+      continue;
+    }
+    if (char === ZWJ) {
+      joined = true;
+      continue;
+    }
+    if (ZERO_WIDTH_PATTERN.test(char)) continue;
+    const code = char.codePointAt(0) ?? 0;
+    width += isWide(code) ? 2 : 1;
+  }
+  return width;
+}
+
+/**
+ * Pad a string with spaces to the given display width.
+ */
+export function padEndToWidth(text: string, width: number): string {
+  return text + ' '.repeat(Math.max(0, width - displayWidth(text)));
+}
+
+function isWide(code: number): boolean {
+  return WIDE_RANGES.some(([start, end]) => code >= start && code <= end);
+}
@@ -0,0 +1,94 @@
+/**
+ * Tests for diagram anonymization.
+ */
+
+import { describe, it, expect } from 'vitest';
+import type { AnonymizeOptions } from '../src/index.js';
+import { anonymizeDiagram, format, parse } from '../src/index.js';
+
+function anonymize(input: string, options?: AnonymizeOptions): string {
+  return format(anonymizeDiagram(parse(input), options));
+}
+
+describe('anonymizeDiagram', () => {
+  it('replaces flowchart IDs, labels, and class names', () => {
+    const input = `flowchart TD
+%% internal service map
+Db[(Orders)] -->|reads| Api{Ok?}:::hot
+Api --> Db
+subgraph pay [Payments]
+  Card@{ shape: rect, label: "Card" }
+end
+style Db fill:#f9f
+classDef hot fill:#f00
+class Db,Api hot
+click Db "https://internal.example.com"`;
+    expect(anonymize(input)).toBe(`flowchart TD
+    %% redacted
+    n1[(text1)] -->|text3| n2{text2}:::class1
+    n2 --> n1
+
+    subgraph n3 [text4]
+        n4@{ shape: rect }
+    end
+    style n1 fill:#f9f
+    classDef class1 fill:#f00
+    class n1,n2 class1
+    %% redacted
+`);
+  });
+
+  it('replaces participants, messages, notes, and block labels', () => {
+    const input = `sequenceDiagram
+participant A as Alice
+actor B
+A->>+B: Hello
+loop Every minute
+  B-->>A: Hello
+end
+Note over A,B: secret`;
+    expect(anonymize(input)).toBe(`sequenceDiagram
+    participant p1 as text1
+    actor p2
+    p1 ->>+ p2: text2
+
+    loop text3
+        p2 -->> p1: text2
+    end
+    Note over p1,p2: text4
+`);
+  });
+
+  it('keeps class members, state, and ER structure', () => {
+    expect(
+      anonymize(`classDiagram
+class Animal {
+  <<interface>>
+  +String name
+  +eat(food) bool
+}
+Animal <|-- Dog : is
+note for Dog "good boy"`)
+    ).toContain('class C1 {\n    <<interface>>\n    +member1\n    +member2()');
+    expect(
+      anonymize('stateDiagram-v2\n[*] --> Idle\nIdle --> Busy : start')
+    ).toBe('stateDiagram-v2\n    [*] --> s1\n    s1 --> s2: text1\n');
+    expect(
+      anonymize('erDiagram\nCUSTOMER ||--o{ ORDER : places\nCUSTOMER {\n  string name PK "full name"\n}')
+    ).toContain('E1 ||--o{ E2 : text1\n\nE1 {\n    string attribute1 PK "text2"');
+  });
+
+  it('derives hash placeholders from the original names', () => {
+    const first = anonymize('graph LR\nApi --> Db', { strategy: 'hash' });
+    const second = anonymize('graph LR\nDb --> Cache', { strategy: 'hash' });
+    const db = first.match(/--> (n_[0-9a-f]{8})/)?.[1];
+    expect(db).toBeDefined();
+    expect(second).toContain(`${db} --> n_`);
+  });
+
+  it('rejects unsupported diagram types', () => {
+    expect(() => anonymizeDiagram(parse('pie\n"A" : 1'))).toThrow(
+      'Cannot anonymize pie diagrams'
+    );
+  });
+});
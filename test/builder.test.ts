@@ -0,0 +1,101 @@
+/**
+ * Tests for the diagram builders.
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  FlowchartBuilder,
+  SequenceDiagramBuilder,
+  formatMermaid,
+} from '../src/index.js';
+
+describe('SequenceDiagramBuilder', () => {
+  it('builds participants, messages, notes, and blocks', () => {
+    const output = new SequenceDiagramBuilder()
+      .autonumber()
+      .participant('A', 'Alice')
+      .actor('B')
+      .message('A', 'B', 'Hello')
+      .note('over', ['A', 'B'], 'Handshake')
+      .alt('ok', (b) =>
+        b
+          .message('B', 'A', 'Hi', '-->>')
+          .else('busy')
+          .message('B', 'A', 'Later')
+      )
+      .par('fan out', (b) => b.activate('A').and().deactivate('A'))
+      .toString();
+
+    expect(output).toBe(`sequenceDiagram
+    autonumber
+    participant A as Alice
+    actor B
+    A ->> B: Hello
+    Note over A,B: Handshake
+
+    alt ok
+        B -->> A: Hi
+    else busy
+        B ->> A: Later
+    end
+
+    par fan out
+        activate A
+    and
+        deactivate A
+    end
+`);
+  });
+
+  it('produces output that is already formatted', () => {
+    const output = new SequenceDiagramBuilder()
+      .participant('A')
+      .loop('retry', (b) => b.message('A', 'B', 'ping'))
+      .toString();
+    expect(formatMermaid(output)).toBe(output);
+  });
+});
+
+describe('FlowchartBuilder', () => {
+  it('builds nodes, edges, subgraphs, and styles', () => {
+    const output = new FlowchartBuilder('LR')
+      .node('A', 'Start', 'stadium')
+      .node('B', 'Is it (really) ok?', 'diamond')
+      .edge('A', 'B')
+      .subgraph('S', 'Workers', (b) =>
+        b.edge('B', 'C', { label: 'yes', arrow: '-.->' })
+      )
+      .classDef('hot', 'fill:#f96')
+      .class(['A', 'C'], 'hot')
+      .style('B', 'stroke:#333')
+      .toString();
+
+    expect(output).toBe(`flowchart LR
+    A([Start])
+    B{"Is it (really) ok?"}
+    A --> B
+
+    subgraph S [Workers]
+        B -.->|yes| C
+    end
+    classDef hot fill:#f96
+    class A,C hot
+    style B stroke:#333
+`);
+  });
+
+  it('escapes quotes in labels that need quoting', () => {
+    const output = new FlowchartBuilder()
+      .edge('A', 'B', { label: 'say "hi" (twice)' })
+      .toString();
+    expect(output).toBe(
+      'flowchart TD\n    A -->|"say #quot;hi#quot; (twice)"| B\n'
+    );
+  });
+
+  it('returns a diagram AST', () => {
+    const diagram = new FlowchartBuilder().node('A').build();
+    expect(diagram.type).toBe('flowchart');
+    expect(diagram.statements).toHaveLength(2);
+  });
+});
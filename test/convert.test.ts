@@ -0,0 +1,81 @@
+/**
+ * Diagram conversion tests
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  convertDiagram,
+  convertDiagramWithWarnings,
+  isConversionTarget,
+} from '../src/index.js';
+
+describe('convertDiagram', () => {
+  it('rewrites graph headers to flowchart, keeping other lines', () => {
+    const input = '%% legacy\ngraph LR\n  A-->B\n\n  B --- C\n';
+    expect(convertDiagram(input, 'flowchart')).toBe(
+      '%% legacy\nflowchart LR\n  A-->B\n\n  B --- C\n'
+    );
+  });
+
+  it('keeps graph node names that start with o or x after a link', () => {
+    const input = 'graph TD\n  dev---ops\n  A===xB\n  A---o B\n  A---x|no| B';
+    expect(convertDiagram(input, 'flowchart')).toBe(
+      'flowchart TD\n  dev--- ops\n  A=== xB\n  A---o B\n  A---x|no| B'
+    );
+  });
+
+  it('keeps links inside quoted labels as written', () => {
+    const input = 'graph TD\n  A["a ---ok"] ---oB';
+    expect(convertDiagram(input, 'flowchart')).toBe(
+      'flowchart TD\n  A["a ---ok"] --- oB'
+    );
+  });
+
+  it('upgrades stateDiagram to stateDiagram-v2', () => {
+    const input = 'stateDiagram\n    [*] --> s1';
+    expect(convertDiagram(input, 'stateDiagram-v2')).toBe(
+      'stateDiagram-v2\n    [*] --> s1'
+    );
+  });
+
+  it('flags state statements it cannot verify', () => {
+    const input = `stateDiagram
+    [*] --> Still
+    Still : waiting
+    state Moving {
+        Fast --> Slow
+        --
+        Idle
+    }
+    note right of Still
+        free text here
+    end note
+    Still -> Moving
+    scale 350 width`;
+
+    const { output, warnings } = convertDiagramWithWarnings(
+      input,
+      'stateDiagram-v2'
+    );
+    expect(output.split('\n')[0]).toBe('stateDiagram-v2');
+    expect(warnings.map((w) => w.line)).toEqual([12, 13]);
+    expect(warnings[0].message).toContain('check it in stateDiagram-v2');
+  });
+
+  it('returns input of the target type unchanged', () => {
+    const input = 'flowchart TD\n  A-->B';
+    expect(convertDiagram(input, 'flowchart')).toBe(input);
+  });
+
+  it('rejects unsupported source types', () => {
+    const input = 'sequenceDiagram\nA->>B: hi';
+    expect(() => convertDiagram(input, 'flowchart')).toThrow(
+      'Cannot convert sequenceDiagram to flowchart'
+    );
+  });
+
+  it('recognizes conversion targets', () => {
+    expect(isConversionTarget('flowchart')).toBe(true);
+    expect(isConversionTarget('graph')).toBe(false);
+  });
+});
@@ -0,0 +1,63 @@
+/**
+ * Daemon protocol tests
+ */
+
+import { describe, it, expect } from 'vitest';
+import { handleDaemonRequest } from '../src/daemon.js';
+
+describe('handleDaemonRequest', () => {
+  it('formats text with the request config', () => {
+    const response = handleDaemonRequest(
+      JSON.stringify({
+        id: 7,
+        method: 'format',
+        text: 'sequenceDiagram\nA->>B:hi',
+        config: { indentSize: 2 },
+      })
+    );
+    expect(response).toEqual({
+      id: 7,
+      result: 'sequenceDiagram\n  A ->> B: hi\n',
+    });
+  });
+
+  it('formats Markdown fences when requested', () => {
+    const text = '```mermaid\nflowchart TD\nA-->B\n```\n';
+    const response = handleDaemonRequest(
+      JSON.stringify({ method: 'format', text, markdown: true })
+    );
+    expect(response.result).toBe('```mermaid\nflowchart TD\n    A-->B\n```\n');
+  });
+
+  it('checks whether text is formatted', () => {
+    const response = handleDaemonRequest(
+      JSON.stringify({ method: 'check', text: 'flowchart TD\n  A-->B\n' })
+    );
+    expect(response.result).toMatchObject({
+      formatted: false,
+      firstDifference: { line: 2 },
+    });
+  });
+
+  it('lints text with rule overrides', () => {
+    const response = handleDaemonRequest(
+      JSON.stringify({
+        method: 'lint',
+        text: 'loop x\nend',
+        rules: { 'missing-diagram-type': 'allow' },
+      })
+    );
+    expect(response.result).toMatchObject([{ ruleId: 'empty-block' }]);
+  });
+
+  it('returns structured errors', () => {
+    expect(handleDaemonRequest('{nope').error?.code).toBe('invalid-json');
+    expect(handleDaemonRequest('42').error?.code).toBe('invalid-request');
+    expect(
+      handleDaemonRequest(JSON.stringify({ id: 1, method: 'render' }))
+    ).toEqual({
+      id: 1,
+      error: { code: 'unknown-method', message: 'Unknown method: render' },
+    });
+  });
+});
@@ -0,0 +1,52 @@
+/**
+ * Tests for minimal text edits.
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  applyEdits,
+  diffEdits,
+  formatEdits,
+  formatMermaid,
+} from '../src/index.js';
+
+describe('diffEdits', () => {
+  it('returns no edits for equal text', () => {
+    expect(diffEdits('a\nb\n', 'a\nb\n')).toEqual([]);
+  });
+
+  it('trims edits to the changed characters', () => {
+    expect(diffEdits('a\n  b\nc\n', 'a\n    b\nc\n')).toEqual([
+      { start: 4, end: 4, newText: '  ' },
+    ]);
+  });
+
+  it('leaves unchanged lines between changes alone', () => {
+    const before = 'x1\nsame\nx2\nsame\n';
+    const after = 'y1\nsame\nsame\nz\n';
+    const edits = diffEdits(before, after);
+    expect(edits).toEqual([
+      { start: 0, end: 1, newText: 'y' },
+      { start: 8, end: 11, newText: '' },
+      { start: 16, end: 16, newText: 'z\n' },
+    ]);
+    expect(applyEdits(before, edits)).toBe(after);
+  });
+
+  it('handles a missing final newline', () => {
+    const edits = diffEdits('a\nb', 'a\nb\n');
+    expect(edits).toEqual([{ start: 3, end: 3, newText: '\n' }]);
+  });
+});
+
+describe('formatEdits', () => {
+  it('reproduces the formatted output', () => {
+    const input = 'sequenceDiagram\nA->>B:hi\n\n\nloop x\nB->>A: ok\nend';
+    const edits = formatEdits(input);
+    expect(applyEdits(input, edits)).toBe(formatMermaid(input));
+  });
+
+  it('returns no edits for formatted input', () => {
+    expect(formatEdits('flowchart TD\n    A --> B\n')).toEqual([]);
+  });
+});
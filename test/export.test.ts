@@ -0,0 +1,214 @@
+/**
+ * Tests for exports to other diagram languages.
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  diagramToD2,
+  diagramToDot,
+  diagramToPlantUML,
+  exportDiagram,
+  isExportFormat,
+  parse,
+} from '../src/index.js';
+
+describe('diagramToDot', () => {
+  it('maps nodes, edges, labels, and subgraphs', () => {
+    const input = `flowchart LR
+A([Start]) -->|go <br/> now| B{"Ok?"}
+subgraph work [Work area]
+  B -.-> C
+  subgraph inner
+    D[(Db)]
+  end
+end
+C === D
+C ~~~ A`;
+    expect(diagramToDot(parse(input))).toBe(`digraph {
+  rankdir=LR;
+  node [shape=box];
+  "A" [label="Start", shape=box, style=rounded];
+  "B" [label="Ok?", shape=diamond];
+  subgraph "cluster_work" {
+    label="Work area";
+    "C";
+    subgraph "cluster_inner" {
+      label="inner";
+      "D" [label="Db", shape=cylinder];
+    }
+  }
+  "A" -> "B" [label="go \\n now"];
+  "B" -> "C" [style=dashed];
+  "C" -> "D" [penwidth=2, arrowhead=none];
+  "C" -> "A" [style=invis];
+}
+`);
+  });
+
+  it('escapes quotes and defaults to top-to-bottom', () => {
+    const dot = diagramToDot(parse('graph\nA["say #quot;hi#quot;"] --o B'));
+    expect(dot).toContain('rankdir=TB;');
+    expect(dot).toContain('"A" [label="say \\"hi\\""];');
+    expect(dot).toContain('"A" -> "B" [arrowhead=odot];');
+  });
+
+  it('rejects other diagram types', () => {
+    expect(() => diagramToDot(parse('sequenceDiagram\nA->>B: hi'))).toThrow(
+      'Cannot export sequenceDiagram to DOT'
+    );
+  });
+});
+
+describe('diagramToD2', () => {
+  it('maps flowchart nodes, edges, and containers', () => {
+    const input = `flowchart LR
+A([Start]) -->|go <br/> now| B{"Ok?"}
+subgraph work [Work area]
+  B -.-> C
+  subgraph inner
+    D[(Db)]
+  end
+end
+C === D
+C ~~~ A
+A <--> B
+A --o D`;
+    expect(diagramToD2(parse(input))).toBe(`direction: right
+A: Start {shape: oval}
+B: Ok? {shape: diamond}
+work: Work area {
+  C
+  inner: {
+    D: Db {shape: cylinder}
+  }
+}
+A -> B: "go \\n now"
+B -> work.C: {style.stroke-dash: 3}
+work.C -- work.inner.D: {style.stroke-width: 4}
+work.C -- A: {style.opacity: 0}
+A <-> B
+A -> work.inner.D: {target-arrowhead.shape: circle}
+`);
+  });
+
+  it('maps sequence actors, messages, notes, and groups', () => {
+    const input = `sequenceDiagram
+participant A as Alice
+actor B
+A->>+B: Hello; there
+loop Every minute
+  B--)A: ping
+  alt ok
+    A-xB: done
+  else failed
+    Note over A,B: retry
+  end
+end
+autonumber`;
+    expect(diagramToD2(parse(input))).toBe(`shape: sequence_diagram
+A: Alice
+B: {shape: person}
+A -> B: "Hello; there"
+loop1: loop Every minute {
+  B -> A: ping {style.stroke-dash: 3}
+  alt1: alt ok {
+    A -> B: done
+  }
+  else1: else failed {
+    A.note1: retry
+  }
+}
+# autonumber
+`);
+  });
+
+  it('rejects other diagram types', () => {
+    expect(() => diagramToD2(parse('classDiagram\nA <|-- B'))).toThrow(
+      'Cannot export classDiagram to D2'
+    );
+  });
+});
+
+describe('exportDiagram', () => {
+  it('dispatches by format name', () => {
+    expect(isExportFormat('dot')).toBe(true);
+    expect(isExportFormat('svg')).toBe(false);
+    const diagram = parse('graph TD\nA --> B');
+    expect(exportDiagram(diagram, 'dot')).toBe(diagramToDot(diagram));
+  });
+});
+
+describe('diagramToPlantUML', () => {
+  it('maps participants, messages, notes, and groups', () => {
+    const input = `sequenceDiagram
+autonumber
+participant A as Alice
+actor B
+%% handshake
+A->>+B: Hello<br/>there
+loop Every minute
+  B--)A: ping
+  alt ok
+    A-xB: done
+  else failed
+    Note over A,B: retry
+  end
+end
+rect rgb(200, 220, 255)
+  B-->>-A: bye
+end
+box Aqua Group
+  participant C
+end`;
+    expect(diagramToPlantUML(parse(input))).toBe(`@startuml
+autonumber
+participant "Alice" as A
+actor B
+' handshake
+A -> B ++ : Hello\\nthere
+loop Every minute
+  B -->> A : ping
+  alt ok
+    A ->x B : done
+  else failed
+    note over A, B : retry
+  end
+end
+B --> A -- : bye
+box "Group" #Aqua
+  participant C
+end box
+@enduml
+`);
+  });
+
+  it('maps classes, members, relationships, and annotations', () => {
+    const input = `classDiagram
+direction LR
+class Shape {
+  <<interface>>
+  +draw() List~Point~
+}
+<<abstract>> Base
+Base <|-- Shape
+Customer "1" --> "*" Order : places
+Order : +int id`;
+    expect(diagramToPlantUML(parse(input))).toBe(`@startuml
+left to right direction
+class Shape <<interface>> {
+  +draw() List<Point>
+}
+class Base <<abstract>>
+Base <|-- Shape
+Customer "1" --> "*" Order : places
+Order : +int id
+@enduml
+`);
+  });
+
+  it('rejects other diagram types', () => {
+    expect(() => diagramToPlantUML(parse('graph TD\nA --> B'))).toThrow(
+      'Cannot export graph to PlantUML'
+    );
+  });
+});
@@ -0,0 +1,83 @@
+/**
+ * Tests for batch file formatting.
+ */
+
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { mkdtempSync, readFileSync, rmSync, writeFileSync } from 'node:fs';
+import { tmpdir } from 'node:os';
+import { join } from 'node:path';
+import { formatFiles } from '../src/files.js';
+
+describe('formatFiles', () => {
+  let dir: string;
+
+  beforeEach(() => {
+    dir = mkdtempSync(join(tmpdir(), 'mermaidfmt-'));
+  });
+
+  afterEach(() => {
+    rmSync(dir, { recursive: true, force: true });
+  });
+
+  it('reports each file without writing by default', () => {
+    const messy = join(dir, 'messy.mmd');
+    const clean = join(dir, 'clean.mmd');
+    writeFileSync(messy, 'graph TD\nA-->B');
+    writeFileSync(clean, 'graph TD\n    A-->B\n');
+
+    expect(formatFiles([messy, clean])).toEqual([
+      {
+        path: messy,
+        changed: true,
+        written: false,
+        output: 'graph TD\n    A-->B\n',
+      },
+      {
+        path: clean,
+        changed: false,
+        written: false,
+        output: 'graph TD\n    A-->B\n',
+      },
+    ]);
+    expect(readFileSync(messy, 'utf-8')).toBe('graph TD\nA-->B');
+  });
+
+  it('writes changed files and formats Markdown fences', () => {
+    const doc = join(dir, 'doc.md');
+    writeFileSync(doc, '# Doc\n\n```mermaid\ngraph TD\nA-->B\n```\n');
+
+    const [result] = formatFiles([doc], {}, { write: true });
+    expect(result).toMatchObject({ changed: true, written: true });
+    expect(readFileSync(doc, 'utf-8')).toBe(
+      '# Doc\n\n```mermaid\ngraph TD\n    A-->B\n```\n'
+    );
+  });
+
+  it('resolves options per path over the shared ones', () => {
+    const a = join(dir, 'a.mmd');
+    const b = join(dir, 'b.mmd');
+    writeFileSync(a, 'graph TD\nA-->B\n');
+    writeFileSync(b, 'graph TD\nA-->B\n');
+
+    const results = formatFiles(
+      [a, b],
+      { indentSize: 2 },
+      { resolveOptions: (path) => (path === b ? { useTabs: true } : {}) }
+    );
+    expect(results.map((r) => r.output)).toEqual([
+      'graph TD\n  A-->B\n',
+      'graph TD\n\tA-->B\n',
+    ]);
+  });
+
+  it('reports unreadable files and continues', () => {
+    const missing = join(dir, 'missing.mmd');
+    const ok = join(dir, 'ok.mmd');
+    writeFileSync(ok, 'graph TD\n');
+
+    const [failed, formatted] = formatFiles([missing, ok]);
+    expect(failed.changed).toBe(false);
+    expect(failed.error).toContain('ENOENT');
+    expect(formatted).toMatchObject({ path: ok, changed: false });
+  });
+});
@@ -0,0 +1,76 @@
+/**
+ * Flowchart structure tests
+ */
+
+import { describe, it, expect } from 'vitest';
+import { parse, parseFlowchartLine } from '../src/index.js';
+import { statementChain } from '../src/flowchart.js';
+
+describe('parseFlowchartLine', () => {
+  it('parses nodes with shapes and links with labels', () => {
+    const chain = parseFlowchartLine('A[Start] -->|yes| B((Done)):::ok');
+
+    expect(chain).toEqual({
+      groups: [
+        [{ id: 'A', shape: { open: '[', text: 'Start', close: ']' } }],
+        [
+          {
+            id: 'B',
+            shape: { open: '((', text: 'Done', close: '))' },
+            className: 'ok',
+          },
+        ],
+      ],
+      links: [{ arrow: '-->', label: 'yes' }],
+    });
+  });
+
+  it('parses chains, ampersand groups, and inline-text links', () => {
+    const chain = parseFlowchartLine('A & B -- text --> C -.-> D');
+
+    expect(chain?.groups.map((g) => g.map((n) => n.id))).toEqual([
+      ['A', 'B'],
+      ['C'],
+      ['D'],
+    ]);
+    expect(chain?.links).toEqual([
+      { inlineOpen: '--', label: 'text', arrow: '-->' },
+      { arrow: '-.->' },
+    ]);
+  });
+
+  it('parses edge IDs and quoted labels containing brackets', () => {
+    const chain = parseFlowchartLine('A["list [0]"] e1@==> node-2');
+
+    expect(chain?.groups[0][0].shape?.text).toBe('"list [0]"');
+    expect(chain?.links[0]).toEqual({ edgeId: 'e1', arrow: '==>' });
+    expect(chain?.groups[1][0].id).toBe('node-2');
+  });
+
+  it('reads icons from labels and icon and image attributes', () => {
+    const chain = parseFlowchartLine(
+      'B[fa:fa-car Car] --> C@{ icon: "fa:user", img: "https://x/a.png" }'
+    );
+
+    expect(chain?.groups.map(([node]) => [node.icon, node.image])).toEqual([
+      ['fa:fa-car', undefined],
+      ['fa:user', 'https://x/a.png'],
+    ]);
+  });
+
+  it('returns null for non-edge statements', () => {
+    expect(parseFlowchartLine('style A fill:#f9f')).toBeNull();
+    expect(parseFlowchartLine('classDef done fill:#0f0')).toBeNull();
+    expect(parseFlowchartLine('A -->')).toBeNull();
+  });
+});
+
+describe('statementChain', () => {
+  it('reuses the chain parsed for the same statement text', () => {
+    const [, edge] = parse('flowchart TD\nA-->B').statements;
+    const chain = statementChain(edge);
+    expect(chain).toEqual(parseFlowchartLine('A-->B'));
+    expect(statementChain(edge, 'A-->B')).toBe(chain);
+    expect(statementChain(edge, 'A --> C')?.groups[1][0].id).toBe('C');
+  });
+});
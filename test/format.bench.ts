@@ -0,0 +1,39 @@
+/**
+ * Parser and formatter benchmarks on large generated diagrams
+ * (`npm run bench`).
+ */
+
+import { bench, describe } from 'vitest';
+import { format, formatMermaid, parse } from '../src/index.js';
+import { largeDiagram } from '../src/testing.js';
+
+// Diagram size in lines, e.g. BENCH_LINES=100000 npm run bench
+const LINES = Number(process.env.BENCH_LINES ?? 10000);
+const FLOWCHART = largeDiagram('flowchart', LINES);
+const SEQUENCE = largeDiagram('sequence', LINES);
+
+describe(`${LINES}-line flowchart`, () => {
+  const diagram = parse(FLOWCHART);
+  bench('formatMermaid', () => {
+    formatMermaid(FLOWCHART);
+  });
+  bench('parse', () => {
+    parse(FLOWCHART);
+  });
+  bench('format', () => {
+    format(diagram);
+  });
+});
+
+describe(`${LINES}-line sequence diagram`, () => {
+  const diagram = parse(SEQUENCE);
+  bench('formatMermaid', () => {
+    formatMermaid(SEQUENCE);
+  });
+  bench('parse', () => {
+    parse(SEQUENCE);
+  });
+  bench('format', () => {
+    format(diagram);
+  });
+});
@@ -3,7 +3,16 @@
  */
 
 import { describe, it, expect } from 'vitest';
-import { formatMermaid } from '../src/index.js';
+import type { Statement, StatementHook, TraceEvent } from '../src/index.js';
+import {
+  checkFormatted,
+  format,
+  formatMermaid,
+  formatStatementText,
+  formatWithCursor,
+  formatWithSourceMap,
+  parse,
+} from '../src/index.js';
 
 describe('formatMermaid', () => {
   describe('indentation', () => {
@@ -44,6 +53,36 @@ participant A`;
 `;
       expect(formatMermaid(input, { useTabs: true })).toBe(expected);
     });
+
+    it('keeps the top level flush-left without indentTopLevel', () => {
+      const input = `flowchart TD
+    A --> B
+    subgraph S
+    C --> D
+    end`;
+      const expected = `flowchart TD
+A --> B
+
+subgraph S
+    C --> D
+end
+`;
+      expect(formatMermaid(input, { indentTopLevel: false })).toBe(expected);
+    });
+  });
+
+  describe('participant aliases', () => {
+    it('keeps quoted aliases intact and normalizes as spacing', () => {
+      const input = `sequenceDiagram
+participant   A   as   "Alice   in  Wonderland"
+participant C as"Carl (admin); <b>"
+actor B  as  Bob  & co`;
+      expect(formatMermaid(input)).toBe(`sequenceDiagram
+    participant A as "Alice   in  Wonderland"
+    participant C as "Carl (admin); <b>"
+    actor B as Bob & co
+`);
+    });
   });
 
   describe('block structures', () => {
@@ -168,6 +207,65 @@ class "HTTP Client" {
       expect(formatMermaid(input)).toBe(expected);
     });
 
+    it('formats box participant groups', () => {
+      const input = `sequenceDiagram
+box  Purple   Alice & John
+participant A
+participant J
+end
+box Another Group
+participant B
+end
+A->>J: Hello`;
+      const expected = `sequenceDiagram
+
+    box Purple Alice & John
+        participant A
+        participant J
+    end
+
+    box Another Group
+        participant B
+    end
+    A ->> J: Hello
+`;
+      expect(formatMermaid(input)).toBe(expected);
+    });
+
+    it('keeps participant link payloads as written', () => {
+      const input = `sequenceDiagram
+participant A
+links  A:{ "Dashboard":  "https://dash.example.com" }
+link A :  Repo @ https://git.example.com
+A->>A: self`;
+      expect(formatMermaid(input)).toBe(`sequenceDiagram
+    participant A
+    links A: { "Dashboard":  "https://dash.example.com" }
+    link A: Repo @ https://git.example.com
+    A ->> A: self
+`);
+    });
+
+    it('separates rect and box color arguments with ", "', () => {
+      const input = `sequenceDiagram
+rect rgb( 191,223 ,255 )
+A->>B: x
+end
+box rgba(0,0,255,0.1) Team  (core)
+participant C
+end`;
+      expect(formatMermaid(input)).toBe(`sequenceDiagram
+
+    rect rgb(191, 223, 255)
+        A ->> B: x
+    end
+
+    box rgba(0, 0, 255, 0.1) Team  (core)
+        participant C
+    end
+`);
+    });
+
     it('formats flowchart with subgraph', () => {
       const input = `flowchart TD
 A --> B
@@ -197,6 +295,35 @@ end`;
 `;
       expect(formatMermaid(input)).toBe(expected);
     });
+
+    it('indents composite state internals at every nesting level', () => {
+      const input = `stateDiagram-v2
+state Active {
+state  fork1  << fork >>
+[*] --> fork1
+note right of fork1 : splits
+--
+state Inner {
+state c <<choice>>
+--
+X --> Y
+}
+}`;
+      expect(formatMermaid(input)).toBe(`stateDiagram-v2
+
+state Active {
+    state fork1 <<fork>>
+    [*] --> fork1
+    note right of fork1: splits
+    --
+    state Inner {
+        state c <<choice>>
+        --
+        X --> Y
+    }
+}
+`);
+    });
   });
 
   describe('normalization', () => {
@@ -256,6 +383,48 @@ end`;
       expect(formatMermaid(input)).toBe(expected);
     });
 
+    it('normalizes autonumber and activation statements', () => {
+      const input = `sequenceDiagram
+autonumber  10   10
+loop Every minute
+activate   Alice
+Alice->>Bob: ping
+deactivate Alice
+end`;
+      const expected = `sequenceDiagram
+    autonumber 10 10
+
+    loop Every minute
+        activate Alice
+        Alice ->> Bob: ping
+        deactivate Alice
+    end
+`;
+      expect(formatMermaid(input)).toBe(expected);
+    });
+
+    it('normalizes note keyword, participant list, and colon spacing', () => {
+      const input = `sequenceDiagram
+    note  right   of A:hello   there
+    NOTE over A , B :  shared
+    Note left of C`;
+      const expected = `sequenceDiagram
+    Note right of A: hello there
+    Note over A,B: shared
+    Note left of C
+`;
+      expect(formatMermaid(input)).toBe(expected);
+    });
+
+    it('uses lowercase note keyword outside sequence diagrams', () => {
+      const input = `stateDiagram-v2
+    Note right of s1 : important`;
+      const expected = `stateDiagram-v2
+    note right of s1: important
+`;
+      expect(formatMermaid(input)).toBe(expected);
+    });
+
     it('does not parse flowchart class syntax as arrow message', () => {
       const input = `flowchart TD
     A --> B:::warning`;
@@ -265,6 +434,17 @@ end`;
       expect(formatMermaid(input)).toBe(expected);
     });
 
+    it('keeps icon label colons out of arrow messages', () => {
+      const input = `flowchart TD
+    B[ fa:fa-car  Car ]-->C(fab:fa-twitter for peace)
+    G["fa:fa-spinner  quoted"]`;
+      const expected = `flowchart TD
+    B[fa:fa-car Car]-->C(fab:fa-twitter for peace)
+    G["fa:fa-spinner quoted"]
+`;
+      expect(formatMermaid(input)).toBe(expected);
+    });
+
     it('normalizes arrows in non-sequence diagrams when pattern matches', () => {
       const input = `classDiagram
     Animal-->Dog:inherits`;
@@ -274,6 +454,37 @@ end`;
       expect(formatMermaid(input)).toBe(expected);
     });
 
+    it('normalizes class relationship cardinality and label spacing', () => {
+      const input = `classDiagram
+    Class01"1"-->"many"Class02:contains
+    Animal  <|--  Duck
+    Customer "1" --> "*" Ticket :  buys`;
+      const expected = `classDiagram
+    Class01 "1" --> "many" Class02: contains
+    Animal <|-- Duck
+    Customer "1" --> "*" Ticket: buys
+`;
+      expect(formatMermaid(input)).toBe(expected);
+    });
+
+    it('normalizes class annotations', () => {
+      const input = `classDiagram
+    <<  interface  >>Shape
+    class Color {
+        <<enumeration>>
+        RED
+    }`;
+      const expected = `classDiagram
+    <<interface>> Shape
+
+class Color {
+    <<enumeration>>
+    RED
+}
+`;
+      expect(formatMermaid(input)).toBe(expected);
+    });
+
     it('normalizes arrows in stateDiagram', () => {
       const input = `stateDiagram-v2
     s1-->s2:transition`;
@@ -288,6 +499,84 @@ end`;
     A[ Start ] --> B[ End ]`;
       const expected = `flowchart TD
     A[Start] --> B[End]
+`;
+      expect(formatMermaid(input)).toBe(expected);
+    });
+
+    it('normalizes nested and unbalanced brackets in one scan', () => {
+      // Flowcharts keep lines the chain parser rejects as written
+      expect(formatMermaid('flowchart TD\n    A[ x [ y ] ] --> B( z')).toBe(
+        'flowchart TD\n    A[ x [ y ] ] --> B( z\n'
+      );
+      const nested: Statement = {
+        type: 'generic-line',
+        content: 'A[ x [ y ] ] --> B( z',
+      };
+      expect(formatStatementText(nested, 'flowchart')).toBe(
+        'A[x [ y ]] --> B( z'
+      );
+      const unbalanced = 'flowchart TD\n    A' + '[ '.repeat(100000) + 'x\n';
+      expect(formatMermaid(unbalanced)).toBe(unbalanced);
+    });
+
+    it('leaves brackets inside quoted labels alone', () => {
+      const input = `flowchart TD
+    A[ "list [0]" ] --> B( "f(x" ) --> C{ "a } b" }
+    D[ "x ]" ] --> E[ "[ y ]" ]
+    F("g( z )") --> G{"{ w }"}`;
+      expect(formatMermaid(input)).toBe(`flowchart TD
+    A["list [0]"] --> B("f(x") --> C{"a } b"}
+    D["x ]"] --> E["[ y ]"]
+    F("g( z )") --> G{"{ w }"}
+`);
+    });
+
+    it('normalizes padded shape tokens to the canonical token', () => {
+      const input = `flowchart TD
+    A([ Stadium ]) --> B[( Database )]
+    C(( Circle )) --> D((( Double ))) --> E[[ Subroutine ]]
+    F{{ Hexagon }}`;
+      expect(formatMermaid(input)).toBe(`flowchart TD
+    A([Stadium]) --> B[(Database)]
+    C((Circle)) --> D(((Double))) --> E[[Subroutine]]
+    F{{Hexagon}}
+`);
+      expect(formatMermaid('stateDiagram-v2\n[ * ] --> A\nA --> [ *]')).toBe(
+        'stateDiagram-v2\n    [*] --> A\n    A --> [*]\n'
+      );
+    });
+
+    it('keeps padding that would turn brackets into another shape', () => {
+      const input = `flowchart TD
+    G( [x] ) --> H[ (y) ]
+    I( (z) ) --> J{ {w} }`;
+      expect(formatMermaid(input)).toBe(input + '\n');
+    });
+
+    it('normalizes flowchart attribute map spacing', () => {
+      const input = `flowchart TD
+    A@{shape:rounded,label:  "Start"}
+    B@{ shape: circle }
+    A@{shape: rect} --> B`;
+      const expected = `flowchart TD
+    A@{ shape: rounded, label: "Start" }
+    B@{ shape: circle }
+    A@{ shape: rect } --> B
+`;
+      expect(formatMermaid(input)).toBe(expected);
+    });
+
+    it('preserves edge IDs and normalizes spacing after the link', () => {
+      const input = `flowchart LR
+    A  e1@-->B
+    B e2@==>|  yes  | C
+    C e3@-.-> D
+    e1@{ animate:true }`;
+      const expected = `flowchart LR
+    A e1@--> B
+    B e2@==>|yes| C
+    C e3@-.-> D
+    e1@{ animate: true }
 `;
       expect(formatMermaid(input)).toBe(expected);
     });
@@ -349,6 +638,26 @@ end`;
       expect(formatMermaid(input)).toBe(expected);
     });
 
+    it('indents erDiagram attribute blocks', () => {
+      const input = `erDiagram
+CUSTOMER ||--o{ ORDER : places
+CUSTOMER {
+string  name
+string custNumber PK
+}
+ORDER ||--|{ LINE-ITEM : contains`;
+      const expected = `erDiagram
+    CUSTOMER ||--o{ ORDER : places
+
+CUSTOMER {
+    string name
+    string custNumber PK
+}
+    ORDER ||--|{ LINE-ITEM : contains
+`;
+      expect(formatMermaid(input)).toBe(expected);
+    });
+
     it('preserves comments', () => {
       const input = `sequenceDiagram
     %% This is a comment
@@ -370,6 +679,26 @@ sequenceDiagram
 `;
       expect(formatMermaid(input)).toBe(expected);
     });
+
+    it('keeps trailing comments verbatim after a single space', () => {
+      const input = `flowchart TD
+A  -->  B[ x ]    %%  keep   [ this ]
+subgraph S%% group
+end %%close`;
+      expect(formatMermaid(input)).toBe(`flowchart TD
+    A --> B[x] %%  keep   [ this ]
+
+    subgraph S %% group
+    end %%close
+`);
+    });
+
+    it('aligns statements with trailing comments', () => {
+      const input = 'sequenceDiagram\nA->>B: hi %% first\nAlice->>B: yo';
+      expect(formatMermaid(input, { alignMessages: true })).toBe(
+        'sequenceDiagram\n    A ->> B:     hi %% first\n    Alice ->> B: yo\n'
+      );
+    });
   });
 
   describe('indent-sensitive diagrams', () => {
@@ -384,13 +713,1165 @@ sequenceDiagram
       expect(formatMermaid(input)).toBe(input + '\n');
     });
 
-    it('preserves timeline indentation', () => {
+    it('reindents mindmaps by depth with reindentMindmaps', () => {
+      const input = `mindmap
+  root((mindmap))
+     Origins
+   ::icon(fa fa-book)
+        )cloud(
+     Research
+       {{ hex }}
+  :::urgent  large
+       end`;
+      const expected = `mindmap
+    root((mindmap))
+        Origins
+            ::icon(fa fa-book)
+            )cloud(
+        Research
+            {{ hex }}
+                :::urgent  large
+            end
+`;
+      expect(formatMermaid(input, { reindentMindmaps: true })).toBe(expected);
+      expect(formatMermaid(expected, { reindentMindmaps: true })).toBe(
+        expected
+      );
+    });
+  });
+
+  describe('block-beta', () => {
+    it('joins width suffixes and indents composite blocks', () => {
+      const input = `block-beta
+columns   3
+a:2 b  c : 1
+space :2
+block : group1:2
+columns 2
+h["x : 2"] i
+end
+a --> b:2
+style a stroke-width: 4
+classDef hot stroke-width : 2`;
+      expect(formatMermaid(input)).toBe(`block-beta
+    columns 3
+    a:2 b c:1
+    space:2
+
+    block:group1:2
+        columns 2
+        h["x : 2"] i
+    end
+    a --> b:2
+    style a stroke-width: 4
+    classDef hot stroke-width : 2
+`);
+    });
+  });
+
+  describe('timeline', () => {
+    it('keeps formatted timelines unchanged', () => {
       const input = `timeline
     title History
     2023 : Event A
         : Sub-event`;
-      // Should preserve original indentation, not reformat
       expect(formatMermaid(input)).toBe(input + '\n');
     });
+
+    it('indents continuation events one level below their period', () => {
+      const input = `timeline
+title History
+section 2000s
+2004 : Facebook
+: Google
+  :  Flickr :  Orkut
+section 2010s
+: Orphan`;
+      expect(formatMermaid(input)).toBe(`timeline
+    title History
+    section 2000s
+    2004 : Facebook
+        : Google
+        : Flickr : Orkut
+    section 2010s
+    : Orphan
+`);
+    });
+
+    it('keeps URLs, clock times, and brackets in events', () => {
+      const input = `timeline
+2024 : Launch ( beta ) : docs https://y.com at 10:30
+: standup 9:15 :  retro`;
+      expect(formatMermaid(input)).toBe(`timeline
+    2024 : Launch ( beta ) : docs https://y.com at 10:30
+        : standup 9:15 : retro
+`);
+    });
+  });
+});
+
+describe('checkFormatted', () => {
+  it('accepts formatted input', () => {
+    expect(checkFormatted('flowchart TD\n    A --> B\n')).toEqual({
+      formatted: true,
+    });
+  });
+
+  it('reports the first differing line', () => {
+    const result = checkFormatted('sequenceDiagram\n    A ->> B: hi\nB->>A: ok');
+    expect(result).toEqual({
+      formatted: false,
+      firstDifference: {
+        line: 3,
+        actual: 'B->>A: ok',
+        expected: '    B ->> A: ok',
+      },
+    });
+  });
+
+  it('reports a missing final newline', () => {
+    expect(checkFormatted('flowchart TD').firstDifference).toEqual({
+      line: 2,
+      actual: null,
+      expected: '',
+    });
+  });
+});
+
+describe('formatWithCursor', () => {
+  it('keeps the cursor next to the same character', () => {
+    // Cursor between "h" and "i"
+    const result = formatWithCursor('sequenceDiagram\nA->>B:hi', 23);
+    expect(result).toEqual({
+      formatted: 'sequenceDiagram\n    A ->> B: hi\n',
+      cursorOffset: 30,
+    });
+  });
+
+  it('moves a cursor in leading whitespace to the content start', () => {
+    const { formatted, cursorOffset } = formatWithCursor(
+      'flowchart TD\n        A-->B',
+      14
+    );
+    expect(formatted.slice(cursorOffset)).toBe('A-->B\n');
+  });
+
+  it('moves a cursor on a collapsed blank line to the next kept line', () => {
+    const input = 'sequenceDiagram\n\n\n\nA->>B:hi';
+    const { formatted, cursorOffset } = formatWithCursor(input, 18);
+    expect(formatted.slice(cursorOffset)).toBe('A ->> B: hi\n');
+  });
+
+  it('tracks the cursor across lines shifted by inserted blanks', () => {
+    const input = 'sequenceDiagram\nA->>B:x\nloop L\nB->>A:y\nend';
+    const { formatted, cursorOffset } = formatWithCursor(
+      input,
+      input.indexOf('y')
+    );
+    expect(formatted.slice(cursorOffset)).toBe('y\n    end\n');
+  });
+
+  it('clamps offsets past the end of the input', () => {
+    const { formatted, cursorOffset } = formatWithCursor('pie\n', 99);
+    expect(cursorOffset).toBeLessThanOrEqual(formatted.length);
+  });
+});
+
+describe('formatWithSourceMap', () => {
+  it('maps input lines to output lines', () => {
+    const result = formatWithSourceMap(
+      'sequenceDiagram\n\n\nA->>B: x\nloop L\nB->>A: y\nend\n'
+    );
+    expect(result.formatted).toBe(
+      'sequenceDiagram\n\n    A ->> B: x\n\n    loop L\n        B ->> A: y\n    end\n'
+    );
+    // Line 3 collapses into line 2; "loop" moves down past an inserted blank
+    expect(result.lineMap).toEqual([1, 2, null, 3, 5, 6, 7, null]);
+  });
+
+  it('maps indent-sensitive diagrams one to one', () => {
+    const result = formatWithSourceMap('mindmap\n  root\n');
+    expect(result.lineMap).toEqual([1, 2, null]);
+  });
+});
+
+describe('format', () => {
+  it('prints a parsed diagram like formatMermaid', () => {
+    const input = 'flowchart LR\n  A-->B\n';
+    expect(format(parse(input), { indentSize: 2 })).toBe(
+      formatMermaid(input, { indentSize: 2 })
+    );
+  });
+});
+
+describe('formatStatementText', () => {
+  it('prints a statement without indentation', () => {
+    const [, message, note] = parse(
+      'sequenceDiagram\n    A->>B:hi\n    note over A,B:  both'
+    ).statements;
+    expect(formatStatementText(message, 'sequenceDiagram')).toBe('A ->> B: hi');
+    expect(formatStatementText(note, 'sequenceDiagram')).toBe(
+      'Note over A,B: both'
+    );
+  });
+
+  it('applies statement-level options', () => {
+    const [, edge] = parse('flowchart TD\nA-->B').statements;
+    expect(formatStatementText(edge, 'flowchart')).toBe('A-->B');
+    expect(formatStatementText(edge, 'flowchart', { spaceArrows: true })).toBe(
+      'A --> B'
+    );
+  });
+});
+
+describe('statementHooks', () => {
+  const upperMessages: StatementHook = {
+    types: ['arrow-message'],
+    diagramTypes: ['sequenceDiagram'],
+    format: (stmt, { formatted }) =>
+      stmt.type === 'arrow-message' && stmt.message === 'shout'
+        ? formatted.toUpperCase()
+        : undefined,
+  };
+
+  it('overrides the rendering of matching statements', () => {
+    const input = 'sequenceDiagram\nloop x\nA->>B:shout\nA->>B:hi\nend';
+    expect(formatMermaid(input, { statementHooks: [upperMessages] })).toBe(
+      'sequenceDiagram\n\n    loop x\n        A ->> B: SHOUT\n' +
+        '        A ->> B: hi\n    end\n'
+    );
+  });
+
+  it('keeps the source text when a hook vetoes', () => {
+    const keepSpacing: StatementHook = {
+      types: ['generic-line'],
+      format: (stmt) => (stmt.content.includes('keep') ? null : undefined),
+    };
+    expect(
+      formatMermaid('graph TD\nA[ keep ]-->B\nC[ x ]', {
+        statementHooks: [keepSpacing],
+      })
+    ).toBe('graph TD\n    A[ keep ]-->B\n    C[x]\n');
+  });
+
+  it('skips hooks for other diagram types and stops at the first result', () => {
+    const first: StatementHook = { format: () => 'first' };
+    const second: StatementHook = { format: () => 'second' };
+    expect(
+      formatMermaid('flowchart TD\nA->>B:shout', {
+        statementHooks: [upperMessages],
+      })
+    ).toBe('flowchart TD\n    A ->> B: shout\n');
+    const [, stmt] = parse('graph TD\nA').statements;
+    expect(
+      formatStatementText(stmt, 'graph', { statementHooks: [first, second] })
+    ).toBe('first');
+  });
+
+  it('leaves hooked lines out of column alignment', () => {
+    const input = 'sequenceDiagram\nA->>B:shout\nAlice->>B:hi\nA->>B:ok';
+    expect(
+      formatMermaid(input, {
+        alignMessages: true,
+        statementHooks: [upperMessages],
+      })
+    ).toBe(
+      'sequenceDiagram\n    A ->> B: SHOUT\n    Alice ->> B: hi\n' +
+        '    A ->> B:     ok\n'
+    );
+  });
+});
+
+describe('alignMessages', () => {
+  it('aligns message texts within a run, per block', () => {
+    const input = `sequenceDiagram
+A->>B: short
+Alice-->>Bob: longer prefix
+loop Retry
+  A->>Carol: ping
+  A->>B: pong
+end`;
+    expect(formatMermaid(input, { alignMessages: true })).toBe(`sequenceDiagram
+    A ->> B:        short
+    Alice -->> Bob: longer prefix
+
+    loop Retry
+        A ->> Carol: ping
+        A ->> B:     pong
+    end
+`);
+  });
+
+  it('measures CJK participants by display width', () => {
+    const input = 'sequenceDiagram\n用户->>B: a\nA->>B: b';
+    expect(formatMermaid(input, { alignMessages: true })).toBe(
+      'sequenceDiagram\n    用户 ->> B: a\n    A ->> B:    b\n'
+    );
+  });
+
+  it('is off by default', () => {
+    const input = 'sequenceDiagram\nA->>B: a\nAlice->>B: b';
+    expect(formatMermaid(input)).toBe(
+      'sequenceDiagram\n    A ->> B: a\n    Alice ->> B: b\n'
+    );
+  });
+});
+
+describe('alignArrows', () => {
+  const input = `flowchart LR
+A-->B
+LongName -.->|maybe| C[Target]
+D e1@==> E
+A --> B --> C
+Start --> End`;
+
+  it('aligns arrows and targets of single-link edges across chains', () => {
+    expect(formatMermaid(input, { alignArrows: true })).toBe(`flowchart LR
+    A        -->         B
+    LongName -.->|maybe| C[Target]
+    D        e1@==>      E
+    A --> B --> C
+    Start    -->         End
+`);
+  });
+
+  it('gives labels their own column with alignEdgeLabels', () => {
+    expect(
+      formatMermaid(input, { alignArrows: true, alignEdgeLabels: true })
+    ).toBe(`flowchart LR
+    A        -->            B
+    LongName -.->   |maybe| C[Target]
+    D        e1@==>         E
+    A --> B --> C
+    Start    -->            End
+`);
+  });
+
+  it('drops the label column when no edge has a label', () => {
+    const result = formatMermaid('graph TD\nA-->B\nCC---D', {
+      alignArrows: true,
+      alignEdgeLabels: true,
+    });
+    expect(result).toBe('graph TD\n    A  --> B\n    CC --- D\n');
+  });
+});
+
+describe('alignErAttributes', () => {
+  it('aligns attribute columns inside each entity block', () => {
+    const input = `erDiagram
+CUSTOMER ||--o{ ORDER : places
+CUSTOMER {
+string name PK "primary"
+int age
+varchar(255) email UK,FK "login"
+}
+ORDER {
+int id PK
+}`;
+    expect(formatMermaid(input, { alignErAttributes: true })).toBe(`erDiagram
+    CUSTOMER ||--o{ ORDER : places
+
+CUSTOMER {
+    string       name  PK     "primary"
+    int          age
+    varchar(255) email UK, FK "login"
+}
+
+ORDER {
+    int id PK
+}
+`);
+  });
+});
+
+describe('alignGanttTasks', () => {
+  const input = `gantt
+dateFormat YYYY-MM-DD
+section Design
+Research :r1, 2024-01-01, 5d
+UX review:ux, after r1,3d
+section Build
+Backend :crit, be, 2024-01-10, 10d
+API:api, after be, 4d`;
+
+  it('aligns task colons and fields per section', () => {
+    expect(formatMermaid(input, { alignGanttTasks: true })).toBe(`gantt
+    dateFormat YYYY-MM-DD
+    section Design
+    Research  : r1, 2024-01-01, 5d
+    UX review : ux, after r1,   3d
+    section Build
+    Backend : crit, be,       2024-01-10, 10d
+    API     : api,  after be, 4d
+`);
+  });
+
+  it('leaves rows that would exceed maxWidth unpadded', () => {
+    const result = formatMermaid(
+      'gantt\nA :a, 1d\nA much longer task name here :b, after a, 2d\nBB :c, 3d',
+      { alignGanttTasks: true, maxWidth: 30 }
+    );
+    expect(result).toBe(`gantt
+    A  : a, 1d
+    A much longer task name here : b, after a, 2d
+    BB : c, 3d
+`);
+  });
+});
+
+describe('quadrant points', () => {
+  const input = `quadrantChart
+x-axis Low Reach --> High Reach
+Campaign A:[0.3,0.6]
+Campaign  B :  [ 0.45 , 0.23 ]  radius: 10
+Point C:::hot: [0.9, 0.0]`;
+
+  it('normalizes coordinate spacing', () => {
+    expect(formatMermaid(input)).toBe(`quadrantChart
+    x-axis Low Reach --> High Reach
+    Campaign A: [0.3, 0.6]
+    Campaign B: [0.45, 0.23] radius: 10
+    Point C:::hot: [0.9, 0.0]
+`);
+  });
+
+  it('aligns labels and coordinates with alignQuadrantPoints', () => {
+    expect(formatMermaid(input, { alignQuadrantPoints: true })).toBe(
+      `quadrantChart
+    x-axis Low Reach --> High Reach
+    Campaign A:    [0.3, 0.6]
+    Campaign B:    [0.45, 0.23] radius: 10
+    Point C:::hot: [0.9, 0.0]
+`
+    );
+  });
+});
+
+describe('requirement relationships', () => {
+  it('normalizes spacing in both directions', () => {
+    const input = `requirementDiagram
+test_entity   -   satisfies  ->   test_req
+test_req <-copies- "entity two"
+test_req - satisfies -> test_entity`;
+    expect(formatMermaid(input)).toBe(`requirementDiagram
+    test_entity - satisfies -> test_req
+    test_req <- copies - "entity two"
+    test_req - satisfies -> test_entity
+`);
+  });
+});
+
+describe('groupParticipants', () => {
+  const input = `sequenceDiagram
+autonumber
+Bob->>Alice: Hi
+%% The service
+participant Svc
+Alice->>Svc: Call
+participant Alice
+box Blue Team
+participant Carol
+end`;
+
+  it('moves top-level declarations and their comments to the top', () => {
+    const result = formatMermaid(input, { groupParticipants: true });
+    expect(result).toBe(`sequenceDiagram
+    autonumber
+    %% The service
+    participant Svc
+    participant Alice
+    Bob ->> Alice: Hi
+    Alice ->> Svc: Call
+
+    box Blue Team
+        participant Carol
+    end
+`);
+  });
+
+  it('sorts by first use', () => {
+    const result = formatMermaid(input, {
+      groupParticipants: true,
+      participantOrder: 'first-use',
+    });
+    expect(result).toContain(
+      '    participant Alice\n    %% The service\n    participant Svc\n'
+    );
+  });
+
+  it('sorts alphabetically and is idempotent', () => {
+    const options = {
+      groupParticipants: true,
+      participantOrder: 'alphabetical' as const,
+    };
+    const result = formatMermaid(input, options);
+    expect(result).toContain(
+      '    participant Alice\n    %% The service\n    participant Svc\n'
+    );
+    expect(formatMermaid(result, options)).toBe(result);
+  });
+
+  it('keeps the source map pointing at moved lines', () => {
+    const { lineMap } = formatWithSourceMap(
+      'sequenceDiagram\nA->>B: x\nparticipant B',
+      { groupParticipants: true }
+    );
+    expect(lineMap).toEqual([1, 3, 2]);
+  });
+});
+
+describe('declareParticipants', () => {
+  it('declares implicit participants in first-use order', () => {
+    const input = `sequenceDiagram
+participant Bob
+Alice->>Bob: Hi
+Note over Carol: Thinking
+create participant Dan
+Bob->>Dan: Hello`;
+    const result = formatMermaid(input, { declareParticipants: true });
+    expect(result).toBe(`sequenceDiagram
+    participant Bob
+    participant Alice
+    participant Carol
+    Alice ->> Bob: Hi
+    Note over Carol: Thinking
+    create participant Dan
+    Bob ->> Dan: Hello
+`);
+  });
+
+  it('leaves participants a declaration cannot spell implicit', () => {
+    const input = 'sequenceDiagram\nNote over par DB: busy\nA->>B: x';
+    const result = formatMermaid(input, { declareParticipants: true });
+    expect(result).toBe(
+      'sequenceDiagram\n    participant A\n    participant B\n' +
+        '    Note over par DB: busy\n    A ->> B: x\n'
+    );
+    expect(formatMermaid(result, { declareParticipants: true })).toBe(result);
+  });
+
+  it('sorts inserted declarations with grouped ones', () => {
+    const result = formatMermaid('sequenceDiagram\nB->>A: x\nparticipant C', {
+      declareParticipants: true,
+      groupParticipants: true,
+      participantOrder: 'alphabetical',
+    });
+    expect(result).toBe(`sequenceDiagram
+    participant A
+    participant B
+    participant C
+    B ->> A: x
+`);
+  });
+
+  it('maps inserted declarations to no input line', () => {
+    const { lineMap } = formatWithSourceMap('sequenceDiagram\nA->>B: x', {
+      declareParticipants: true,
+    });
+    expect(lineMap).toEqual([1, 4]);
+  });
+});
+
+describe('sortClassMembers', () => {
+  const input = `classDiagram
+class Account {
++withdraw(amount) bool
+-String owner
+%% Cached balance
+-int balance
++deposit(amount) bool
+<<entity>>
++String id
+}`;
+
+  it('orders annotations, fields, and methods by visibility', () => {
+    const result = formatMermaid(input, { sortClassMembers: true });
+    expect(result).toBe(`classDiagram
+
+class Account {
+    <<entity>>
+    +String id
+    -String owner
+    %% Cached balance
+    -int balance
+    +withdraw(amount) bool
+    +deposit(amount) bool
+}
+`);
+  });
+
+  it('alphabetizes within groups', () => {
+    const result = formatMermaid(input, {
+      sortClassMembers: true,
+      alphabetizeClassMembers: true,
+    });
+    expect(result).toBe(`classDiagram
+
+class Account {
+    <<entity>>
+    +String id
+    %% Cached balance
+    -int balance
+    -String owner
+    +deposit(amount) bool
+    +withdraw(amount) bool
+}
+`);
+  });
+
+  it('sorts blank-line separated sections separately', () => {
+    const result = formatMermaid(
+      'classDiagram\nclass A {\n+b()\n+a\n\n-d()\n-c\n}',
+      { sortClassMembers: true }
+    );
+    expect(result).toBe(
+      'classDiagram\n\nclass A {\n    +a\n    +b()\n\n    -c\n    -d()\n}\n'
+    );
+  });
+});
+
+describe('groupStyleStatements', () => {
+  it('moves styling statements below the structure in order', () => {
+    const input = `flowchart LR
+classDef warn fill:#f96
+A --> B
+style A stroke:#333
+subgraph S
+%% Highlight C
+class C warn
+B --> C
+end
+click C callback
+C --> D`;
+    const result = formatMermaid(input, { groupStyleStatements: true });
+    expect(result).toBe(`flowchart LR
+    A --> B
+
+    subgraph S
+        B --> C
+    end
+    C --> D
+
+    classDef warn fill:#f96
+    style A stroke:#333
+    %% Highlight C
+    class C warn
+    click C callback
+`);
+    expect(formatMermaid(result, { groupStyleStatements: true })).toBe(result);
+  });
+
+  it('leaves diagrams without styling statements unchanged', () => {
+    const input = 'flowchart TD\n    A --> B\n';
+    expect(formatMermaid(input, { groupStyleStatements: true })).toBe(input);
+  });
+});
+
+describe('groupGanttSettings', () => {
+  const input = `gantt
+title Release
+dateFormat   YYYY-MM-DD
+section Build
+Compile : a1, 2014-01-01, 3d
+%% Marker for the demo
+todayMarker stroke-width:5px,  stroke:#0f0
+section Ship
+tickInterval 1week
+Release : milestone, m1, 2014-01-25, 0d`;
+
+  it('keeps setting values as written', () => {
+    expect(formatMermaid(input)).toContain(
+      '    todayMarker stroke-width:5px,  stroke:#0f0\n'
+    );
+  });
+
+  it('moves settings above the first section in order', () => {
+    const result = formatMermaid(input, { groupGanttSettings: true });
+    expect(result).toBe(`gantt
+    title Release
+    dateFormat YYYY-MM-DD
+    %% Marker for the demo
+    todayMarker stroke-width:5px,  stroke:#0f0
+    tickInterval 1week
+    section Build
+    Compile : a1, 2014-01-01, 3d
+    section Ship
+    Release : milestone, m1, 2014-01-25, 0d
+`);
+    expect(formatMermaid(result, { groupGanttSettings: true })).toBe(result);
+  });
+});
+
+describe('directives', () => {
+  const input = `sequenceDiagram
+%%{init: {"theme": "dark"}}%%
+Bob->>Alice: Hi
+participant Alice
+%%{init: {"mirrorActors": false}}%%
+participant Svc`;
+
+  it('never moves statements across a directive', () => {
+    const grouped = formatMermaid(input, { groupParticipants: true });
+    expect(grouped).toBe(`sequenceDiagram
+%%{init: {"theme": "dark"}}%%
+    participant Alice
+    Bob ->> Alice: Hi
+%%{init: {"mirrorActors": false}}%%
+    participant Svc
+`);
+    const flowchart = 'flowchart TD\nstyle A fill:#f9f\n%%{init: {}}%%\nA --> B';
+    expect(formatMermaid(flowchart, { groupStyleStatements: true })).toBe(
+      'flowchart TD\n\n    style A fill:#f9f\n%%{init: {}}%%\n    A --> B\n'
+    );
+  });
+
+  it('moves directives to the top with hoistDirectives', () => {
+    const hoisted = formatMermaid(input, { hoistDirectives: true });
+    expect(hoisted).toBe(`%%{init: {"theme": "dark"}}%%
+%%{init: {"mirrorActors": false}}%%
+sequenceDiagram
+    Bob ->> Alice: Hi
+    participant Alice
+    participant Svc
+`);
+  });
+
+  it('keeps YAML frontmatter as written', () => {
+    const input = `---
+title: Flow   %% not a comment
+config:
+  flowchart:
+    curve: basis
+---
+flowchart TD
+A-->B`;
+    expect(formatMermaid(input)).toBe(`---
+title: Flow   %% not a comment
+config:
+  flowchart:
+    curve: basis
+---
+flowchart TD
+    A-->B
+`);
+  });
+
+  it('hoists below frontmatter and leaves directives in blocks', () => {
+    const input = `---
+title: Shapes
+---
+classDiagram
+class A {
+%%{init: {"theme": "dark"}}%%
+}
+%%{init: {"look": "handDrawn"}}%%`;
+    expect(formatMermaid(input, { hoistDirectives: true })).toBe(`---
+title: Shapes
+---
+%%{init: {"look": "handDrawn"}}%%
+classDiagram
+
+class A {
+%%{init: {"theme": "dark"}}%%
+}
+`);
+  });
+});
+
+describe('spaceArrows', () => {
+  const space = (line: string) =>
+    formatMermaid(`flowchart TD\n${line}`, { spaceArrows: true });
+
+  it('puts single spaces around link operators', () => {
+    expect(space('A-->B')).toBe('flowchart TD\n    A --> B\n');
+    expect(space('A & B-->C-.->D;')).toBe(
+      'flowchart TD\n    A & B --> C -.-> D;\n'
+    );
+    expect(space('A-->|yes|B')).toBe('flowchart TD\n    A -->|yes| B\n');
+  });
+
+  it('spaces inline-text links', () => {
+    expect(space('A--text-->B')).toBe('flowchart TD\n    A -- text --> B\n');
+  });
+
+  it('does not touch operators inside labels and shapes', () => {
+    expect(space('A[a-->b]-->|x--y|B')).toBe(
+      'flowchart TD\n    A[a-->b] -->|x--y| B\n'
+    );
+    expect(space('A-- a-b -->B')).toBe('flowchart TD\n    A -- a-b --> B\n');
+  });
+
+  it('leaves lines the flowchart parser does not model', () => {
+    expect(space('style A fill:#f9f')).toBe(
+      'flowchart TD\n    style A fill:#f9f\n'
+    );
+  });
+});
+
+describe('dedupeNodeLabels', () => {
+  it('keeps a repeated label only on the first definition', () => {
+    const input = `flowchart TD
+A[Foo] --> B(Bar)
+A[Foo] --> C:::warn
+B(Bar) & A[Foo] --> D`;
+    expect(formatMermaid(input, { dedupeNodeLabels: true })).toBe(`flowchart TD
+    A[Foo] --> B(Bar)
+    A --> C:::warn
+    B & A --> D
+`);
+  });
+
+  it('keeps redefinitions with a different label', () => {
+    const input = 'flowchart TD\nA[Foo] --> B\nA[Baz] --> C\nA[Foo] --> D';
+    expect(formatMermaid(input, { dedupeNodeLabels: true })).toBe(
+      'flowchart TD\n    A[Foo] --> B\n    A[Baz] --> C\n    A[Foo] --> D\n'
+    );
+  });
+
+  it('keeps the class of a deduplicated node', () => {
+    const input = 'flowchart TD\nA[Foo]:::hot --> B\nA[Foo]:::hot --> C';
+    expect(formatMermaid(input, { dedupeNodeLabels: true })).toBe(
+      'flowchart TD\n    A[Foo]:::hot --> B\n    A:::hot --> C\n'
+    );
+  });
+
+  it('keeps the trailing comment of a deduplicated line', () => {
+    const input = 'flowchart TD\nA[Foo] --> B\nA[Foo] --> C %% again';
+    expect(formatMermaid(input, { dedupeNodeLabels: true })).toBe(
+      'flowchart TD\n    A[Foo] --> B\n    A --> C %% again\n'
+    );
+  });
+});
+
+describe('wrapEdgeLabels', () => {
+  const input =
+    'flowchart LR\nA -->|the request is retried after the backoff expires| B';
+
+  it('breaks labels of long lines with <br/>', () => {
+    const result = formatMermaid(input, { wrapEdgeLabels: 'br', maxWidth: 40 });
+    expect(result).toBe(
+      'flowchart LR\n    A -->|the request is retried<br/>after the backoff expires| B\n'
+    );
+    expect(formatMermaid(result, { wrapEdgeLabels: 'br', maxWidth: 40 })).toBe(
+      result
+    );
+  });
+
+  it('quotes wrapped labels with the quoted strategy', () => {
+    const result = formatMermaid(input, {
+      wrapEdgeLabels: 'quoted',
+      maxWidth: 40,
+    });
+    expect(result).toContain(
+      '|"the request is retried<br/>after the backoff expires"|'
+    );
+  });
+
+  it('wraps inline-text labels and keeps existing breaks', () => {
+    const result = formatMermaid(
+      'flowchart LR\nA -- first part<br>second part that is quite long --> B',
+      { wrapEdgeLabels: 'br', maxWidth: 30 }
+    );
+    expect(result).toBe(
+      'flowchart LR\n    A -- first part<br>second part that<br/>is quite long --> B\n'
+    );
+  });
+
+  it('leaves lines within maxWidth unchanged', () => {
+    const short = 'flowchart LR\n    A -->|a short label| B\n';
+    expect(formatMermaid(short, { wrapEdgeLabels: 'br' })).toBe(short);
+  });
+
+  it('keeps trailing comments of wrapped lines that are aligned', () => {
+    const result = formatMermaid(`${input} %% retry\nCC --> D`, {
+      wrapEdgeLabels: 'br',
+      maxWidth: 40,
+      alignArrows: true,
+    });
+    expect(result.split('\n')[1]).toMatch(/expires\| B %% retry$/);
+  });
+
+  it('measures tab indentation as emitted', () => {
+    const line = 'A -->|the request is retried| B';
+    const options = { wrapEdgeLabels: 'br', maxWidth: 34 } as const;
+    expect(formatMermaid(`flowchart LR\n${line}`, options)).toContain('<br/>');
+    expect(
+      formatMermaid(`flowchart LR\n${line}`, { ...options, useTabs: true })
+    ).toBe(`flowchart LR\n\t${line}\n`);
+  });
+});
+
+describe('blankLineBetweenSections', () => {
+  const options = { blankLineBetweenSections: true };
+
+  it('separates the participant block from messages', () => {
+    const input =
+      'sequenceDiagram\nparticipant A\n%% Backend\nparticipant B\nA->>B: hi';
+    expect(formatMermaid(input, options)).toBe(`sequenceDiagram
+    participant A
+    %% Backend
+    participant B
+
+    A ->> B: hi
+`);
+  });
+
+  it('separates content after closed blocks', () => {
+    const input = `flowchart TD
+subgraph One
+A --> B
+end
+subgraph Two
+C --> D
+end
+B --> C`;
+    expect(formatMermaid(input, options)).toBe(`flowchart TD
+
+    subgraph One
+        A --> B
+    end
+
+    subgraph Two
+        C --> D
+    end
+
+    B --> C
+`);
+  });
+
+  it('separates class definitions', () => {
+    const input = 'classDiagram\nclass A {\n+x\n}\nclass B\nA <|-- B';
+    expect(formatMermaid(input, options)).toBe(
+      'classDiagram\n\nclass A {\n    +x\n}\n\n    class B\n    A <|-- B\n'
+    );
+  });
+
+  it('separates gantt sections', () => {
+    const input = `gantt
+dateFormat YYYY-MM-DD
+section Design
+Research :r1, 2024-01-01, 5d
+section Build
+Backend :b1, after r1, 10d`;
+    expect(formatMermaid(input, options)).toBe(`gantt
+    dateFormat YYYY-MM-DD
+
+    section Design
+    Research :r1, 2024-01-01, 5d
+
+    section Build
+    Backend :b1, after r1, 10d
+`);
+  });
+
+  it('does not separate nested blocks from their closers', () => {
+    const input = 'sequenceDiagram\nalt ok\nloop x\nA->>B: y\nend\nelse\nend';
+    expect(formatMermaid(input, options)).toBe(formatMermaid(input));
+  });
+});
+
+describe('expandChains', () => {
+  const options = { expandChains: true };
+
+  it('splits chained edges into one edge per line', () => {
+    const input = 'flowchart LR\nA[Start] -->|go| B(Mid) -.-> C & D --- E;';
+    expect(formatMermaid(input, options)).toBe(`flowchart LR
+    A[Start] -->|go| B(Mid);
+    B -.-> C & D;
+    C & D --- E;
+`);
+  });
+
+  it('keeps single edges and non-edge lines', () => {
+    const input = 'flowchart LR\n    A-->B\n    style A fill:#f9f\n';
+    expect(formatMermaid(input, options)).toBe(input);
+  });
+
+  it('keeps a trailing comment on the last edge', () => {
+    const input = 'flowchart LR\nA --> B --> C %% main path';
+    expect(formatMermaid(input, options)).toBe(
+      'flowchart LR\n    A --> B\n    B --> C %% main path\n'
+    );
+  });
+
+  it('maps the chained line to its first output line', () => {
+    const { lineMap } = formatWithSourceMap('graph TD\nA --> B --> C\nC --> D', {
+      expandChains: true,
+    });
+    expect(lineMap).toEqual([1, 2, 4]);
+  });
+});
+
+describe('lossless', () => {
+  const input = `graph   TD
+
+
+A-->B   %% main path
+      subgraph  S
+C --- D · x  
+end   `;
+
+  it('changes only indentation, trailing whitespace, and blank lines', () => {
+    expect(
+      formatMermaid(input, { lossless: true, spaceArrows: true })
+    ).toBe(`graph   TD
+
+    A-->B   %% main path
+
+    subgraph  S
+        C --- D · x
+    end
+`);
+  });
+
+  it('is idempotent', () => {
+    const once = formatMermaid(input, { lossless: true });
+    expect(formatMermaid(once, { lossless: true })).toBe(once);
+  });
+
+  it('keeps source text in formatStatementText', () => {
+    const [, edge] = parse('graph TD\nA-->B   %% main path').statements;
+    expect(edge.raw).toBe('A-->B   %% main path');
+    expect(formatStatementText(edge, 'graph', { lossless: true })).toBe(
+      'A-->B   %% main path'
+    );
+  });
+});
+
+describe('unknown statements', () => {
+  it('keeps unrecognized lines as written', () => {
+    const input = `sequenceDiagram
+properties   A: {"class":  "internal"}
+A->>B:hello`;
+    expect(formatMermaid(input)).toBe(`sequenceDiagram
+    properties   A: {"class":  "internal"}
+    A ->> B: hello
+`);
+  });
+});
+
+describe('styleLevel', () => {
+  const input = `sequenceDiagram
+participant Alice
+Alice->>Bob:   hi
+Bob-->>Alice: hello there`;
+
+  it('changes only whitespace when conservative', () => {
+    expect(formatMermaid(input, { styleLevel: 'conservative' })).toBe(
+      formatMermaid(input, { lossless: true })
+    );
+  });
+
+  it('aligns and spaces when aggressive', () => {
+    expect(formatMermaid(input, { styleLevel: 'aggressive' })).toBe(
+      formatMermaid(input, { alignMessages: true })
+    );
+    expect(
+      formatMermaid('graph TD\nA-->B', { styleLevel: 'aggressive' })
+    ).toBe('graph TD\n    A --> B\n');
+  });
+
+  it('lets explicit options override the level', () => {
+    expect(
+      formatMermaid(input, { styleLevel: 'aggressive', alignMessages: false })
+    ).toBe(formatMermaid(input));
+  });
+});
+
+describe('trace', () => {
+  const traced = (input: string, options = {}) => {
+    const events: TraceEvent[] = [];
+    formatMermaid(input, { ...options, trace: (event) => events.push(event) });
+    return events;
+  };
+
+  it('reports each normalizer that changed a line', () => {
+    expect(traced('graph TD\nA  -->  B[ x ]\n')).toEqual([
+      {
+        line: 2,
+        statementType: 'generic-line',
+        rule: 'collapse-spaces',
+        before: 'A  -->  B[ x ]',
+        after: 'A --> B[ x ]',
+      },
+      {
+        line: 2,
+        statementType: 'generic-line',
+        rule: 'bracket-padding',
+        before: 'A --> B[ x ]',
+        after: 'A --> B[x]',
+      },
+    ]);
+  });
+
+  it('reports nothing for formatted input', () => {
+    expect(traced('sequenceDiagram\n    A ->> B: hi\n')).toEqual([]);
+  });
+
+  it('reports statement formatting and alignment', () => {
+    const events = traced('sequenceDiagram\nA->>B:hi\nAlice ->> B: yo\n', {
+      alignMessages: true,
+    });
+    const changes = events.map(({ line, rule, after }) => [line, rule, after]);
+    expect(changes).toEqual([
+      [2, 'arrow-message', 'A ->> B: hi'],
+      [2, 'align-messages', 'A ->> B:     hi'],
+    ]);
+  });
+
+  it('reports blank lines removed and inserted', () => {
+    const events = traced('\ngraph TD\nA\n\n\nsubgraph S\nend\n\n');
+    expect(events.map(({ line, rule }) => [line, rule])).toEqual([
+      [1, 'leading-blank-lines'],
+      [5, 'collapse-blank-lines'],
+      [8, 'trailing-blank-lines'],
+    ]);
+    expect(
+      traced('graph TD\nA\nsubgraph S\nend\n').map(({ rule }) => rule)
+    ).toEqual(['blank-before-block']);
+  });
+
+  it('reports statement hooks', () => {
+    const hook: StatementHook = { format: () => 'B' };
+    const events = traced('graph TD\nA\n', { statementHooks: [hook] });
+    const changes = events.map(({ rule, before, after }) => [
+      rule,
+      before,
+      after,
+    ]);
+    expect(changes).toEqual([
+      ['statement-hook', 'graph TD', 'B'],
+      ['statement-hook', 'A', 'B'],
+    ]);
+  });
+});
+
+describe('alignment with wide characters', () => {
+  it('aligns sequence messages by display width', () => {
+    const input = 'sequenceDiagram\n用户->>服务: 请求\nA->>B: ok';
+    expect(formatMermaid(input, { alignMessages: true })).toBe(
+      'sequenceDiagram\n    用户 ->> 服务: 请求\n    A ->> B:       ok\n'
+    );
+  });
+
+  it('aligns flowchart edges by display width', () => {
+    const input = 'flowchart LR\n开始[开始] --> 结束\nA --> B';
+    expect(formatMermaid(input, { alignArrows: true })).toBe(
+      'flowchart LR\n    开始[开始] --> 结束\n    A          --> B\n'
+    );
+  });
+
+  it('aligns gantt tasks by display width', () => {
+    const input = 'gantt\n设计评审 :a, 1d\nBuild :b, after a, 2d';
+    expect(formatMermaid(input, { alignGanttTasks: true })).toBe(
+      'gantt\n    设计评审 : a, 1d\n    Build    : b, after a, 2d\n'
+    );
+  });
+
+  it('measures wrapped labels by display width', () => {
+    const input = 'flowchart LR\nA -->|共享工作区 本地工具 写文件 调用| B';
+    expect(formatMermaid(input, { wrapEdgeLabels: 'br', maxWidth: 32 })).toBe(
+      'flowchart LR\n    A -->|共享工作区 本地工具<br/>写文件 调用| B\n'
+    );
   });
 });
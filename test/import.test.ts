@@ -0,0 +1,163 @@
+/**
+ * Tests for imports from other diagram languages.
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  diagramFromDot,
+  diagramFromPlantUML,
+  diagramToDot,
+  format,
+  importDiagram,
+  isImportFormat,
+  parse,
+} from '../src/index.js';
+
+describe('diagramFromDot', () => {
+  it('converts nodes, edges, labels, and clusters', () => {
+    const input = `// pipeline
+digraph G {
+  rankdir=LR;
+  node [shape=box];
+  start [label="Start here", shape=ellipse];
+  "my node" -> start [style=dashed, label="go\\nnow"];
+  start -> {a b} [penwidth=2];
+  subgraph cluster_work {
+    label = "Work area";
+    c [shape=diamond, label="Ok?"];
+    c -> d [dir=both];
+  }
+  a -> c [arrowhead=none];
+  b:east -> c [style=invis];
+}`;
+    expect(format(diagramFromDot(input))).toBe(`flowchart LR
+    start([Start here])
+    my_node[my node] -.->|"go<br/>now"| start
+    start ==> a & b
+
+    subgraph work [Work area]
+        c{Ok?}
+        c <--> d
+    end
+    a --- c
+    b ~~~ c
+`);
+  });
+
+  it('reads undirected graphs and escapes', () => {
+    const input = 'graph { end -- b [label="say \\"hi\\""]; /* done */ }';
+    expect(format(diagramFromDot(input))).toBe(
+      'flowchart TB\n    End[end] ---|"say #quot;hi#quot;"| b\n'
+    );
+  });
+
+  it('imports what diagramToDot exports', () => {
+    const input = 'flowchart LR\n    A{Ok?} -->|yes| B\n';
+    const dot = diagramToDot(parse(input));
+    expect(format(diagramFromDot(dot))).toBe(
+      'flowchart LR\n    A{Ok?}\n    B\n    A -->|yes| B\n'
+    );
+  });
+
+  it('reports syntax errors with their line', () => {
+    expect(() => diagramFromDot('digraph {\n  a -> ;\n}')).toThrow(
+      'Cannot import DOT: expected node ID at line 2, found ";"'
+    );
+    expect(() => diagramFromDot('flowchart TD')).toThrow(
+      'expected graph or digraph'
+    );
+  });
+});
+
+describe('diagramFromPlantUML', () => {
+  it('converts participants, messages, notes, and groups', () => {
+    const input = `@startuml
+participant "Web Shop" as Shop
+actor User #red
+' checkout
+User -> Shop ++ : order\\nnow
+Shop -[#blue]->> Db : save
+Shop <-- Db -- : ok
+alt success
+  Shop -> User : done
+else failure
+  note over Shop, User : retry
+end
+par
+  Shop ->x Db
+else
+  Shop -> User
+end
+@enduml`;
+    expect(format(diagramFromPlantUML(input))).toBe(`sequenceDiagram
+    participant Shop as Web Shop
+    actor User
+    %% checkout
+    User ->>+ Shop: order<br/>now
+    Shop --) Db: save
+    Db -->>- Shop: ok
+
+    alt success
+        Shop ->> User: done
+    else failure
+        Note over Shop,User: retry
+    end
+
+    par
+        Shop -x Db:
+    and
+        Shop ->> User:
+    end
+`);
+  });
+
+  it('joins multi-line notes and keeps unsupported lines as comments', () => {
+    const input = `note left of A
+  first
+  second
+end note
+== Setup ==
+group Payment
+  A -> B
+end
+/' multi
+line '/`;
+    expect(format(diagramFromPlantUML(input))).toBe(`sequenceDiagram
+    Note left of A: first<br/>second
+    %% == Setup ==
+    rect rgba(0, 0, 0, 0.05)
+        %% Payment
+        A ->> B:
+    end
+    %% multi
+    %% line
+`);
+  });
+
+  it('converts boxes', () => {
+    const input = 'box "Internal" #LightBlue\nparticipant Svc\nend box';
+    expect(format(diagramFromPlantUML(input))).toBe(
+      'sequenceDiagram\n\n    box LightBlue Internal\n        participant Svc\n    end\n'
+    );
+    const hex = 'box "Edge" #ff0000\nparticipant A\nend box';
+    expect(format(diagramFromPlantUML(hex))).toContain('box #ff0000 Edge\n');
+  });
+
+  it('rejects other PlantUML documents', () => {
+    expect(() => diagramFromPlantUML('@startmindmap\n* root')).toThrow(
+      'Cannot import @startmindmap'
+    );
+  });
+});
+
+describe('importDiagram', () => {
+  it('dispatches by format name', () => {
+    expect(isImportFormat('plantuml')).toBe(true);
+    expect(isImportFormat('dot')).toBe(true);
+    expect(isImportFormat('svg')).toBe(false);
+    const input = 'A -> B : hi';
+    expect(importDiagram(input, 'plantuml')).toEqual(
+      diagramFromPlantUML(input)
+    );
+  });
+});
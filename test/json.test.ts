@@ -0,0 +1,66 @@
+/**
+ * Tests for AST JSON serialization.
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  diagramFromJSON,
+  diagramToJSON,
+  format,
+  formatMermaid,
+  parse,
+} from '../src/index.js';
+
+describe('diagramToJSON', () => {
+  it('round-trips through diagramFromJSON', () => {
+    const input = 'sequenceDiagram\nloop L\nA->>B: hi\nend\n';
+    const diagram = parse(input);
+    const restored = diagramFromJSON(diagramToJSON(diagram));
+    expect(restored).toEqual(diagram);
+    expect(format(restored)).toBe(formatMermaid(input));
+  });
+
+  it('prints on one line with indent 0', () => {
+    const json = diagramToJSON(parse('flowchart TD\nA-->B'), 0);
+    expect(json).not.toContain('\n');
+  });
+});
+
+describe('diagramFromJSON', () => {
+  it('accepts an already parsed value', () => {
+    const diagram = diagramFromJSON({
+      type: 'flowchart',
+      statements: [
+        {
+          type: 'diagram-decl',
+          diagramType: 'flowchart',
+          content: 'flowchart TD',
+        },
+        { type: 'generic-line', content: 'A-->B' },
+      ],
+    });
+    expect(format(diagram)).toBe('flowchart TD\n    A-->B\n');
+  });
+
+  it('names the first invalid field', () => {
+    const check = (value: unknown): string => {
+      try {
+        diagramFromJSON(value);
+        return '';
+      } catch (err) {
+        return (err as Error).message;
+      }
+    };
+    expect(check([])).toContain('expected an object');
+    expect(check({ type: 'nope', statements: [] })).toContain(
+      'unknown diagram type "nope"'
+    );
+    expect(check({ type: 'flowchart' })).toContain('"statements"');
+    expect(
+      check({ type: 'flowchart', statements: [{ type: 'edge', content: '' }] })
+    ).toContain('statements[0].type "edge"');
+    expect(
+      check({ type: 'flowchart', statements: [{ type: 'comment' }] })
+    ).toContain('statements[0].content must be a string');
+  });
+});
@@ -0,0 +1,414 @@
+/**
+ * Lint tests
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  FlowchartBuilder,
+  fixMermaid,
+  lint,
+  lintDiagram,
+  LINT_RULES,
+  parse,
+} from '../src/index.js';
+
+describe('lint', () => {
+  it('reports nothing for a clean diagram', () => {
+    expect(lint('flowchart TD\n    A --> B')).toEqual([]);
+  });
+
+  it('denies a missing diagram type by default', () => {
+    const diagnostics = lint('\nA --> B');
+    expect(diagnostics).toHaveLength(1);
+    expect(diagnostics[0]).toMatchObject({
+      ruleId: 'missing-diagram-type',
+      severity: 'error',
+      span: {
+        start: { line: 2, column: 1 },
+        end: { line: 2, column: 8 },
+      },
+    });
+  });
+
+  it('warns on empty blocks with the location of the opener', () => {
+    const diagnostics = lint(`sequenceDiagram
+    loop Every minute
+    end
+    loop Retry
+        A->>B: ping
+    end`);
+
+    expect(diagnostics).toHaveLength(1);
+    expect(diagnostics[0]).toMatchObject({
+      ruleId: 'empty-block',
+      severity: 'warning',
+      span: { start: { line: 2, column: 5 } },
+    });
+  });
+
+  it('reports empty brace blocks', () => {
+    const diagnostics = lint('classDiagram\nclass Foo {\n}');
+    expect(diagnostics).toHaveLength(1);
+    expect(diagnostics[0].message).toContain("'class'");
+  });
+
+  it('applies rule level overrides', () => {
+    const input = 'sequenceDiagram\nloop x\nend';
+    expect(lint(input, { rules: { 'empty-block': 'deny' } })[0].severity).toBe(
+      'error'
+    );
+    expect(lint(input, { rules: { 'empty-block': 'allow' } })).toEqual([]);
+  });
+
+  it('sorts diagnostics by line', () => {
+    const diagnostics = lint('loop x\nend');
+    expect(diagnostics.map((d) => d.ruleId)).toEqual([
+      'missing-diagram-type',
+      'empty-block',
+    ]);
+  });
+
+  it('lints a diagram AST', () => {
+    const input = 'sequenceDiagram\nloop x\nend';
+    expect(lintDiagram(parse(input))).toEqual(lint(input));
+
+    const built = new FlowchartBuilder().classDef('hot', 'fill:#f00').build();
+    expect(lintDiagram(built)).toMatchObject([
+      { ruleId: 'unused-class-def', span: { start: { line: 2, column: 1 } } },
+    ]);
+  });
+
+  it('exposes unique rule IDs', () => {
+    const ids = LINT_RULES.map((rule) => rule.id);
+    expect(new Set(ids).size).toBe(ids.length);
+  });
+});
+
+describe('lint fixes', () => {
+  it('flags participants missing from explicit declarations', () => {
+    const diagnostics = lint(`sequenceDiagram
+    participant A
+    A->>B: hello
+    B->>A: hi`);
+
+    expect(diagnostics).toHaveLength(1);
+    expect(diagnostics[0]).toMatchObject({
+      ruleId: 'undeclared-participant',
+      span: { start: { line: 3 } },
+      fix: { index: 2, deleteCount: 0, insert: ['    participant B'] },
+    });
+  });
+
+  it('flags participants first used in notes and activations', () => {
+    const diagnostics = lint(`sequenceDiagram
+    participant A
+    Note over A,B: both
+    activate C
+    A->>C: hi
+    deactivate C`);
+
+    expect(diagnostics.map((d) => [d.message, d.span.start.line])).toEqual([
+      ["Participant 'B' is used but not declared", 3],
+      ["Participant 'C' is used but not declared", 4],
+    ]);
+  });
+
+  it('offers no fix for participants a declaration cannot spell', () => {
+    const diagnostics = lint(`sequenceDiagram
+    participant A
+    Note over par DB: busy`);
+
+    expect(diagnostics).toHaveLength(1);
+    expect(diagnostics[0].message).toBe(
+      "Participant 'par DB' is used but not declared"
+    );
+    expect(diagnostics[0].fix).toBeUndefined();
+  });
+
+  it('accepts fully implicit participants', () => {
+    expect(lint('sequenceDiagram\n    A->>B: hello')).toEqual([]);
+  });
+
+  it('declares missing participants and formats', () => {
+    const { output, applied } = fixMermaid(`sequenceDiagram
+participant A
+A->>B:hello
+C->>A:hi`);
+
+    expect(output).toBe(`sequenceDiagram
+    participant A
+    participant B
+    participant C
+    A ->> B: hello
+    C ->> A: hi
+`);
+    expect(applied.map((d) => d.ruleId)).toEqual([
+      'undeclared-participant',
+      'undeclared-participant',
+    ]);
+  });
+
+  it('removes unused classDefs', () => {
+    const { output, applied } = fixMermaid(`flowchart TD
+    A:::used --> B
+    class B other
+    classDef used fill:#f9f
+    classDef other fill:#ccc
+    classDef unused fill:#000`);
+
+    expect(output).toBe(`flowchart TD
+    A:::used --> B
+    class B other
+    classDef used fill:#f9f
+    classDef other fill:#ccc
+`);
+    expect(applied).toHaveLength(1);
+    expect(applied[0].message).toContain("'unused'");
+  });
+
+  it('reports nodes redefined with a different label or shape', () => {
+    const diagnostics = lint(`flowchart TD
+    A[Start] --> B
+    A[ Start ] --> C
+    A(Begin) --> D
+    B{Check}`);
+
+    expect(diagnostics).toHaveLength(1);
+    expect(diagnostics[0]).toMatchObject({
+      ruleId: 'conflicting-definition',
+      severity: 'warning',
+      message:
+        "Node 'A' is redefined as (Begin) (first defined as [Start] on line 2)",
+      span: { start: { line: 4 } },
+    });
+  });
+
+  it('reports participants redeclared with a different alias', () => {
+    const diagnostics = lint(`sequenceDiagram
+    participant A as Alice
+    participant B
+    actor A as Alicia
+    participant A as Alice`);
+
+    expect(diagnostics.map((d) => d.message)).toEqual([
+      "Participant 'A' is redefined as 'Alicia' " +
+        "(first defined as 'Alice' on line 2)",
+    ]);
+  });
+
+  it("reports flowchart nodes named 'end'", () => {
+    const diagnostics = lint(`flowchart TD
+    subgraph S
+        Start --> end
+        End[end] --> B
+        end[Done] & C --> D
+    end`);
+
+    expect(diagnostics).toMatchObject([
+      { ruleId: 'end-node-id', span: { start: { line: 3 } } },
+      { ruleId: 'end-node-id', span: { start: { line: 5 } } },
+    ]);
+    expect(diagnostics[0].severity).toBe('warning');
+    const sequence = lint('sequenceDiagram\n    end --> B');
+    expect(sequence.map((d) => d.ruleId)).not.toContain('end-node-id');
+  });
+
+  it('checks gantt dates, durations, and task references', () => {
+    const diagnostics = lint(`gantt
+    dateFormat YYYY-MM-DD
+    Design :des1, 2014-01-06, 3d
+    Build :b1, after des1 ghost, 3days
+    Test :2014/01/20, 2d
+    Ship :crit, milestone, s1, 2014-13-01, 0d
+    Docs :until b1`);
+
+    expect(diagnostics).toMatchObject([
+      {
+        ruleId: 'invalid-gantt-duration',
+        span: { start: { line: 4, column: 34 }, end: { column: 39 } },
+      },
+      {
+        ruleId: 'undefined-gantt-task',
+        message: "Task 'ghost' in 'after' is not defined",
+        span: { start: { line: 4, column: 27 }, end: { column: 32 } },
+      },
+      {
+        ruleId: 'invalid-gantt-date',
+        message: "Start date '2014/01/20' does not match dateFormat 'YYYY-MM-DD'",
+        span: { start: { line: 5, column: 11 } },
+      },
+      { ruleId: 'invalid-gantt-date', span: { start: { line: 6 } } },
+    ]);
+    expect(diagnostics).toHaveLength(4);
+  });
+
+  it('reads gantt dates in the declared dateFormat', () => {
+    const diagnostics = lint(`gantt
+    dateFormat DD.MM.YYYY HH:mm
+    A :a1, 06.01.2014 09:30, 2014-01-08
+    B :b1, after a1, 08.01.2014 17:00`);
+
+    expect(diagnostics.map((d) => d.message)).toEqual([
+      "End date '2014-01-08' does not match dateFormat 'DD.MM.YYYY HH:mm'",
+    ]);
+  });
+
+  it('checks journey task scores and actors', () => {
+    const diagnostics = lint(`journey
+    title My working day
+    section Go to work
+      Make tea: 5: Me
+      Go upstairs: 9: Me, Cat
+      Do work: 3
+      Rest:: Me`);
+
+    expect(diagnostics).toMatchObject([
+      {
+        ruleId: 'invalid-journey-score',
+        message: "Score '9' of task 'Go upstairs' is not 1-7",
+        span: { start: { line: 5, column: 20 }, end: { column: 21 } },
+      },
+      { ruleId: 'missing-journey-actors', span: { start: { line: 6 } } },
+      { ruleId: 'invalid-journey-score', message: "Task 'Rest' has no score" },
+    ]);
+    expect(diagnostics).toHaveLength(3);
+  });
+
+  it('checks requirement relationship keywords', () => {
+    const diagnostics = lint(`requirementDiagram
+    test_entity - satisfies -> test_req
+    test_req <- satisfy - test_entity
+    test_req - Traces -> other`);
+
+    expect(diagnostics).toMatchObject([
+      {
+        ruleId: 'unknown-requirement-relation',
+        message:
+          "Unknown requirement relationship 'satisfy'; expected one of " +
+          'contains, copies, derives, satisfies, verifies, refines, traces',
+        span: { start: { line: 3, column: 17 }, end: { column: 24 } },
+      },
+    ]);
+    expect(diagnostics).toHaveLength(1);
+  });
+
+  it('checks init directive config values', () => {
+    const diagnostics = lint(`%%{init: {"flowchart": {"defaultRenderer": "elk"}}}%%
+%%{init: {'theme': 'midnight', 'flowchart': {'nodeSpacing': '50'}}}%%
+%%{init: {"theme": }}%%
+%%{wrap}%%
+flowchart TD
+    A --> B`);
+
+    expect(diagnostics).toMatchObject([
+      {
+        ruleId: 'invalid-config-value',
+        message:
+          "Config 'theme' must be one of 'default', 'base', 'dark', " +
+          `'forest', 'neutral', 'null', got "midnight"`,
+        span: { start: { line: 2, column: 11 }, end: { column: 18 } },
+      },
+      {
+        ruleId: 'invalid-config-value',
+        message: `Config 'flowchart.nodeSpacing' must be a number, got "50"`,
+      },
+      { ruleId: 'invalid-init-directive', span: { start: { line: 3 } } },
+    ]);
+    expect(diagnostics).toHaveLength(3);
+  });
+
+  it('reports unknown config keys when enabled', () => {
+    const input = `%%{initialize: {"flowchart": {"renderer": "elk"}, "themeVariables": {"x": 1}}}%%
+graph TD
+    A --> B`;
+
+    expect(lint(input)).toEqual([]);
+    expect(
+      lint(input, { rules: { 'unknown-config-key': 'warn' } })
+    ).toMatchObject([
+      {
+        ruleId: 'unknown-config-key',
+        message: "Unknown config key 'flowchart.renderer'",
+        span: { start: { line: 1, column: 31 }, end: { column: 41 } },
+      },
+    ]);
+  });
+
+  it('warns about unrecognized syntax', () => {
+    const diagnostics = lint(`sequenceDiagram
+    title Checkout
+    create participant C
+    A-)B: ping
+    properties A: {"class": "internal"}`);
+
+    expect(diagnostics).toMatchObject([
+      {
+        ruleId: 'unknown-statement',
+        severity: 'warning',
+        message:
+          'Unrecognized sequenceDiagram syntax; ' +
+          'the formatter keeps this line as written',
+        span: { start: { line: 5, column: 5 } },
+      },
+    ]);
+    expect(diagnostics).toHaveLength(1);
+  });
+
+  it('reports stray closers', () => {
+    const diagnostics = lint(`sequenceDiagram
+    A->>B: ping
+    end
+    }`);
+
+    expect(diagnostics).toMatchObject([
+      { ruleId: 'unmatched-close', span: { start: { line: 3 } } },
+      { ruleId: 'unmatched-close', span: { start: { line: 4 } } },
+    ]);
+    expect(diagnostics[0].message).toBe("'end' has no matching opener");
+  });
+
+  it('matches closers to openers of the same kind', () => {
+    const diagnostics = lint(`stateDiagram-v2
+    state Busy {
+        loop x
+            A --> B
+    }`);
+
+    expect(diagnostics).toMatchObject([
+      { ruleId: 'unclosed-block', message: "'loop' block is never closed" },
+    ]);
+  });
+
+  it('closes unbalanced blocks innermost first', () => {
+    const { output, applied } = fixMermaid(`sequenceDiagram
+    loop Outer
+        alt ok
+            A->>B: ping
+
+`);
+
+    expect(output).toBe(`sequenceDiagram
+
+    loop Outer
+        alt ok
+            A ->> B: ping
+        end
+    end
+`);
+    expect(applied.map((d) => d.severity)).toEqual(['error', 'error']);
+  });
+
+  it('closes brace blocks with a brace', () => {
+    const { output } = fixMermaid('classDiagram\nclass Foo {\n+int id');
+    expect(output).toBe('classDiagram\n\nclass Foo {\n    +int id\n}\n');
+  });
+
+  it('skips fixes for allowed rules', () => {
+    const input = 'flowchart TD\n    classDef unused fill:#000\n';
+    const { output, applied } = fixMermaid(input, {
+      rules: { 'unused-class-def': 'allow' },
+    });
+    expect(output).toBe(input);
+    expect(applied).toEqual([]);
+  });
+});
@@ -0,0 +1,141 @@
+/**
+ * Language server tests
+ */
+
+import { describe, it, expect } from 'vitest';
+import type { RpcMessage } from '../src/lsp.js';
+import {
+  createLanguageServer,
+  createMessageParser,
+  encodeMessage,
+} from '../src/lsp.js';
+
+function setup() {
+  const sent: RpcMessage[] = [];
+  const handle = createLanguageServer((message) => sent.push(message));
+  return { sent, handle };
+}
+
+const uri = 'file:///tmp/diagram.mmd';
+
+describe('language server', () => {
+  it('advertises formatting capabilities', () => {
+    const { sent, handle } = setup();
+    handle({ jsonrpc: '2.0', id: 1, method: 'initialize', params: {} });
+    expect(sent[0]).toMatchObject({
+      id: 1,
+      result: {
+        capabilities: {
+          documentFormattingProvider: true,
+          documentRangeFormattingProvider: true,
+        },
+      },
+    });
+  });
+
+  it('publishes lint diagnostics on open and change', () => {
+    const { sent, handle } = setup();
+    handle({
+      jsonrpc: '2.0',
+      method: 'textDocument/didOpen',
+      params: { textDocument: { uri, text: 'sequenceDiagram\nloop x\nend' } },
+    });
+    expect(sent[0]).toMatchObject({
+      method: 'textDocument/publishDiagnostics',
+      params: {
+        uri,
+        diagnostics: [
+          {
+            code: 'empty-block',
+            severity: 2,
+            range: { start: { line: 1, character: 0 } },
+          },
+        ],
+      },
+    });
+
+    handle({
+      jsonrpc: '2.0',
+      method: 'textDocument/didChange',
+      params: {
+        textDocument: { uri },
+        contentChanges: [{ text: 'sequenceDiagram\n' }],
+      },
+    });
+    expect(sent[1]).toMatchObject({ params: { uri, diagnostics: [] } });
+  });
+
+  it('formats with editor indentation as minimal edits', () => {
+    const { sent, handle } = setup();
+    const text = 'sequenceDiagram\n  A ->> B: hi\nB ->> A: ok\n';
+    handle({
+      jsonrpc: '2.0',
+      method: 'textDocument/didOpen',
+      params: { textDocument: { uri, text } },
+    });
+    handle({
+      jsonrpc: '2.0',
+      id: 2,
+      method: 'textDocument/rangeFormatting',
+      params: {
+        textDocument: { uri },
+        options: { tabSize: 2, insertSpaces: true },
+      },
+    });
+    expect(sent[1].result).toEqual([
+      {
+        range: {
+          start: { line: 2, character: 0 },
+          end: { line: 2, character: 0 },
+        },
+        newText: '  ',
+      },
+    ]);
+  });
+
+  it('rejects unknown requests', () => {
+    const { sent, handle } = setup();
+    handle({ jsonrpc: '2.0', id: 3, method: 'textDocument/hover' });
+    expect(sent[0].error?.code).toBe(-32601);
+  });
+});
+
+describe('message framing', () => {
+  it('reassembles messages split across chunks', () => {
+    const received: RpcMessage[] = [];
+    const parse = createMessageParser((message) => received.push(message));
+    const frame = encodeMessage({ jsonrpc: '2.0', id: 1, method: 'ü' });
+    const bytes = Buffer.from(frame + frame, 'utf-8');
+
+    parse(bytes.subarray(0, 10));
+    parse(bytes.subarray(10, 40));
+    parse(bytes.subarray(40));
+
+    expect(received.map((m) => m.method)).toEqual(['ü', 'ü']);
+  });
+
+  it('answers a malformed body with a parse error and keeps reading', () => {
+    const received: RpcMessage[] = [];
+    const errors: RpcMessage[] = [];
+    const parse = createMessageParser(
+      (message) => received.push(message),
+      (response) => errors.push(response)
+    );
+    const broken = '{"jsonrpc": "2.0", "id": 1,';
+    parse(
+      Buffer.from(
+        `Content-Length: ${broken.length}\r\n\r\n${broken}` +
+          encodeMessage({ jsonrpc: '2.0', id: 2, method: 'shutdown' })
+      )
+    );
+
+    expect(errors).toEqual([
+      {
+        jsonrpc: '2.0',
+        id: null,
+        error: { code: -32700, message: 'Parse error' },
+      },
+    ]);
+    expect(received).toEqual([{ jsonrpc: '2.0', id: 2, method: 'shutdown' }]);
+  });
+});
@@ -3,7 +3,41 @@
  */
 
 import { describe, it, expect } from 'vitest';
-import { formatMarkdownMermaidBlocks } from '../src/index.js';
+import {
+  formatMarkdown,
+  formatMarkdownMermaidBlocks,
+  MarkdownFormatError,
+} from '../src/index.js';
+import {
+  extractDiagrams,
+  fenceFileNames,
+  findMermaidFences,
+  replaceMermaidFences,
+} from '../src/markdown.js';
+
+describe('formatMarkdown', () => {
+  it('formats fences, keeping indentation and CRLF line endings', () => {
+    const input = '- item\r\n\r\n  ```mermaid\r\n  graph TD\r\n  A-->B\r\n  ```\r\n';
+    expect(formatMarkdown(input, { indentSize: 2 })).toBe(
+      '- item\r\n\r\n  ```mermaid\r\n  graph TD\r\n    A-->B\r\n  ```\r\n'
+    );
+  });
+
+  it('reports failing fences by line', () => {
+    const input =
+      '# A\n\n```mermaid\ngraph TD\n```\n\n```mermaid\nflowchart LR\nA-->B\n```\n';
+    let error: unknown;
+    try {
+      formatMarkdown(input, { indentSize: -1 });
+    } catch (err) {
+      error = err;
+    }
+    expect(error).toBeInstanceOf(MarkdownFormatError);
+    const { errors, output } = error as MarkdownFormatError;
+    expect(errors.map((e) => e.line)).toEqual([3, 7]);
+    expect(output).toBe(input);
+  });
+});
 
 describe('formatMarkdownMermaidBlocks', () => {
   it('formats mermaid code blocks in markdown', () => {
@@ -85,3 +119,122 @@ flowchart TD
     });
   });
 });
+
+describe('findMermaidFences', () => {
+  const markdown = `# Overview
+
+\`\`\`mermaid
+flowchart TD
+    A --> B
+\`\`\`
+
+## Login Flow
+
+- Step:
+  \`\`\`mermaid
+  sequenceDiagram
+      A->>B: hi
+  \`\`\`
+
+\`\`\`bash
+# not a heading
+\`\`\`
+
+\`\`\`mermaid
+pie
+\`\`\`
+`;
+
+  it('finds fences with line, indent, and nearest heading', () => {
+    const fences = findMermaidFences(markdown);
+    expect(fences.map((f) => [f.line, f.indent, f.heading])).toEqual([
+      [3, '', 'Overview'],
+      [11, '  ', 'Login Flow'],
+      [20, '', 'Login Flow'],
+    ]);
+    expect(fences[1].content).toBe('sequenceDiagram\n    A->>B: hi\n');
+  });
+
+  it('names files by heading slug, deduplicating repeats', () => {
+    const fences = findMermaidFences(markdown);
+    expect(fenceFileNames(fences)).toEqual([
+      'overview',
+      'login-flow',
+      'login-flow-2',
+    ]);
+  });
+
+  it('falls back to the fence index without a heading', () => {
+    const fences = findMermaidFences('```mermaid\npie\n```\n');
+    expect(fenceFileNames(fences)).toEqual(['diagram-1']);
+  });
+});
+
+describe('extractDiagrams', () => {
+  it('returns fence and content offsets', () => {
+    const markdown = 'Intro\r\n\r\n  ```mermaid\r\n  pie\r\n  ```\r\nEnd\r\n';
+    const [block] = extractDiagrams(markdown);
+    expect(block).toMatchObject({
+      source: 'pie\n',
+      indent: '  ',
+      infoString: 'mermaid',
+      line: 3,
+    });
+    const { range, contentRange } = block;
+    expect(markdown.slice(range.start, range.end)).toBe(
+      '  ```mermaid\r\n  pie\r\n  ```'
+    );
+    expect(markdown.slice(contentRange.start, contentRange.end)).toBe(
+      '  pie\r\n'
+    );
+  });
+
+  it('runs an unterminated fence to the end of the document', () => {
+    const markdown = '```mermaid\ngraph TD\nA-->B';
+    const [block] = extractDiagrams(markdown);
+    expect(block.range).toEqual({ start: 0, end: markdown.length });
+    expect(block.contentRange).toEqual({ start: 11, end: markdown.length });
+  });
+
+  it('skips mermaid text inside other code blocks', () => {
+    const markdown = '~~~\n```mermaid\n~~~\n\n```mermaid\npie\n```';
+    expect(extractDiagrams(markdown).map((b) => b.line)).toEqual([5]);
+  });
+});
+
+describe('replaceMermaidFences', () => {
+  it('reads include markers placed before a fence', () => {
+    const fences = findMermaidFences(`<!-- mermaidfmt:include diagrams/a.mmd -->
+
+\`\`\`mermaid
+pie
+\`\`\`
+
+<!-- mermaidfmt:include diagrams/b.mmd -->
+Some text.
+
+\`\`\`mermaid
+pie
+\`\`\`
+`);
+    expect(fences.map((f) => f.include)).toEqual(['diagrams/a.mmd', undefined]);
+  });
+
+  it('replaces selected fences, keeping indentation and line endings', () => {
+    const crlf = (lines: string[]) => lines.join('\r\n') + '\r\n';
+    const nested = ['- item', '  ```mermaid', '  keep', '  ```'];
+    const input = crlf(['# A', '```mermaid', 'old', '```', '', ...nested]);
+    const output = replaceMermaidFences(input, (_, index) =>
+      index === 0 ? 'flowchart TD\n    A --> B\n' : undefined
+    );
+    const replaced = ['```mermaid', 'flowchart TD', '    A --> B', '```'];
+    expect(output).toBe(crlf(['# A', ...replaced, '', ...nested]));
+  });
+
+  it('replaces nested fence content with the fence indentation', () => {
+    const input = '- item\n  ```mermaid\n  old\n  ```\n';
+    expect(replaceMermaidFences(input, () => 'pie\n    "a": 1\n')).toBe(
+      '- item\n  ```mermaid\n  pie\n      "a": 1\n  ```\n'
+    );
+  });
+});
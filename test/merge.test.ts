@@ -0,0 +1,135 @@
+/**
+ * Tests for merging diagrams.
+ */
+
+import { describe, it, expect } from 'vitest';
+import { format, mergeDiagrams, parse } from '../src/index.js';
+
+function merge(a: string, b: string) {
+  const { diagram, conflicts } = mergeDiagrams(parse(a), parse(b));
+  return { output: format(diagram), conflicts };
+}
+
+describe('mergeDiagrams', () => {
+  it('unions participants and deduplicates messages and blocks', () => {
+    const { output, conflicts } = merge(
+      `sequenceDiagram
+participant A as Alice
+A->>B: order
+loop retry
+  B->>A: ack
+end
+`,
+      `sequenceDiagram
+participant A as Alice
+participant C as Carol
+A ->> B: order
+loop retry
+  B->>A: ack
+end
+B->>C: pay
+`
+    );
+    expect(output).toBe(`sequenceDiagram
+    participant A as Alice
+    participant C as Carol
+    A ->> B: order
+
+    loop retry
+        B ->> A: ack
+    end
+
+    B ->> C: pay
+`);
+    expect(conflicts).toEqual([]);
+  });
+
+  it('reports participants declared with another alias', () => {
+    const { output, conflicts } = merge(
+      'sequenceDiagram\nparticipant A as Alice\nA->>B: hi',
+      'sequenceDiagram\nparticipant A as Al\nparticipant B\nB->>A: bye'
+    );
+    expect(output).toContain('participant A as Alice');
+    expect(output).not.toContain('Al\n');
+    expect(conflicts).toEqual([
+      {
+        line: 2,
+        message:
+          "Participant 'A' is defined as 'Al', but as 'Alice' on line 2 " +
+          'of the first diagram; keeping the first',
+      },
+    ]);
+  });
+
+  it('keeps only new flowchart edges and first node definitions', () => {
+    const { output, conflicts } = merge(
+      'flowchart LR\nA[Start] --> B{Ok?}\nB --> C',
+      `flowchart LR
+A[Start] --> B[Check] --> D
+B --> C
+C --> E
+X[x]
+A
+subgraph s
+  B[Other] --> F
+end`
+    );
+    expect(output).toBe(`flowchart LR
+    A[Start] --> B{Ok?}
+    B --> C
+
+    B --> D
+    C --> E
+    X[x]
+
+    subgraph s
+        B --> F
+    end
+`);
+    expect(conflicts.map((conflict) => conflict.line)).toEqual([2, 8]);
+    expect(conflicts[0].message).toBe(
+      "Node 'B' is defined as [Check], but as {Ok?} on line 2 of the " +
+        'first diagram; keeping the first'
+    );
+  });
+
+  it("keeps the first diagram's frontmatter and directives", () => {
+    const { output, conflicts } = merge(
+      '---\ntitle: A\n---\n%%{init: {"theme": "dark"}}%%\nflowchart TD\nA-->B',
+      `---
+title: B
+---
+%%{init: {"theme": "dark"}}%%
+%%{init: {"theme": "forest"}}%%
+flowchart TD
+B-->C`
+    );
+    expect(output).toBe(
+      '---\ntitle: A\n---\n%%{init: {"theme": "dark"}}%%\nflowchart TD\n' +
+        '    A-->B\n\n    B-->C\n'
+    );
+    expect(conflicts).toEqual([
+      {
+        line: 2,
+        message:
+          "Frontmatter line 'title: B' is dropped; the first diagram's " +
+          'configuration is kept',
+      },
+      {
+        line: 5,
+        message:
+          `Directive '%%{init: {"theme": "forest"}}%%' is dropped; the ` +
+          "first diagram's configuration is kept",
+      },
+    ]);
+  });
+
+  it('rejects diagrams of different or indent-sensitive types', () => {
+    expect(() =>
+      mergeDiagrams(parse('graph TD'), parse('sequenceDiagram'))
+    ).toThrow('Cannot merge sequenceDiagram into graph');
+    expect(() =>
+      mergeDiagrams(parse('mindmap\n  a'), parse('mindmap\n  b'))
+    ).toThrow('Cannot merge mindmap diagrams');
+  });
+});
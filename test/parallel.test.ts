@@ -0,0 +1,77 @@
+/**
+ * Tests for parallel Markdown formatting.
+ *
+ * Worker threads load the built `parallel-worker.js`, so these tests cover
+ * the calling-thread path, which shares the worker's formatting code.
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  formatMarkdown,
+  MarkdownFormatError,
+  registerDiagramType,
+  unregisterDiagramType,
+} from '../src/index.js';
+import { formatMarkdownParallel } from '../src/parallel.js';
+import { registeredDiagramTypes } from '../src/rules.js';
+
+const HANDBOOK = `# Services
+
+\`\`\`mermaid
+sequenceDiagram
+A->>B: hi
+\`\`\`
+
+- Nested:
+  \`\`\`mermaid
+  graph TD
+  A-->B
+  \`\`\`
+`;
+
+describe('formatMarkdownParallel', () => {
+  it('gives the same output as formatMarkdown', async () => {
+    const output = await formatMarkdownParallel(HANDBOOK, {}, { threads: 1 });
+    expect(output).toBe(formatMarkdown(HANDBOOK));
+  });
+
+  it('reports failing fences like formatMarkdown', async () => {
+    let error: unknown;
+    try {
+      const options = { indentSize: -1 };
+      await formatMarkdownParallel(HANDBOOK, options, { threads: 1 });
+    } catch (err) {
+      error = err;
+    }
+    expect(error).toBeInstanceOf(MarkdownFormatError);
+    const { errors, output } = error as MarkdownFormatError;
+    expect(errors.map((e) => e.line)).toEqual([3, 9]);
+    expect(output).toBe(HANDBOOK);
+  });
+
+  it('lists registered diagram types to send to workers', () => {
+    registerDiagramType('netGraph', 'flowchart');
+    registerDiagramType('orgTree', 'hierarchy');
+    try {
+      expect(registeredDiagramTypes()).toEqual([
+        ['netGraph', 'flowchart'],
+        ['orgTree', 'hierarchy'],
+      ]);
+    } finally {
+      unregisterDiagramType('netGraph');
+      unregisterDiagramType('orgTree');
+    }
+    expect(registeredDiagramTypes()).toEqual([]);
+  });
+
+  it('rejects statement hooks', async () => {
+    const statementHooks = [{ format: () => undefined }];
+    let error: unknown;
+    try {
+      await formatMarkdownParallel(HANDBOOK, { statementHooks });
+    } catch (err) {
+      error = err;
+    }
+    expect(String(error)).toContain('statementHooks cannot be used');
+  });
+});
@@ -3,7 +3,16 @@
  */
 
 import { describe, it, expect } from 'vitest';
-import { parse, detectDiagramType } from '../src/index.js';
+import {
+  parse,
+  parseStrict,
+  ParseError,
+  LimitExceededError,
+  detectDiagramType,
+  formatMermaid,
+  registerDiagramType,
+  unregisterDiagramType,
+} from '../src/index.js';
 
 describe('detectDiagramType', () => {
   it('detects sequenceDiagram', () => {
@@ -27,6 +36,94 @@ describe('detectDiagramType', () => {
       'sequenceDiagram'
     );
   });
+
+  it('parses frontmatter whose keys are diagram keywords', () => {
+    const input = `---
+title: Flow
+config:
+  flowchart:
+    curve: basis
+---
+sequenceDiagram
+A->>B: hi`;
+    expect(detectDiagramType(input)).toBe('sequenceDiagram');
+    const diagram = parse(input);
+    expect(diagram.type).toBe('sequenceDiagram');
+    expect(diagram.statements.slice(2, 6)).toEqual([
+      { type: 'frontmatter', content: 'config:' },
+      { type: 'frontmatter', content: '  flowchart:' },
+      { type: 'frontmatter', content: '    curve: basis' },
+      { type: 'frontmatter', content: '---' },
+    ]);
+    expect(diagram.statements[6].type).toBe('diagram-decl');
+  });
+
+  it('skips directives and frontmatter', () => {
+    const input = `---
+title: Flow
+config:
+  flowchart:
+    curve: linear
+---
+%%{init: {"theme": "dark"}}%%
+sequenceDiagram
+    A->>B: hi`;
+    expect(detectDiagramType(input)).toBe('sequenceDiagram');
+  });
+
+  it('returns unknown without a declaration', () => {
+    expect(detectDiagramType('')).toBe('unknown');
+    expect(detectDiagramType('%% only a comment\n---\nA --> B')).toBe(
+      'unknown'
+    );
+  });
+});
+
+describe('registerDiagramType', () => {
+  it('handles registered headers as their base behavior', () => {
+    registerDiagramType('netGraph', 'flowchart');
+    registerDiagramType('orgTree', 'hierarchy');
+    try {
+      expect(detectDiagramType('%% c\nnetGraph LR\nA-->B')).toBe('flowchart');
+      expect(parse('netGraph-x').type).toBe('unknown');
+      expect(formatMermaid('netGraph LR\nA-->B')).toBe(
+        'netGraph LR\n    A-->B\n'
+      );
+      expect(formatMermaid('orgTree\n  root\n      child')).toBe(
+        'orgTree\n  root\n      child\n'
+      );
+    } finally {
+      unregisterDiagramType('netGraph');
+      unregisterDiagramType('orgTree');
+    }
+    expect(formatMermaid('netGraph\n  A-->B')).toBe('netGraph\nA-->B\n');
+  });
+
+  it('reindents hierarchy diagrams but never passthrough ones', () => {
+    registerDiagramType('orgTree', 'hierarchy');
+    registerDiagramType('rawChart', 'passthrough');
+    try {
+      const options = { reindentMindmaps: true };
+      expect(formatMermaid('orgTree\n  root\n      child', options)).toBe(
+        'orgTree\n    root\n        child\n'
+      );
+      expect(formatMermaid('rawChart\n  root\n      child', options)).toBe(
+        'rawChart\n  root\n      child\n'
+      );
+    } finally {
+      unregisterDiagramType('orgTree');
+      unregisterDiagramType('rawChart');
+    }
+  });
+
+  it('rejects built-in and malformed keywords', () => {
+    expect(() => registerDiagramType('flowchart', 'passthrough')).toThrow(
+      'built-in'
+    );
+    expect(() => registerDiagramType('my chart', 'passthrough')).toThrow(
+      'Invalid diagram keyword'
+    );
+  });
 });
 
 describe('parse', () => {
@@ -48,6 +145,26 @@ describe('parse', () => {
     expect(arrow.type === 'arrow-message' && arrow.message).toBe('Hello');
   });
 
+  it('parses participant aliases', () => {
+    const diagram = parse(`sequenceDiagram
+    participant A as "Alice in Wonderland"
+    actor B as Bob
+    participant C`);
+
+    expect(diagram.statements.slice(1)).toMatchObject([
+      {
+        type: 'participant',
+        kind: 'participant',
+        id: 'A',
+        alias: '"Alice in Wonderland"',
+        aliasQuoted: true,
+      },
+      { type: 'participant', kind: 'actor', id: 'B', aliasQuoted: false },
+      { type: 'participant', kind: 'participant', id: 'C' },
+    ]);
+    expect(diagram.statements[3]).not.toHaveProperty('alias');
+  });
+
   it('parses arrow message without space', () => {
     const input = `sequenceDiagram
     A->>B:Hello`;
@@ -70,6 +187,66 @@ describe('parse', () => {
     expect(arrow.type === 'arrow-message' && arrow.to).toBe('B');
   });
 
+  it('parses autonumber and activation statements', () => {
+    const input = `sequenceDiagram
+    autonumber
+    activate A
+    A->>B: Hello
+    deactivate A`;
+    const diagram = parse(input);
+
+    expect(diagram.statements[1].type).toBe('autonumber');
+    expect(diagram.statements[2].type).toBe('activate');
+    expect(
+      diagram.statements[2].type === 'activate' &&
+        diagram.statements[2].participant
+    ).toBe('A');
+    expect(diagram.statements[4].type).toBe('deactivate');
+    expect(
+      diagram.statements[4].type === 'deactivate' &&
+        diagram.statements[4].participant
+    ).toBe('A');
+  });
+
+  it('treats activate as generic line outside sequence diagrams', () => {
+    const input = `flowchart TD
+    activate --> B`;
+    const diagram = parse(input);
+    expect(diagram.statements[1].type).toBe('generic-line');
+  });
+
+  it('parses positional notes into structured nodes', () => {
+    const input = `sequenceDiagram
+    Note right of A: hello
+    NOTE over A , B :  shared
+    note left of C
+    note "free text"`;
+    const diagram = parse(input);
+
+    expect(diagram.statements[1]).toMatchObject({
+      type: 'note',
+      position: 'right of',
+      participants: ['A'],
+      text: 'hello',
+    });
+    expect(diagram.statements[2]).toMatchObject({
+      type: 'note',
+      position: 'over',
+      participants: ['A', 'B'],
+      text: 'shared',
+    });
+    expect(diagram.statements[3]).toMatchObject({
+      type: 'note',
+      position: 'left of',
+      participants: ['C'],
+      text: undefined,
+    });
+    expect(diagram.statements[4]).toMatchObject({
+      type: 'note',
+      position: undefined,
+    });
+  });
+
   it('does not parse flowchart class syntax as arrow message', () => {
     const input = `flowchart TD
     A --> B:::warning`;
@@ -78,17 +255,91 @@ describe('parse', () => {
   });
 
   it('parses arrows in non-sequence diagrams when pattern matches', () => {
-    const input = `classDiagram
-    Animal-->Dog:inherits`;
+    const input = `stateDiagram-v2
+    Idle-->Running:start`;
     const diagram = parse(input);
 
-    expect(diagram.type).toBe('classDiagram');
+    expect(diagram.type).toBe('stateDiagram-v2');
     expect(diagram.statements[1].type).toBe('arrow-message');
     const arrow = diagram.statements[1];
-    expect(arrow.type === 'arrow-message' && arrow.from).toBe('Animal');
+    expect(arrow.type === 'arrow-message' && arrow.from).toBe('Idle');
     expect(arrow.type === 'arrow-message' && arrow.arrow).toBe('-->');
-    expect(arrow.type === 'arrow-message' && arrow.to).toBe('Dog');
-    expect(arrow.type === 'arrow-message' && arrow.message).toBe('inherits');
+    expect(arrow.type === 'arrow-message' && arrow.to).toBe('Running');
+    expect(arrow.type === 'arrow-message' && arrow.message).toBe('start');
+  });
+
+  it('parses class relationships with cardinalities and labels', () => {
+    const input = `classDiagram
+    Animal-->Dog:inherits
+    Class01 "1" --> "many" Class02 : contains
+    Animal <|-- Duck`;
+    const diagram = parse(input);
+
+    expect(diagram.statements[1]).toMatchObject({
+      type: 'class-relation',
+      from: 'Animal',
+      arrow: '-->',
+      to: 'Dog',
+      label: 'inherits',
+    });
+    expect(diagram.statements[2]).toMatchObject({
+      type: 'class-relation',
+      from: 'Class01',
+      fromCardinality: '1',
+      arrow: '-->',
+      toCardinality: 'many',
+      to: 'Class02',
+      label: 'contains',
+    });
+    expect(diagram.statements[3]).toMatchObject({
+      type: 'class-relation',
+      from: 'Animal',
+      arrow: '<|--',
+      to: 'Duck',
+      label: undefined,
+    });
+  });
+
+  it('parses class annotations inline and in class bodies', () => {
+    const input = `classDiagram
+    <<interface>> Shape
+    class Color {
+        << enumeration >>
+        RED
+    }`;
+    const diagram = parse(input);
+
+    expect(diagram.statements[1]).toMatchObject({
+      type: 'annotation',
+      name: 'interface',
+      target: 'Shape',
+    });
+    expect(diagram.statements[3]).toMatchObject({
+      type: 'annotation',
+      name: 'enumeration',
+      target: undefined,
+    });
+  });
+
+  it('parses flowchart attribute maps', () => {
+    const input = `flowchart TD
+    A@{ shape: rounded, label: "x, y: z" }
+    B@{}`;
+    const diagram = parse(input);
+
+    expect(diagram.statements[1]).toMatchObject({
+      type: 'attribute-map',
+      id: 'A',
+      attributes: [
+        { key: 'shape', value: 'rounded' },
+        { key: 'label', value: '"x, y: z"' },
+      ],
+    });
+    expect(diagram.statements[2]).toMatchObject({
+      type: 'attribute-map',
+      id: 'B',
+      attributes: [],
+    });
   });
 
   it('parses block structures', () => {
@@ -133,16 +384,248 @@ end`;
     expect(diagram.statements[5].type).toBe('block-end');
   });
 
-  it('treats else/option/and without valid parent block as generic lines', () => {
+  it('parses box blocks with color and label', () => {
+    const input = `sequenceDiagram
+box Purple Alice & John
+    participant A
+end
+box rgb(33,66,99)
+    participant B
+end
+box Another Group
+    participant C
+end`;
+    const diagram = parse(input);
+
+    expect(diagram.statements[1]).toMatchObject({
+      type: 'block-start',
+      blockKind: 'box',
+      color: 'Purple',
+      label: 'Alice & John',
+    });
+    expect(diagram.statements[3].type).toBe('block-end');
+    expect(diagram.statements[4]).toMatchObject({
+      blockKind: 'box',
+      color: 'rgb(33,66,99)',
+      label: undefined,
+    });
+    expect(diagram.statements[7]).toMatchObject({
+      blockKind: 'box',
+      color: undefined,
+      label: 'Another Group',
+    });
+  });
+
+  it('parses state stereotypes and concurrency separators', () => {
+    const diagram = parse(`stateDiagram-v2
+state Active {
+state c <<choice>>
+--
+}`);
+    expect(diagram.statements[2]).toEqual({
+      type: 'state-stereotype',
+      name: 'c',
+      stereotype: 'choice',
+      content: 'state c <<choice>>',
+    });
+    expect(diagram.statements[3].type).toBe('state-separator');
+    expect(parse('flowchart TD\n--').statements[1].type).toBe('unknown');
+  });
+
+  it('parses gantt settings apart from tasks', () => {
+    const diagram = parse(`gantt
+dateFormat  YYYY-MM-DD HH:mm
+tickInterval 1week
+inclusiveEndDates
+section Build
+Release : milestone, m1, 2014-01-25, 0d`);
+    expect(diagram.statements[1]).toEqual({
+      type: 'gantt-setting',
+      keyword: 'dateFormat',
+      value: 'YYYY-MM-DD HH:mm',
+      content: 'dateFormat  YYYY-MM-DD HH:mm',
+    });
+    expect(diagram.statements[3]).toMatchObject({
+      keyword: 'inclusiveEndDates',
+      value: '',
+    });
+    expect(diagram.statements.map((stmt) => stmt.type).slice(4)).toEqual([
+      'generic-line',
+      'generic-line',
+    ]);
+  });
+
+  it('parses timeline continuations as events of the period above', () => {
+    const diagram = parse(`timeline
+2004 : Facebook
+%% More
+  : Google : Flickr http://x.org at 10:30
+section Later
+: Orphan`);
+    expect(diagram.statements[3]).toEqual({
+      type: 'timeline-event',
+      period: '2004',
+      events: ['Google', 'Flickr http://x.org at 10:30'],
+      content: ': Google : Flickr http://x.org at 10:30',
+    });
+    expect(diagram.statements[5].type).toBe('generic-line');
+  });
+
+  it('parses mindmap nodes with their depth and decorations', () => {
+    const diagram = parse(`mindmap
+  root
+      A
+    ::icon(fa fa-book)
+    B
+        end
+  :::urgent`);
+    expect(
+      diagram.statements.map((stmt) =>
+        'depth' in stmt ? [stmt.type, stmt.depth] : [stmt.type]
+      )
+    ).toEqual([
+      ['diagram-decl'],
+      ['mindmap-node', 0],
+      ['mindmap-node', 1],
+      ['mindmap-decoration', 1],
+      ['mindmap-node', 1],
+      ['mindmap-node', 2],
+      ['mindmap-decoration', 2],
+    ]);
+    expect(diagram.statements[3]).toMatchObject({
+      kind: 'icon',
+      value: 'fa fa-book',
+    });
+  });
+
+  it('parses block-beta composite blocks', () => {
+    const diagram = parse(
+      'block-beta\nblock:group1:2\nblock\na --> b:2\nend\nend'
+    );
+    expect(diagram.statements[1]).toEqual({
+      type: 'block-start',
+      blockKind: 'block',
+      label: 'group1:2',
+      content: 'block:group1:2',
+    });
+    expect(diagram.statements.map((stmt) => stmt.type).slice(2)).toEqual([
+      'block-start',
+      'generic-line',
+      'block-end',
+      'block-end',
+    ]);
+  });
+
+  it('parses participant links', () => {
+    const diagram = parse(`sequenceDiagram
+participant A
+links A: {"Dashboard":  "https://dash.example.com", "Wiki": "https://w"}
+link A : Repo @ https://git.example.com/a-b`);
+    expect(diagram.statements[2]).toEqual({
+      type: 'link',
+      keyword: 'links',
+      participant: 'A',
+      payload: '{"Dashboard":  "https://dash.example.com", "Wiki": "https://w"}',
+      content:
+        'links A: {"Dashboard":  "https://dash.example.com", "Wiki": "https://w"}',
+    });
+    expect(diagram.statements[3]).toMatchObject({
+      type: 'link',
+      keyword: 'link',
+      participant: 'A',
+      payload: 'Repo @ https://git.example.com/a-b',
+    });
+  });
+
+  it('parses messages from a participant named link', () => {
+    const diagram = parse(`sequenceDiagram
+link ->> B: hi
+links -x B: bye
+link web-server: Docs @ https://docs`);
+    expect(diagram.statements[1]).toMatchObject({
+      type: 'arrow-message',
+      from: 'link',
+      to: 'B',
+    });
+    expect(diagram.statements[2]).toMatchObject({
+      type: 'arrow-message',
+      from: 'links',
+    });
+    expect(diagram.statements[3]).toMatchObject({
+      type: 'link',
+      participant: 'web-server',
+    });
+  });
+
+  it('parses the color of rect blocks', () => {
+    const diagram = parse(`sequenceDiagram
+rect rgb( 191,223 ,255 )
+end
+rect rgba(0, 0, 255, .1) Retry path
+end`);
+    expect(diagram.statements[1]).toMatchObject({
+      blockKind: 'rect',
+      color: 'rgb( 191,223 ,255 )',
+      label: undefined,
+    });
+    expect(diagram.statements[3]).toMatchObject({
+      color: 'rgba(0, 0, 255, .1)',
+      label: 'Retry path',
+    });
+  });
+
+  it('does not treat and as a branch of a nested non-par block', () => {
+    const input = `sequenceDiagram
+par Branch A
+    loop Retry
+        and again
+    end
+end`;
+    const diagram = parse(input);
+
+    expect(diagram.statements[3].type).toBe('unknown');
+  });
+
+  it('treats else/option/and without valid parent block as unknown', () => {
     const input = `sequenceDiagram
 else fallback
 option maybe
 and branch`;
     const diagram = parse(input);
 
-    expect(diagram.statements[1].type).toBe('generic-line');
-    expect(diagram.statements[2].type).toBe('generic-line');
-    expect(diagram.statements[3].type).toBe('generic-line');
+    expect(diagram.statements[1].type).toBe('unknown');
+    expect(diagram.statements[2].type).toBe('unknown');
+    expect(diagram.statements[3].type).toBe('unknown');
+  });
+
+  it('keeps lines outside the modelled syntax as unknown', () => {
+    const sequence = parse(`sequenceDiagram
+title Checkout
+create participant C
+properties A: {"class": "internal"}`);
+    expect(sequence.statements.map((s) => s.type)).toEqual([
+      'diagram-decl',
+      'generic-line',
+      'generic-line',
+      'unknown',
+    ]);
+    expect(sequence.statements[3]).toEqual({
+      type: 'unknown',
+      content: 'properties A: {"class": "internal"}',
+    });
+
+    const flowchart = parse(`flowchart TD
+direction LR
+A --> B
+style A fill:#f9f
+A ~~~> B`);
+    expect(flowchart.statements.map((s) => s.type)).toEqual([
+      'diagram-decl',
+      'generic-line',
+      'generic-line',
+      'generic-line',
+      'unknown',
+    ]);
   });
 
   it('parses brace blocks', () => {
@@ -183,6 +666,38 @@ class "HTTP Client" {
     expect(diagram.statements[3].type).toBe('brace-block-end');
   });
 
+  it('parses ER entity attribute blocks but not relationships', () => {
+    const input = `erDiagram
+CUSTOMER ||--o{ ORDER : places
+CUSTOMER {
+    string name
+}
+p[Person] {
+    string id PK
+}`;
+    const diagram = parse(input);
+
+    expect(diagram.statements[1].type).toBe('generic-line');
+    expect(diagram.statements[2]).toMatchObject({
+      type: 'brace-block-start',
+      blockKind: 'entity',
+      name: 'CUSTOMER',
+    });
+    expect(diagram.statements[4].type).toBe('brace-block-end');
+    expect(diagram.statements[5]).toMatchObject({
+      type: 'brace-block-start',
+      blockKind: 'entity',
+      name: 'p[Person]',
+    });
+  });
+
+  it('does not treat bare brace lines as entity blocks outside erDiagram', () => {
+    const input = `flowchart TD
+A {`;
+    const diagram = parse(input);
+    expect(diagram.statements[1].type).toBe('unknown');
+  });
+
   it('parses state blocks with spaces in name', () => {
     const input = `stateDiagram-v2
 state In Progress {
@@ -200,4 +715,141 @@ state In Progress {
         diagram.statements[1].name
     ).toBe('In Progress');
   });
+
+  it("treats only a whole 'end' line inside a block as a block end", () => {
+    const diagram = parse(`flowchart TD
+subgraph S
+End[end state] --> end
+end --> B
+endpoint
+end
+end`);
+    expect(diagram.statements.map((s) => s.type)).toEqual([
+      'diagram-decl',
+      'block-start',
+      'generic-line',
+      'generic-line',
+      'generic-line',
+      'block-end',
+      'generic-line',
+    ]);
+  });
+
+  it('parses trailing comments separately from the statement', () => {
+    const diagram = parse(`sequenceDiagram
+alt ok %% happy path
+A->>B: "50%% done" %% progress
+end %%{init: {}}%% %% closes alt
+%% whole line`);
+    expect(diagram.statements[1]).toMatchObject({
+      type: 'block-start',
+      label: 'ok',
+      trailingComment: '%% happy path',
+    });
+    expect(diagram.statements[2]).toMatchObject({
+      type: 'arrow-message',
+      message: '"50%% done"',
+      trailingComment: '%% progress',
+    });
+    expect(diagram.statements[3]).toMatchObject({
+      content: 'end %%{init: {}}%%',
+      trailingComment: '%% closes alt',
+    });
+    expect(diagram.statements[4]).toEqual({
+      type: 'comment',
+      content: '%% whole line',
+    });
+  });
+});
+
+describe('parseStrict', () => {
+  const parseError = (input: string): ParseError => {
+    try {
+      parseStrict(input);
+    } catch (err) {
+      if (err instanceof ParseError) return err;
+      throw err;
+    }
+    throw new Error('Expected a ParseError');
+  };
+
+  it('returns the diagram for valid input', () => {
+    const input = 'sequenceDiagram\nloop x\nA->>B: hi\nend';
+    expect(parseStrict(input)).toEqual(parse(input));
+  });
+
+  it('reports a missing diagram declaration', () => {
+    const err = parseError('%% note\n  A --> B');
+    expect(err.line).toBe(2);
+    expect(err.column).toBe(3);
+    expect(err.expected).toEqual(['diagram type']);
+  });
+
+  it('reports the opener of an unclosed block', () => {
+    const err = parseError('sequenceDiagram\n    alt ok\n        A->>B: hi');
+    expect(err.message).toBe("'alt' block is never closed (line 2, column 5)");
+    expect(err.span).toEqual({
+      start: { line: 2, column: 5 },
+      end: { line: 2, column: 11 },
+    });
+    expect(err.expected).toEqual(['end']);
+  });
+
+  it('reports stray closers', () => {
+    const err = parseError('classDiagram\nclass A\n}');
+    expect(err.line).toBe(3);
+    expect(err.message).toContain("Unexpected '}'");
+  });
+});
+
+describe('parse limits', () => {
+  const limitError = (input: string, limits: object): LimitExceededError => {
+    try {
+      parse(input, limits);
+    } catch (err) {
+      if (err instanceof LimitExceededError) return err;
+      throw err;
+    }
+    throw new Error('Expected a LimitExceededError');
+  };
+
+  it('accepts input within the limits', () => {
+    const input = 'graph TD\n    subgraph a\n    A-->B\n    end';
+    const limits = {
+      maxInputLength: input.length,
+      maxLineLength: 14,
+      maxStatements: 4,
+      maxDepth: 1,
+    };
+    expect(parse(input, limits)).toEqual(parse(input));
+  });
+
+  it('rejects input over each limit at the offending line', () => {
+    const input = 'graph TD\nsubgraph a\nsubgraph b\nA-->B\nend\nend';
+    const tooLong = limitError(input, { maxInputLength: 20 });
+    expect(tooLong).toBeInstanceOf(ParseError);
+    expect([tooLong.limit, tooLong.max, tooLong.line]).toEqual([
+      'maxInputLength',
+      20,
+      1,
+    ]);
+    const line = limitError(input, { maxLineLength: 9 });
+    expect(line.message).toBe(
+      'Line is 10 characters, over 9 (line 2, column 10)'
+    );
+    const statements = limitError(input, { maxStatements: 3 });
+    expect([statements.limit, statements.line]).toEqual(['maxStatements', 4]);
+    const depth = limitError(input, { maxDepth: 1 });
+    expect([depth.limit, depth.line]).toEqual(['maxDepth', 3]);
+  });
+
+  it('applies limits when formatting, also to passed-through input', () => {
+    const limits = { maxStatements: 2 };
+    expect(() => formatMermaid('graph TD\nA\nB', { limits })).toThrow(
+      LimitExceededError
+    );
+    expect(() => formatMermaid('mindmap\n  a\n  b', { limits })).toThrow(
+      'More than 2 statements'
+    );
+  });
 });
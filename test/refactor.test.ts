@@ -0,0 +1,84 @@
+/**
+ * Tests for structural refactorings.
+ */
+
+import { describe, it, expect } from 'vitest';
+import { renameId } from '../src/index.js';
+
+describe('renameId', () => {
+  it('renames sequence participants but not message text', () => {
+    const { output, changes } = renameId(
+      `sequenceDiagram
+    participant A as A service
+    A->>+B: ask A
+    Note over A,B: A and B
+    activate A
+    B-->>A: done
+    link A: A docs @ https://a.example.com`,
+      'A',
+      'Api'
+    );
+
+    expect(output).toBe(`sequenceDiagram
+    participant Api as A service
+    Api->>+B: ask A
+    Note over Api,B: A and B
+    activate Api
+    B-->>Api: done
+    link Api: A docs @ https://a.example.com`);
+    expect(changes).toHaveLength(6);
+    expect(changes[0]).toEqual({
+      start: { line: 2, column: 17 },
+      end: { line: 2, column: 18 },
+    });
+  });
+
+  it('renames flowchart nodes, subgraphs, and style targets', () => {
+    const { output } = renameId(
+      `flowchart TD
+    subgraph A [A group]
+        A[A label] -->|to A| B & A
+    end
+    style A fill:#f9f
+    class A,B hot
+    click A "https://example.com/A"
+    classDef A fill:#000`,
+      'A',
+      'Start'
+    );
+
+    expect(output).toBe(`flowchart TD
+    subgraph Start [A group]
+        Start[A label] -->|to A| B & Start
+    end
+    style Start fill:#f9f
+    class Start,B hot
+    click Start "https://example.com/A"
+    classDef A fill:#000`);
+  });
+
+  it('renames states and classes outside member bodies', () => {
+    expect(
+      renameId(
+        'stateDiagram-v2\n[*] --> Idle\nIdle --> Busy : Idle ends\nstate Idle {\n  x --> y\n}',
+        'Idle',
+        'Ready'
+      ).output
+    ).toBe(
+      'stateDiagram-v2\n[*] --> Ready\nReady --> Busy : Idle ends\nstate Ready {\n  x --> y\n}'
+    );
+
+    expect(
+      renameId(
+        'classDiagram\nclass Foo {\n  +Foo()\n}\nFoo <|-- Bar : Foo',
+        'Foo',
+        'Base'
+      ).output
+    ).toBe('classDiagram\nclass Base {\n  +Foo()\n}\nBase <|-- Bar : Foo');
+  });
+
+  it('returns the renamed diagram', () => {
+    const { diagram } = renameId('sequenceDiagram\nA->>B: hi', 'B', 'Bob');
+    expect(diagram.statements[1]).toMatchObject({ to: 'Bob' });
+  });
+});
@@ -0,0 +1,50 @@
+/**
+ * Arbitrary-input tests: the lenient APIs accept any string without
+ * throwing, so callers never need to guard them.
+ */
+
+import { describe, it } from 'vitest';
+import {
+  checkFormatted,
+  diagramStructure,
+  fixMermaid,
+  formatMarkdownMermaidBlocks,
+  formatMermaid,
+  getDiagramStats,
+  lint,
+  parse,
+} from '../src/index.js';
+import { randomFormatOptions, randomInput } from '../src/testing.js';
+
+const TARGETS: [string, (input: string, seed: number) => unknown][] = [
+  ['parse', (input) => parse(input)],
+  [
+    'formatMermaid',
+    (input, seed) => formatMermaid(input, randomFormatOptions(seed)),
+  ],
+  ['checkFormatted', (input) => checkFormatted(input)],
+  [
+    'formatMarkdownMermaidBlocks',
+    (input) => formatMarkdownMermaidBlocks('```mermaid\n' + input),
+  ],
+  ['lint', (input) => lint(input)],
+  ['fixMermaid', (input) => fixMermaid(input)],
+  ['getDiagramStats', (input) => getDiagramStats(input)],
+  ['diagramStructure', (input) => diagramStructure(parse(input))],
+];
+
+describe('arbitrary input', () => {
+  for (const [name, target] of TARGETS) {
+    it(`${name} never throws`, () => {
+      for (let seed = 0; seed < 1000; seed++) {
+        const input = randomInput(seed);
+        try {
+          target(input, seed);
+        } catch (err) {
+          const quoted = JSON.stringify(input);
+          throw new Error(`${name} threw for seed ${seed}, ${quoted}: ${err}`);
+        }
+      }
+    });
+  }
+});
@@ -0,0 +1,53 @@
+/**
+ * Diagram stats tests
+ */
+
+import { describe, it, expect } from 'vitest';
+import { getDiagramStats } from '../src/index.js';
+
+describe('getDiagramStats', () => {
+  it('counts flowchart nodes, edges, and nesting depth', () => {
+    const stats = getDiagramStats(`flowchart TD
+    A --> B & C
+    subgraph Group
+        B --> D
+    end`);
+
+    expect(stats).toMatchObject({
+      diagramType: 'flowchart',
+      statements: 5,
+      nodes: 4,
+      edges: 3,
+      maxDepth: 1,
+    });
+  });
+
+  it('counts declared and implicit sequence participants and messages', () => {
+    const stats = getDiagramStats(`sequenceDiagram
+    participant A as Alice
+    A->>B: hello
+    B->>C: forward`);
+
+    expect(stats.participants).toBe(3);
+    expect(stats.messages).toBe(2);
+  });
+
+  it('counts class relationships and state transitions', () => {
+    expect(
+      getDiagramStats(`classDiagram
+    Animal <|-- Duck
+    Animal <|-- Fish`)
+    ).toMatchObject({ nodes: 3, edges: 2 });
+
+    expect(
+      getDiagramStats(`stateDiagram-v2
+    [*] --> Idle
+    Idle --> Running: start`)
+    ).toMatchObject({ nodes: 2, edges: 2 });
+  });
+
+  it('reports the longest line', () => {
+    const stats = getDiagramStats('graph LR\n    A --> LongerNode\n    A --> B');
+    expect(stats.longestLine).toEqual({ line: 2, length: 20 });
+  });
+});
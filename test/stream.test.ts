@@ -0,0 +1,146 @@
+/**
+ * Tests for the streaming formatter.
+ */
+
+import { describe, it, expect } from 'vitest';
+import type { FormatOptions } from '../src/index.js';
+import {
+  formatMermaid,
+  formatStream,
+  LimitExceededError,
+} from '../src/index.js';
+
+async function collect(
+  chunks: Iterable<string | Uint8Array>,
+  options?: FormatOptions
+): Promise<string[]> {
+  const output: string[] = [];
+  for await (const chunk of formatStream(chunks, options)) output.push(chunk);
+  return output;
+}
+
+/** Split text into chunks of `size` characters */
+function chunked(text: string, size: number): string[] {
+  const chunks: string[] = [];
+  for (let i = 0; i < text.length; i += size) {
+    chunks.push(text.slice(i, i + size));
+  }
+  return chunks;
+}
+
+const SEQUENCE = `%% header
+sequenceDiagram
+participant A
+loop Every minute
+A->>B:ping
+
+
+alt ok
+B-->>A:pong
+else
+B-->>A:error
+end
+end
+
+`;
+
+describe('formatStream', () => {
+  it('matches formatMermaid for any chunking', async () => {
+    const expected = formatMermaid(SEQUENCE);
+    for (const size of [1, 3, 7, SEQUENCE.length]) {
+      const output = await collect(chunked(SEQUENCE, size));
+      expect(output.join('')).toBe(expected);
+    }
+  });
+
+  it('yields output before the input ends', async () => {
+    const output = await collect(['flowchart TD\nA-->B\n', 'B-->C\n']);
+    expect(output).toEqual(['flowchart TD\n    A-->B\n', '    B-->C\n']);
+  });
+
+  it('holds blank lines until more content follows', async () => {
+    const output = await collect(['graph TD\nA\n\n', '\n', 'B\n\n']);
+    expect(output).toEqual(['graph TD\n    A\n', '\n    B\n']);
+  });
+
+  it('decodes byte chunks split inside a character', async () => {
+    const bytes = new TextEncoder().encode('graph TD\nA[café]-->B\n');
+    const at = bytes.indexOf(0xc3) + 1;
+    const output = await collect([bytes.slice(0, at), bytes.slice(at)]);
+    expect(output.join('')).toBe('graph TD\n    A[café]-->B\n');
+  });
+
+  it('formats input without a trailing newline or declaration', async () => {
+    expect((await collect(['graph TD\nA-->B'])).join('')).toBe(
+      formatMermaid('graph TD\nA-->B')
+    );
+    expect((await collect(['A', '-->B'])).join('')).toBe('A-->B\n');
+    expect((await collect([])).join('')).toBe(formatMermaid(''));
+  });
+
+  it('expands chains per statement', async () => {
+    const input = 'flowchart LR\nA --> B --> C\n';
+    const options = { expandChains: true };
+    expect((await collect([input], options)).join('')).toBe(
+      formatMermaid(input, options)
+    );
+  });
+
+  it('applies lossless and style levels like formatMermaid', async () => {
+    const input = 'flowchart LR\nA-->B-->C   %% chain\n';
+    for (const options of [
+      { expandChains: true, lossless: true },
+      { expandChains: true, styleLevel: 'conservative' as const },
+    ]) {
+      const expected = formatMermaid(input, options);
+      expect(expected).toContain('A-->B-->C   %% chain');
+      expect((await collect(chunked(input, 3), options)).join('')).toBe(
+        expected
+      );
+    }
+  });
+
+  it('passes indent-sensitive diagrams through', async () => {
+    const input = 'mindmap\n  root\n    child';
+    expect((await collect(chunked(input, 4))).join('')).toBe(`${input}\n`);
+  });
+
+  it('buffers diagrams for options that need every statement', async () => {
+    const options = { alignMessages: true };
+    const output = await collect(chunked(SEQUENCE, 5), options);
+    expect(output).toHaveLength(1);
+    expect(output[0]).toBe(formatMermaid(SEQUENCE, options));
+  });
+
+  it('rejects input over the limits before the input ends', async () => {
+    const lines = function* () {
+      yield 'graph TD\n';
+      yield `A[${'x'.repeat(100)}]\n`;
+      throw new Error('read past the limit');
+    };
+    let error: unknown;
+    try {
+      await collect(lines(), { limits: { maxLineLength: 50 } });
+    } catch (err) {
+      error = err;
+    }
+    expect(error).toBeInstanceOf(LimitExceededError);
+  });
+
+  it('rejects an over-long line before its newline arrives', async () => {
+    for (const limits of [{ maxLineLength: 50 }, { maxInputLength: 60 }]) {
+      const chunks = function* () {
+        yield 'graph TD\n';
+        for (;;) yield 'x'.repeat(10);
+      };
+      let error: unknown;
+      try {
+        await collect(chunks(), { limits });
+      } catch (err) {
+        error = err;
+      }
+      expect(error).toBeInstanceOf(LimitExceededError);
+      expect((error as LimitExceededError).line).toBe(2);
+    }
+  });
+});
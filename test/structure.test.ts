@@ -0,0 +1,103 @@
+/**
+ * Tests for the structural export.
+ */
+
+import { describe, it, expect } from 'vitest';
+import { diagramStructure, parse } from '../src/index.js';
+
+const structureOf = (input: string) => diagramStructure(parse(input));
+
+describe('diagramStructure', () => {
+  it('exports flowchart nodes and one edge per node pair', () => {
+    const structure = structureOf(
+      'flowchart TD\nA(Start) & B -->|go| C["Done"]\nC -.-> A[Again]\n' +
+        'e1@{ animate: true }\nD e1@--> A'
+    );
+    expect(structure.version).toBe(1);
+    expect(structure.diagramType).toBe('flowchart');
+    expect(structure.nodes).toEqual([
+      { id: 'A', line: 2, label: 'Start', shape: { open: '(', close: ')' } },
+      { id: 'B', line: 2 },
+      { id: 'C', line: 2, label: 'Done', shape: { open: '[', close: ']' } },
+      { id: 'e1', line: 4 },
+      { id: 'D', line: 5 },
+    ]);
+    expect(structure.edges).toEqual([
+      { from: 'A', to: 'C', arrow: '-->', label: 'go', line: 2 },
+      { from: 'B', to: 'C', arrow: '-->', label: 'go', line: 2 },
+      { from: 'C', to: 'A', arrow: '-.->', line: 3 },
+      { from: 'D', to: 'A', arrow: '-->', line: 5 },
+    ]);
+  });
+
+  it('exports sequence participants and messages', () => {
+    const structure = structureOf(
+      'sequenceDiagram\nactor U as User\nU->>S: login\nS-->>U: ok\n' +
+        'participant S'
+    );
+    expect(structure.participants).toEqual([
+      { id: 'U', alias: 'User', kind: 'actor', declared: true, line: 2 },
+      { id: 'S', kind: 'participant', declared: false, line: 3 },
+    ]);
+    expect(structure.messages).toEqual([
+      { from: 'U', to: 'S', arrow: '->>', text: 'login', line: 3 },
+      { from: 'S', to: 'U', arrow: '-->>', text: 'ok', line: 4 },
+    ]);
+    expect(structure.nodes).toEqual([]);
+  });
+
+  it('exports ER relationships with labels', () => {
+    const structure = structureOf(
+      'erDiagram\nCUSTOMER ||--o{ ORDER : "places"\nORDER {\nint id\n}'
+    );
+    expect(structure.nodes.map((node) => node.id)).toEqual([
+      'CUSTOMER',
+      'ORDER',
+    ]);
+    expect(structure.edges).toEqual([
+      {
+        from: 'CUSTOMER',
+        to: 'ORDER',
+        arrow: '||--o{',
+        label: 'places',
+        line: 2,
+      },
+    ]);
+  });
+
+  it('exports flowchart node icons and images', () => {
+    const structure = structureOf(
+      'flowchart TD\nA[fab:fa-twitter Tweet] --> B\n' +
+        'B@{ icon: "fa:user", form: "square" }\nC@{ img: "https://x/c.png" }'
+    );
+
+    expect(structure.nodes).toEqual([
+      {
+        id: 'A',
+        label: 'fab:fa-twitter Tweet',
+        shape: { open: '[', close: ']' },
+        icon: 'fab:fa-twitter',
+        line: 2,
+      },
+      { id: 'B', icon: 'fa:user', line: 2 },
+      { id: 'C', image: 'https://x/c.png', line: 4 },
+    ]);
+  });
+
+  it('exports state transitions and class relations', () => {
+    const states = structureOf(
+      'stateDiagram-v2\n[*] --> Idle\nIdle --> Run: go'
+    );
+    expect(states.nodes.map((node) => node.id)).toEqual(['Idle', 'Run']);
+    expect(states.edges).toEqual([
+      { from: '[*]', to: 'Idle', arrow: '-->', line: 2 },
+      { from: 'Idle', to: 'Run', arrow: '-->', label: 'go', line: 3 },
+    ]);
+
+    const classes = structureOf('classDiagram\nclass Animal\nAnimal <|-- Dog');
+    expect(classes.nodes.map((node) => node.id)).toEqual(['Animal', 'Dog']);
+    expect(classes.edges).toMatchObject([
+      { from: 'Animal', to: 'Dog', arrow: '<|--', line: 3 },
+    ]);
+  });
+});
@@ -0,0 +1,87 @@
+/**
+ * Tests for the idempotence helpers and random diagram generator.
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  assertIdempotent,
+  largeDiagram,
+  randomDiagram,
+  randomFormatOptions,
+} from '../src/testing.js';
+import { detectDiagramType, parseStrict } from '../src/index.js';
+
+describe('assertIdempotent', () => {
+  it('returns the formatted output', () => {
+    expect(assertIdempotent('graph TD\nA-->B')).toBe('graph TD\n    A-->B\n');
+  });
+});
+
+describe('randomDiagram', () => {
+  it('is deterministic per seed', () => {
+    expect(randomDiagram(42)).toBe(randomDiagram(42));
+    expect(randomDiagram(1)).not.toBe(randomDiagram(2));
+  });
+
+  it('produces diagrams with a known type', () => {
+    for (let seed = 0; seed < 50; seed++) {
+      expect(detectDiagramType(randomDiagram(seed))).not.toBe('unknown');
+    }
+  });
+});
+
+describe('idempotence property', () => {
+  it('holds with default options', () => {
+    for (let seed = 0; seed < 300; seed++) {
+      assertIdempotent(randomDiagram(seed));
+    }
+  });
+
+  it('holds across random options', () => {
+    for (let seed = 0; seed < 300; seed++) {
+      assertIdempotent(randomDiagram(seed), randomFormatOptions(seed));
+    }
+  });
+
+  it('holds when quoted label wrapping meets edge label alignment', () => {
+    assertIdempotent(randomDiagram(3253), {
+      alignArrows: true,
+      alignEdgeLabels: true,
+      wrapEdgeLabels: 'quoted',
+      maxWidth: 40,
+    });
+  });
+});
+
+describe('randomFormatOptions', () => {
+  it('leaves the style level to decide in some samples', () => {
+    const samples = Array.from({ length: 50 }, (_, seed) =>
+      randomFormatOptions(seed)
+    );
+    expect(samples.some((o) => o.alignArrows === undefined)).toBe(true);
+    expect(samples.some((o) => o.alignArrows !== undefined)).toBe(true);
+    const levels = samples
+      .filter((o) => o.lossless === undefined)
+      .map((o) => o.styleLevel);
+    expect(new Set(levels).size).toBe(3);
+  });
+});
+
+describe('largeDiagram', () => {
+  it('generates the requested number of lines, deterministically', () => {
+    for (const kind of ['flowchart', 'sequence'] as const) {
+      const diagram = largeDiagram(kind, 2000);
+      expect(diagram.split('\n')).toHaveLength(2000);
+      expect(diagram).toBe(largeDiagram(kind, 2000));
+      expect(largeDiagram(kind, 2000, 2)).not.toBe(diagram);
+    }
+  });
+
+  it('generates valid diagrams that format idempotently', () => {
+    for (const kind of ['flowchart', 'sequence'] as const) {
+      const diagram = largeDiagram(kind, 500);
+      expect(() => parseStrict(diagram)).not.toThrow();
+      assertIdempotent(diagram);
+    }
+  });
+});
@@ -0,0 +1,106 @@
+/**
+ * Tests for typed diagram views.
+ */
+
+import { describe, it, expect } from 'vitest';
+import { format, parse, typedDiagram, untypedDiagram } from '../src/index.js';
+
+describe('typedDiagram', () => {
+  it('keeps sequence statements as parsed', () => {
+    const diagram = parse('sequenceDiagram\nparticipant A\nA->>B: hi');
+    const typed = typedDiagram(diagram);
+    expect(typed.family).toBe('sequence');
+    expect(typed.statements).toEqual(diagram.statements);
+  });
+
+  it('types flowchart chains', () => {
+    const typed = typedDiagram(
+      parse('graph TD\nA --> B %% edge\nparticipant A')
+    );
+    expect(typed).toMatchObject({ family: 'flowchart', type: 'graph' });
+    expect(typed.statements[1]).toMatchObject({
+      type: 'flowchart-chain',
+      chain: { groups: [[{ id: 'A' }], [{ id: 'B' }]] },
+      trailingComment: '%% edge',
+      content: 'A --> B',
+    });
+    // Statements of other families become generic lines
+    expect(typed.statements[2]).toEqual({
+      type: 'generic-line',
+      content: 'participant A',
+    });
+  });
+
+  it('types labeled and unlabeled state transitions', () => {
+    const typed = typedDiagram(
+      parse('stateDiagram-v2\n[*] --> Idle\nIdle --> Busy : start')
+    );
+    expect(typed.family).toBe('state');
+    expect(typed.statements.slice(1)).toEqual([
+      {
+        type: 'state-transition',
+        from: '[*]',
+        to: 'Idle',
+        content: '[*] --> Idle',
+      },
+      {
+        type: 'state-transition',
+        from: 'Idle',
+        to: 'Busy',
+        label: 'start',
+        content: 'Idle --> Busy : start',
+      },
+    ]);
+  });
+
+  it('types ER relationships and attributes', () => {
+    const typed = typedDiagram(
+      parse(`erDiagram
+    CUSTOMER ||--o{ ORDER : "places"
+    p[Person] {
+        string name PK "full name"
+    }`)
+    );
+    expect(typed.family).toBe('er');
+    expect(typed.statements[1]).toMatchObject({
+      type: 'er-relation',
+      from: 'CUSTOMER',
+      to: 'ORDER',
+      cardinality: '||--o{',
+      label: 'places',
+    });
+    expect(typed.statements[3]).toMatchObject({
+      type: 'er-attribute',
+      entity: 'p',
+      dataType: 'string',
+      name: 'name',
+      keys: ['PK'],
+      comment: 'full name',
+    });
+  });
+
+  it('keeps other diagram types generic', () => {
+    const diagram = parse('pie\n"A" : 1');
+    expect(typedDiagram(diagram)).toEqual({
+      family: 'generic',
+      type: 'pie',
+      statements: diagram.statements,
+    });
+  });
+});
+
+describe('untypedDiagram', () => {
+  it('formats the same as the parsed diagram', () => {
+    const input = `erDiagram
+CUSTOMER ||--o{ ORDER : places
+CUSTOMER {
+string name
+}`;
+    const diagram = parse(input);
+    const round = untypedDiagram(typedDiagram(diagram));
+    expect(round.statements.map((s) => s.type)).toEqual(
+      diagram.statements.map((s) => s.type)
+    );
+    expect(format(round)).toBe(format(diagram));
+  });
+});
@@ -0,0 +1,89 @@
+/**
+ * Tests for AST traversal helpers.
+ */
+
+import { describe, it, expect } from 'vitest';
+import { format, parse, transformDiagram, walkDiagram } from '../src/index.js';
+
+const input = `sequenceDiagram
+participant A
+loop Every minute
+A->>B: ping
+alt ok
+B-->>A: pong
+end
+end
+A->>C: done`;
+
+describe('walkDiagram', () => {
+  it('visits typed statements with their enclosing blocks', () => {
+    const seen: string[] = [];
+    walkDiagram(parse(input), {
+      'arrow-message': (msg, { parents }) => {
+        seen.push(`${msg.from}>${msg.to}@${parents.length}`);
+      },
+    });
+    expect(seen).toEqual(['A>B@1', 'B>A@2', 'A>C@0']);
+  });
+
+  it('reports entering and leaving blocks in order', () => {
+    const events: string[] = [];
+    walkDiagram(parse(input), {
+      enterBlock: (block) => events.push(`enter ${block.blockKind}`),
+      leaveBlock: (block) => events.push(`leave ${block.blockKind}`),
+    });
+    expect(events).toEqual([
+      'enter loop',
+      'enter alt',
+      'leave alt',
+      'leave loop',
+    ]);
+  });
+
+  it('visits every statement with its index', () => {
+    const indices: number[] = [];
+    walkDiagram(parse(input), {
+      statement: (_, { index }) => indices.push(index),
+    });
+    expect(indices).toEqual([0, 1, 2, 3, 4, 5, 6, 7, 8]);
+  });
+
+  it('tracks brace blocks', () => {
+    const depths: number[] = [];
+    walkDiagram(parse('stateDiagram-v2\nstate S {\nA --> B\n}'), {
+      'generic-line': (_, { parents }) => depths.push(parents.length),
+    });
+    expect(depths).toEqual([1]);
+  });
+});
+
+describe('transformDiagram', () => {
+  it('replaces, expands, and removes statements', () => {
+    const diagram = transformDiagram(parse(input), {
+      'arrow-message': (msg) =>
+        msg.to === 'C' ? null : { ...msg, message: msg.message.toUpperCase() },
+      participant: (stmt) => [
+        stmt,
+        { type: 'participant', content: 'participant B' },
+      ],
+    });
+    expect(format(diagram)).toBe(`sequenceDiagram
+    participant A
+    participant B
+
+    loop Every minute
+        A ->> B: PING
+
+        alt ok
+            B -->> A: PONG
+        end
+    end
+`);
+  });
+
+  it('does not modify the input diagram', () => {
+    const diagram = parse(input);
+    transformDiagram(diagram, { 'arrow-message': () => null });
+    expect(diagram.statements).toHaveLength(9);
+  });
+});
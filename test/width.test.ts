@@ -0,0 +1,33 @@
+/**
+ * Tests for display width measurement.
+ */
+
+import { describe, it, expect } from 'vitest';
+import { displayWidth, padEndToWidth } from '../src/width.js';
+
+describe('displayWidth', () => {
+  it('counts ASCII as one column per character', () => {
+    expect(displayWidth('Agent')).toBe(5);
+  });
+
+  it('counts CJK and fullwidth characters as two columns', () => {
+    expect(displayWidth('共享工作区')).toBe(10);
+    expect(displayWidth('Agent本地')).toBe(9);
+    expect(displayWidth('ｘ，')).toBe(4);
+    expect(displayWidth('한국어')).toBe(6);
+  });
+
+  it('counts combining marks and joined emoji as zero columns', () => {
+    expect(displayWidth('é')).toBe(1);
+    expect(displayWidth('🚀')).toBe(2);
+    expect(displayWidth('👩‍💻')).toBe(2);
+  });
+});
+
+describe('padEndToWidth', () => {
+  it('pads by display width', () => {
+    expect(padEndToWidth('用户', 6)).toBe('用户  ');
+    expect(padEndToWidth('user', 6)).toBe('user  ');
+    expect(padEndToWidth('用户名', 4)).toBe('用户名');
+  });
+});